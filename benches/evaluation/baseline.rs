@@ -0,0 +1,11 @@
+//! The interpreter as it was before the `Ast` arena (17cf39a): a tree of `Box<dyn Expression>` and `Box<dyn Statement>`
+//! with its own parser, copied over unchanged apart from `ExpressionResult` and `ExpressionType`, which it shares with the
+//! library. Modules the arena left alone are the library's own.
+
+pub mod expressions;
+pub mod statements;
+pub mod card;
+pub mod parser;
+pub mod optimizer;
+
+pub use mage_duel::parsing::{script_context, symbol_table, tokens};
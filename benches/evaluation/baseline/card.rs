@@ -0,0 +1,92 @@
+use std::rc::Rc;
+
+use super::{expressions::Expression, statements::{simplify_block, Statement}};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Legendary
+}
+
+#[derive(Debug)]
+pub struct RarityParseError;
+
+impl TryFrom<&str> for Rarity {
+    type Error = RarityParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "common" => Ok(Rarity::Common),
+            "uncommon" => Ok(Rarity::Uncommon),
+            "rare" => Ok(Rarity::Rare),
+            "legendary" => Ok(Rarity::Legendary),
+            _ => Err(RarityParseError)
+        }
+    }
+}
+
+/// Everything about a card that isn't rules: `@name "Firebolt"`, `@rarity rare`, etc.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CardMetadata {
+    /// Human-readable name
+    pub name: Option<Rc<str>>,
+    /// Rules text as printed on the card
+    pub text: Option<Rc<str>>,
+    pub rarity: Rarity,
+    /// Code of the set the card belongs to (e.g. "BASE")
+    pub set_code: Option<Rc<str>>,
+    pub flavor: Option<Rc<str>>,
+    /// Designer notes from the `///` comments before the card
+    pub doc: Option<Rc<str>>
+}
+
+/// A parsed card script: `@metadata... #tag [cost]: { body }`
+pub struct Card {
+    metadata: CardMetadata,
+    tags: Vec<Rc<str>>,
+    cost: Box<dyn Expression>,
+    body: Vec<Box<dyn Statement>>
+}
+
+impl Card {
+    pub fn new(metadata: CardMetadata, tags: Vec<Rc<str>>, cost: Box<dyn Expression>, body: Vec<Box<dyn Statement>>) -> Self {
+        Card { metadata, tags, cost, body }
+    }
+
+    pub fn get_metadata(&self) -> &CardMetadata {
+        &self.metadata
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.metadata.name.as_deref()
+    }
+
+    pub fn get_doc(&self) -> Option<&str> {
+        self.metadata.doc.as_deref()
+    }
+
+    pub fn get_tags(&self) -> &[Rc<str>] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.as_ref() == tag)
+    }
+
+    /// Cost expression; may depend on the game (e.g. `[3 - $discount]`)
+    pub fn get_cost(&self) -> &dyn Expression {
+        self.cost.as_ref()
+    }
+
+    pub fn get_body(&self) -> &[Box<dyn Statement>] {
+        &self.body
+    }
+
+    /// The same card with its cost and body simplified, to play rather than to read
+    pub fn simplified(self) -> Card {
+        Card { cost: self.cost.simplify(), body: simplify_block(self.body), ..self }
+    }
+}
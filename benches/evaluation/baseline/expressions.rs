@@ -0,0 +1,904 @@
+use crate::game_zones::types::Dice;
+
+use super::{optimizer::{fold, is_pure}, script_context::ScriptContext, symbol_table::SymbolTable, tokens::{Token, Tokens}};
+use std::rc::Rc;
+
+// results and types are the same as the library's, which the symbol table and the script context deal in
+pub use mage_duel::parsing::expressions::{ExpressionResult, ExpressionType};
+
+#[derive(Debug)]
+pub enum ParseExpressionError {
+    MismatchedOperands,
+    InvalidOperator,
+    OperandTypesNotSupported
+}
+
+impl std::fmt::Display for ParseExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseExpressionError::MismatchedOperands => write!(f, "operands have different types"),
+            ParseExpressionError::InvalidOperator => write!(f, "invalid operator"),
+            ParseExpressionError::OperandTypesNotSupported => write!(f, "operator doesn't work on these types")
+        }
+    }
+}
+
+pub trait Expression: std::fmt::Debug {
+    fn get_type(&self) -> ExpressionType;
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult;
+    /// Only literals know their value without being evaluated
+    fn as_literal(&self) -> Option<&ExpressionResult> {
+        None
+    }
+    /// What kind of expression it is, for passes that look inside a card (such as lints)
+    fn get_kind(&self) -> ExpressionKind<'_>;
+    /// Subexpressions, in the order they're evaluated
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ ]
+    }
+    /// Fold constant subexpressions into literals and drop identities such as `x + 0`.
+    /// The result always evaluates the same, rolling the same dice in the same order.
+    fn simplify(self: Box<Self>) -> Box<dyn Expression>;
+}
+
+/// Kind of expression, from outside the tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpressionKind<'a> {
+    Literal(&'a ExpressionResult),
+    Variable(&'a str),
+    /// Operator applied to the operands, e.g. `+!` or `~`
+    Operation(&'a str),
+    List,
+    /// Text with `{expressions}` in it
+    Interpolation,
+    /// Dice roll, different every time it's evaluated
+    Roll,
+    /// Targets a player chooses
+    Target,
+    /// Property read from the game
+    Property
+}
+
+#[derive(Debug)]
+pub struct BinaryOperation {
+    left: Box<dyn Expression>,
+    operator: Tokens,
+    right: Box<dyn Expression>,
+}
+
+impl BinaryOperation {
+    pub fn new(left: Box<dyn Expression>, operator: Tokens, right: Box<dyn Expression>) -> Self {
+        BinaryOperation { left, operator, right }
+    }
+
+    fn operator_str(&self) -> Result<&str, ParseExpressionError> {
+        if let Tokens::Symbol(ref symbol) = self.operator {
+            return Ok(symbol.as_str());
+        }
+        Err(ParseExpressionError::InvalidOperator)
+    }
+
+    fn simplify(self) -> Self {
+        BinaryOperation { left: self.left.simplify(), operator: self.operator, right: self.right.simplify() }
+    }
+
+    fn is_left(&self, value: ExpressionResult) -> bool {
+        self.left.as_literal() == Some(&value)
+    }
+
+    fn is_right(&self, value: ExpressionResult) -> bool {
+        self.right.as_literal() == Some(&value)
+    }
+}
+
+#[derive(Debug)]
+pub struct UnaryOperation {
+    operator: Tokens,
+    right: Box<dyn Expression>
+}
+
+impl UnaryOperation {
+    pub fn new(operator: Tokens, right: Box<dyn Expression>) -> Self {
+        UnaryOperation { operator, right }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AdditiveOperation {
+    Add,
+    Subtract,
+    Concatenate,
+    ConcatenateUnique,
+    Except
+}
+
+#[derive(Debug)]
+pub struct AdditiveExpression {
+    op: BinaryOperation,
+    operation: AdditiveOperation,
+    result_type: ExpressionType
+}
+
+impl TryFrom<BinaryOperation> for AdditiveExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: BinaryOperation) -> Result<Self, Self::Error> {
+        let operator = value.operator_str()?;
+        if operator != "+" && operator != "+!" && operator != "-" {
+            return Err(ParseExpressionError::InvalidOperator);
+        }
+
+        let lhs: ExpressionType;
+        let rhs: ExpressionType;
+        let mut lh_is_list = false;
+        let mut rh_is_list = false;
+
+        if let ExpressionType::List(list) = value.left.get_type() {
+            lhs = *list;
+            lh_is_list = true;
+        } else {
+            lhs = value.left.get_type();
+        }
+
+        if let ExpressionType::List(list) = value.right.get_type() {
+            rhs = *list;
+            rh_is_list = true;
+        } else {
+            rhs = value.right.get_type();
+        }
+        let is_list = lh_is_list || rh_is_list;
+
+        if !lh_is_list && rh_is_list {
+            // this is like 7 - [ 7 ] or 7 + [ 7 ], which makes no sense
+            return Err(ParseExpressionError::MismatchedOperands);
+        }
+
+        if !is_list && operator == "+!" {
+            // +! is only a list operator
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+
+        if lhs == ExpressionType::Integer && rhs == ExpressionType::Integer && !is_list { }
+        else if lhs == ExpressionType::Text && rhs == ExpressionType::Text && !is_list && operator == "+" { /* joining text */ }
+        else if is_list && lhs == rhs { /* either concatenating two lists or a single item to a list */ }
+        else {
+            // on the error path, kids
+            if lhs != rhs {
+                return Err(ParseExpressionError::MismatchedOperands);
+            }
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+
+        let operation = match (is_list, operator) {
+            (false, "+") => AdditiveOperation::Add,
+            (false, _) => AdditiveOperation::Subtract,
+            (true, "+") => AdditiveOperation::Concatenate,
+            (true, "+!") => AdditiveOperation::ConcatenateUnique,
+            (true, _) => AdditiveOperation::Except
+        };
+        let result_type = value.left.get_type();
+
+        Ok(AdditiveExpression { op: value, operation, result_type })
+    }
+}
+
+impl Expression for AdditiveExpression {
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Operation(self.op.operator_str().unwrap_or_default())
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.op.left.as_ref(), self.op.right.as_ref() ]
+    }
+
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let lhs = self.op.left.evaluate(symbol_table, context);
+        let rhs = self.op.right.evaluate(symbol_table, context);
+
+        if let ExpressionResult::Integer(l) = lhs {
+            if let ExpressionResult::Integer(r) = rhs {
+                if self.operation == AdditiveOperation::Subtract {
+                    return ExpressionResult::Integer(l - r);
+                }
+                return ExpressionResult::Integer(l + r);
+            }
+            panic!("Right-hand side did not evaluate to integer expression.")
+        } else if let ExpressionResult::Text(l) = lhs {
+            if let ExpressionResult::Text(r) = rhs {
+                return ExpressionResult::Text([ l.as_ref(), r.as_ref() ].concat().into());
+            }
+            panic!("Right-hand side did not evaluate to text expression.")
+        } else if let ExpressionResult::List(list) = lhs {
+            let other: Rc<[ExpressionResult]> = match rhs {
+                ExpressionResult::List(other) => other,
+                single => [ single ].into()
+            };
+            return match self.operation {
+                AdditiveOperation::Concatenate => ExpressionResult::List([ list, other ].concat().into()),
+                AdditiveOperation::ConcatenateUnique => {
+                    let mut unique: Vec<ExpressionResult> = vec![ ];
+                    for item in list.iter().chain(other.iter()) {
+                        if !unique.contains(item) {
+                            unique.push(item.clone());
+                        }
+                    }
+                    ExpressionResult::List(unique.into())
+                },
+                _ => ExpressionResult::List(list.iter().filter(|item| !other.contains(item)).cloned().collect())
+            };
+        }
+        // something is super messed up
+        panic!("Left-hand side did not evaluate to integer, text or list expression.");
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        self.result_type.clone()
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let AdditiveExpression { op, operation, result_type } = *self;
+        let op = op.simplify();
+        let zero = || ExpressionResult::Integer(0);
+        match operation {
+            AdditiveOperation::Add | AdditiveOperation::Subtract if op.is_right(zero()) => op.left,
+            AdditiveOperation::Add if op.is_left(zero()) => op.right,
+            _ => fold(Box::new(AdditiveExpression { op, operation, result_type }))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FactorExpression {
+    op: BinaryOperation,
+    is_division: bool
+}
+
+impl TryFrom<BinaryOperation> for FactorExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: BinaryOperation) -> Result<Self, Self::Error> {
+        let operator = value.operator_str()?;
+        if operator != "*" && operator != "/" {
+            return Err(ParseExpressionError::InvalidOperator);
+        }
+        if value.left.get_type() != ExpressionType::Integer || value.right.get_type() != ExpressionType::Integer {
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+        let is_division = operator == "/";
+
+        Ok(FactorExpression { op: value, is_division })
+    }
+}
+
+impl Expression for FactorExpression {
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Operation(self.op.operator_str().unwrap_or_default())
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.op.left.as_ref(), self.op.right.as_ref() ]
+    }
+
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let lhs = self.op.left.evaluate(symbol_table, context).expect_integer();
+        let rhs = self.op.right.evaluate(symbol_table, context).expect_integer();
+
+        if !self.is_division {
+            return ExpressionResult::Integer(lhs * rhs);
+        }
+        // dividing by zero leaves nothing to deal, so it's just zero
+        ExpressionResult::Integer(lhs.checked_div(rhs).unwrap_or(0))
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        ExpressionType::Integer
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let FactorExpression { op, is_division } = *self;
+        let op = op.simplify();
+        let (one, zero) = (|| ExpressionResult::Integer(1), || ExpressionResult::Integer(0));
+        if op.is_right(one()) {
+            return op.left;
+        }
+        if !is_division && op.is_left(one()) {
+            return op.right;
+        }
+        // times zero, zero divided by anything and anything divided by zero all come out as zero,
+        // but the other side still has to be evaluated if it rolls dice or picks targets
+        if (op.is_left(zero()) || op.is_right(zero())) && is_pure(op.left.as_ref()) && is_pure(op.right.as_ref()) {
+            return Box::new(LiteralExpression::new(zero()));
+        }
+        fold(Box::new(FactorExpression { op, is_division }))
+    }
+}
+
+#[derive(Debug)]
+pub struct ComparisonExpression {
+    op: BinaryOperation
+}
+
+impl TryFrom<BinaryOperation> for ComparisonExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: BinaryOperation) -> Result<Self, Self::Error> {
+        let operator = value.operator_str()?;
+        if ![ "<", ">", "<=", ">=" ].contains(&operator) {
+            return Err(ParseExpressionError::InvalidOperator);
+        }
+        if value.left.get_type() != ExpressionType::Integer || value.right.get_type() != ExpressionType::Integer {
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+
+        Ok(ComparisonExpression { op: value })
+    }
+}
+
+impl Expression for ComparisonExpression {
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Operation(self.op.operator_str().unwrap_or_default())
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.op.left.as_ref(), self.op.right.as_ref() ]
+    }
+
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let lhs = self.op.left.evaluate(symbol_table, context).expect_integer();
+        let rhs = self.op.right.evaluate(symbol_table, context).expect_integer();
+
+        let result = match self.op.operator_str() {
+            Ok("<") => lhs < rhs,
+            Ok(">") => lhs > rhs,
+            Ok("<=") => lhs <= rhs,
+            _ => lhs >= rhs
+        };
+        ExpressionResult::Boolean(result)
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        ExpressionType::Boolean
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        fold(Box::new(ComparisonExpression { op: self.op.simplify() }))
+    }
+}
+
+#[derive(Debug)]
+pub struct EqualityExpression {
+    op: BinaryOperation,
+    negated: bool
+}
+
+impl TryFrom<BinaryOperation> for EqualityExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: BinaryOperation) -> Result<Self, Self::Error> {
+        let operator = value.operator_str()?;
+        if operator != "==" && operator != "~=" {
+            return Err(ParseExpressionError::InvalidOperator);
+        }
+        if value.left.get_type() != value.right.get_type() {
+            return Err(ParseExpressionError::MismatchedOperands);
+        }
+        let negated = operator == "~=";
+
+        Ok(EqualityExpression { op: value, negated })
+    }
+}
+
+impl Expression for EqualityExpression {
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Operation(self.op.operator_str().unwrap_or_default())
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.op.left.as_ref(), self.op.right.as_ref() ]
+    }
+
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let lhs = self.op.left.evaluate(symbol_table, context);
+        let rhs = self.op.right.evaluate(symbol_table, context);
+
+        ExpressionResult::Boolean((lhs == rhs) != self.negated)
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        ExpressionType::Boolean
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        fold(Box::new(EqualityExpression { op: self.op.simplify(), negated: self.negated }))
+    }
+}
+
+#[derive(Debug)]
+pub struct LogicalExpression {
+    op: BinaryOperation,
+    is_and: bool
+}
+
+impl TryFrom<BinaryOperation> for LogicalExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: BinaryOperation) -> Result<Self, Self::Error> {
+        let operator = value.operator_str()?;
+        if operator != "&" && operator != "|" {
+            return Err(ParseExpressionError::InvalidOperator);
+        }
+        if value.left.get_type() != ExpressionType::Boolean || value.right.get_type() != ExpressionType::Boolean {
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+        let is_and = operator == "&";
+
+        Ok(LogicalExpression { op: value, is_and })
+    }
+}
+
+impl Expression for LogicalExpression {
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Operation(self.op.operator_str().unwrap_or_default())
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.op.left.as_ref(), self.op.right.as_ref() ]
+    }
+
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let lhs = self.op.left.evaluate(symbol_table, context).expect_boolean();
+
+        // short-circuit so that dice on the right-hand side aren't rolled for nothing
+        if lhs != self.is_and {
+            return ExpressionResult::Boolean(lhs);
+        }
+        self.op.right.evaluate(symbol_table, context)
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        ExpressionType::Boolean
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let LogicalExpression { op, is_and } = *self;
+        let op = op.simplify();
+        // `true & x`, `x & true`, `false | x` and `x | false` are all just x
+        if op.is_left(ExpressionResult::Boolean(is_and)) {
+            return op.right;
+        }
+        if op.is_right(ExpressionResult::Boolean(is_and)) {
+            return op.left;
+        }
+        // `x & false` and `x | true` don't depend on x, but it's still evaluated first
+        if op.is_right(ExpressionResult::Boolean(!is_and)) && is_pure(op.left.as_ref()) {
+            return op.right;
+        }
+        fold(Box::new(LogicalExpression { op, is_and }))
+    }
+}
+
+#[derive(Debug)]
+pub struct UnaryExpression {
+    op: UnaryOperation
+}
+
+impl TryFrom<UnaryOperation> for UnaryExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: UnaryOperation) -> Result<Self, Self::Error> {
+        let op: &str;
+        if let Tokens::Symbol(ref op_token) = value.operator {
+            op = op_token.as_str();
+        } else {
+            return Err(ParseExpressionError::InvalidOperator);
+        }
+        if op != "-" && op != "~" && op != "^" {
+            return Err(ParseExpressionError::InvalidOperator);
+        }
+        if (op == "-" || op == "^") && value.right.get_type() != ExpressionType::Integer {
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+        if op == "~" && value.right.get_type() != ExpressionType::Boolean {
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+
+        Ok(UnaryExpression { op: value })
+    }
+}
+
+impl Expression for UnaryExpression {
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        match &self.op.operator {
+            Tokens::Symbol(op) => ExpressionKind::Operation(op.as_str()),
+            _ => ExpressionKind::Operation("")
+        }
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.op.right.as_ref() ]
+    }
+
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let right = self.op.right.evaluate(symbol_table, context);
+        match &self.op.operator {
+            Tokens::Symbol(op) if op.as_str() == "-" => ExpressionResult::Integer(-right.expect_integer()),
+            Tokens::Symbol(op) if op.as_str() == "~" => ExpressionResult::Boolean(!right.expect_boolean()),
+            // ^ only marks an upper bound (e.g. "target(^2 in Player)"), so the value itself is unchanged
+            _ => right
+        }
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        self.op.right.get_type()
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let UnaryOperation { operator, right } = self.op;
+        fold(Box::new(UnaryExpression { op: UnaryOperation { operator, right: right.simplify() } }))
+    }
+}
+
+/// Literal value straight from a token, or worked out from constants before the script runs
+#[derive(Debug)]
+pub struct LiteralExpression {
+    value: ExpressionResult
+}
+
+impl LiteralExpression {
+    pub fn new(value: ExpressionResult) -> Self {
+        LiteralExpression { value }
+    }
+}
+
+impl TryFrom<Tokens> for LiteralExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: Tokens) -> Result<Self, Self::Error> {
+        let value = match value {
+            Tokens::Numeric(int_token) => ExpressionResult::Integer(int_token.get_value() as i32),
+            Tokens::Boolean(bool_token) => ExpressionResult::Boolean(bool_token.get_value()),
+            Tokens::DamageType(damage_type_token) => ExpressionResult::DamageType(damage_type_token.get_value()),
+            Tokens::Dice(dice_token) => ExpressionResult::Dice(dice_token.get_value()),
+            Tokens::String(ref string_token) => match string_token.get_plain_text() {
+                Some(text) => ExpressionResult::Text(Rc::from(text)),
+                // interpolated strings are an InterpolatedStringExpression
+                None => return Err(ParseExpressionError::OperandTypesNotSupported)
+            },
+            _ => return Err(ParseExpressionError::OperandTypesNotSupported)
+        };
+        Ok(LiteralExpression { value })
+    }
+}
+
+impl Expression for LiteralExpression {
+    fn evaluate(&self, _symbol_table: &SymbolTable, _context: &mut dyn ScriptContext) -> ExpressionResult {
+        self.value.clone()
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        self.value.get_type()
+    }
+
+    fn as_literal(&self) -> Option<&ExpressionResult> {
+        Some(&self.value)
+    }
+
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Literal(&self.value)
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        self
+    }
+}
+
+/// Reference to a variable, whose type was resolved when the script was parsed
+#[derive(Debug)]
+pub struct IdentifierExpression {
+    name: Rc<str>,
+    expression_type: ExpressionType
+}
+
+impl IdentifierExpression {
+    pub fn new(name: &str, expression_type: ExpressionType) -> Self {
+        IdentifierExpression { name: Rc::from(name), expression_type }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Expression for IdentifierExpression {
+    fn evaluate(&self, symbol_table: &SymbolTable, _context: &mut dyn ScriptContext) -> ExpressionResult {
+        match symbol_table.get_value(&self.name) {
+            Some(value) => value.clone(),
+            None => panic!("Variable '{}' was read before it was assigned.", self.name)
+        }
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        self.expression_type.clone()
+    }
+
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Variable(&self.name)
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        self
+    }
+}
+
+/// Dice roll such as `2d6`: rolls `count` dice and sums them
+#[derive(Debug)]
+pub struct DiceRollExpression {
+    count: u16,
+    dice: Dice
+}
+
+impl DiceRollExpression {
+    pub fn new(count: u16, dice: Dice) -> Self {
+        DiceRollExpression { count, dice }
+    }
+}
+
+impl Expression for DiceRollExpression {
+    fn evaluate(&self, _symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let total: i32 = (0 .. self.count).map(|_| context.roll(self.dice) as i32).sum();
+        ExpressionResult::Integer(total)
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        ExpressionType::Integer
+    }
+
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Roll
+    }
+
+    /// Rolls stay as they are, so that each one is still rolled every time
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        self
+    }
+}
+
+/// List literal such as `[fire, ice]`
+#[derive(Debug)]
+pub struct ListExpression {
+    items: Vec<Box<dyn Expression>>,
+    item_type: ExpressionType
+}
+
+impl TryFrom<Vec<Box<dyn Expression>>> for ListExpression {
+    type Error = ParseExpressionError;
+
+    fn try_from(value: Vec<Box<dyn Expression>>) -> Result<Self, Self::Error> {
+        let item_type = match value.first() {
+            Some(first) => first.get_type(),
+            // no way of knowing what an empty list holds
+            None => return Err(ParseExpressionError::OperandTypesNotSupported)
+        };
+        if value.iter().any(|item| item.get_type() != item_type) {
+            return Err(ParseExpressionError::MismatchedOperands);
+        }
+        Ok(ListExpression { items: value, item_type })
+    }
+}
+
+impl Expression for ListExpression {
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let items: Vec<ExpressionResult> = self.items.iter()
+            .map(|item| item.evaluate(symbol_table, context))
+            .collect();
+        ExpressionResult::List(items.into())
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        ExpressionType::List(Box::new(self.item_type.clone()))
+    }
+
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::List
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        self.items.iter().map(Box::as_ref).collect()
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let ListExpression { items, item_type } = *self;
+        let items = items.into_iter().map(|item| item.simplify()).collect();
+        fold(Box::new(ListExpression { items, item_type }))
+    }
+}
+
+/// `target(1 in Player)`: the player of the card chooses targets when this is evaluated
+#[derive(Debug)]
+pub struct TargetExpression {
+    count: Box<dyn Expression>,
+    up_to: bool,
+    /// Exactly one target is a single value, anything else is a list
+    single: bool,
+    target_type: ExpressionType
+}
+
+impl TargetExpression {
+    pub fn new(count: Box<dyn Expression>, up_to: bool, target_type: ExpressionType) -> Result<Self, ParseExpressionError> {
+        if count.get_type() != ExpressionType::Integer {
+            return Err(ParseExpressionError::OperandTypesNotSupported);
+        }
+        let single = !up_to && count.as_literal() == Some(&ExpressionResult::Integer(1));
+        Ok(TargetExpression { count, up_to, single, target_type })
+    }
+}
+
+impl Expression for TargetExpression {
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let count = self.count.evaluate(symbol_table, context).expect_integer().max(0) as u16;
+        let mut targets = context.choose_targets(&self.target_type, count, self.up_to);
+
+        if self.single {
+            return targets.pop().expect("Context must choose exactly one target.");
+        }
+        ExpressionResult::List(targets.into())
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        if self.single {
+            return self.target_type.clone();
+        }
+        ExpressionType::List(Box::new(self.target_type.clone()))
+    }
+
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Target
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.count.as_ref() ]
+    }
+
+    /// Whether there's a single target was settled by the parser, so a count that folds to 1 still gives a list
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let TargetExpression { count, up_to, single, target_type } = *self;
+        Box::new(TargetExpression { count: count.simplify(), up_to, single, target_type })
+    }
+}
+
+/// Piece of an interpolated string: either text or an expression to format
+#[derive(Debug)]
+pub enum InterpolationPart {
+    Text(Rc<str>),
+    Expression(Box<dyn Expression>)
+}
+
+/// `"Fireball hits {$.name} for {dmg}"`: any value can be interpolated
+#[derive(Debug)]
+pub struct InterpolatedStringExpression {
+    parts: Vec<InterpolationPart>
+}
+
+impl InterpolatedStringExpression {
+    pub fn new(parts: Vec<InterpolationPart>) -> Self {
+        InterpolatedStringExpression { parts }
+    }
+}
+
+impl Expression for InterpolatedStringExpression {
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let mut text = String::new();
+        for part in &self.parts {
+            match part {
+                InterpolationPart::Text(t) => text.push_str(t),
+                InterpolationPart::Expression(expression) => {
+                    let value = expression.evaluate(symbol_table, context);
+                    text.push_str(&format_for_text(&value, context));
+                }
+            }
+        }
+        ExpressionResult::Text(text.into())
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        ExpressionType::Text
+    }
+
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Interpolation
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        self.parts.iter()
+            .filter_map(|part| match part {
+                InterpolationPart::Expression(expression) => Some(expression.as_ref()),
+                InterpolationPart::Text(_) => None
+            })
+            .collect()
+    }
+
+    /// Constant parts become text, joined to the text around them
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let mut parts: Vec<InterpolationPart> = vec![ ];
+        for part in self.parts {
+            let part = match part {
+                InterpolationPart::Expression(expression) => {
+                    let expression = expression.simplify();
+                    match expression.as_literal() {
+                        // literals are never players, so they show up in text as they display
+                        Some(value) => InterpolationPart::Text(value.to_string().into()),
+                        None => InterpolationPart::Expression(expression)
+                    }
+                },
+                text => text
+            };
+            match (parts.last_mut(), part) {
+                (Some(InterpolationPart::Text(previous)), InterpolationPart::Text(text)) => *previous = [ previous.as_ref(), text.as_ref() ].concat().into(),
+                (_, part) => parts.push(part)
+            }
+        }
+        fold(Box::new(InterpolatedStringExpression { parts }))
+    }
+}
+
+/// Players show up by name in text; everything else uses its display form
+fn format_for_text(value: &ExpressionResult, context: &mut dyn ScriptContext) -> String {
+    match value {
+        ExpressionResult::Player(_) => format_for_text(&context.get_property(value, "name"), context),
+        ExpressionResult::List(items) => {
+            let items: Vec<String> = items.iter().map(|item| format_for_text(item, context)).collect();
+            format!("[{}]", items.join(", "))
+        },
+        _ => value.to_string()
+    }
+}
+
+/// `$.hp`: read a property of a player
+#[derive(Debug)]
+pub struct PropertyExpression {
+    owner: Box<dyn Expression>,
+    property: Rc<str>,
+    property_type: ExpressionType
+}
+
+impl PropertyExpression {
+    pub fn new(owner: Box<dyn Expression>, property: &str) -> Result<Self, ParseExpressionError> {
+        let property_type = get_property_type(&owner.get_type(), property)
+            .ok_or(ParseExpressionError::OperandTypesNotSupported)?;
+        Ok(PropertyExpression { owner, property: Rc::from(property), property_type })
+    }
+}
+
+impl Expression for PropertyExpression {
+    fn evaluate(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> ExpressionResult {
+        let owner = self.owner.evaluate(symbol_table, context);
+        context.get_property(&owner, &self.property)
+    }
+
+    fn get_type(&self) -> ExpressionType {
+        self.property_type.clone()
+    }
+
+    fn get_kind(&self) -> ExpressionKind<'_> {
+        ExpressionKind::Property
+    }
+
+    fn get_operands(&self) -> Vec<&dyn Expression> {
+        vec![ self.owner.as_ref() ]
+    }
+
+    fn simplify(self: Box<Self>) -> Box<dyn Expression> {
+        let PropertyExpression { owner, property, property_type } = *self;
+        Box::new(PropertyExpression { owner: owner.simplify(), property, property_type })
+    }
+}
+
+/// Properties that can be read with `.`, by the type that owns them
+pub fn get_property_type(owner: &ExpressionType, property: &str) -> Option<ExpressionType> {
+    match (owner, property) {
+        (ExpressionType::Player, "name") => Some(ExpressionType::Text),
+        (ExpressionType::Player, "hp") => Some(ExpressionType::Integer),
+        (ExpressionType::Player, "mana") => Some(ExpressionType::Integer),
+        _ => None
+    }
+}
@@ -0,0 +1,91 @@
+use crate::game_zones::types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+
+use super::expressions::{Expression, ExpressionKind, ExpressionResult, ExpressionType, LiteralExpression};
+use super::script_context::ScriptContext;
+use super::symbol_table::SymbolTable;
+
+/// The value of an expression that comes out the same every time: literals, and operations on them.
+/// Anything that reads a variable, rolls dice, picks targets or looks at the game is left alone,
+/// and so is integer arithmetic that would overflow.
+pub fn constant_value(expression: &dyn Expression) -> Option<ExpressionResult> {
+    let operands = expression.get_operands();
+    match expression.get_kind() {
+        ExpressionKind::Literal(value) => Some(value.clone()),
+        // `false & ...` and `true | ...` don't depend on what's on the right
+        ExpressionKind::Operation(operator @ ("&" | "|")) => match constant_value(operands[0])? {
+            ExpressionResult::Boolean(left) if left == (operator == "|") => Some(ExpressionResult::Boolean(left)),
+            _ => constant_value(operands[1])
+        },
+        kind @ (ExpressionKind::Operation(_) | ExpressionKind::List | ExpressionKind::Interpolation) => {
+            let values = operands.into_iter().map(constant_value).collect::<Option<Vec<ExpressionResult>>>()?;
+            let integer = ExpressionResult::Integer;
+            match (kind, values.as_slice()) {
+                (ExpressionKind::Operation("+"), &[ ExpressionResult::Integer(l), ExpressionResult::Integer(r) ]) => l.checked_add(r).map(integer),
+                (ExpressionKind::Operation("-"), &[ ExpressionResult::Integer(l), ExpressionResult::Integer(r) ]) => l.checked_sub(r).map(integer),
+                (ExpressionKind::Operation("*"), &[ ExpressionResult::Integer(l), ExpressionResult::Integer(r) ]) => l.checked_mul(r).map(integer),
+                (ExpressionKind::Operation("-"), &[ ExpressionResult::Integer(right) ]) => right.checked_neg().map(integer),
+                _ => Some(expression.evaluate(&SymbolTable::new(), &mut ConstantContext))
+            }
+        },
+        ExpressionKind::Variable(_) | ExpressionKind::Roll | ExpressionKind::Target | ExpressionKind::Property => None
+    }
+}
+
+/// Whether evaluating the expression can be skipped without anyone noticing: it rolls no dice and picks no targets
+pub fn is_pure(expression: &dyn Expression) -> bool {
+    !matches!(expression.get_kind(), ExpressionKind::Roll | ExpressionKind::Target)
+        && expression.get_operands().into_iter().all(is_pure)
+}
+
+/// A literal in place of an expression with a constant value; any other expression comes back as it was
+pub fn fold(expression: Box<dyn Expression>) -> Box<dyn Expression> {
+    if expression.as_literal().is_some() {
+        return expression;
+    }
+    match constant_value(expression.as_ref()) {
+        // an empty list doesn't know what it holds, so it keeps the type the parser worked out
+        Some(value) if value.get_type() == expression.get_type() => Box::new(LiteralExpression::new(value)),
+        _ => expression
+    }
+}
+
+/// Context for evaluating expressions made only of literals, which never need one
+pub struct ConstantContext;
+
+impl ScriptContext for ConstantContext {
+    fn roll(&mut self, _dice: Dice) -> u16 {
+        unreachable!("constant expressions don't roll dice")
+    }
+
+    fn choose_targets(&mut self, _target_type: &ExpressionType, _count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+        unreachable!("constant expressions don't choose targets")
+    }
+
+    fn deal_damage(&mut self, _amount: i32, _damage_type: DamageType, _target: &ExpressionResult) {
+        unreachable!("expressions don't deal damage")
+    }
+
+    fn apply_status(&mut self, _status: StatusKind, _turns: i32, _target: &ExpressionResult) {
+        unreachable!("expressions don't apply statuses")
+    }
+
+    fn add_modifier(&mut self, _value: ModifiedValue, _operation: ModifierOperation, _target: Option<&ExpressionResult>) {
+        unreachable!("expressions don't add modifiers")
+    }
+
+    fn add_replacement(&mut self, _event: ReplacedEvent, _outcome: ReplacementOutcome, _uses: Option<u16>) {
+        unreachable!("expressions don't add replacements")
+    }
+
+    fn get_property(&mut self, _target: &ExpressionResult, _property: &str) -> ExpressionResult {
+        unreachable!("constant expressions don't read properties")
+    }
+
+    fn log(&mut self, _message: &str) {
+        unreachable!("expressions don't log")
+    }
+
+    fn counter(&mut self) -> bool {
+        unreachable!("expressions don't counter cards")
+    }
+}
\ No newline at end of file
@@ -0,0 +1,367 @@
+use std::rc::Rc;
+
+use crate::game_zones::types::{DamageType, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+
+use super::{expressions::{Expression, ExpressionResult, LiteralExpression}, script_context::ScriptContext, symbol_table::SymbolTable};
+
+pub trait Statement: std::fmt::Debug {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext);
+    /// What kind of statement it is, for passes that look inside a card (such as lints)
+    fn get_kind(&self) -> StatementKind<'_>;
+    /// Expressions the statement evaluates itself (not those of the statements inside it), in order
+    fn get_expressions(&self) -> Vec<&dyn Expression>;
+    /// Simplify every expression in the statement (see `Expression::simplify`).
+    /// An `if` with a constant condition is replaced by the body that runs, which may be nothing at all.
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>>;
+}
+
+/// Kind of statement, from outside the tree
+#[derive(Debug, Clone, Copy)]
+pub enum StatementKind<'a> {
+    /// Name assigned to
+    Assignment(&'a str),
+    Damage(&'a DamageType),
+    /// The bodies run when the condition is true and when it's false
+    If(&'a [Box<dyn Statement>], &'a [Box<dyn Statement>]),
+    /// Any other effect on the game
+    Effect
+}
+
+/// `$x = expression;`
+#[derive(Debug)]
+pub struct AssignmentStatement {
+    name: Rc<str>,
+    value: Box<dyn Expression>,
+    /// First assignment to this name in its scope, so it has to be declared at runtime as well
+    declares: bool
+}
+
+impl AssignmentStatement {
+    pub fn new(name: &str, value: Box<dyn Expression>, declares: bool) -> Self {
+        AssignmentStatement { name: Rc::from(name), value, declares }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Statement for AssignmentStatement {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        let value = self.value.evaluate(symbol_table, context);
+        if self.declares {
+            symbol_table.declare(&self.name, self.value.get_type());
+        }
+        symbol_table.assign(&self.name, value)
+            .expect("Assignment was type-checked when the script was parsed.");
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::Assignment(&self.name)
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        vec![ self.value.as_ref() ]
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        let AssignmentStatement { name, value, declares } = *self;
+        vec![ Box::new(AssignmentStatement { name, value: value.simplify(), declares }) ]
+    }
+}
+
+/// `1d4 + 4 fire => $;`
+#[derive(Debug)]
+pub struct DamageStatement {
+    amount: Box<dyn Expression>,
+    damage_type: DamageType,
+    target: Box<dyn Expression>
+}
+
+impl DamageStatement {
+    pub fn new(amount: Box<dyn Expression>, damage_type: DamageType, target: Box<dyn Expression>) -> Self {
+        DamageStatement { amount, damage_type, target }
+    }
+}
+
+impl Statement for DamageStatement {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        let amount = self.amount.evaluate(symbol_table, context).expect_integer();
+        let target = self.target.evaluate(symbol_table, context);
+
+        if let ExpressionResult::List(targets) = target {
+            for target in targets.iter() {
+                context.deal_damage(amount, self.damage_type.clone(), target);
+            }
+        } else {
+            context.deal_damage(amount, self.damage_type.clone(), &target);
+        }
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::Damage(&self.damage_type)
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        vec![ self.amount.as_ref(), self.target.as_ref() ]
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        let DamageStatement { amount, damage_type, target } = *self;
+        vec![ Box::new(DamageStatement { amount: amount.simplify(), damage_type, target: target.simplify() }) ]
+    }
+}
+
+/// `apply burning 2 => $;`
+#[derive(Debug)]
+pub struct StatusStatement {
+    status: StatusKind,
+    turns: Box<dyn Expression>,
+    target: Box<dyn Expression>
+}
+
+impl StatusStatement {
+    pub fn new(status: StatusKind, turns: Box<dyn Expression>, target: Box<dyn Expression>) -> Self {
+        StatusStatement { status, turns, target }
+    }
+}
+
+impl Statement for StatusStatement {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        let turns = self.turns.evaluate(symbol_table, context).expect_integer();
+        let target = self.target.evaluate(symbol_table, context);
+
+        if let ExpressionResult::List(targets) = target {
+            for target in targets.iter() {
+                context.apply_status(self.status, turns, target);
+            }
+        } else {
+            context.apply_status(self.status, turns, &target);
+        }
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::Effect
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        vec![ self.turns.as_ref(), self.target.as_ref() ]
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        let StatusStatement { status, turns, target } = *self;
+        vec![ Box::new(StatusStatement { status, turns: turns.simplify(), target: target.simplify() }) ]
+    }
+}
+
+/// `modify fire damage + 2;` or `modify cost - 1 => target(1 in Player);`
+#[derive(Debug)]
+pub struct ModifierStatement {
+    value: ModifiedValue,
+    operation: fn(i32) -> ModifierOperation,
+    amount: Box<dyn Expression>,
+    target: Option<Box<dyn Expression>>
+}
+
+impl ModifierStatement {
+    pub fn new(value: ModifiedValue, operation: fn(i32) -> ModifierOperation, amount: Box<dyn Expression>, target: Option<Box<dyn Expression>>) -> Self {
+        ModifierStatement { value, operation, amount, target }
+    }
+}
+
+impl Statement for ModifierStatement {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        // the amount is fixed when the modifier is added, not every time it applies
+        let operation = (self.operation)(self.amount.evaluate(symbol_table, context).expect_integer());
+        let target = match &self.target {
+            Some(target) => target.evaluate(symbol_table, context),
+            None => return context.add_modifier(self.value.clone(), operation, None)
+        };
+
+        if let ExpressionResult::List(targets) = target {
+            for target in targets.iter() {
+                context.add_modifier(self.value.clone(), operation, Some(target));
+            }
+        } else {
+            context.add_modifier(self.value.clone(), operation, Some(&target));
+        }
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::Effect
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        let mut expressions = vec![ self.amount.as_ref() ];
+        expressions.extend(self.target.as_deref());
+        expressions
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        let ModifierStatement { value, operation, amount, target } = *self;
+        vec![ Box::new(ModifierStatement { value, operation, amount: amount.simplify(), target: target.map(|target| target.simplify()) }) ]
+    }
+}
+
+/// `instead next fire damage prevent;` or `instead discard exile;`
+#[derive(Debug)]
+pub struct ReplacementStatement {
+    event: ReplacedEvent,
+    outcome: ReplacementOutcome,
+    /// Player to redirect damage to, in place of the outcome
+    redirect: Option<Box<dyn Expression>>,
+    uses: Option<u16>
+}
+
+impl ReplacementStatement {
+    pub fn new(event: ReplacedEvent, outcome: ReplacementOutcome, uses: Option<u16>) -> Self {
+        ReplacementStatement { event, outcome, redirect: None, uses }
+    }
+
+    pub fn redirecting(event: ReplacedEvent, target: Box<dyn Expression>, uses: Option<u16>) -> Self {
+        ReplacementStatement { event, outcome: ReplacementOutcome::Prevent, redirect: Some(target), uses }
+    }
+}
+
+impl Statement for ReplacementStatement {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        let outcome = match &self.redirect {
+            Some(target) => match target.evaluate(symbol_table, context) {
+                ExpressionResult::Player(player) => ReplacementOutcome::Redirect(player),
+                other => panic!("Damage can only be redirected to a player, not {:?}", other)
+            },
+            None => self.outcome
+        };
+        context.add_replacement(self.event.clone(), outcome, self.uses);
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::Effect
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        self.redirect.as_deref().into_iter().collect()
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        let ReplacementStatement { event, outcome, redirect, uses } = *self;
+        vec![ Box::new(ReplacementStatement { event, outcome, redirect: redirect.map(|target| target.simplify()), uses }) ]
+    }
+}
+
+/// `log "Fireball hits {$.name}";`
+#[derive(Debug)]
+pub struct LogStatement {
+    message: Box<dyn Expression>
+}
+
+impl LogStatement {
+    pub fn new(message: Box<dyn Expression>) -> Self {
+        LogStatement { message }
+    }
+}
+
+impl Statement for LogStatement {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        let message = self.message.evaluate(symbol_table, context);
+        context.log(&message.to_string());
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::Effect
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        vec![ self.message.as_ref() ]
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        vec![ Box::new(LogStatement { message: self.message.simplify() }) ]
+    }
+}
+
+/// `counter;` removes the card this one was played in response to
+#[derive(Debug)]
+pub struct CounterStatement;
+
+impl Statement for CounterStatement {
+    fn execute(&self, _symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        context.counter();
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::Effect
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        vec![ ]
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        vec![ self ]
+    }
+}
+
+/// `if condition { ... } else { ... }`
+#[derive(Debug)]
+pub struct IfStatement {
+    condition: Box<dyn Expression>,
+    then_body: Vec<Box<dyn Statement>>,
+    else_body: Vec<Box<dyn Statement>>
+}
+
+impl IfStatement {
+    pub fn new(condition: Box<dyn Expression>, then_body: Vec<Box<dyn Statement>>, else_body: Vec<Box<dyn Statement>>) -> Self {
+        IfStatement { condition, then_body, else_body }
+    }
+}
+
+impl Statement for IfStatement {
+    fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        let body = if self.condition.evaluate(symbol_table, context).expect_boolean() {
+            &self.then_body
+        } else {
+            &self.else_body
+        };
+        execute_block(body, symbol_table, context);
+    }
+
+    fn get_kind(&self) -> StatementKind<'_> {
+        StatementKind::If(&self.then_body, &self.else_body)
+    }
+
+    fn get_expressions(&self) -> Vec<&dyn Expression> {
+        vec![ self.condition.as_ref() ]
+    }
+
+    fn simplify(self: Box<Self>) -> Vec<Box<dyn Statement>> {
+        let condition = self.condition.simplify();
+        let then_body = simplify_block(self.then_body);
+        let else_body = simplify_block(self.else_body);
+        let body = match condition.as_literal() {
+            Some(ExpressionResult::Boolean(true)) => then_body,
+            Some(ExpressionResult::Boolean(false)) => else_body,
+            _ => return vec![ Box::new(IfStatement { condition, then_body, else_body }) ]
+        };
+        // the body has its own scope, so it can only take the place of the if when it declares nothing
+        if body.iter().any(|statement| matches!(statement.get_kind(), StatementKind::Assignment(_))) {
+            let always = Box::new(LiteralExpression::new(ExpressionResult::Boolean(true)));
+            return vec![ Box::new(IfStatement { condition: always, then_body: body, else_body: vec![ ] }) ];
+        }
+        body
+    }
+}
+
+/// Run statements in their own scope
+pub fn execute_block(body: &[Box<dyn Statement>], symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+    symbol_table.push_scope();
+    for statement in body {
+        statement.execute(symbol_table, context);
+    }
+    symbol_table.pop_scope();
+}
+
+/// Simplify every statement of a block (see `Statement::simplify`)
+pub fn simplify_block(body: Vec<Box<dyn Statement>>) -> Vec<Box<dyn Statement>> {
+    body.into_iter().flat_map(|statement| statement.simplify()).collect()
+}
@@ -0,0 +1,200 @@
+//! `cargo bench --bench evaluation [-- [workload] [--iterations N] [--rounds N] [--seed N] [--simplify]]`:
+//! how long the interpreter takes over a few typical expressions and cards, on the `Ast` arena and on the boxed tree
+//! it replaced. Each parses the script with its own parser; then they take turns, round after round, keeping their
+//! fastest round, so that whatever else the machine is doing weighs on both alike.
+
+// kept whole, including what only the old lints and tools used
+#[allow(dead_code)]
+mod baseline;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use mage_duel::cli::arguments::Arguments;
+// the baseline's `crate::game_zones`
+use mage_duel::game_zones;
+use game_zones::dice_roller::DiceRoller;
+use game_zones::types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+use mage_duel::parsing::ast::Ast;
+use mage_duel::parsing::expressions::{ExpressionResult, ExpressionType};
+use mage_duel::parsing::optimizer;
+use mage_duel::parsing::parser::{parse_card, parse_expression};
+use mage_duel::parsing::script_context::ScriptContext;
+use mage_duel::parsing::symbol_table::SymbolTable;
+use mage_duel::parsing::tokenizer::tokenize;
+
+/// What's timed: lone expressions, and whole cards (cost, then body)
+const EXPRESSIONS: [ (&str, &str); 5 ] = [
+    ("arithmetic", "(2d6 + 3) * 2 - $turn / 2 > 7 & ~($mana == 0)"),
+    ("literals", "(1 + 2) * 3 - 4 / 2 + -5 * (6 - 7)"),
+    ("variables", "$turn * $mana - $discount > $mana + $turn | $turn == $mana"),
+    ("lists", "[fire, ice, fire] +! [lightning] - [ice] == [fire, lightning]"),
+    ("text", "\"turn {$turn}: {1d20 + $mana} against {[fire, ice]}\"")
+];
+const CARDS: [ (&str, &str); 2 ] = [
+    ("firebolt", "@name \"Firebolt\" [1 + $discount]: { $x = 1d10 + 2; if $x > 8 { $x = $x * 2; log \"critical {$x}\"; } $x fire => target(1 in Player); }"),
+    ("storm", "@name \"Storm\" [3]: { $hits = 0; if $turn > 2 | $mana >= 3 { $hits = 2d4; } else { $hits = 1; } $hits * 2 - 1 lightning => target(1 in Player); apply burning $hits / 2 + 1 => target(1 in Player); }")
+];
+
+fn main() -> Result<(), String> {
+    // cargo hands every bench `--bench`, which means nothing here
+    let args: Vec<String> = std::env::args().skip(1).filter(|arg| arg != "--bench").collect();
+    let arguments = Arguments::parse(&args, &[ "iterations", "rounds", "seed" ], &[ "simplify" ])?;
+    let filter = arguments.get_positional().first().map(String::as_str).unwrap_or_default();
+    let iterations: u32 = arguments.get_option("iterations", 200_000)?;
+    let rounds: u32 = arguments.get_option("rounds", 10)?;
+    let seed = arguments.get_option("seed", 0)?;
+    let simplify = arguments.has_switch("simplify");
+
+    let mut symbol_table = SymbolTable::with_built_ins();
+    for (name, value) in [ ("$turn", 4), ("$mana", 3), ("$discount", 0) ] {
+        symbol_table.assign(name, ExpressionResult::Integer(value)).expect("Built-ins are integers.");
+    }
+    let bench = Bench { iterations, rounds, seed };
+    println!("{:<12} {:>12} {:>12} {:>8}", "workload", "boxed", "arena", "change");
+
+    for (name, script) in EXPRESSIONS.into_iter().filter(|(name, _)| name.contains(filter)) {
+        let tokens = tokenize(script).map_err(|err| format!("{}: {}", name, err))?;
+        let mut tree = baseline::parser::parse_expression(tokens.clone().into_iter(), &symbol_table).map_err(|err| format!("{}: {}", name, err))?;
+        let mut ast = Ast::default();
+        let mut expression = parse_expression(tokens.into_iter(), &symbol_table, &mut ast).map_err(|err| format!("{}: {}", name, err))?;
+        if simplify {
+            tree = tree.simplify();
+            expression = optimizer::simplify(&mut ast, expression);
+        }
+
+        bench.compare(name,
+            |context| tree.evaluate(&symbol_table, context),
+            |context| ast.evaluate(expression, &symbol_table, context)
+        )?;
+    }
+
+    for (name, script) in CARDS.into_iter().filter(|(name, _)| name.contains(filter)) {
+        let tokens = tokenize(script).map_err(|err| format!("{}: {}", name, err))?;
+        let mut boxed_card = baseline::parser::parse_card(tokens.clone().into_iter()).map_err(|err| format!("{}: {}", name, err))?;
+        let mut card = parse_card(tokens.into_iter()).map_err(|err| format!("{}: {}", name, err))?;
+        if simplify {
+            boxed_card = boxed_card.simplified();
+            card = card.simplified();
+        }
+
+        bench.compare(name,
+            |context| {
+                let mut symbol_table = symbol_table.clone();
+                let cost = boxed_card.get_cost().evaluate(&symbol_table, context);
+                baseline::statements::execute_block(boxed_card.get_body(), &mut symbol_table, context);
+                cost
+            },
+            |context| {
+                let mut symbol_table = symbol_table.clone();
+                let cost = card.evaluate_cost(&symbol_table, context);
+                card.execute(&mut symbol_table, context);
+                ExpressionResult::Integer(cost)
+            }
+        )?;
+    }
+    Ok(())
+}
+
+/// Runs of a script that agree whenever the dice do
+const CHECKED_RUNS: u32 = 100;
+
+struct Bench {
+    iterations: u32,
+    rounds: u32,
+    seed: u64
+}
+
+impl Bench {
+    /// Make sure both trees do the same, then time them a round at a time, taking turns, and print each one's fastest round
+    fn compare(&self, name: &str, mut boxed: impl FnMut(&mut BenchContext) -> ExpressionResult, mut arena: impl FnMut(&mut BenchContext) -> ExpressionResult) -> Result<(), String> {
+        if self.record(&mut boxed) != self.record(&mut arena) {
+            return Err(format!("{}: the boxed tree and the arena don't agree", name));
+        }
+
+        let (mut boxed_context, mut arena_context) = (self.context(false), self.context(false));
+        let (mut boxed_seconds, mut arena_seconds) = (f64::MAX, f64::MAX);
+        for _ in 0 .. self.rounds {
+            boxed_seconds = boxed_seconds.min(self.time(|| black_box(boxed(&mut boxed_context))));
+            arena_seconds = arena_seconds.min(self.time(|| black_box(arena(&mut arena_context))));
+        }
+        let nanoseconds = |seconds: f64| seconds * 1e9 / self.iterations as f64;
+        println!("{:<12} {:>9.1} ns {:>9.1} ns {:>+7.1}%", name, nanoseconds(boxed_seconds), nanoseconds(arena_seconds), (arena_seconds / boxed_seconds - 1.0) * 100.0);
+        Ok(())
+    }
+
+    /// Everything a few runs evaluate to and do to the game
+    fn record(&self, run: &mut impl FnMut(&mut BenchContext) -> ExpressionResult) -> Vec<String> {
+        let mut context = self.context(true);
+        for _ in 0 .. CHECKED_RUNS {
+            let value = run(&mut context);
+            context.effects.push(value.to_string());
+        }
+        context.effects
+    }
+
+    fn time<T>(&self, mut run: impl FnMut() -> T) -> f64 {
+        let started = Instant::now();
+        for _ in 0 .. self.iterations {
+            run();
+        }
+        started.elapsed().as_secs_f64()
+    }
+
+    fn context(&self, recording: bool) -> BenchContext {
+        BenchContext { dice_roller: DiceRoller::new(self.seed), recording, effects: vec![ ] }
+    }
+}
+
+/// Rolls real dice, always targets the first player, and writes down what the script does to the game when `recording`
+struct BenchContext {
+    dice_roller: DiceRoller,
+    recording: bool,
+    effects: Vec<String>
+}
+
+impl BenchContext {
+    fn record(&mut self, effect: impl FnOnce() -> String) {
+        if self.recording {
+            self.effects.push(effect());
+        }
+    }
+}
+
+impl ScriptContext for BenchContext {
+    fn roll(&mut self, dice: Dice) -> u16 {
+        dice.roll(&mut self.dice_roller)
+    }
+
+    fn choose_targets(&mut self, _target_type: &ExpressionType, count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+        vec![ ExpressionResult::Player(0); count as usize ]
+    }
+
+    fn deal_damage(&mut self, amount: i32, damage_type: DamageType, _target: &ExpressionResult) {
+        self.record(|| format!("{} {}", amount, damage_type));
+    }
+
+    fn apply_status(&mut self, status: StatusKind, turns: i32, _target: &ExpressionResult) {
+        self.record(|| format!("{} {}", status, turns));
+    }
+
+    fn add_modifier(&mut self, value: ModifiedValue, operation: ModifierOperation, _target: Option<&ExpressionResult>) {
+        self.record(|| format!("{:?} {:?}", value, operation));
+    }
+
+    fn add_replacement(&mut self, event: ReplacedEvent, outcome: ReplacementOutcome, _uses: Option<u16>) {
+        self.record(|| format!("{:?} {:?}", event, outcome));
+    }
+
+    fn get_property(&mut self, _target: &ExpressionResult, _property: &str) -> ExpressionResult {
+        ExpressionResult::Integer(20)
+    }
+
+    fn log(&mut self, message: &str) {
+        self.record(|| message.to_string());
+    }
+
+    fn counter(&mut self) -> bool {
+        false
+    }
+}
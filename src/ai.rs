@@ -0,0 +1,5 @@
+pub mod targeting;
+pub mod mcts;
+pub mod greedy;
+mod mcts_tests;
+mod greedy_tests;
\ No newline at end of file
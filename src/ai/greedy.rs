@@ -0,0 +1,185 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::engine::controller::{Action, Controller};
+use crate::engine::game_state::GameState;
+use crate::game_zones::{types::Dice, zone::Zone};
+use crate::library::card_library::CardLibrary;
+use crate::parsing::expressions::ExpressionResult;
+
+use super::targeting::TargetOpponents;
+
+/// How much the greedy AI cares about each part of a position
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeuristicWeights {
+    /// Per point of damage an action is expected to deal to opponents
+    pub damage: f64,
+    /// Per point of our HP minus the opponents' HP
+    pub hp_difference: f64,
+    /// Per card in our hand minus the opponents' hands
+    pub card_advantage: f64,
+    /// For an action that wins the duel outright
+    pub win: f64
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        HeuristicWeights { damage: 1.0, hp_difference: 0.5, card_advantage: 0.25, win: 1000.0 }
+    }
+}
+
+#[derive(Debug)]
+pub enum WeightsErrorKind {
+    Io(std::io::Error),
+    /// Line isn't `<weight> <number>`
+    InvalidLine,
+    UnknownWeight(String)
+}
+
+#[derive(Debug)]
+pub struct WeightsError {
+    /// 1-based line of the file (0 when it's about the file as a whole)
+    pub line: usize,
+    pub kind: WeightsErrorKind
+}
+
+impl Display for WeightsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            WeightsErrorKind::Io(err) => write!(f, "{}", err),
+            WeightsErrorKind::InvalidLine => write!(f, "expected '<weight> <number>'"),
+            WeightsErrorKind::UnknownWeight(name) => write!(f, "unknown weight '{}'", name)
+        }
+    }
+}
+
+impl HeuristicWeights {
+    /// One `<weight> <number>` per line, with `//` comments; weights not given keep their default
+    pub fn parse(source: &str) -> Result<Self, WeightsError> {
+        let mut weights = HeuristicWeights::default();
+        for (index, content) in source.lines().enumerate() {
+            let line = index + 1;
+            let content = content.split("//").next().unwrap_or_default().trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            let parts: Vec<&str> = content.split_whitespace().collect();
+            let (name, value) = match parts.as_slice() {
+                &[ name, value ] => (name, value.parse::<f64>().map_err(|_| WeightsError { line, kind: WeightsErrorKind::InvalidLine })?),
+                _ => return Err(WeightsError { line, kind: WeightsErrorKind::InvalidLine })
+            };
+            match name {
+                "damage" => weights.damage = value,
+                "hp_difference" => weights.hp_difference = value,
+                "card_advantage" => weights.card_advantage = value,
+                "win" => weights.win = value,
+                _ => return Err(WeightsError { line, kind: WeightsErrorKind::UnknownWeight(name.to_string()) })
+            }
+        }
+        Ok(weights)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, WeightsError> {
+        let source = std::fs::read_to_string(path).map_err(|err| WeightsError { line: 0, kind: WeightsErrorKind::Io(err) })?;
+        HeuristicWeights::parse(&source)
+    }
+}
+
+/// Plays whichever action scores best right now, without looking further ahead.
+/// Each card is tried on copies of the duel, once with every die rolling low and once rolling high,
+/// so the two average out to the dice's expected value. Never concedes.
+pub struct GreedyController<'a> {
+    library: &'a CardLibrary,
+    weights: HeuristicWeights
+}
+
+impl<'a> GreedyController<'a> {
+    pub fn new(library: &'a CardLibrary, weights: HeuristicWeights) -> Self {
+        GreedyController { library, weights }
+    }
+
+    /// How much better (or worse) the position gets for the player with priority by taking the action.
+    /// Whatever is on the stack resolves in both cases, so a reaction is judged by how it changes that.
+    pub fn score(&self, state: &GameState, action: Action) -> f64 {
+        let instance = match action {
+            Action::PlayCard(instance) => instance,
+            // doing nothing is the baseline every card is compared to
+            _ => return 0.0
+        };
+        let player = state.get_priority_player();
+
+        let outcomes = [ low_roll as fn(Dice) -> u16, high_roll ].map(|roll| {
+            let mut baseline = state.clone();
+            baseline.fix_dice(roll);
+            let mut preview = baseline.clone();
+            if preview.cast(player, instance, self.library, &mut TargetOpponents).is_err() {
+                return f64::NEG_INFINITY;
+            }
+            baseline.resolve_stack(self.library, &mut TargetOpponents);
+            preview.resolve_stack(self.library, &mut TargetOpponents);
+
+            let damage = opponents_hp(&baseline, player) - opponents_hp(&preview, player);
+            self.weights.damage * damage as f64 + self.evaluate(&preview, player) - self.evaluate(&baseline, player)
+        });
+        (outcomes[0] + outcomes[1]) / 2.0
+    }
+
+    /// Weighted value of the position for the player
+    fn evaluate(&self, state: &GameState, player: usize) -> f64 {
+        if state.get_winner() == Some(player) {
+            return self.weights.win;
+        }
+        let my_hp = state.get_player(player).map(|p| p.get_hp().max(0)).unwrap_or_default();
+        let hand = |p: usize| state.get_players()[p].get_zones().get(Zone::Hand).len() as f64;
+        let opponents_hand: f64 = (0 .. state.get_players().len()).filter(|&p| p != player).map(hand).sum();
+
+        self.weights.hp_difference * (my_hp - opponents_hp(state, player)) as f64
+            + self.weights.card_advantage * (hand(player) - opponents_hand)
+    }
+}
+
+impl Controller for GreedyController<'_> {
+    fn choose_action(&mut self, state: &GameState, options: &[Action]) -> Action {
+        let mut best = (state.get_default_action(), 0.0);
+        for &action in options {
+            if matches!(action, Action::Concede | Action::EndTurn | Action::Pass) {
+                continue;
+            }
+            let score = self.score(state, action);
+            // ties go to whatever came first, and to doing nothing over a pointless play
+            if score > best.1 {
+                best = (action, score);
+            }
+        }
+        best.0
+    }
+
+    fn choose_targets(&mut self, state: &GameState, candidates: &[ExpressionResult], count: u16, up_to: bool) -> Vec<ExpressionResult> {
+        TargetOpponents.choose_targets(state, candidates, count, up_to)
+    }
+}
+
+/// Total HP of everyone but the player, not counting anything below zero
+fn opponents_hp(state: &GameState, player: usize) -> i32 {
+    state.get_players().iter().enumerate()
+        .filter(|&(p, _)| p != player)
+        .map(|(_, p)| p.get_hp().max(0))
+        .sum()
+}
+
+/// A die's average roll, rounded down
+fn low_roll(dice: Dice) -> u16 {
+    let sides = dice.get_sides() as u16;
+    sides / 2 + sides % 2
+}
+
+/// A die's average roll, rounded up
+fn high_roll(dice: Dice) -> u16 {
+    let sides = dice.get_sides() as u16;
+    if sides == 0 {
+        return 0;
+    }
+    sides / 2 + 1
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use crate::ai::greedy::{GreedyController, HeuristicWeights, WeightsErrorKind};
+    use crate::ai::mcts::{MctsConfig, MctsController, SearchBudget};
+    use crate::engine::controller::{Action, Controller, RandomController};
+    use crate::engine::duel::DuelConfig;
+    use crate::engine::game_state::GameState;
+    use crate::engine::mana::ManaPool;
+    use crate::engine::player::Player;
+    use crate::engine::simulator::{factory, simulate, Simulation};
+    use crate::game_zones::dice_roller::DiceRoller;
+    use crate::game_zones::zone::Zone;
+    use crate::library::card_library::CardLibrary;
+    use crate::library::deck_list::DeckList;
+    use crate::library::test_library::{library, rules};
+    use test_case::test_case;
+
+    const SCRIPTS: [ (&str, &str); 4 ] = [
+        ("bolt.card", "@name \"Bolt\" [2]: { 5 lightning => target(1 in Player); }"),
+        ("fireball.card", "@name \"Fireball\" [2]: { 2d6 fire => target(1 in Player); }"),
+        ("spark.card", "@name \"Spark\" [1]: { 1d8 fire => target(1 in Player); }"),
+        ("fizzle.card", "@name \"Fizzle\" [0]: { log \"nothing happens\"; }")
+    ];
+
+    /// Player 0's turn with every card of the deck list in hand
+    fn holding(library: &CardLibrary, deck_list: &str, opponent_hp: i32) -> GameState {
+        let players = vec![ Player::new("Alice", 20, ManaPool::new(2, 1, 10)), Player::new("Bob", opponent_hp, ManaPool::new(2, 1, 10)) ];
+        let mut state = GameState::new(players, DiceRoller::new(3));
+        let deck = DeckList::parse(deck_list).unwrap().validate(library, &rules()).unwrap();
+        state.add_deck(0, &deck);
+        for _ in 0 .. deck.len() {
+            state.draw(0);
+        }
+        state.start_turn(library);
+        state
+    }
+
+    fn choice(library: &CardLibrary, state: &GameState, weights: HeuristicWeights) -> String {
+        let options = state.get_legal_actions(library);
+        match GreedyController::new(library, weights).choose_action(state, &options) {
+            Action::PlayCard(instance) => library.get(state.get_instance(instance).unwrap().card).unwrap().get_name().to_string(),
+            other => format!("{:?}", other)
+        }
+    }
+
+    #[test]
+    fn prefers_more_expected_damage() {
+        let library = library(&SCRIPTS);
+        // 2d6 averages 7, which beats a flat 5
+        let state = holding(&library, "1 Bolt\n1 Fireball", 20);
+        assert_eq!(choice(&library, &state, HeuristicWeights::default()), "Fireball");
+    }
+
+    #[test]
+    fn takes_the_sure_win() {
+        let library = library(&SCRIPTS);
+        // Spark only finishes the opponent off on a good roll; Bolt always does
+        let state = holding(&library, "1 Bolt\n1 Spark", 5);
+        assert_eq!(choice(&library, &state, HeuristicWeights::default()), "Bolt");
+    }
+
+    #[test]
+    fn pointless_cards_stay_in_hand() {
+        let library = library(&SCRIPTS);
+        let state = holding(&library, "1 Fizzle", 20);
+        assert_eq!(choice(&library, &state, HeuristicWeights::default()), "EndTurn");
+    }
+
+    #[test]
+    fn weights_change_the_choice() {
+        let library = library(&SCRIPTS);
+        let state = holding(&library, "1 Fizzle", 20);
+        let weights = HeuristicWeights { card_advantage: -1.0, ..HeuristicWeights::default() };
+        assert_eq!(choice(&library, &state, weights), "Fizzle");
+    }
+
+    #[test]
+    fn expected_damage_uses_dice_averages() {
+        let library = library(&SCRIPTS);
+        let state = holding(&library, "1 Fireball", 20);
+        let instance = state.get_player(0).unwrap().get_zones().get(Zone::Hand)[0];
+        let weights = HeuristicWeights { damage: 1.0, hp_difference: 0.0, card_advantage: 0.0, win: 0.0 };
+
+        let score = GreedyController::new(&library, weights).score(&state, Action::PlayCard(instance));
+
+        assert_eq!(score, 7.0);
+    }
+
+    #[test]
+    fn parse_weights() {
+        let weights = HeuristicWeights::parse("// aggressive\ndamage 2\n\nwin 50 // enough\n").unwrap();
+        assert_eq!(weights, HeuristicWeights { damage: 2.0, win: 50.0, ..HeuristicWeights::default() });
+    }
+
+    #[test_case("damage", 1; "missing value")]
+    #[test_case("damage 1\ndamage lots", 2; "not a number")]
+    #[test_case("\nluck 3", 2; "unknown weight")]
+    fn invalid_weights(source: &str, line: usize) {
+        let error = HeuristicWeights::parse(source).unwrap_err();
+        assert_eq!(error.line, line);
+        assert!(!matches!(error.kind, WeightsErrorKind::Io(_)));
+    }
+
+    #[test]
+    fn beats_random_play_and_guides_playouts() {
+        let library = library(&SCRIPTS);
+        let simulation = Simulation {
+            deck_lists: [ String::from("10 Fireball\n5 Bolt\n5 Fizzle"), String::from("10 Fireball\n5 Bolt\n5 Fizzle") ],
+            games: 10,
+            seed: 1,
+            config: DuelConfig::default(),
+            threads: 1
+        };
+        let greedy = factory(|library, _seed| Box::new(GreedyController::new(library, HeuristicWeights::default())));
+        let random = factory(|_library, seed| Box::new(RandomController::new(seed)));
+        let guided = factory(|library, seed| {
+            let config = MctsConfig { budget: SearchBudget::Iterations(20), seed, ..MctsConfig::default() };
+            Box::new(MctsController::new(library, config).with_playout(Box::new(GreedyController::new(library, HeuristicWeights::default()))))
+        });
+
+        let report = simulate(&library, &simulation, &rules(), [ &greedy, &random ]).unwrap();
+        assert!(report.wins[0] > report.wins[1] * 2, "{}", report);
+
+        let report = simulate(&library, &simulation, &rules(), [ &guided, &random ]).unwrap();
+        assert!(report.wins[0] > report.wins[1] * 2, "{}", report);
+    }
+}
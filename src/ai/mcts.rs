@@ -0,0 +1,240 @@
+use std::time::{Duration, Instant};
+
+use crate::engine::controller::{Action, Controller, RandomController};
+use crate::engine::game_state::GameState;
+use crate::engine::player::Player;
+use crate::game_zones::{dice_roller::DiceRoller, zone::Zone};
+use crate::library::card_library::CardLibrary;
+use crate::parsing::expressions::ExpressionResult;
+
+use super::targeting::TargetOpponents;
+
+/// How long the search may think about each decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchBudget {
+    /// Fixed number of playouts; the same seed always gives the same choice
+    Iterations(u32),
+    /// Wall-clock time per decision
+    Time(Duration)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MctsConfig {
+    pub budget: SearchBudget,
+    /// UCB1 exploration constant; higher tries more unlikely actions
+    pub exploration: f64,
+    /// Playouts stop after this many turns and score the position by remaining HP
+    pub playout_turns: u16,
+    pub seed: u64
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig { budget: SearchBudget::Iterations(500), exploration: std::f64::consts::SQRT_2, playout_turns: 20, seed: 0 }
+    }
+}
+
+/// Search tree node: an action and what was learned by playing it
+struct Node {
+    action: Option<Action>,
+    /// Player who chose the action
+    player: usize,
+    visits: u32,
+    /// Sum of the rewards for `player`
+    reward: f64,
+    /// Times the action was legal when its parent was visited; with hidden information, not every action always is
+    available: u32,
+    children: Vec<usize>
+}
+
+/// Monte Carlo Tree Search over the duel.
+/// The opponent's hand and deck, the order of our own deck and future dice rolls are hidden,
+/// so every iteration searches a different guess at them (a determinization) and the tree is shared between guesses.
+/// Never concedes.
+pub struct MctsController<'a> {
+    library: &'a CardLibrary,
+    config: MctsConfig,
+    roller: DiceRoller,
+    /// Plays both sides once a playout leaves the tree
+    playout: Box<dyn Controller + 'a>
+}
+
+impl<'a> MctsController<'a> {
+    pub fn new(library: &'a CardLibrary, config: MctsConfig) -> Self {
+        let roller = DiceRoller::new(config.seed);
+        let playout = Box::new(RandomController::new(config.seed.wrapping_add(1)));
+        MctsController { library, config, roller, playout }
+    }
+
+    /// Use another controller (e.g. a heuristic one) to finish playouts instead of random play
+    pub fn with_playout(mut self, playout: Box<dyn Controller + 'a>) -> Self {
+        self.playout = playout;
+        self
+    }
+
+    fn search(&mut self, state: &GameState) -> Option<Action> {
+        let me = state.get_priority_player();
+        let mut nodes = vec![ Node { action: None, player: me, visits: 0, reward: 0.0, available: 0, children: vec![ ] } ];
+        let started = Instant::now();
+        let mut iterations = 0;
+
+        while match self.config.budget {
+            SearchBudget::Iterations(limit) => iterations < limit,
+            SearchBudget::Time(limit) => iterations == 0 || started.elapsed() < limit
+        } {
+            iterations += 1;
+            let mut sample = determinize(state, me, &mut self.roller);
+            let horizon = state.get_turn().saturating_add(self.config.playout_turns);
+            let path = self.select_and_expand(&mut nodes, &mut sample, horizon);
+            self.play_out(&mut sample, horizon);
+            for node in path {
+                let reward = evaluate(&sample, nodes[node].player);
+                nodes[node].visits += 1;
+                nodes[node].reward += reward;
+            }
+        }
+
+        // the most visited action is the most trusted one
+        nodes[0].children.iter()
+            .max_by_key(|&&child| nodes[child].visits)
+            .and_then(|&child| nodes[child].action)
+    }
+
+    /// Walk down the tree by UCB1 until reaching an action not tried yet, and play every action on the way
+    fn select_and_expand(&mut self, nodes: &mut Vec<Node>, sample: &mut GameState, horizon: u16) -> Vec<usize> {
+        let mut path = vec![ 0 ];
+        let mut current = 0;
+
+        while !sample.is_over() && sample.get_turn() < horizon {
+            let player = sample.get_priority_player();
+            let options = searched_actions(sample, self.library);
+            let available: Vec<usize> = nodes[current].children.iter().copied()
+                .filter(|&child| nodes[child].action.is_some_and(|a| options.contains(&a)))
+                .collect();
+            for &child in &available {
+                nodes[child].available += 1;
+            }
+
+            let untried: Vec<Action> = options.iter().copied()
+                .filter(|&a| !available.iter().any(|&child| nodes[child].action == Some(a)))
+                .collect();
+            let next = if !untried.is_empty() {
+                let action = untried[self.roller.next_below(untried.len() as u64) as usize];
+                nodes.push(Node { action: Some(action), player, visits: 0, reward: 0.0, available: 1, children: vec![ ] });
+                let child = nodes.len() - 1;
+                nodes[current].children.push(child);
+                child
+            } else {
+                match self.best_child(nodes, &available) {
+                    Some(child) => child,
+                    None => break
+                }
+            };
+
+            let action = nodes[next].action.expect("Only the root has no action.");
+            apply_action(sample, action, self.library, &mut TargetOpponents);
+            path.push(next);
+            current = next;
+            if !untried.is_empty() {
+                break;
+            }
+        }
+        path
+    }
+
+    fn best_child(&self, nodes: &[Node], children: &[usize]) -> Option<usize> {
+        let ucb = |child: usize| {
+            let node = &nodes[child];
+            let visits = node.visits.max(1) as f64;
+            node.reward / visits + self.config.exploration * ((node.available.max(1) as f64).ln() / visits).sqrt()
+        };
+        children.iter().copied().max_by(|&a, &b| ucb(a).total_cmp(&ucb(b)))
+    }
+
+    fn play_out(&mut self, sample: &mut GameState, horizon: u16) {
+        while !sample.is_over() && sample.get_turn() < horizon {
+            let options = sample.get_legal_actions(self.library);
+            let action = self.playout.choose_action(sample, &options);
+            let action = if options.contains(&action) { action } else { sample.get_default_action() };
+            apply_action(sample, action, self.library, self.playout.as_mut());
+        }
+    }
+}
+
+impl Controller for MctsController<'_> {
+    fn choose_action(&mut self, state: &GameState, options: &[Action]) -> Action {
+        let candidates: Vec<Action> = options.iter().copied().filter(|&a| a != Action::Concede).collect();
+        if candidates.len() <= 1 {
+            return candidates.first().copied().unwrap_or(state.get_default_action());
+        }
+        self.search(state)
+            .filter(|action| candidates.contains(action))
+            .unwrap_or(state.get_default_action())
+    }
+
+    fn choose_targets(&mut self, state: &GameState, candidates: &[ExpressionResult], count: u16, up_to: bool) -> Vec<ExpressionResult> {
+        TargetOpponents.choose_targets(state, candidates, count, up_to)
+    }
+}
+
+/// A copy of the duel as `observer` might imagine it: what they can't see is shuffled and the dice are reseeded
+pub fn determinize(state: &GameState, observer: usize, roller: &mut DiceRoller) -> GameState {
+    let mut sample = state.clone();
+    for player in 0 .. sample.get_players().len() {
+        let zones = sample.get_player_mut(player).expect("Player index is in range.").get_zones_mut();
+        // we know which cards are in our own hand, but not the order of our deck
+        let hidden_zones: &[Zone] = if player == observer { &[ Zone::Deck ] } else { &[ Zone::Hand, Zone::Deck ] };
+        let mut hidden: Vec<_> = hidden_zones.iter().flat_map(|&zone| zones.get(zone).to_vec()).collect();
+        for i in (1 .. hidden.len()).rev() {
+            let j = roller.next_below(i as u64 + 1) as usize;
+            hidden.swap(i, j);
+        }
+        for &zone in hidden_zones {
+            let size = zones.get(zone).len();
+            *zones.get_mut(zone) = hidden.drain(.. size).collect();
+        }
+    }
+    sample.reseed_dice(roller.next_u64());
+    sample
+}
+
+/// Actions worth searching: conceding never is
+fn searched_actions(state: &GameState, library: &CardLibrary) -> Vec<Action> {
+    state.get_legal_actions(library).into_iter().filter(|&a| a != Action::Concede).collect()
+}
+
+/// Carry out an action of the player with priority the way a duel would, with `controller` choosing for everyone
+fn apply_action(state: &mut GameState, action: Action, library: &CardLibrary, controller: &mut dyn Controller) {
+    let player = state.get_priority_player();
+    match action {
+        Action::PlayCard(instance) => {
+            let _ = state.cast(player, instance, library, controller);
+        },
+        Action::Pass => state.pass_priority(),
+        Action::EndTurn => {
+            state.end_turn();
+            state.start_turn(library);
+        },
+        Action::Concede => state.concede(player)
+    }
+    while state.is_ready_to_resolve() && !state.is_over() {
+        state.resolve_top(library, controller);
+    }
+}
+
+/// 1 for a win, 0 for a loss, and each player's share of the HP left when nobody has won yet
+fn evaluate(state: &GameState, player: usize) -> f64 {
+    if state.is_over() {
+        return match state.get_winner() {
+            Some(winner) if winner == player => 1.0,
+            Some(_) => 0.0,
+            None => 0.5
+        };
+    }
+    let hp = |p: &Player| p.get_hp().max(0) as f64;
+    let total: f64 = state.get_players().iter().map(hp).sum();
+    if total == 0.0 {
+        return 0.5;
+    }
+    hp(&state.get_players()[player]) / total
+}
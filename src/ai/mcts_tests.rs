@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use crate::ai::mcts::{determinize, MctsConfig, MctsController, SearchBudget};
+    use crate::engine::controller::{Action, Controller, RandomController};
+    use crate::engine::duel::DuelConfig;
+    use crate::engine::game_state::GameState;
+    use crate::engine::mana::ManaPool;
+    use crate::engine::player::Player;
+    use crate::engine::simulator::{factory, simulate, Simulation};
+    use crate::game_zones::dice_roller::DiceRoller;
+    use crate::game_zones::zone::Zone;
+    use crate::library::card_library::CardLibrary;
+    use crate::library::deck_list::DeckList;
+    use crate::library::test_library::{library, rules};
+    use test_case::test_case;
+
+    const SCRIPTS: [ (&str, &str); 3 ] = [
+        ("bolt.card", "@name \"Bolt\" [2]: { 5 lightning => target(1 in Player); }"),
+        ("spark.card", "@name \"Spark\" [1]: { 1d4 fire => target(1 in Player); }"),
+        ("fizzle.card", "@name \"Fizzle\" [0]: { log \"nothing happens\"; }")
+    ];
+
+    /// Player 0's turn with Bolt and Fizzle in hand, while player 1 is on 3 HP
+    fn lethal_on_board(library: &CardLibrary) -> GameState {
+        let players = vec![ Player::new("Alice", 20, ManaPool::new(2, 1, 10)), Player::new("Bob", 20, ManaPool::new(2, 1, 10)) ];
+        let mut state = GameState::new(players, DiceRoller::new(3));
+        state.add_deck(0, &DeckList::parse("1 Bolt\n1 Fizzle").unwrap().validate(library, &rules()).unwrap());
+        state.add_deck(1, &DeckList::parse("5 Spark\n5 Bolt").unwrap().validate(library, &rules()).unwrap());
+        for player in [ 0, 0, 1, 1, 1 ] {
+            state.draw(player);
+        }
+        state.start_turn(library);
+        state.get_player_mut(1).unwrap().take_damage(17);
+        state
+    }
+
+    fn mcts(library: &CardLibrary, iterations: u32, seed: u64) -> MctsController<'_> {
+        MctsController::new(library, MctsConfig { budget: SearchBudget::Iterations(iterations), seed, ..MctsConfig::default() })
+    }
+
+    fn card_name(library: &CardLibrary, state: &GameState, action: Action) -> String {
+        match action {
+            Action::PlayCard(instance) => library.get(state.get_instance(instance).unwrap().card).unwrap().get_name().to_string(),
+            other => format!("{:?}", other)
+        }
+    }
+
+    #[test_case(1)]
+    #[test_case(2)]
+    #[test_case(3)]
+    fn finds_lethal(seed: u64) {
+        let library = library(&SCRIPTS);
+        let state = lethal_on_board(&library);
+        let options = state.get_legal_actions(&library);
+
+        let action = mcts(&library, 200, seed).choose_action(&state, &options);
+
+        assert_eq!(card_name(&library, &state, action), "Bolt");
+    }
+
+    #[test]
+    fn same_seed_same_choice() {
+        let library = library(&SCRIPTS);
+        let state = lethal_on_board(&library);
+        let options = state.get_legal_actions(&library);
+
+        let first = mcts(&library, 50, 9).choose_action(&state, &options);
+        let second = mcts(&library, 50, 9).choose_action(&state, &options);
+
+        assert_eq!(first, second);
+        assert_ne!(first, Action::Concede);
+    }
+
+    #[test]
+    fn determinization_only_hides_what_is_hidden() {
+        let library = library(&SCRIPTS);
+        let state = lethal_on_board(&library);
+        let sorted = |state: &GameState, player: usize, zones: &[Zone]| {
+            let mut ids: Vec<u32> = zones.iter().flat_map(|&z| state.get_player(player).unwrap().get_zones().get(z).to_vec()).map(|id| id.0).collect();
+            ids.sort();
+            ids
+        };
+
+        let mut roller = DiceRoller::new(5);
+        for _ in 0 .. 10 {
+            let sample = determinize(&state, 0, &mut roller);
+            let (mine, theirs) = (sample.get_player(0).unwrap(), sample.get_player(1).unwrap());
+
+            assert_eq!(mine.get_zones().get(Zone::Hand), state.get_player(0).unwrap().get_zones().get(Zone::Hand));
+            assert_eq!(sorted(&sample, 0, &[ Zone::Deck ]), sorted(&state, 0, &[ Zone::Deck ]));
+            assert_eq!(theirs.get_zones().get(Zone::Hand).len(), 3);
+            assert_eq!(sorted(&sample, 1, &[ Zone::Hand, Zone::Deck ]), sorted(&state, 1, &[ Zone::Hand, Zone::Deck ]));
+            assert_eq!(sample.get_players()[1].get_hp(), 3);
+            assert_ne!(sample.get_dice_roller(), state.get_dice_roller());
+        }
+    }
+
+    #[test]
+    fn beats_random_play() {
+        let library = library(&SCRIPTS);
+        let simulation = Simulation {
+            deck_lists: [ String::from("10 Spark\n5 Bolt\n5 Fizzle"), String::from("10 Spark\n5 Bolt\n5 Fizzle") ],
+            games: 10,
+            seed: 1,
+            config: DuelConfig::default(),
+            threads: 1
+        };
+        let mcts = factory(|library, seed| Box::new(mcts(library, 30, seed)));
+        let random = factory(|_library, seed| Box::new(RandomController::new(seed)));
+
+        let report = simulate(&library, &simulation, &rules(), [ &mcts, &random ]).unwrap();
+
+        assert!(report.wins[0] > report.wins[1] * 2, "{}", report);
+    }
+}
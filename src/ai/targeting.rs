@@ -0,0 +1,36 @@
+use crate::engine::controller::{Action, Controller};
+use crate::engine::game_state::GameState;
+use crate::parsing::expressions::ExpressionResult;
+
+/// Aims at the weakest opponents first, and only at ourselves when it has to
+pub struct TargetOpponents;
+
+impl Controller for TargetOpponents {
+    fn choose_action(&mut self, _state: &GameState, _options: &[Action]) -> Action {
+        Action::EndTurn
+    }
+
+    fn choose_targets(&mut self, state: &GameState, candidates: &[ExpressionResult], count: u16, up_to: bool) -> Vec<ExpressionResult> {
+        let me = state.get_deciding_player();
+        let mut opponents: Vec<&ExpressionResult> = candidates.iter()
+            .filter(|c| !matches!(c, ExpressionResult::Player(p) if *p == me))
+            .collect();
+        opponents.sort_by_key(|c| match c {
+            ExpressionResult::Player(p) => state.get_player(*p).map(|p| p.get_hp()).unwrap_or(i32::MAX),
+            _ => i32::MAX
+        });
+        let mut targets: Vec<ExpressionResult> = opponents.into_iter().take(count as usize).cloned().collect();
+        if !up_to {
+            // the rest has to be made up from whatever is left
+            for candidate in candidates {
+                if targets.len() >= count as usize {
+                    break;
+                }
+                if !targets.contains(candidate) {
+                    targets.push(candidate.clone());
+                }
+            }
+        }
+        targets
+    }
+}
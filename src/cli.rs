@@ -0,0 +1,11 @@
+pub mod arguments;
+pub mod simulate;
+pub mod repl;
+pub mod check;
+pub mod lsp;
+pub mod fmt;
+pub mod catalog;
+mod arguments_tests;
+mod repl_tests;
+mod check_tests;
+mod fmt_tests;
\ No newline at end of file
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Command-line arguments for a subcommand: positional values, `--name value` options and `--name` switches
+#[derive(Debug, Default)]
+pub struct Arguments {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    switches: Vec<String>
+}
+
+impl Arguments {
+    /// `options` are the flags that take a value and `switches` the ones that don't; any other flag is an error
+    pub fn parse(args: &[String], options: &[&str], switches: &[&str]) -> Result<Self, String> {
+        let mut arguments = Arguments::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if options.contains(&name) => {
+                    let value = args.next().ok_or(format!("--{} needs a value", name))?;
+                    arguments.options.insert(name.to_string(), value.clone());
+                },
+                Some(name) if switches.contains(&name) => arguments.switches.push(name.to_string()),
+                Some(name) => return Err(format!("unknown flag --{}", name)),
+                None => arguments.positional.push(arg.clone())
+            }
+        }
+        Ok(arguments)
+    }
+
+    pub fn get_positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// Value of `--name`, or the default when it wasn't given
+    pub fn get_option<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.options.get(name) {
+            Some(value) => value.parse().map_err(|_| format!("invalid value for --{}: '{}'", name, value)),
+            None => Ok(default)
+        }
+    }
+
+    pub fn has_switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+}
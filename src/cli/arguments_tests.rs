@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::arguments::Arguments;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn positional_options_and_switches() {
+        let arguments = Arguments::parse(&args("cards --games 10 a.deck --json b.deck"), &[ "games" ], &[ "json" ]).unwrap();
+
+        assert_eq!(arguments.get_positional(), &args("cards a.deck b.deck"));
+        assert_eq!(arguments.get_option("games", 1).unwrap(), 10);
+        assert_eq!(arguments.get_option("seed", 7).unwrap(), 7);
+        assert!(arguments.has_switch("json"));
+        assert!(!arguments.has_switch("games"));
+    }
+
+    #[test]
+    fn invalid_option() {
+        let arguments = Arguments::parse(&args("--games many"), &[ "games" ], &[ ]).unwrap();
+        assert!(arguments.get_option("games", 1).is_err());
+        assert!(Arguments::parse(&args("--games"), &[ "games" ], &[ ]).is_err());
+    }
+
+    #[test]
+    fn unknown_flag() {
+        let error = Arguments::parse(&args("cards --thread 4"), &[ "threads" ], &[ "json" ]).unwrap_err();
+        assert_eq!(error, "unknown flag --thread");
+        assert!(Arguments::parse(&args("cards --jsn"), &[ "threads" ], &[ "json" ]).is_err());
+    }
+}
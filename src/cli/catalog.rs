@@ -0,0 +1,27 @@
+use std::path::Path;
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::library::card_library::CardLibrary;
+use crate::library::catalog::catalog;
+
+use super::arguments::Arguments;
+
+pub const USAGE: &str = "mage_duel catalog <library> [--damage-types <file>]";
+
+/// `mage_duel catalog`: print every card in a library, with its designer notes, as Markdown
+pub fn run(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[ "damage-types" ], &[ ])?;
+    let [ library_path ] = arguments.get_positional() else {
+        return Err(format!("usage: {}", USAGE));
+    };
+
+    let damage_types = match arguments.get_option("damage-types", String::new())? {
+        path if path.is_empty() => DamageTypeRegistry::default(),
+        path => DamageTypeRegistry::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+    let library = CardLibrary::load_with(Path::new(library_path), &damage_types)
+        .map_err(|diagnostics| diagnostics.iter().map(|d| d.to_string()).collect::<Vec<String>>().join("\n"))?;
+
+    print!("{}", catalog(&library));
+    Ok(())
+}
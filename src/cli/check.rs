@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::library::card_library::{CardLibrary, LoadError};
+use crate::library::lints::{apply_levels, lint_card, Level, LintLevels, Severity};
+use crate::lsp::json::Json;
+
+use super::arguments::Arguments;
+
+pub const USAGE: &str = "mage_duel check <library> [--damage-types <file>] [--allow <rules>] [--warn <rules>] [--deny <rules>] [--json] [--deny-warnings]";
+
+/// `mage_duel check`: load every card in a library, report what's wrong, and fail if anything is broken
+pub fn run(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[ "damage-types", "allow", "warn", "deny" ], &[ "json", "deny-warnings" ])?;
+    let [ library_path ] = arguments.get_positional() else {
+        return Err(format!("usage: {}", USAGE));
+    };
+
+    let damage_types = match arguments.get_option("damage-types", String::new())? {
+        path if path.is_empty() => DamageTypeRegistry::default(),
+        path => DamageTypeRegistry::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+    // rules are comma-separated, e.g. `--allow unnamed-card,empty-body`
+    let mut levels = LintLevels::default();
+    for (option, level) in [ ("allow", Level::Allow), ("warn", Level::Warn), ("deny", Level::Deny) ] {
+        let rules = arguments.get_option(option, String::new())?;
+        for rule in rules.split(',').map(str::trim).filter(|rule| !rule.is_empty()) {
+            levels.set(rule, level)?;
+        }
+    }
+    let report = check(Path::new(library_path), &damage_types, &levels);
+
+    if arguments.has_switch("json") {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report);
+    }
+
+    let failed = report.count(Severity::Error) > 0 || (arguments.has_switch("deny-warnings") && report.count(Severity::Warning) > 0);
+    if failed {
+        return Err(report.get_summary());
+    }
+    if !arguments.has_switch("json") {
+        println!("{}", report.get_summary());
+    }
+    Ok(())
+}
+
+/// Where in a script a finding is, 1-based (the end is just past the last character)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// The script's text on `line`, for showing the problem in context
+    pub source_line: String
+}
+
+/// One problem with one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub severity: Severity,
+    /// Kind of problem: `tokenizer`, `parse`, `io`, `duplicate-name`, `duplicate-id`, or the lint rule
+    pub code: &'static str,
+    pub message: String,
+    pub location: Option<Location>
+}
+
+/// Rendered like a compiler error, with the offending line underlined when we know where it is
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        let Some(location) = &self.location else {
+            return writeln!(f, " --> {}", self.path.display());
+        };
+
+        let gutter = " ".repeat(location.line.to_string().len());
+        writeln!(f, "{}--> {}:{}:{}", gutter, self.path.display(), location.line, location.column)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", location.line, location.source_line)?;
+        // only the first line of a span that runs over several
+        let width = match location.end_line {
+            end_line if end_line == location.line => location.end_column.saturating_sub(location.column).max(1),
+            _ => location.source_line.chars().count().saturating_sub(location.column - 1).max(1)
+        };
+        let padding: String = location.source_line.chars().take(location.column - 1).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        writeln!(f, "{} | {}{}", gutter, padding, "^".repeat(width))
+    }
+}
+
+/// Everything `check` found in a library
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Card scripts looked at, whether they loaded or not
+    pub files: usize,
+    pub findings: Vec<Finding>
+}
+
+impl Report {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings.iter().filter(|finding| finding.severity == severity).count()
+    }
+
+    /// e.g. `checked 12 files: 1 error, 2 warnings`
+    pub fn get_summary(&self) -> String {
+        let plural = |count: usize, word: &str| format!("{} {}{}", count, word, if count == 1 { "" } else { "s" });
+        format!("checked {}: {}, {}", plural(self.files, "file"), plural(self.count(Severity::Error), "error"), plural(self.count(Severity::Warning), "warning"))
+    }
+
+    /// `{"files": N, "errors": N, "warnings": N, "diagnostics": [...]}`, where each diagnostic has its
+    /// path, severity, code and message, and line, column, end_line and end_column (null when unknown)
+    pub fn to_json(&self) -> String {
+        let diagnostics: Vec<Json> = self.findings.iter()
+            .map(|finding| {
+                let location = finding.location.as_ref();
+                Json::object([
+                    ("path", Json::from(finding.path.to_string_lossy().as_ref())),
+                    ("severity", Json::from(finding.severity.to_string())),
+                    ("code", Json::from(finding.code)),
+                    ("message", Json::from(finding.message.as_str())),
+                    ("line", Json::from(location.map(|location| location.line))),
+                    ("column", Json::from(location.map(|location| location.column))),
+                    ("end_line", Json::from(location.map(|location| location.end_line))),
+                    ("end_column", Json::from(location.map(|location| location.end_column)))
+                ])
+            })
+            .collect();
+        Json::object([
+            ("files", Json::from(self.files)),
+            ("errors", Json::from(self.count(Severity::Error))),
+            ("warnings", Json::from(self.count(Severity::Warning))),
+            ("diagnostics", Json::from(diagnostics))
+        ]).to_string()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        Ok(())
+    }
+}
+
+/// Load the library the way a duel would, then lint every card that loaded, at the given levels
+/// (and those of the `// lint:` comments in each script)
+pub fn check(root: &Path, damage_types: &DamageTypeRegistry, levels: &LintLevels) -> Report {
+    let (library, diagnostics) = CardLibrary::load_partial(root, damage_types);
+    // diagnostics about a script are relative to the root; ones about reading the file system aren't
+    let full_path = |path: &Path| if path.starts_with(root) { path.to_path_buf() } else { root.join(path) };
+
+    let mut files: BTreeSet<PathBuf> = library.iter().map(|card| full_path(card.get_path())).collect();
+    let mut findings = vec![ ];
+    for diagnostic in diagnostics {
+        let path = full_path(&diagnostic.path);
+        let location = diagnostic.span.and_then(|span| {
+            let source = std::fs::read_to_string(&path).ok()?;
+            let (line, column) = span.get_start(&source);
+            let (end_line, end_column) = span.get_end(&source);
+            let source_line = source.lines().nth(line - 1).unwrap_or_default().to_string();
+            Some(Location { line, column, end_line, end_column, source_line })
+        });
+        let (code, message) = match &diagnostic.error {
+            LoadError::Io(err) => ("io", err.to_string()),
+            LoadError::Tokenizer(err) => ("tokenizer", err.to_string()),
+            LoadError::Parse(err) => ("parse", err.to_string()),
+            LoadError::DuplicateName(name) => ("duplicate-name", format!("another card is already named '{}'", name)),
+            LoadError::DuplicateId(id) => ("duplicate-id", format!("card ID {} is already taken", id)),
+            LoadError::SymlinkedDirectory => ("symlinked-directory", String::from("symlinked directories aren't followed"))
+        };
+        if path.is_file() {
+            files.insert(path.clone());
+        }
+        findings.push(Finding { path, severity: Severity::Error, code, message, location });
+    }
+
+    for card in library.iter() {
+        let path = full_path(card.get_path());
+        let source = std::fs::read_to_string(&path).unwrap_or_default();
+        let (levels, mut lints) = levels.for_script(&source, damage_types);
+        lints.extend(lint_card(card.get_card()));
+        for (lint, severity) in apply_levels(lints, &levels) {
+            findings.push(Finding { path: path.clone(), severity, code: lint.rule, message: lint.message, location: None });
+        }
+    }
+    // stable, so each file's findings keep the order they were found in
+    findings.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Report { files: files.len(), findings }
+}
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::cli::check::{check, run};
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::library::lints::{Level, LintLevels, Severity};
+
+    /// Library directory with the given cards, removed when dropped
+    struct TempLibrary(PathBuf);
+
+    impl TempLibrary {
+        fn new(name: &str, cards: &[ (&str, &str) ]) -> Self {
+            let root = std::env::temp_dir().join(format!("mage_duel_check_{}_{}", name, std::process::id()));
+            std::fs::create_dir_all(&root).unwrap();
+            for (path, script) in cards {
+                std::fs::write(root.join(path), script).unwrap();
+            }
+            TempLibrary(root)
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+
+        fn args(&self, flags: &[&str]) -> Vec<String> {
+            let mut args = vec![ self.0.to_string_lossy().into_owned() ];
+            args.extend(flags.iter().map(|flag| flag.to_string()));
+            args
+        }
+    }
+
+    impl Drop for TempLibrary {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const GOOD: &str = "@name \"Zap\" [1]: { 1 fire => target(1 in Player); }";
+
+    #[test]
+    fn clean_library() {
+        let library = TempLibrary::new("clean", &[ ("zap.card", GOOD) ]);
+        let report = check(library.path(), &DamageTypeRegistry::default(), &LintLevels::default());
+
+        assert_eq!(report.files, 1);
+        assert!(report.findings.is_empty());
+        assert_eq!(report.get_summary(), "checked 1 file: 0 errors, 0 warnings");
+        assert!(run(&library.args(&[ ])).is_ok());
+    }
+
+    #[test]
+    fn errors_are_located_and_rendered() {
+        let library = TempLibrary::new("errors", &[ ("zap.card", GOOD), ("bad.card", "@name \"Bad\" [1]: {\n  2 fire => ; }") ]);
+        let report = check(library.path(), &DamageTypeRegistry::default(), &LintLevels::default());
+
+        assert_eq!(report.files, 2);
+        assert_eq!(report.count(Severity::Error), 1);
+        let finding = &report.findings[0];
+        assert_eq!(finding.code, "parse");
+        assert_eq!(finding.path, library.path().join("bad.card"));
+        let location = finding.location.as_ref().unwrap();
+        assert_eq!((location.line, location.column, location.end_line, location.end_column), (2, 13, 2, 14));
+
+        let rendered = finding.to_string();
+        assert!(rendered.starts_with("error[parse]: unexpected ';'\n"));
+        assert!(rendered.contains("bad.card:2:13\n"));
+        assert!(rendered.ends_with("2 |   2 fire => ; }\n  |             ^\n"));
+        assert_eq!(run(&library.args(&[ ])), Err(String::from("checked 2 files: 1 error, 0 warnings")));
+    }
+
+    #[test]
+    fn lints_are_warnings() {
+        let library = TempLibrary::new("lints", &[ ("blank.card", "[1]: { }") ]);
+        let report = check(library.path(), &DamageTypeRegistry::default(), &LintLevels::default());
+
+        let codes: Vec<&str> = report.findings.iter().map(|finding| finding.code).collect();
+        assert_eq!(codes, vec![ "unnamed-card", "empty-body" ]);
+        assert_eq!(report.count(Severity::Warning), 2);
+        assert!(run(&library.args(&[ ])).is_ok());
+        assert!(run(&library.args(&[ "--deny-warnings" ])).is_err());
+    }
+
+    #[test]
+    fn lint_levels() {
+        let library = TempLibrary::new("levels", &[ ("blank.card", "[1]: { }"), ("quiet.card", "// lint: allow(empty-body, unnamed-card)\n[1]: { }") ]);
+
+        let mut levels = LintLevels::default();
+        levels.set("empty-body", Level::Deny).unwrap();
+        let report = check(library.path(), &DamageTypeRegistry::default(), &levels);
+        let found: Vec<(&str, Severity)> = report.findings.iter().map(|finding| (finding.code, finding.severity)).collect();
+        // the file's own pragma wins over the levels it's checked with
+        assert_eq!(found, vec![ ("unnamed-card", Severity::Warning), ("empty-body", Severity::Error) ]);
+
+        assert!(run(&library.args(&[ "--deny", "empty-body" ])).is_err());
+        assert!(run(&library.args(&[ "--allow", "unnamed-card,empty-body", "--deny-warnings" ])).is_ok());
+        assert_eq!(run(&library.args(&[ "--warn", "no-such-rule" ])), Err(String::from("unknown lint rule 'no-such-rule'")));
+    }
+
+    #[test]
+    fn json_output() {
+        let library = TempLibrary::new("json", &[ ("bad.card", "@name \"Bad\" [1]: { log \"oops; }") ]);
+        let json = check(library.path(), &DamageTypeRegistry::default(), &LintLevels::default()).to_json();
+
+        assert!(json.starts_with("{\"files\":1,\"errors\":1,\"warnings\":0,\"diagnostics\":[{\"path\":"));
+        assert!(json.contains("\"severity\":\"error\",\"code\":\"tokenizer\",\"message\":\"string is missing its closing quote\",\"line\":1,\"column\":24"));
+    }
+
+    #[test]
+    fn json_escapes_strings() {
+        let library = TempLibrary::new("escape", &[ ("quote.card", "@name \"a\\\"b\" [1]: { }\n"), ("other.card", "@name \"a\\\"b\" [1]: { }") ]);
+        let json = check(library.path(), &DamageTypeRegistry::default(), &LintLevels::default()).to_json();
+
+        assert!(json.contains("\"message\":\"another card is already named 'a\\\"b'\",\"line\":null"));
+    }
+
+    #[test]
+    fn registered_damage_types() {
+        let library = TempLibrary::new("types", &[ ("shriek.card", "@name \"Shriek\" [1]: { 3 sonic => target(1 in Player); }") ]);
+
+        assert_eq!(check(library.path(), &DamageTypeRegistry::default(), &LintLevels::default()).count(Severity::Error), 1);
+        let damage_types = DamageTypeRegistry::parse("sonic \"Sonic\"").unwrap();
+        assert!(check(library.path(), &damage_types, &LintLevels::default()).findings.is_empty());
+    }
+
+    #[test]
+    fn usage() {
+        assert!(run(&[ ]).is_err());
+    }
+}
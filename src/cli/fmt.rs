@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::library::card_library::collect_card_paths;
+use crate::parsing::formatter::format_script;
+
+use super::arguments::Arguments;
+
+pub const USAGE: &str = "mage_duel fmt <file or directory>... [--check] [--damage-types <file>]";
+
+/// `mage_duel fmt`: reformat card scripts in place, or with `--check`, fail if any aren't formatted
+pub fn run(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[ "damage-types" ], &[ "check" ])?;
+    if arguments.get_positional().is_empty() {
+        return Err(format!("usage: {}", USAGE));
+    }
+    let damage_types = match arguments.get_option("damage-types", String::new())? {
+        path if path.is_empty() => DamageTypeRegistry::default(),
+        path => DamageTypeRegistry::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+    let check = arguments.has_switch("check");
+
+    let mut problems = vec![ ];
+    let mut paths = vec![ ];
+    for path in arguments.get_positional().iter().map(PathBuf::from) {
+        if path.is_dir() {
+            let mut diagnostics = vec![ ];
+            collect_card_paths(&path, &mut paths, &mut diagnostics);
+            problems.extend(diagnostics.iter().map(|diagnostic| diagnostic.to_string()));
+        } else {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut unformatted = 0;
+    for path in &paths {
+        match format_file(path, check, &damage_types) {
+            Ok(true) if check => {
+                println!("{}: not formatted", path.display());
+                unformatted += 1;
+            },
+            Ok(true) => println!("formatted {}", path.display()),
+            Ok(false) => { },
+            Err(err) => problems.push(format!("{}: {}", path.display(), err))
+        }
+    }
+
+    if unformatted > 0 {
+        problems.push(format!("{} of {} files need formatting", unformatted, paths.len()));
+    }
+    if !problems.is_empty() {
+        return Err(problems.join("\n"));
+    }
+    Ok(())
+}
+
+/// Whether the file needs formatting; it's rewritten unless only checking
+fn format_file(path: &Path, check: bool, damage_types: &DamageTypeRegistry) -> Result<bool, String> {
+    let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let formatted = format_script(&source, damage_types).map_err(|err| err.to_string())?;
+    if formatted == source {
+        return Ok(false);
+    }
+    if !check {
+        std::fs::write(path, formatted).map_err(|err| err.to_string())?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::fmt::run;
+
+    #[test]
+    fn check_then_format() {
+        let root = std::env::temp_dir().join(format!("mage_duel_fmt_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("spells")).unwrap();
+        std::fs::write(root.join("spells/zap.card"), "@name \"Zap\" [1]:{2 fire=>target(1 in Player);}").unwrap();
+        std::fs::write(root.join("tidy.card"), "[1]: { }\n").unwrap();
+        std::fs::write(root.join("broken.txt"), "[1]: {").unwrap();
+        let args = |flags: &[&str]| {
+            let mut args = vec![ root.to_string_lossy().into_owned() ];
+            args.extend(flags.iter().map(|flag| flag.to_string()));
+            args
+        };
+
+        let checked = run(&args(&[ "--check" ]));
+        let unchanged = std::fs::read_to_string(root.join("spells/zap.card")).unwrap();
+        let formatted = run(&args(&[ ]));
+        let zap = std::fs::read_to_string(root.join("spells/zap.card")).unwrap();
+        let rechecked = run(&args(&[ "--check" ]));
+        std::fs::write(root.join("broken.card"), "[1]: {").unwrap();
+        let broken = run(&args(&[ ]));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(checked, Err(String::from("1 of 2 files need formatting")));
+        assert_eq!(unchanged, "@name \"Zap\" [1]:{2 fire=>target(1 in Player);}");
+        assert!(formatted.is_ok());
+        assert_eq!(zap, "@name \"Zap\"\n[1]: {\n    2 fire => target(1 in Player);\n}\n");
+        assert!(rechecked.is_ok());
+        assert!(broken.unwrap_err().contains("broken.card: parse error: unexpected end of file"));
+    }
+
+    #[test]
+    fn usage() {
+        assert!(run(&[ ]).is_err());
+    }
+}
@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::lsp::server::{serve, Server};
+
+use super::arguments::Arguments;
+
+pub const USAGE: &str = "mage_duel lsp [--damage-types <file>]";
+
+/// `mage_duel lsp`: language server for card scripts, speaking the protocol over stdin and stdout
+pub fn run(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[ "damage-types" ], &[ ])?;
+    let damage_types = match arguments.get_option("damage-types", String::new())? {
+        path if path.is_empty() => DamageTypeRegistry::default(),
+        path => DamageTypeRegistry::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+
+    let mut server = Server::new(damage_types);
+    serve(&mut server, &mut std::io::stdin().lock(), &mut std::io::stdout().lock()).map_err(|err| err.to_string())?;
+    // the protocol says to exit with an error when the editor didn't shut the server down first
+    if !server.was_shut_down() {
+        return Err(String::from("exited without a shutdown request"));
+    }
+    Ok(())
+}
@@ -0,0 +1,441 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::engine::controller::{Action, Controller};
+use crate::engine::game_state::GameState;
+use crate::engine::mana::ManaPool;
+use crate::engine::player::Player;
+use crate::engine::status::StatusTarget;
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::game_zones::dice_roller::DiceRoller;
+use crate::game_zones::types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+use crate::parsing::ast::{Ast, ExpressionId};
+use crate::parsing::expressions::{ExpressionResult, ExpressionType};
+use crate::parsing::parser::{parse_expression, parse_statement};
+use crate::parsing::script_context::ScriptContext;
+use crate::parsing::symbol_table::{SymbolTable, BUILT_IN_SYMBOLS};
+use crate::parsing::tokenizer::tokenize_with;
+use crate::parsing::tokens::Tokens;
+
+use super::arguments::Arguments;
+
+pub const USAGE: &str = "mage_duel repl [--seed N] [--damage-types <file>]";
+
+const HELP: &str = "\
+Type an expression (`2d6 + 1`) to see its value and type, or a statement ending in `;` or `}` (`5 fire => target(1 in Player);`) to run it.
+  :tokens <script>  tokens the script is made of
+  :ast <script>     what the script parses to
+  :dist <integer>   probability of every value the expression can take
+  :seed [N]         show the dice seed, or roll with new dice
+  :state            the scratch duel
+  :reset            forget every variable and start a new scratch duel
+  :quit";
+
+/// Stop working out every combination of rolls after this many, and sample instead
+const MAX_COMBINATIONS: usize = 100_000;
+const SAMPLES: usize = 100_000;
+
+/// `mage_duel repl`: read lines from stdin until `:quit` or the end of input
+pub fn run(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[ "seed", "damage-types" ], &[ ])?;
+    if !arguments.get_positional().is_empty() {
+        return Err(format!("usage: {}", USAGE));
+    }
+    let seed = arguments.get_option("seed", DiceRoller::from_time().get_seed())?;
+    let damage_types = match arguments.get_option("damage-types", String::new())? {
+        path if path.is_empty() => DamageTypeRegistry::default(),
+        path => DamageTypeRegistry::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+
+    let mut repl = Repl::new(seed, damage_types);
+    println!("seed {}, :help for help", seed);
+    let mut input = std::io::stdin().lock();
+    loop {
+        print!("> ");
+        std::io::stdout().flush().map_err(|err| err.to_string())?;
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(|err| err.to_string())? == 0 || matches!(line.trim(), ":quit" | ":q") {
+            return Ok(());
+        }
+        let output = repl.eval(&line);
+        if !output.is_empty() {
+            println!("{}", output);
+        }
+    }
+}
+
+/// A scratch duel between "You" (player 0) and "Opponent" (player 1), and the variables typed in so far
+pub struct Repl {
+    state: GameState,
+    symbol_table: SymbolTable,
+    damage_types: DamageTypeRegistry,
+    seed: u64
+}
+
+impl Repl {
+    pub fn new(seed: u64, damage_types: DamageTypeRegistry) -> Self {
+        let state = new_state(seed, &damage_types);
+        let symbol_table = state.symbol_table_for(0);
+        Repl { state, symbol_table, damage_types, seed }
+    }
+
+    pub fn get_state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Run one line and describe what happened
+    pub fn eval(&mut self, line: &str) -> String {
+        let line = line.trim();
+        let (command, rest) = match line.strip_prefix(':') {
+            Some(command) => command.split_once(' ').map_or((command, ""), |(command, rest)| (command, rest.trim())),
+            None if line.is_empty() => return String::new(),
+            None if line.ends_with(';') || line.ends_with('}') => return self.run_statement(line).unwrap_or_else(error),
+            None => return self.evaluate(line).unwrap_or_else(error)
+        };
+
+        let result = match command {
+            "help" | "h" => Ok(HELP.to_string()),
+            "tokens" => self.tokenize(rest).map(|tokens| {
+                tokens.iter().map(|token| format!("{:?}", token)).collect::<Vec<String>>().join("\n")
+            }),
+            "ast" => self.describe_ast(rest),
+            "dist" => self.describe_distribution(rest),
+            "seed" if rest.is_empty() => Ok(format!("seed {}", self.seed)),
+            "seed" => rest.parse().map_err(|_| format!("invalid seed '{}'", rest)).map(|seed| {
+                self.seed = seed;
+                self.state.reseed_dice(seed);
+                format!("seed {}", seed)
+            }),
+            "state" => Ok(describe_state(&self.state)),
+            "reset" => {
+                *self = Repl::new(self.seed, std::mem::take(&mut self.damage_types));
+                Ok(String::from("new duel"))
+            },
+            _ => Err(format!("unknown command ':{}', :help for help", command))
+        };
+        result.unwrap_or_else(error)
+    }
+
+    /// Tokens of the script, without the end of file
+    fn tokenize(&self, script: &str) -> Result<Vec<Tokens>, String> {
+        let mut tokens = tokenize_with(script, &self.damage_types).map_err(|err| err.to_string())?;
+        tokens.pop();
+        Ok(tokens)
+    }
+
+    /// The expression, in an `Ast` of its own
+    fn parse_expression(&self, script: &str) -> Result<(Ast, ExpressionId), String> {
+        let mut ast = Ast::default();
+        let expression = parse_expression(self.tokenize(script)?.into_iter(), &self.symbol_table, &mut ast).map_err(|err| err.to_string())?;
+        Ok((ast, expression))
+    }
+
+    fn describe_ast(&self, script: &str) -> Result<String, String> {
+        if script.ends_with(';') || script.ends_with('}') {
+            // parsing declares variables, which only running the statement should do
+            let mut ast = Ast::default();
+            let statement = parse_statement(self.tokenize(script)?.into_iter(), &mut self.symbol_table.clone(), &mut ast).map_err(|err| err.to_string())?;
+            return Ok(format!("{:#?}", ast.statement_tree(statement)));
+        }
+        let (ast, expression) = self.parse_expression(script)?;
+        Ok(format!("{:#?}", ast.expression_tree(expression)))
+    }
+
+    /// Value and type of an expression, and the dice it rolled
+    fn evaluate(&mut self, script: &str) -> Result<String, String> {
+        let (ast, expression) = self.parse_expression(script)?;
+        self.refresh_built_ins();
+
+        let symbol_table = &self.symbol_table;
+        let (value, rolls) = self.state.run_script(0, &mut TargetOpponent, |context| {
+            let mut context = RecordRolls { context, rolls: vec![ ] };
+            let value = ast.evaluate(expression, symbol_table, &mut context);
+            (value, context.rolls)
+        });
+
+        let mut lines = vec![ format!("{} : {}", describe_value(&value), ast.get_type(expression)) ];
+        lines.extend(describe_rolls(&rolls));
+        Ok(lines.join("\n"))
+    }
+
+    /// Run a statement against the scratch duel and show what it did
+    fn run_statement(&mut self, script: &str) -> Result<String, String> {
+        let mut symbol_table = self.symbol_table.clone();
+        let mut ast = Ast::default();
+        let statement = parse_statement(self.tokenize(script)?.into_iter(), &mut symbol_table, &mut ast).map_err(|err| err.to_string())?;
+        self.symbol_table = symbol_table;
+        self.refresh_built_ins();
+
+        let log_length = self.state.get_log().len();
+        let hp: Vec<i32> = self.state.get_players().iter().map(Player::get_hp).collect();
+        let symbol_table = &mut self.symbol_table;
+        let rolls = self.state.run_script(0, &mut TargetOpponent, |context| {
+            let mut context = RecordRolls { context, rolls: vec![ ] };
+            ast.execute(statement, symbol_table, &mut context);
+            context.rolls
+        });
+
+        let mut lines: Vec<String> = self.state.get_log()[log_length ..].to_vec();
+        for (player, before) in self.state.get_players().iter().zip(hp) {
+            if player.get_hp() != before {
+                lines.push(format!("{}: {} -> {} hp", player.get_name(), before, player.get_hp()));
+            }
+        }
+        lines.extend(describe_rolls(&rolls));
+        if lines.is_empty() {
+            lines.push(String::from("ok"));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn describe_distribution(&self, script: &str) -> Result<String, String> {
+        let (ast, expression) = self.parse_expression(script)?;
+        if *ast.get_type(expression) != ExpressionType::Integer {
+            return Err(format!("can only show the distribution of an integer, not a {}", ast.get_type(expression)));
+        }
+
+        let mut symbol_table = self.symbol_table.clone();
+        let mut scratch = self.state.clone();
+        refresh_built_ins(&scratch, &mut symbol_table);
+        let mut dice_roller = DiceRoller::new(self.seed);
+        let distribution = scratch.run_script(0, &mut TargetOpponent, |context| {
+            Distribution::of(&ast, expression, &symbol_table, context, &mut dice_roller)
+        });
+        Ok(distribution.to_string())
+    }
+
+    /// `$turn` and friends always show the scratch duel as it is now
+    fn refresh_built_ins(&mut self) {
+        refresh_built_ins(&self.state, &mut self.symbol_table);
+    }
+}
+
+fn new_state(seed: u64, damage_types: &DamageTypeRegistry) -> GameState {
+    let players = vec![ Player::new("You", 20, ManaPool::default()), Player::new("Opponent", 20, ManaPool::default()) ];
+    let mut state = GameState::new(players, DiceRoller::new(seed));
+    state.set_damage_types(Arc::new(damage_types.clone()));
+    state
+}
+
+fn refresh_built_ins(state: &GameState, symbol_table: &mut SymbolTable) {
+    let current = state.symbol_table_for(0);
+    for (name, _) in BUILT_IN_SYMBOLS {
+        if let Some(value) = current.get_value(name) {
+            symbol_table.assign(name, value.clone()).expect("Built-ins are always declared as integers.");
+        }
+    }
+}
+
+fn error(message: String) -> String {
+    format!("error: {}", message)
+}
+
+fn describe_value(value: &ExpressionResult) -> String {
+    match value {
+        ExpressionResult::Text(text) => format!("\"{}\"", text),
+        value => value.to_string()
+    }
+}
+
+fn describe_rolls(rolls: &[ (Dice, u16) ]) -> Option<String> {
+    if rolls.is_empty() {
+        return None;
+    }
+    let rolls: Vec<String> = rolls.iter().map(|(dice, roll)| format!("d{} = {}", dice.get_sides(), roll)).collect();
+    Some(format!("rolled {}", rolls.join(", ")))
+}
+
+fn describe_state(state: &GameState) -> String {
+    let mut lines = vec![ format!("turn {}, dice seed {}", state.get_turn(), state.get_dice_roller().get_seed()) ];
+    for (index, player) in state.get_players().iter().enumerate() {
+        lines.push(format!("{} (player {}): {} hp, {} mana", player.get_name(), index, player.get_hp(), player.get_mana().get_available()));
+    }
+    for status in state.get_statuses() {
+        let target = match status.target {
+            StatusTarget::Player(index) => state.get_players()[index].get_name().to_string(),
+            StatusTarget::Instance(instance) => format!("card {}", instance.0)
+        };
+        lines.push(format!("{} is {} for {} turns ({} stacks)", target, status.kind, status.turns, status.stacks));
+    }
+    for modifier in state.get_modifiers() {
+        lines.push(format!("player {}: {:?} {:?}", modifier.player, modifier.value, modifier.operation));
+    }
+    for replacement in state.get_replacements() {
+        lines.push(format!("player {}: {:?} becomes {:?}", replacement.player, replacement.event, replacement.outcome));
+    }
+    lines.join("\n")
+}
+
+/// Targets the opponent before the player typing
+struct TargetOpponent;
+
+impl Controller for TargetOpponent {
+    fn choose_action(&mut self, state: &GameState, _options: &[Action]) -> Action {
+        state.get_default_action()
+    }
+
+    fn choose_targets(&mut self, _state: &GameState, candidates: &[ExpressionResult], count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+        let you = ExpressionResult::Player(0);
+        candidates.iter().filter(|&candidate| *candidate != you)
+            .chain(candidates.iter().filter(|&candidate| *candidate == you))
+            .take(count as usize)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Every value an integer expression can take, and how likely each one is
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub probabilities: BTreeMap<i32, f64>,
+    /// Worked out from every combination of rolls, rather than estimated from samples
+    pub exact: bool
+}
+
+impl Distribution {
+    /// Go through every combination of rolls, or sample the expression when there are too many of them
+    pub fn of(ast: &Ast, expression: ExpressionId, symbol_table: &SymbolTable, context: &mut dyn ScriptContext, dice_roller: &mut DiceRoller) -> Self {
+        let mut probabilities = BTreeMap::new();
+        // (sides, face) of each roll on the current path through the expression
+        let mut faces: Vec<(u16, u16)> = vec![ ];
+        for _ in 0 .. MAX_COMBINATIONS {
+            let mut enumerate = EnumerateRolls { context: &mut *context, faces: &mut faces, position: 0 };
+            let value = ast.evaluate(expression, symbol_table, &mut enumerate).expect_integer();
+            let position = enumerate.position;
+            faces.truncate(position);
+
+            let probability = faces.iter().fold(1.0, |probability, &(sides, _)| probability / sides as f64);
+            *probabilities.entry(value).or_insert(0.0) += probability;
+
+            // move on to the next combination like an odometer, last roll first
+            while let Some((sides, face)) = faces.last_mut() {
+                if *face < *sides {
+                    *face += 1;
+                    break;
+                }
+                faces.pop();
+            }
+            if faces.is_empty() {
+                return Distribution { probabilities, exact: true };
+            }
+        }
+
+        let mut counts: BTreeMap<i32, usize> = BTreeMap::new();
+        for _ in 0 .. SAMPLES {
+            let mut sample = SampleRolls { context: &mut *context, dice_roller: &mut *dice_roller };
+            *counts.entry(ast.evaluate(expression, symbol_table, &mut sample).expect_integer()).or_default() += 1;
+        }
+        let probabilities = counts.into_iter().map(|(value, count)| (value, count as f64 / SAMPLES as f64)).collect();
+        Distribution { probabilities, exact: false }
+    }
+
+    pub fn get_mean(&self) -> f64 {
+        self.probabilities.iter().map(|(&value, probability)| value as f64 * probability).sum()
+    }
+}
+
+/// One line per value with a bar for its probability, then the mean
+impl std::fmt::Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let highest = self.probabilities.values().cloned().fold(0.0, f64::max);
+        let width = self.probabilities.keys().map(|value| value.to_string().len()).max().unwrap_or(0);
+        for (value, probability) in &self.probabilities {
+            let bar = if highest > 0.0 { (probability / highest * 40.0).round() as usize } else { 0 };
+            writeln!(f, "{:>width$} {:>6.2}% {}", value, probability * 100.0, "#".repeat(bar), width = width)?;
+        }
+        write!(f, "mean {:.2}", self.get_mean())?;
+        if !self.exact {
+            write!(f, " (estimated from {} samples)", SAMPLES)?;
+        }
+        Ok(())
+    }
+}
+
+/// Forwards everything to the context, keeping track of the dice rolled
+struct RecordRolls<'a> {
+    context: &'a mut dyn ScriptContext,
+    rolls: Vec<(Dice, u16)>
+}
+
+/// Forwards everything to the context except rolls, which follow `faces` and then start from 1
+struct EnumerateRolls<'a> {
+    context: &'a mut dyn ScriptContext,
+    faces: &'a mut Vec<(u16, u16)>,
+    position: usize
+}
+
+/// Forwards everything to the context except rolls, which use the dice roller
+struct SampleRolls<'a> {
+    context: &'a mut dyn ScriptContext,
+    dice_roller: &'a mut DiceRoller
+}
+
+/// Forward every part of `ScriptContext` but `roll` to `self.context`
+macro_rules! forward_script_context {
+    () => {
+        fn choose_targets(&mut self, target_type: &ExpressionType, count: u16, up_to: bool) -> Vec<ExpressionResult> {
+            self.context.choose_targets(target_type, count, up_to)
+        }
+
+        fn deal_damage(&mut self, amount: i32, damage_type: DamageType, target: &ExpressionResult) {
+            self.context.deal_damage(amount, damage_type, target)
+        }
+
+        fn apply_status(&mut self, status: StatusKind, turns: i32, target: &ExpressionResult) {
+            self.context.apply_status(status, turns, target)
+        }
+
+        fn add_modifier(&mut self, value: ModifiedValue, operation: ModifierOperation, target: Option<&ExpressionResult>) {
+            self.context.add_modifier(value, operation, target)
+        }
+
+        fn add_replacement(&mut self, event: ReplacedEvent, outcome: ReplacementOutcome, uses: Option<u16>) {
+            self.context.add_replacement(event, outcome, uses)
+        }
+
+        fn get_property(&mut self, target: &ExpressionResult, property: &str) -> ExpressionResult {
+            self.context.get_property(target, property)
+        }
+
+        fn log(&mut self, message: &str) {
+            self.context.log(message)
+        }
+
+        fn counter(&mut self) -> bool {
+            self.context.counter()
+        }
+    };
+}
+
+impl ScriptContext for RecordRolls<'_> {
+    fn roll(&mut self, dice: Dice) -> u16 {
+        let roll = self.context.roll(dice);
+        self.rolls.push((dice, roll));
+        roll
+    }
+
+    forward_script_context!();
+}
+
+impl ScriptContext for EnumerateRolls<'_> {
+    fn roll(&mut self, dice: Dice) -> u16 {
+        if self.position == self.faces.len() {
+            self.faces.push((dice.get_sides() as u16, 1));
+        }
+        let (_, face) = self.faces[self.position];
+        self.position += 1;
+        face
+    }
+
+    forward_script_context!();
+}
+
+impl ScriptContext for SampleRolls<'_> {
+    fn roll(&mut self, dice: Dice) -> u16 {
+        dice.roll(self.dice_roller)
+    }
+
+    forward_script_context!();
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::repl::Repl;
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use test_case::test_case;
+
+    fn repl() -> Repl {
+        Repl::new(7, DamageTypeRegistry::default())
+    }
+
+    #[test_case("1 + 2 * 3", "7 : integer" ; "Integer")]
+    #[test_case("1 < 2 & ~false", "true : boolean" ; "Boolean")]
+    #[test_case("[1, 2] + [3]", "[1, 2, 3] : list of integer" ; "List")]
+    #[test_case("\"turn {$turn}\"", "\"turn 0\" : text" ; "Interpolated text")]
+    #[test_case("target(1 in Player).name", "\"Opponent\" : text" ; "Target the opponent")]
+    fn evaluate_expression(line: &str, expected: &str) {
+        assert_eq!(repl().eval(line), expected);
+    }
+
+    #[test]
+    fn show_dice_rolls() {
+        let output = repl().eval("2d6");
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" : integer"));
+        assert!(lines[1].starts_with("rolled d6 = "));
+        // same seed, same rolls
+        assert_eq!(repl().eval("2d6"), output);
+    }
+
+    #[test]
+    fn variables_persist_between_lines() {
+        let mut repl = repl();
+
+        assert_eq!(repl.eval("$x = 4;"), "ok");
+        assert_eq!(repl.eval("$x * 2"), "8 : integer");
+        assert_eq!(repl.eval("if $x > 3 { $x = 1; }"), "ok");
+        assert_eq!(repl.eval("$x"), "1 : integer");
+    }
+
+    #[test]
+    fn statements_change_the_scratch_duel() {
+        let mut repl = repl();
+
+        assert_eq!(repl.eval("5 fire => target(1 in Player);"), "Opponent: 20 -> 15 hp");
+        assert_eq!(repl.eval("target(1 in Player).hp"), "15 : integer");
+        assert_eq!(repl.eval(":reset"), "new duel");
+        assert_eq!(repl.get_state().get_players()[1].get_hp(), 20);
+    }
+
+    #[test]
+    fn failed_statements_declare_nothing() {
+        let mut repl = repl();
+
+        assert!(repl.eval("$y = 1 +;").starts_with("error: "));
+        assert!(repl.eval("$y").starts_with("error: '$y' is used before"));
+    }
+
+    #[test]
+    fn exact_distribution() {
+        let output = repl().eval(":dist 2d6");
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 12);
+        assert!(lines[0].starts_with(" 2   2.78% #"));
+        assert!(lines[5].starts_with(" 7  16.67% ########################################"));
+        assert_eq!(lines[11], "mean 7.00");
+    }
+
+    #[test]
+    fn distribution_follows_conditions() {
+        let mut repl = repl();
+        repl.eval("$bonus = 3;");
+
+        // only one die is rolled on each path
+        let output = repl.eval(":dist 1d2 + $bonus");
+
+        assert_eq!(output, "4  50.00% ########################################\n5  50.00% ########################################\nmean 4.50");
+    }
+
+    #[test]
+    fn too_many_combinations_are_sampled() {
+        let output = repl().eval(":dist 8d10");
+        assert!(output.ends_with("(estimated from 100000 samples)"));
+    }
+
+    #[test]
+    fn seed_changes_the_dice() {
+        let mut repl = repl();
+        assert_eq!(repl.eval(":seed"), "seed 7");
+
+        assert_eq!(repl.eval(":seed 42"), "seed 42");
+        assert_eq!(repl.get_state().get_dice_roller().get_seed(), 42);
+        assert!(repl.eval(":seed many").starts_with("error: "));
+    }
+
+    #[test]
+    fn tokens_and_ast() {
+        let mut repl = repl();
+
+        assert_eq!(repl.eval(":tokens 1 fire").lines().count(), 2);
+        assert!(repl.eval(":ast 1 + 2").starts_with("Binary {"));
+        assert!(repl.eval(":ast $z = 1;").starts_with("Assignment {"));
+        // showing the tree doesn't run or declare anything
+        assert!(repl.eval("$z").starts_with("error: "));
+    }
+
+    #[test]
+    fn registered_damage_types() {
+        let mut repl = Repl::new(7, DamageTypeRegistry::parse("sonic \"Sonic\" vulnerable").unwrap());
+        assert_eq!(repl.eval("2 sonic => target(1 in Player);"), "Opponent: 20 -> 16 hp");
+    }
+
+    #[test_case(":bogus" ; "Unknown command")]
+    #[test_case(":dist true" ; "Distribution of a boolean")]
+    #[test_case("1 +" ; "Incomplete expression")]
+    fn errors(line: &str) {
+        assert!(repl().eval(line).starts_with("error: "));
+    }
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::ai::greedy::{GreedyController, HeuristicWeights};
+use crate::ai::mcts::{MctsConfig, MctsController, SearchBudget};
+use crate::engine::controller::RandomController;
+use crate::engine::duel::DuelConfig;
+use crate::engine::interactions::InteractionTable;
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::engine::simulator::{factory, simulate, simulate_parallel, ControllerFactory, Simulation};
+use crate::library::card_library::{CardLibrary, Diagnostic, LibrarySources};
+use crate::library::deck_list::DeckRules;
+
+use super::arguments::Arguments;
+
+pub const USAGE: &str = "mage_duel simulate <library> <deck 1> <deck 2> [--games N] [--seed N] [--threads N] [--ai <ai>[,<ai>]] [--iterations N] [--weights <file>] [--interactions <file>] [--damage-types <file>] [--deck-rules <file>]\n  AIs: random, greedy, mcts, mcts-greedy (greedy playouts)";
+
+/// `mage_duel simulate`: play two deck files against each other and print the report
+pub fn run(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[ "games", "seed", "threads", "ai", "iterations", "weights", "interactions", "damage-types", "deck-rules" ], &[ ])?;
+    let [ library_path, first, second ] = arguments.get_positional() else {
+        return Err(format!("usage: {}", USAGE));
+    };
+
+    let damage_types = match arguments.get_option("damage-types", String::new())? {
+        path if path.is_empty() => DamageTypeRegistry::default(),
+        path => DamageTypeRegistry::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+    // read once: threads build their own library from the scripts
+    let join_diagnostics = |diagnostics: Vec<Diagnostic>| diagnostics.iter().map(|d| d.to_string()).collect::<Vec<String>>().join("\n");
+    let sources = LibrarySources::read(Path::new(library_path), &damage_types).map_err(join_diagnostics)?;
+    let read_deck = |path: &String| std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err));
+
+    let interactions = match arguments.get_option("interactions", String::new())? {
+        path if path.is_empty() => InteractionTable::default(),
+        path => InteractionTable::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+
+    let simulation = Simulation {
+        deck_lists: [ read_deck(first)?, read_deck(second)? ],
+        games: arguments.get_option("games", 1000)?,
+        seed: arguments.get_option("seed", 0)?,
+        config: DuelConfig { interactions: Arc::new(interactions), damage_types: Arc::new(damage_types.clone()), ..DuelConfig::default() },
+        threads: arguments.get_option("threads", 1)?
+    };
+    let ai = arguments.get_option("ai", String::from("random"))?;
+    let (first_ai, second_ai) = ai.split_once(',').unwrap_or((&ai, &ai));
+    let iterations = arguments.get_option("iterations", 500)?;
+    let weights = match arguments.get_option("weights", String::new())? {
+        path if path.is_empty() => HeuristicWeights::default(),
+        path => HeuristicWeights::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+    let mcts_config = move |seed| MctsConfig { budget: SearchBudget::Iterations(iterations), seed, ..MctsConfig::default() };
+
+    let random = factory(|_library, seed| Box::new(RandomController::new(seed)));
+    let greedy = factory(|library, _seed| Box::new(GreedyController::new(library, weights.clone())));
+    let mcts = factory(|library, seed| Box::new(MctsController::new(library, mcts_config(seed))));
+    let mcts_greedy = factory(|library, seed| {
+        let playout = Box::new(GreedyController::new(library, weights.clone()));
+        Box::new(MctsController::new(library, mcts_config(seed)).with_playout(playout))
+    });
+    let ais: [ (&str, &ControllerFactory); 4 ] = [ ("random", &random), ("greedy", &greedy), ("mcts", &mcts), ("mcts-greedy", &mcts_greedy) ];
+    let controllers = [ pick_ai(&ais, first_ai)?, pick_ai(&ais, second_ai)? ];
+
+    let rules = match arguments.get_option("deck-rules", String::new())? {
+        path if path.is_empty() => DeckRules::default(),
+        path => DeckRules::load(Path::new(&path)).map_err(|err| format!("{}: {}", path, err))?
+    };
+    let report = if simulation.threads > 1 {
+        simulate_parallel(&sources, &simulation, &rules, controllers).map_err(|err| err.to_string())
+    } else {
+        let library = sources.build().map(CardLibrary::simplified).map_err(join_diagnostics)?;
+        simulate(&library, &simulation, &rules, controllers).map_err(|invalid| invalid.to_string())
+    };
+    let report = report.map_err(|message| message.trim_end().to_string())?;
+    print!("{}", report);
+    Ok(())
+}
+
+fn pick_ai<'a>(ais: &[ (&str, &'a ControllerFactory<'a>) ], name: &str) -> Result<&'a ControllerFactory<'a>, String> {
+    ais.iter()
+        .find(|&&(ai, _)| ai == name)
+        .map(|&(_, factory)| factory)
+        .ok_or(format!("unknown AI '{}'", name))
+}
@@ -0,0 +1,18 @@
+pub mod mana;
+pub mod player;
+pub mod controller;
+pub mod game_state;
+pub mod status;
+pub mod modifiers;
+pub mod replacements;
+pub mod interactions;
+pub mod snapshot;
+pub mod duel;
+pub mod replay;
+pub mod stats;
+pub mod simulator;
+mod game_state_tests;
+mod interactions_tests;
+mod snapshot_tests;
+mod replay_tests;
+mod simulator_tests;
\ No newline at end of file
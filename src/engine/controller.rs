@@ -0,0 +1,74 @@
+use crate::game_zones::{card_instance::InstanceId, dice_roller::DiceRoller};
+use crate::parsing::expressions::ExpressionResult;
+
+use super::game_state::GameState;
+
+/// Something the player with priority can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    PlayCard(InstanceId),
+    EndTurn,
+    /// Let the top of the stack resolve without responding
+    Pass,
+    /// Give up: the opponent wins
+    Concede
+}
+
+/// Makes decisions on behalf of a player: a human at a prompt, an AI, or a recording being replayed
+pub trait Controller {
+    /// Pick one of the legal actions for the player with priority
+    fn choose_action(&mut self, state: &GameState, options: &[Action]) -> Action;
+    /// Pick `count` of the candidates (or at most `count` when `up_to` is set)
+    fn choose_targets(&mut self, state: &GameState, candidates: &[ExpressionResult], count: u16, up_to: bool) -> Vec<ExpressionResult>;
+}
+
+/// The targets a choice actually ends up with: a controller can't pick something that isn't a candidate,
+/// the same thing twice, nor more than it was asked for; unless `up_to` is set, the first legal candidates
+/// fill in whatever it left out
+pub fn settle_targets(chosen: Vec<ExpressionResult>, candidates: &[ExpressionResult], count: u16, up_to: bool) -> Vec<ExpressionResult> {
+    let mut targets: Vec<ExpressionResult> = vec![ ];
+    for target in chosen {
+        if targets.len() < count as usize && candidates.contains(&target) && !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    if !up_to {
+        for candidate in candidates {
+            if targets.len() >= count as usize {
+                break;
+            }
+            if !targets.contains(candidate) {
+                targets.push(candidate.clone());
+            }
+        }
+    }
+    targets
+}
+
+/// Picks uniformly at random among its options; never concedes
+pub struct RandomController {
+    roller: DiceRoller
+}
+
+impl RandomController {
+    pub fn new(seed: u64) -> Self {
+        RandomController { roller: DiceRoller::new(seed) }
+    }
+}
+
+impl Controller for RandomController {
+    fn choose_action(&mut self, _state: &GameState, options: &[Action]) -> Action {
+        let options: Vec<Action> = options.iter().copied().filter(|&a| a != Action::Concede).collect();
+        if options.is_empty() {
+            return Action::EndTurn;
+        }
+        options[self.roller.next_below(options.len() as u64) as usize]
+    }
+
+    fn choose_targets(&mut self, _state: &GameState, candidates: &[ExpressionResult], count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+        (0 .. count)
+            .filter(|_| !candidates.is_empty())
+            .map(|_| candidates[self.roller.next_below(candidates.len() as u64) as usize].clone())
+            .collect()
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use crate::game_zones::{damage_types::DamageTypeRegistry, dice_roller::DiceRoller};
+use crate::library::{card_library::CardLibrary, deck_list::{Deck, DeckError, DeckList, DeckRules}};
+
+use super::{controller::{Action, Controller}, game_state::GameState, interactions::InteractionTable, mana::ManaPool, player::Player};
+
+/// House rules every duel is played with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuelConfig {
+    pub starting_hp: i32,
+    pub mana: ManaPool,
+    /// Cards each player draws before the first turn
+    pub opening_hand: usize,
+    /// Duel is a draw if nobody has won after this many turns (counting each player's turn)
+    pub max_turns: u16,
+    /// How damage types interact with statuses
+    pub interactions: Arc<InteractionTable>,
+    /// Damage types the cards were loaded with, and how players take each one by default
+    pub damage_types: Arc<DamageTypeRegistry>
+}
+
+impl Default for DuelConfig {
+    fn default() -> Self {
+        DuelConfig { starting_hp: 20, mana: ManaPool::default(), opening_hand: 3, max_turns: 100, interactions: Arc::default(), damage_types: Arc::default() }
+    }
+}
+
+/// One side of a duel before it starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerSetup {
+    pub name: String,
+    /// Deck list as written (see `DeckList`)
+    pub deck_list: String
+}
+
+/// Everything needed to start a duel; together with every decision made, it reproduces the duel exactly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuelSetup {
+    pub players: Vec<PlayerSetup>,
+    pub seed: u64,
+    pub config: DuelConfig
+}
+
+/// How a duel ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuelOutcome {
+    /// `None` for a draw
+    pub winner: Option<usize>,
+    pub turns: u16
+}
+
+/// A duel being played out between controllers
+pub struct Duel<'a> {
+    state: GameState,
+    library: &'a CardLibrary,
+    config: DuelConfig
+}
+
+impl<'a> Duel<'a> {
+    /// Shuffle each player's deck and draw their opening hands
+    pub fn new(library: &'a CardLibrary, players: Vec<(String, Deck)>, seed: u64, config: DuelConfig) -> Self {
+        let (names, decks): (Vec<String>, Vec<Deck>) = players.into_iter().unzip();
+        let players = names.iter().map(|name| Player::new(name, config.starting_hp, config.mana.clone())).collect();
+        let mut state = GameState::new(players, DiceRoller::new(seed));
+        state.set_interactions(Arc::clone(&config.interactions));
+        state.set_damage_types(Arc::clone(&config.damage_types));
+
+        for (player, deck) in decks.iter().enumerate() {
+            state.add_deck(player, deck);
+            for _ in 0 .. config.opening_hand {
+                state.draw(player);
+            }
+        }
+
+        Duel { state, library, config }
+    }
+
+    /// Validate every deck list against the rules, then set up the duel
+    pub fn from_setup(library: &'a CardLibrary, setup: &DuelSetup, rules: &DeckRules) -> Result<Self, Vec<DeckError>> {
+        let mut players = vec![ ];
+        let mut errors = vec![ ];
+        for player in &setup.players {
+            match DeckList::parse(&player.deck_list).and_then(|list| list.validate(library, rules)) {
+                Ok(deck) => players.push((player.name.clone(), deck)),
+                Err(mut deck_errors) => errors.append(&mut deck_errors)
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(Duel::new(library, players, setup.seed, setup.config.clone()));
+        }
+        Err(errors)
+    }
+
+    pub fn get_state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Play turns until someone wins or the turn limit is reached.
+    /// `controllers[i]` makes every decision for player `i`.
+    pub fn run(&mut self, controllers: &mut [&mut dyn Controller]) -> DuelOutcome {
+        while !self.state.is_over() && self.state.get_turn() < self.config.max_turns {
+            self.play_turn(controllers);
+        }
+        DuelOutcome { winner: self.state.get_winner(), turns: self.state.get_turn() }
+    }
+
+    /// The active player's turn, from start to end.
+    /// Every card played and every ability that triggers goes on the stack, and resolves once every player has passed on responding to it.
+    pub fn play_turn(&mut self, controllers: &mut [&mut dyn Controller]) {
+        self.state.start_turn(self.library);
+
+        while !self.state.is_over() {
+            if self.state.is_ready_to_resolve() {
+                let owner = self.state.get_stack().last().map(|entry| entry.player).unwrap_or_default();
+                self.state.resolve_top(self.library, &mut *controllers[owner]);
+                continue;
+            }
+
+            let player = self.state.get_priority_player();
+            let options = self.state.get_legal_actions(self.library);
+            let mut action = controllers[player].choose_action(&self.state, &options);
+            if !options.contains(&action) {
+                // an illegal choice forfeits the rest of the turn, or the chance to respond
+                action = self.state.get_default_action();
+            }
+
+            match action {
+                Action::PlayCard(instance) => {
+                    // legal actions are always affordable and in hand, so this can't fail
+                    let _ = self.state.cast(player, instance, self.library, &mut *controllers[player]);
+                },
+                Action::Pass => self.state.pass_priority(),
+                Action::EndTurn => break,
+                Action::Concede => self.state.concede(player)
+            }
+        }
+
+        self.state.end_turn();
+    }
+}
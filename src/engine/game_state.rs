@@ -0,0 +1,909 @@
+use std::fmt::Display;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::game_zones::{card_instance::{CardInstance, InstanceId}, damage_types::DamageTypeRegistry, dice_roller::DiceRoller, types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind, TriggerEvent}, zone::Zone};
+use crate::library::{card_library::{CardId, CardLibrary}, deck_list::Deck};
+use crate::parsing::{card::Card, expressions::{get_property_type, ExpressionResult, ExpressionType}, script_context::ScriptContext, symbol_table::SymbolTable};
+
+use super::{controller::{settle_targets, Action, Controller}, interactions::{InteractionEffect, InteractionTable}, mana::InsufficientMana, modifiers::{apply_modifiers, Modifier, AURA_TAG}, player::Player, replacements::{Event, ReplacementEffect}, stats::DuelStats, status::{StatusEffect, StatusTarget, TickDamage}};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayCardError {
+    /// No player at this index
+    InvalidPlayer(usize),
+    /// Player doesn't have priority: it's not their turn, nor their chance to respond
+    NotYourTurn(usize),
+    /// Only reactions can be played while something is waiting on the stack
+    NotAReaction(InstanceId),
+    /// Player can't afford the card's cost
+    InsufficientMana(InsufficientMana),
+    /// Card instance isn't in the player's hand
+    NotInHand(InstanceId),
+    /// Card instance refers to a card that isn't in the library
+    UnknownCard(CardId),
+    /// Player or card has a status that stops it from being played (e.g. frozen)
+    Prevented(StatusKind)
+}
+
+impl From<InsufficientMana> for PlayCardError {
+    fn from(err: InsufficientMana) -> Self {
+        PlayCardError::InsufficientMana(err)
+    }
+}
+
+impl Display for PlayCardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayCardError::InvalidPlayer(player) => write!(f, "there is no player {}", player),
+            PlayCardError::NotYourTurn(player) => write!(f, "player {} doesn't have priority", player),
+            PlayCardError::NotAReaction(instance) => write!(f, "card {} can't be played in response", instance.0),
+            PlayCardError::InsufficientMana(err) => write!(f, "card costs {} mana, but only {} is available", err.cost, err.available),
+            PlayCardError::NotInHand(instance) => write!(f, "card {} is not in the player's hand", instance.0),
+            PlayCardError::UnknownCard(card) => write!(f, "card {} is not in the library", card),
+            PlayCardError::Prevented(status) => write!(f, "can't play cards while {}", status)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Upkeep: mana refreshes and the active player draws
+    Start,
+    /// Active player plays cards
+    Main,
+    /// Turn is wrapping up before passing to the next player
+    End
+}
+
+/// Cards with this tag can be played in response to another card
+pub const REACTION_TAG: &str = "reaction";
+
+/// A card that has been played, or one of an aura's abilities that has triggered, waiting to resolve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackEntry {
+    pub instance: InstanceId,
+    /// Player who played it, or who owns the aura
+    pub player: usize,
+    /// Which of the card's `when` abilities this is; `None` for the card itself
+    pub ability: Option<usize>
+}
+
+/// Everything about a duel in progress
+#[derive(Debug, Clone)]
+pub struct GameState {
+    players: Vec<Player>,
+    /// Every card instance in the duel, indexed by its ID
+    instances: Vec<CardInstance>,
+    active_player: usize,
+    turn: u16,
+    phase: Phase,
+    dice_roller: DiceRoller,
+    /// Messages written by cards as they resolve
+    log: Vec<String>,
+    /// Played cards and triggered abilities waiting to resolve; the last one resolves first
+    stack: Vec<StackEntry>,
+    /// Player who gets to act next
+    priority: usize,
+    /// Players who passed in a row since the stack last changed; once everyone has, the top of the stack resolves
+    passes: usize,
+    /// Player whose card or ability is resolving right now
+    resolving: Option<usize>,
+    stats: DuelStats,
+    /// Statuses on players and card instances, in the order they were first applied
+    statuses: Vec<StatusEffect>,
+    /// Continuous modifiers, oldest first
+    modifiers: Vec<Modifier>,
+    /// Replacement effects, oldest first
+    replacements: Vec<ReplacementEffect>,
+    /// How damage types interact with statuses; part of the rules like the card library, so snapshots leave it out
+    interactions: Arc<InteractionTable>,
+    /// Damage types and how players take them by default; part of the rules too
+    damage_types: Arc<DamageTypeRegistry>,
+    /// Replaces the dice on hypothetical copies of the duel
+    fixed_dice: Option<fn(Dice) -> u16>
+}
+
+impl GameState {
+    pub fn new(players: Vec<Player>, dice_roller: DiceRoller) -> Self {
+        GameState { players, instances: vec![ ], active_player: 0, turn: 0, phase: Phase::Start, dice_roller, log: vec![ ], stack: vec![ ], priority: 0, passes: 0, resolving: None, stats: DuelStats::default(), statuses: vec![ ], modifiers: vec![ ], replacements: vec![ ], interactions: Arc::default(), damage_types: Arc::default(), fixed_dice: None }
+    }
+
+    /// Put together a state from its parts (e.g. a saved snapshot) with nothing on the stack and no statuses, modifiers or replacement effects; stats start over
+    pub fn restore(players: Vec<Player>, instances: Vec<CardInstance>, active_player: usize, turn: u16, phase: Phase, dice_roller: DiceRoller, log: Vec<String>) -> Self {
+        GameState {
+            players, instances, active_player, turn, phase, dice_roller, log,
+            stack: vec![ ], priority: active_player, passes: 0, resolving: None, stats: DuelStats::default(), statuses: vec![ ], modifiers: vec![ ], replacements: vec![ ], interactions: Arc::default(), damage_types: Arc::default(), fixed_dice: None
+        }
+    }
+
+    /// Put back what was waiting on the stack and who was to act
+    pub fn restore_stack(&mut self, stack: Vec<StackEntry>, priority: usize, passes: usize) {
+        self.stack = stack;
+        self.priority = priority;
+        self.passes = passes;
+    }
+
+    /// Put back the statuses that were in effect
+    pub fn restore_statuses(&mut self, statuses: Vec<StatusEffect>) {
+        self.statuses = statuses;
+    }
+
+    /// Put back the modifiers that were in effect
+    pub fn restore_modifiers(&mut self, modifiers: Vec<Modifier>) {
+        self.modifiers = modifiers;
+    }
+
+    /// Play by a different interaction table between damage types and statuses
+    pub fn set_interactions(&mut self, interactions: Arc<InteractionTable>) {
+        self.interactions = interactions;
+    }
+
+    pub fn get_interactions(&self) -> &InteractionTable {
+        &self.interactions
+    }
+
+    /// Play with different damage types, or different defaults for how players take them
+    pub fn set_damage_types(&mut self, damage_types: Arc<DamageTypeRegistry>) {
+        self.damage_types = damage_types;
+    }
+
+    pub fn get_damage_types(&self) -> &DamageTypeRegistry {
+        &self.damage_types
+    }
+
+    /// Put back the replacement effects that were in effect
+    pub fn restore_replacements(&mut self, replacements: Vec<ReplacementEffect>) {
+        self.replacements = replacements;
+    }
+
+    pub fn get_players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn get_player(&self, player: usize) -> Option<&Player> {
+        self.players.get(player)
+    }
+
+    pub fn get_player_mut(&mut self, player: usize) -> Option<&mut Player> {
+        self.players.get_mut(player)
+    }
+
+    pub fn get_active_player(&self) -> usize {
+        self.active_player
+    }
+
+    /// Player who gets to act next: the active player, or someone with the chance to respond
+    pub fn get_priority_player(&self) -> usize {
+        self.priority
+    }
+
+    /// Player who makes the choices right now: whoever played the card that is resolving, otherwise whoever has priority
+    pub fn get_deciding_player(&self) -> usize {
+        self.resolving.unwrap_or(self.priority)
+    }
+
+    /// Bottom to top
+    pub fn get_stack(&self) -> &[StackEntry] {
+        &self.stack
+    }
+
+    pub fn get_passes(&self) -> usize {
+        self.passes
+    }
+
+    pub fn get_turn(&self) -> u16 {
+        self.turn
+    }
+
+    pub fn get_phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn get_instances(&self) -> &[CardInstance] {
+        &self.instances
+    }
+
+    pub fn get_instance(&self, instance: InstanceId) -> Option<&CardInstance> {
+        self.instances.get(instance.0 as usize)
+    }
+
+    pub fn get_dice_roller(&self) -> &DiceRoller {
+        &self.dice_roller
+    }
+
+    /// Swap in different dice, e.g. so an AI looking ahead on a copy of the duel can't know the real rolls
+    pub fn reseed_dice(&mut self, seed: u64) {
+        self.dice_roller = DiceRoller::new(seed);
+    }
+
+    /// From now on every die rolls `roll(dice)` instead of using the dice roller,
+    /// e.g. so an AI can see what a card does on an average roll. Only meant for copies of the duel.
+    pub fn fix_dice(&mut self, roll: fn(Dice) -> u16) {
+        self.fixed_dice = Some(roll);
+    }
+
+    pub fn get_log(&self) -> &[String] {
+        &self.log
+    }
+
+    pub fn get_stats(&self) -> &DuelStats {
+        &self.stats
+    }
+
+    pub fn get_statuses(&self) -> &[StatusEffect] {
+        &self.statuses
+    }
+
+    pub fn get_status(&self, target: StatusTarget, kind: StatusKind) -> Option<&StatusEffect> {
+        self.statuses.iter().find(|status| status.target == target && status.kind == kind)
+    }
+
+    /// Put a status on a player or card instance for a number of turns (nothing happens for 0),
+    /// or stack it with the one already there
+    pub fn apply_status(&mut self, kind: StatusKind, target: StatusTarget, turns: u16) {
+        if turns == 0 {
+            return;
+        }
+        match self.statuses.iter_mut().find(|status| status.target == target && status.kind == kind) {
+            Some(status) => status.reapply(turns),
+            None => self.statuses.push(StatusEffect { kind, target, turns, stacks: 1 })
+        }
+    }
+
+    pub fn get_modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    /// Change one of the player's values for as long as the aura stays in play, or until the end of the turn without one
+    pub fn add_modifier(&mut self, player: usize, value: ModifiedValue, operation: ModifierOperation, source: Option<InstanceId>) {
+        let timestamp = self.modifiers.last().map_or(0, |modifier| modifier.timestamp + 1);
+        self.modifiers.push(Modifier { player, value, operation, source, timestamp });
+    }
+
+    /// The player's value after every modifier in effect has had its say
+    pub fn get_modified(&self, player: usize, value: &ModifiedValue, base: i32) -> i32 {
+        let in_effect = self.modifiers.iter().filter(|modifier| {
+            modifier.player == player && modifier.value.covers(value) && match modifier.source {
+                Some(aura) => self.is_in_play(aura),
+                None => true
+            }
+        });
+        apply_modifiers(base, in_effect)
+    }
+
+    pub fn get_replacements(&self) -> &[ReplacementEffect] {
+        &self.replacements
+    }
+
+    /// Change an event that would happen to the player (or their cards) from now on.
+    /// With a number of uses, it lasts until they're used up; otherwise for as long as the aura stays in play, or until the end of the turn without one.
+    pub fn add_replacement(&mut self, player: usize, event: ReplacedEvent, outcome: ReplacementOutcome, uses: Option<u16>, source: Option<InstanceId>) {
+        let timestamp = self.replacements.last().map_or(0, |replacement| replacement.timestamp + 1);
+        self.replacements.push(ReplacementEffect { player, event, outcome, uses, source, timestamp });
+    }
+
+    /// Run an event past every replacement effect in effect, oldest first.
+    /// Each effect changes the event at most once, so effects that undo each other can't loop.
+    /// Returns what happens instead, or `None` when nothing does.
+    fn replace_event(&mut self, mut event: Event) -> Option<Event> {
+        let mut applied: Vec<u32> = vec![ ];
+        loop {
+            let next = self.replacements.iter().position(|replacement| {
+                !applied.contains(&replacement.timestamp)
+                    && replacement.source.is_none_or(|aura| self.is_in_play(aura))
+                    && replacement.watches(&event)
+            });
+            let index = match next {
+                Some(index) => index,
+                None => return Some(event)
+            };
+
+            let replacement = self.replacements[index].clone();
+            applied.push(replacement.timestamp);
+            if let Some(uses) = replacement.uses {
+                if uses <= 1 {
+                    self.replacements.remove(index);
+                } else {
+                    self.replacements[index].uses = Some(uses - 1);
+                }
+            }
+
+            let replaced = replacement.replace(event.clone());
+            self.log.push(self.describe_replacement(&event, replaced.as_ref()));
+            event = replaced?;
+        }
+    }
+
+    fn describe_replacement(&self, event: &Event, replaced: Option<&Event>) -> String {
+        match (event, replaced) {
+            (Event::Damage { player, amount, damage_type }, None) => {
+                format!("{} {} damage to {} is prevented", amount, damage_type, self.players[*player].get_name())
+            },
+            (Event::Damage { player, amount, damage_type }, Some(Event::Damage { player: to, .. })) => {
+                format!("{} {} damage to {} goes to {} instead", amount, damage_type, self.players[*player].get_name(), self.players[*to].get_name())
+            },
+            (Event::Move { instance, to, .. }, Some(Event::Move { to: instead, .. })) => {
+                format!("card {} goes to {} instead of {}", instance.0, instead, to)
+            },
+            _ => String::from("an event is replaced")
+        }
+    }
+
+    /// Let damage interact with every status the player has, as the interaction table says, and explain each in the log
+    fn interact(&mut self, player: usize, mut amount: i32, damage_type: &DamageType) -> i32 {
+        let target = StatusTarget::Player(player);
+        let statuses: Vec<StatusKind> = self.statuses.iter().filter(|status| status.target == target).map(|status| status.kind).collect();
+        let interactions = Arc::clone(&self.interactions);
+
+        for status in statuses {
+            for interaction in interactions.find(damage_type, status) {
+                // an earlier interaction may have removed the status since
+                if self.get_status(target, status).is_none() {
+                    break;
+                }
+                let before = amount;
+                for &effect in &interaction.effects {
+                    match effect {
+                        InteractionEffect::Amount(operation) => amount = operation.apply(amount),
+                        InteractionEffect::Remove(kind) => self.statuses.retain(|status| status.target != target || status.kind != kind),
+                        InteractionEffect::Apply(kind, turns) => self.apply_status(kind, target, turns)
+                    }
+                }
+                let effects: Vec<String> = interaction.effects.iter().map(InteractionEffect::to_string).collect();
+                self.log.push(format!("{} damage meets {} on {} ({}): {} becomes {}", damage_type, status, self.players[player].get_name(), effects.join(", "), before, amount));
+            }
+        }
+        amount
+    }
+
+    /// Move one of the cards to another of its owner's zones, unless a replacement effect sends it elsewhere
+    fn move_card(&mut self, instance: InstanceId, to: Zone) {
+        let owner = self.instances[instance.0 as usize].owner;
+        let to = match self.replace_event(Event::Move { instance, owner, to }) {
+            Some(Event::Move { to, .. }) => to,
+            _ => to
+        };
+        let zones = self.players[owner].get_zones_mut();
+        // a card coming off the stack isn't in any zone yet
+        if !zones.move_to(instance, to) {
+            zones.get_mut(to).push(instance);
+        }
+    }
+
+    fn is_in_play(&self, instance: InstanceId) -> bool {
+        let owner = self.instances[instance.0 as usize].owner;
+        self.players[owner].get_zones().get(Zone::Play).contains(&instance)
+    }
+
+    /// Player whose turns a status follows
+    fn get_affected_player(&self, target: StatusTarget) -> usize {
+        match target {
+            StatusTarget::Player(player) => player,
+            StatusTarget::Instance(instance) => self.instances[instance.0 as usize].owner
+        }
+    }
+
+    /// The status stopping the player from playing cards (or this card in particular), if any
+    fn get_preventing_status(&self, player: usize, instance: Option<InstanceId>) -> Option<StatusKind> {
+        self.statuses.iter()
+            .find(|status| {
+                let affected = match status.target {
+                    StatusTarget::Player(p) => p == player,
+                    StatusTarget::Instance(i) => Some(i) == instance
+                };
+                affected && status.get_rules().prevents_plays
+            })
+            .map(|status| status.kind)
+    }
+
+    /// Deal damage from every status on the active player (or their cards) that ticks in this phase.
+    /// At the end of their turn, those statuses also get a turn closer to wearing off.
+    fn tick_statuses(&mut self, phase: Phase) {
+        // the damage can set off interactions that remove statuses, so go by what was ticking before any of it
+        let ticking: Vec<(StatusTarget, StatusKind, TickDamage)> = self.statuses.iter()
+            .filter(|status| self.get_affected_player(status.target) == self.active_player)
+            .filter_map(|status| match status.get_rules().tick {
+                Some(tick) if tick.phase == phase => Some((status.target, status.kind, tick)),
+                _ => None
+            })
+            .collect();
+
+        for (target, kind, tick) in ticking {
+            let Some(&status) = self.get_status(target, kind) else {
+                continue;
+            };
+            let player = self.get_affected_player(target);
+            let amount: i32 = (0 .. status.stacks)
+                .map(|_| tick.dice.map(|dice| self.roll(dice)).unwrap_or(1) as i32)
+                .sum();
+            self.damage_player(player, amount, tick.damage_type.clone());
+            self.log.push(format!("{} takes {} {} damage from being {}", self.players[player].get_name(), amount, tick.damage_type, status.kind));
+        }
+
+        if phase == Phase::End {
+            let active_player = self.active_player;
+            for index in 0 .. self.statuses.len() {
+                if self.get_affected_player(self.statuses[index].target) == active_player {
+                    self.statuses[index].turns -= 1;
+                }
+            }
+            self.statuses.retain(|status| status.turns > 0);
+        }
+    }
+
+    /// Roll a die with the duel's dice, unless they've been fixed
+    fn roll(&mut self, dice: Dice) -> u16 {
+        if let Some(roll) = self.fixed_dice {
+            return roll(dice);
+        }
+        dice.roll(&mut self.dice_roller)
+    }
+
+    /// Damage after replacement effects (which may prevent or redirect it), interactions with the player's statuses,
+    /// the damage type's default resistance or vulnerability and the player's modifiers to damage taken
+    fn damage_player(&mut self, player: usize, amount: i32, damage_type: DamageType) {
+        if player >= self.players.len() {
+            return;
+        }
+        // nothing to replace when there's no damage to speak of
+        let player = if amount <= 0 { player } else {
+            match self.replace_event(Event::Damage { player, amount, damage_type: damage_type.clone() }) {
+                Some(Event::Damage { player, .. }) => player,
+                _ => return
+            }
+        };
+        let amount = self.interact(player, amount, &damage_type);
+        let amount = match self.damage_types.get_susceptibility(&damage_type).get_operation() {
+            Some(operation) => operation.apply(amount),
+            None => amount
+        };
+        let amount = self.get_modified(player, &ModifiedValue::DamageTaken(Some(damage_type.clone())), amount).max(0);
+        self.players[player].take_damage(amount);
+        self.stats.record_damage(damage_type, amount);
+    }
+
+    /// Create an instance of every card in the deck and shuffle them into the player's deck zone
+    pub fn add_deck(&mut self, player: usize, deck: &Deck) {
+        for card in deck.expand() {
+            let id = InstanceId(self.instances.len() as u32);
+            self.instances.push(CardInstance { id, card, owner: player });
+            self.players[player].get_zones_mut().get_mut(Zone::Deck).push(id);
+        }
+        self.shuffle_deck(player);
+    }
+
+    pub fn shuffle_deck(&mut self, player: usize) {
+        let deck = self.players[player].get_zones_mut().get_mut(Zone::Deck);
+        // Fisher-Yates
+        for i in (1 .. deck.len()).rev() {
+            let j = self.dice_roller.next_below(i as u64 + 1) as usize;
+            deck.swap(i, j);
+        }
+    }
+
+    /// Draw the top card of the player's deck, if there is one.
+    /// A replacement effect may send it somewhere other than their hand.
+    pub fn draw(&mut self, player: usize) -> Option<InstanceId> {
+        let instance = *self.players.get(player)?.get_zones().get(Zone::Deck).last()?;
+        self.move_card(instance, Zone::Hand);
+        Some(instance)
+    }
+
+    /// Begin the active player's turn: refresh their mana, tick their statuses, draw a card,
+    /// and put the `when turn start` abilities of their auras on the stack
+    pub fn start_turn(&mut self, library: &CardLibrary) {
+        self.turn += 1;
+        self.phase = Phase::Start;
+        if let Some(player) = self.players.get_mut(self.active_player) {
+            player.get_mana_mut().refresh();
+        }
+        self.tick_statuses(Phase::Start);
+        self.draw(self.active_player);
+        self.trigger(TriggerEvent::TurnStart, library);
+        self.phase = Phase::Main;
+    }
+
+    /// Tick the active player's statuses, then pass the turn to the next player
+    pub fn end_turn(&mut self) {
+        self.phase = Phase::End;
+        self.tick_statuses(Phase::End);
+        if !self.players.is_empty() {
+            self.active_player = (self.active_player + 1) % self.players.len();
+        }
+        self.priority = self.active_player;
+        self.passes = 0;
+        self.modifiers.retain(|modifier| modifier.source.is_some());
+        self.replacements.retain(|replacement| replacement.source.is_some() || replacement.uses.is_some());
+        self.phase = Phase::Start;
+    }
+
+    /// Play a card from the player's hand and resolve it straight away, without giving anyone the chance to respond.
+    /// Abilities it sets off go on the stack above it, so they resolve first.
+    pub fn play_from_hand(&mut self, player: usize, instance: InstanceId, library: &CardLibrary, controller: &mut dyn Controller) -> Result<(), PlayCardError> {
+        let below = self.stack.len();
+        self.cast(player, instance, library, controller)?;
+        while self.stack.len() > below {
+            self.resolve_top(library, controller);
+        }
+        Ok(())
+    }
+
+    /// Pay for a card in the player's hand and put it on the stack, then give the next player the chance to respond
+    pub fn cast(&mut self, player: usize, instance: InstanceId, library: &CardLibrary, controller: &mut dyn Controller) -> Result<(), PlayCardError> {
+        if player >= self.players.len() {
+            return Err(PlayCardError::InvalidPlayer(player));
+        }
+        if player != self.priority {
+            return Err(PlayCardError::NotYourTurn(player));
+        }
+        if !self.players[player].get_zones().get(Zone::Hand).contains(&instance) {
+            return Err(PlayCardError::NotInHand(instance));
+        }
+        let card_id = self.instances[instance.0 as usize].card;
+        let card = library.get(card_id).ok_or(PlayCardError::UnknownCard(card_id))?;
+        if !self.stack.is_empty() && !card.get_card().has_tag(REACTION_TAG) {
+            return Err(PlayCardError::NotAReaction(instance));
+        }
+        if let Some(status) = self.get_preventing_status(player, Some(instance)) {
+            return Err(PlayCardError::Prevented(status));
+        }
+
+        self.pay_cost(player, card.get_card(), controller)?;
+        self.players[player].get_zones_mut().get_mut(Zone::Hand).retain(|&i| i != instance);
+        self.stack.push(StackEntry { instance, player, ability: None });
+        self.stats.record_play(card_id);
+
+        // playing a card counts as passing on everything below it
+        self.passes = 1;
+        self.priority = (player + 1) % self.players.len();
+        self.trigger(TriggerEvent::Play, library);
+        Ok(())
+    }
+
+    /// Put every ability of an aura in play that the event sets off on the stack: the active player's first,
+    /// so theirs resolve last. `when turn start` only watches its owner's turn.
+    /// When anything triggers, everyone gets a say on the stack again, starting with the active player.
+    fn trigger(&mut self, event: TriggerEvent, library: &CardLibrary) {
+        let mut triggered = vec![ ];
+        for offset in 0 .. self.players.len() {
+            let player = (self.active_player + offset) % self.players.len();
+            if event == TriggerEvent::TurnStart && player != self.active_player {
+                continue;
+            }
+            for &instance in self.players[player].get_zones().get(Zone::Play) {
+                let Some(card) = library.get(self.instances[instance.0 as usize].card) else {
+                    continue;
+                };
+                for (ability, _) in card.get_card().get_abilities().iter().enumerate().filter(|(_, ability)| ability.event == event) {
+                    triggered.push(StackEntry { instance, player, ability: Some(ability) });
+                }
+            }
+        }
+        if triggered.is_empty() {
+            return;
+        }
+        self.stack.extend(triggered);
+        self.passes = 0;
+        self.priority = self.active_player;
+    }
+
+    /// The player with priority doesn't respond; the next player gets the chance to
+    pub fn pass_priority(&mut self) {
+        self.passes += 1;
+        self.priority = (self.priority + 1) % self.players.len();
+    }
+
+    /// Everyone has passed since the top of the stack was played
+    pub fn is_ready_to_resolve(&self) -> bool {
+        !self.stack.is_empty() && self.passes >= self.players.len()
+    }
+
+    /// Take the top card or ability off the stack and run it, with `controller` choosing for whoever played it.
+    /// Auras go into play, other cards to their owner's discard pile, and the active player gets priority back.
+    pub fn resolve_top(&mut self, library: &CardLibrary, controller: &mut dyn Controller) -> Option<StackEntry> {
+        let entry = self.stack.pop()?;
+        let card_id = self.instances[entry.instance.0 as usize].card;
+        if let Some(ability) = entry.ability {
+            // the ability resolves even if its aura has left play since, and leaves the aura where it is
+            if let Some(card) = library.get(card_id) {
+                let mut symbol_table = self.symbol_table_for(entry.player);
+                self.resolving = Some(entry.player);
+                card.get_card().execute_ability(ability, &mut symbol_table, &mut CardResolution { state: self, controller, player: entry.player, aura: None });
+                self.resolving = None;
+            }
+            self.passes = 0;
+            self.priority = self.active_player;
+            return Some(entry);
+        }
+        let mut aura = None;
+        if let Some(card) = library.get(card_id) {
+            aura = Some(entry.instance).filter(|_| card.get_card().has_tag(AURA_TAG));
+            let mut symbol_table = self.symbol_table_for(entry.player);
+            self.resolving = Some(entry.player);
+            card.get_card().execute(&mut symbol_table, &mut CardResolution { state: self, controller, player: entry.player, aura });
+            self.resolving = None;
+        }
+        match aura {
+            Some(instance) => self.move_card(instance, Zone::Play),
+            None => self.discard(entry.instance)
+        }
+
+        self.passes = 0;
+        self.priority = self.active_player;
+        Some(entry)
+    }
+
+    /// Resolve everything on the stack without anyone responding, e.g. on a copy of the duel
+    pub fn resolve_stack(&mut self, library: &CardLibrary, controller: &mut dyn Controller) {
+        while self.resolve_top(library, controller).is_some() { }
+    }
+
+    /// Put a card that left the stack into its owner's discard pile
+    fn discard(&mut self, instance: InstanceId) {
+        self.move_card(instance, Zone::Discard);
+    }
+
+    /// Built-in variables as seen by a card played by this player
+    pub fn symbol_table_for(&self, player: usize) -> SymbolTable {
+        let mut symbol_table = SymbolTable::with_built_ins();
+        if let Some(p) = self.players.get(player) {
+            let built_ins = [
+                ("$turn", self.turn as i32),
+                ("$mana", p.get_mana().get_available() as i32),
+                ("$discount", p.get_discount() as i32),
+            ];
+            for (name, value) in built_ins {
+                symbol_table.assign(name, ExpressionResult::Integer(value))
+                    .expect("Built-ins are always declared as integers.");
+            }
+        }
+        symbol_table
+    }
+
+    /// A property of a target as scripts read it, after modifiers.
+    /// The parser only lets scripts read properties players have, but a target can still come from anywhere
+    /// (a controller, a snapshot), so anything else reads as the property type's empty value.
+    pub fn get_property(&self, target: &ExpressionResult, property: &str) -> ExpressionResult {
+        let (index, player) = match *target {
+            ExpressionResult::Player(index) if index < self.players.len() => (index, &self.players[index]),
+            _ => return empty_property(property)
+        };
+        let base = match property {
+            "name" => return ExpressionResult::Text(Rc::from(player.get_name())),
+            "hp" => player.get_hp(),
+            "mana" => player.get_mana().get_available() as i32,
+            _ => return empty_property(property)
+        };
+        ExpressionResult::Integer(self.get_modified(index, &ModifiedValue::Property(Rc::from(property)), base))
+    }
+
+    /// What the card would cost the player right now.
+    /// Any dice in the cost are rolled on a copy of the duel's dice, so this never changes the game.
+    pub fn preview_cost(&self, player: usize, card: &Card) -> u16 {
+        let symbol_table = self.symbol_table_for(player);
+        let mut preview = CostPreview { state: self, dice_roller: self.dice_roller.clone() };
+        let cost = card.evaluate_cost(&symbol_table, &mut preview);
+        self.get_modified(player, &ModifiedValue::Cost, cost).clamp(0, u16::MAX as i32) as u16
+    }
+
+    /// Options for the player with priority. With an empty stack: every card in hand they can afford, or ending the turn.
+    /// Otherwise: every reaction they can afford, or passing. Conceding is always an option.
+    pub fn get_legal_actions(&self, library: &CardLibrary) -> Vec<Action> {
+        let player = &self.players[self.priority];
+        let responding = !self.stack.is_empty();
+        let mut actions: Vec<Action> = player.get_zones().get(Zone::Hand).iter()
+            .filter(|&&instance| {
+                let card_id = self.instances[instance.0 as usize].card;
+                library.get(card_id).is_some_and(|c| {
+                    (!responding || c.get_card().has_tag(REACTION_TAG))
+                        && self.get_preventing_status(self.priority, Some(instance)).is_none()
+                        && player.get_mana().can_pay(self.preview_cost(self.priority, c.get_card()))
+                })
+            })
+            .map(|&instance| Action::PlayCard(instance))
+            .collect();
+        actions.push(self.get_default_action());
+        actions.push(Action::Concede);
+        actions
+    }
+
+    /// What doing nothing means right now: passing while something is on the stack, otherwise ending the turn
+    pub fn get_default_action(&self) -> Action {
+        if self.stack.is_empty() {
+            return Action::EndTurn;
+        }
+        Action::Pass
+    }
+
+    /// Duel is over once a player is defeated
+    pub fn is_over(&self) -> bool {
+        self.players.iter().any(Player::is_defeated)
+    }
+
+    /// The only player left standing, if the duel is over and it wasn't a draw
+    pub fn get_winner(&self) -> Option<usize> {
+        let standing: Vec<usize> = (0 .. self.players.len()).filter(|&p| !self.players[p].is_defeated()).collect();
+        if self.is_over() && standing.len() == 1 {
+            return Some(standing[0]);
+        }
+        None
+    }
+
+    /// The player gives up, which defeats them on the spot
+    pub fn concede(&mut self, player: usize) {
+        if let Some(p) = self.players.get_mut(player) {
+            p.concede();
+        }
+    }
+
+    /// Check and pay the card's cost, then run its body.
+    /// Nothing happens (and no mana is spent) when the player can't afford it.
+    pub fn play_card(&mut self, player: usize, card: &Card, controller: &mut dyn Controller) -> Result<(), PlayCardError> {
+        if player >= self.players.len() {
+            return Err(PlayCardError::InvalidPlayer(player));
+        }
+        if player != self.active_player {
+            return Err(PlayCardError::NotYourTurn(player));
+        }
+        if let Some(status) = self.get_preventing_status(player, None) {
+            return Err(PlayCardError::Prevented(status));
+        }
+
+        self.pay_cost(player, card, controller)?;
+        let mut symbol_table = self.symbol_table_for(player);
+        card.execute(&mut symbol_table, &mut CardResolution { state: self, controller, player, aura: None });
+        Ok(())
+    }
+
+    /// Run script code as the player without any card, e.g. a line typed into the REPL.
+    /// Rolls use the duel's dice and choices go to the controller, as if the player had played a card.
+    pub fn run_script<R>(&mut self, player: usize, controller: &mut dyn Controller, run: impl FnOnce(&mut dyn ScriptContext) -> R) -> R {
+        run(&mut CardResolution { state: self, controller, player, aura: None })
+    }
+
+    /// Roll the cost and pay it. When the player can't, the dice are put back as they were,
+    /// so a rejected play leaves no trace on the duel (nor on a replay or snapshot of it).
+    fn pay_cost(&mut self, player: usize, card: &Card, controller: &mut dyn Controller) -> Result<(), PlayCardError> {
+        let dice_roller = self.dice_roller.clone();
+        let symbol_table = self.symbol_table_for(player);
+        let mut resolution = CardResolution { state: self, controller, player, aura: None };
+        let cost = card.evaluate_cost(&symbol_table, &mut resolution);
+
+        // costs can't go below zero, no matter how big the discount
+        let cost = self.get_modified(player, &ModifiedValue::Cost, cost).clamp(0, u16::MAX as i32) as u16;
+        if let Err(err) = self.players[player].get_mana_mut().pay(cost) {
+            self.dice_roller = dice_roller;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+/// What a property reads as on something that doesn't have it
+fn empty_property(property: &str) -> ExpressionResult {
+    match get_property_type(&ExpressionType::Player, property) {
+        Some(ExpressionType::Text) => ExpressionResult::Text(Rc::from("")),
+        _ => ExpressionResult::Integer(0)
+    }
+}
+
+/// Context for previewing a cost: it only reads the duel, rolls on its own copy of the dice and never asks anyone anything
+struct CostPreview<'a> {
+    state: &'a GameState,
+    dice_roller: DiceRoller
+}
+
+impl ScriptContext for CostPreview<'_> {
+    fn roll(&mut self, dice: Dice) -> u16 {
+        if let Some(roll) = self.state.fixed_dice {
+            return roll(dice);
+        }
+        dice.roll(&mut self.dice_roller)
+    }
+
+    fn choose_targets(&mut self, target_type: &ExpressionType, count: u16, up_to: bool) -> Vec<ExpressionResult> {
+        let candidates: Vec<ExpressionResult> = match target_type {
+            ExpressionType::Player => (0 .. self.state.players.len()).map(ExpressionResult::Player).collect(),
+            _ => vec![ ]
+        };
+        settle_targets(vec![ ], &candidates, count, up_to)
+    }
+
+    fn deal_damage(&mut self, _amount: i32, _damage_type: DamageType, _target: &ExpressionResult) {}
+
+    fn apply_status(&mut self, _status: StatusKind, _turns: i32, _target: &ExpressionResult) {}
+
+    fn add_modifier(&mut self, _value: ModifiedValue, _operation: ModifierOperation, _target: Option<&ExpressionResult>) {}
+
+    fn add_replacement(&mut self, _event: ReplacedEvent, _outcome: ReplacementOutcome, _uses: Option<u16>) {}
+
+    fn get_property(&mut self, target: &ExpressionResult, property: &str) -> ExpressionResult {
+        self.state.get_property(target, property)
+    }
+
+    fn log(&mut self, _message: &str) {}
+
+    fn counter(&mut self) -> bool {
+        false
+    }
+}
+
+/// Context for a card being played: rolls use the duel's dice, choices go to the player's controller
+struct CardResolution<'a> {
+    state: &'a mut GameState,
+    controller: &'a mut dyn Controller,
+    /// Player who played the card
+    player: usize,
+    /// The card itself, when it's an aura that will stay in play
+    aura: Option<InstanceId>
+}
+
+impl ScriptContext for CardResolution<'_> {
+    fn roll(&mut self, dice: Dice) -> u16 {
+        self.state.roll(dice)
+    }
+
+    fn choose_targets(&mut self, target_type: &ExpressionType, count: u16, up_to: bool) -> Vec<ExpressionResult> {
+        let candidates: Vec<ExpressionResult> = match target_type {
+            ExpressionType::Player => (0 .. self.state.players.len()).map(ExpressionResult::Player).collect(),
+            _ => vec![ ]
+        };
+        let chosen = self.controller.choose_targets(self.state, &candidates, count, up_to);
+        settle_targets(chosen, &candidates, count, up_to)
+    }
+
+    fn deal_damage(&mut self, amount: i32, damage_type: DamageType, target: &ExpressionResult) {
+        if let ExpressionResult::Player(index) = target {
+            let amount = self.state.get_modified(self.player, &ModifiedValue::DamageDealt(Some(damage_type.clone())), amount);
+            self.state.damage_player(*index, amount, damage_type);
+        }
+    }
+
+    fn add_replacement(&mut self, event: ReplacedEvent, outcome: ReplacementOutcome, uses: Option<u16>) {
+        if let ReplacementOutcome::Redirect(player) = outcome {
+            if player >= self.state.players.len() {
+                return;
+            }
+        }
+        self.state.add_replacement(self.player, event, outcome, uses, self.aura);
+    }
+
+    fn add_modifier(&mut self, value: ModifiedValue, operation: ModifierOperation, target: Option<&ExpressionResult>) {
+        let player = match target {
+            Some(ExpressionResult::Player(index)) if *index < self.state.players.len() => *index,
+            Some(_) => return,
+            None => self.player
+        };
+        self.state.add_modifier(player, value, operation, self.aura);
+    }
+
+    fn apply_status(&mut self, status: StatusKind, turns: i32, target: &ExpressionResult) {
+        if let ExpressionResult::Player(index) = target {
+            if *index < self.state.players.len() {
+                self.state.apply_status(status, StatusTarget::Player(*index), turns.clamp(0, u16::MAX as i32) as u16);
+            }
+        }
+    }
+
+    fn get_property(&mut self, target: &ExpressionResult, property: &str) -> ExpressionResult {
+        self.state.get_property(target, property)
+    }
+
+    fn log(&mut self, message: &str) {
+        self.state.log.push(message.to_string());
+    }
+
+    fn counter(&mut self) -> bool {
+        match self.state.stack.pop() {
+            Some(entry) => {
+                // a countered ability just goes away; its aura stays in play
+                if entry.ability.is_none() {
+                    self.state.discard(entry.instance);
+                }
+                true
+            },
+            None => false
+        }
+    }
+}
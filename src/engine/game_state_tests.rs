@@ -0,0 +1,600 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use crate::engine::controller::{Action, Controller};
+    use crate::engine::game_state::{GameState, PlayCardError, StackEntry};
+    use crate::engine::interactions::InteractionTable;
+    use crate::engine::mana::{InsufficientMana, ManaPool};
+    use crate::engine::player::Player;
+    use crate::engine::snapshot::{load_snapshot, save_snapshot};
+    use crate::engine::status::{StatusEffect, StatusTarget};
+    use crate::game_zones::card_instance::InstanceId;
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::game_zones::dice_roller::DiceRoller;
+    use crate::game_zones::types::{DamageType, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+    use crate::game_zones::zone::Zone;
+    use crate::library::card_library::CardLibrary;
+    use crate::library::deck_list::{DeckList, DeckRules};
+    use crate::parsing::card::Card;
+    use crate::parsing::expressions::ExpressionResult;
+    use crate::parsing::parser::parse_card;
+    use crate::parsing::tokenizer::tokenize;
+    use test_case::test_case;
+
+    /// Always targets the opponent of player 0
+    struct TargetOpponent;
+
+    impl Controller for TargetOpponent {
+        fn choose_action(&mut self, _state: &GameState, _options: &[Action]) -> Action {
+            Action::EndTurn
+        }
+
+        fn choose_targets(&mut self, _state: &GameState, _candidates: &[ExpressionResult], count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+            vec![ ExpressionResult::Player(1); count as usize ]
+        }
+    }
+
+    fn card(script: &str) -> Card {
+        parse_card(tokenize(script).unwrap().into_iter()).unwrap()
+    }
+
+    fn new_game(mana: ManaPool) -> GameState {
+        let players = vec![ Player::new("Alice", 20, mana.clone()), Player::new("Bob", 20, mana) ];
+        GameState::new(players, DiceRoller::new(7))
+    }
+
+    #[test]
+    fn mana_grows_each_turn() {
+        let mut game = new_game(ManaPool::new(0, 1, 2));
+        for expected in [ 1, 2, 2 ] {
+            game.start_turn(&CardLibrary::default());
+            assert_eq!(game.get_player(0).unwrap().get_mana().get_available(), expected);
+        }
+    }
+
+    #[test]
+    fn play_affordable_card() {
+        let mut game = new_game(ManaPool::new(2, 0, 10));
+        let fireball = card("#attack [2]: { 5 fire => target(1 in Player); }");
+
+        game.play_card(0, &fireball, &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(0).unwrap().get_mana().get_available(), 0);
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 15);
+    }
+
+    #[test]
+    fn reject_unaffordable_card() {
+        let mut game = new_game(ManaPool::new(2, 0, 10));
+        let fireball = card("#attack [3]: { 5 fire => target(1 in Player); }");
+
+        let result = game.play_card(0, &fireball, &mut TargetOpponent);
+
+        let expected = PlayCardError::InsufficientMana(InsufficientMana { cost: 3, available: 2 });
+        assert_eq!(result, Err(expected.clone()));
+        assert_eq!(expected.to_string(), "card costs 3 mana, but only 2 is available");
+        // nothing was paid and nothing happened
+        assert_eq!(game.get_player(0).unwrap().get_mana().get_available(), 2);
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20);
+    }
+
+    #[test]
+    fn rejected_play_leaves_the_dice_alone() {
+        let mut game = new_game(ManaPool::new(2, 0, 10));
+        let expensive = card("[1d6 + 2]: { }");
+        let dice = game.get_dice_roller().clone();
+
+        assert!(matches!(game.play_card(0, &expensive, &mut TargetOpponent), Err(PlayCardError::InsufficientMana(_))));
+        assert_eq!(game.get_dice_roller(), &dice);
+    }
+
+    #[test]
+    fn preview_cost_leaves_the_dice_alone() {
+        let mut game = new_game(ManaPool::new(20, 0, 20));
+        let dice = game.get_dice_roller().clone();
+        let card = card("[2d6 + 1]: { }");
+
+        let preview = game.preview_cost(0, &card);
+        assert_eq!(game.get_dice_roller(), &dice);
+        assert!((3 ..= 13).contains(&preview));
+
+        // the preview rolls the same dice the play would
+        game.play_card(0, &card, &mut TargetOpponent).unwrap();
+        assert_eq!(game.get_players()[0].get_mana().get_available(), 20 - preview);
+    }
+
+    #[test_case(ExpressionResult::Player(1), "hp", ExpressionResult::Integer(20) ; "Player")]
+    #[test_case(ExpressionResult::Player(5), "hp", ExpressionResult::Integer(0) ; "Missing player")]
+    #[test_case(ExpressionResult::Integer(1), "name", ExpressionResult::Text("".into()) ; "Not a player")]
+    #[test_case(ExpressionResult::Player(0), "armor", ExpressionResult::Integer(0) ; "Unknown property")]
+    fn read_property(target: ExpressionResult, property: &str, expected: ExpressionResult) {
+        let mut game = new_game(ManaPool::default());
+
+        let value = game.run_script(0, &mut TargetOpponent, |context| context.get_property(&target, property));
+
+        assert_eq!(value, expected);
+    }
+
+    #[test_case(0, 3 ; "No discount")]
+    #[test_case(2, 1 ; "Some discount")]
+    #[test_case(5, 0 ; "Cost never goes negative")]
+    fn dynamic_cost(discount: u16, expected_cost: u16) {
+        let mut game = new_game(ManaPool::new(3, 0, 10));
+        game.get_player_mut(0).unwrap().set_discount(discount);
+        let spell = card("[3 - $discount]: { }");
+
+        game.play_card(0, &spell, &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(0).unwrap().get_mana().get_available(), 3 - expected_cost);
+    }
+
+    #[test]
+    fn only_active_player_plays_cards() {
+        let mut game = new_game(ManaPool::default());
+        let spell = card("[0]: { }");
+
+        assert_eq!(game.play_card(1, &spell, &mut TargetOpponent), Err(PlayCardError::NotYourTurn(1)));
+        assert_eq!(game.play_card(2, &spell, &mut TargetOpponent), Err(PlayCardError::InvalidPlayer(2)));
+    }
+
+    fn stack_library() -> CardLibrary {
+        let sources = [
+            ("bolt.card", "@name \"Bolt\" [1]: { log \"bolt\"; 5 lightning => target(1 in Player); }"),
+            ("shout.card", "@name \"Shout\" #reaction [0]: { log \"shout\"; }"),
+            ("negate.card", "@name \"Negate\" #reaction [1]: { counter; }")
+        ];
+        CardLibrary::from_sources(sources.map(|(path, script)| (PathBuf::from(path), String::from(script)))).unwrap()
+    }
+
+    /// Alice holds two Bolts, Bob holds one of each reaction; it's Alice's turn
+    fn stack_game(library: &CardLibrary) -> GameState {
+        let mut game = new_game(ManaPool::new(2, 0, 10));
+        let rules = DeckRules { min_size: 1, ..DeckRules::default() };
+        game.add_deck(0, &DeckList::parse("2 Bolt").unwrap().validate(library, &rules).unwrap());
+        game.add_deck(1, &DeckList::parse("1 Shout\n1 Negate").unwrap().validate(library, &rules).unwrap());
+        for player in [ 0, 0, 1, 1 ] {
+            game.draw(player);
+        }
+        game
+    }
+
+    fn in_hand(game: &GameState, library: &CardLibrary, player: usize, name: &str) -> InstanceId {
+        *game.get_player(player).unwrap().get_zones().get(Zone::Hand).iter()
+            .find(|&&i| library.get(game.get_instance(i).unwrap().card).unwrap().get_name() == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn played_card_waits_for_responses() {
+        let library = stack_library();
+        let mut game = stack_game(&library);
+        let bolt = in_hand(&game, &library, 0, "Bolt");
+
+        game.cast(0, bolt, &library, &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_stack(), &[ StackEntry { instance: bolt, player: 0, ability: None } ]);
+        assert_eq!(game.get_priority_player(), 1);
+        assert!(!game.is_ready_to_resolve());
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20);
+        assert_eq!(game.get_legal_actions(&library), vec![
+            Action::PlayCard(in_hand(&game, &library, 1, "Shout")),
+            Action::PlayCard(in_hand(&game, &library, 1, "Negate")),
+            Action::Pass,
+            Action::Concede
+        ]);
+
+        game.pass_priority();
+        assert!(game.is_ready_to_resolve());
+        game.resolve_top(&library, &mut TargetOpponent);
+
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 15);
+        assert!(game.get_stack().is_empty());
+        assert_eq!(game.get_priority_player(), 0);
+        assert_eq!(game.get_player(0).unwrap().get_zones().get(Zone::Discard), &[ bolt ]);
+    }
+
+    #[test]
+    fn last_in_first_out() {
+        let library = stack_library();
+        let mut game = stack_game(&library);
+
+        game.cast(0, in_hand(&game, &library, 0, "Bolt"), &library, &mut TargetOpponent).unwrap();
+        game.cast(1, in_hand(&game, &library, 1, "Shout"), &library, &mut TargetOpponent).unwrap();
+        assert_eq!(game.get_priority_player(), 0);
+        game.pass_priority();
+        assert!(game.is_ready_to_resolve());
+        game.resolve_stack(&library, &mut TargetOpponent);
+
+        assert_eq!(game.get_log(), &[ "shout", "bolt" ]);
+    }
+
+    #[test]
+    fn counter_removes_the_card_below() {
+        let library = stack_library();
+        let mut game = stack_game(&library);
+        let bolt = in_hand(&game, &library, 0, "Bolt");
+        let negate = in_hand(&game, &library, 1, "Negate");
+
+        game.cast(0, bolt, &library, &mut TargetOpponent).unwrap();
+        game.cast(1, negate, &library, &mut TargetOpponent).unwrap();
+        game.pass_priority();
+        game.resolve_top(&library, &mut TargetOpponent);
+
+        assert!(game.get_stack().is_empty());
+        assert!(game.get_log().is_empty());
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20);
+        assert_eq!(game.get_player(0).unwrap().get_zones().get(Zone::Discard), &[ bolt ]);
+        assert_eq!(game.get_player(1).unwrap().get_zones().get(Zone::Discard), &[ negate ]);
+        assert_eq!(game.get_player(1).unwrap().get_mana().get_available(), 1);
+    }
+
+    #[test]
+    fn only_reactions_in_response() {
+        let library = stack_library();
+        let mut game = stack_game(&library);
+        game.cast(0, in_hand(&game, &library, 0, "Bolt"), &library, &mut TargetOpponent).unwrap();
+        let second_bolt = in_hand(&game, &library, 0, "Bolt");
+
+        assert_eq!(game.cast(0, second_bolt, &library, &mut TargetOpponent), Err(PlayCardError::NotYourTurn(0)));
+        game.cast(1, in_hand(&game, &library, 1, "Shout"), &library, &mut TargetOpponent).unwrap();
+        assert_eq!(game.cast(0, second_bolt, &library, &mut TargetOpponent), Err(PlayCardError::NotAReaction(second_bolt)));
+        // a Bolt is no answer, so all Alice can do is let things resolve
+        assert_eq!(game.get_legal_actions(&library), vec![ Action::Pass, Action::Concede ]);
+    }
+
+    #[test]
+    fn stack_survives_snapshots() {
+        let library = stack_library();
+        let mut game = stack_game(&library);
+        game.cast(0, in_hand(&game, &library, 0, "Bolt"), &library, &mut TargetOpponent).unwrap();
+        game.cast(1, in_hand(&game, &library, 1, "Shout"), &library, &mut TargetOpponent).unwrap();
+
+        let loaded = load_snapshot(&save_snapshot(&game)).unwrap();
+
+        assert_eq!(loaded.get_stack(), game.get_stack());
+        assert_eq!(loaded.get_priority_player(), 0);
+        assert_eq!(loaded.get_passes(), 1);
+    }
+
+    #[test]
+    fn burning_ticks_at_the_start_of_each_turn() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.fix_dice(|_| 3);
+        game.play_card(0, &card("[0]: { apply burning 2 => target(1 in Player); apply burning 1 => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        // reapplying keeps the longer duration, but burning never stacks
+        let status = StatusEffect { kind: StatusKind::Burning, target: StatusTarget::Player(1), turns: 2, stacks: 1 };
+        assert_eq!(game.get_statuses(), &[ status ]);
+
+        let mut hp = vec![ ];
+        for _ in 0 .. 6 {
+            game.end_turn();
+            game.start_turn(&CardLibrary::default());
+            hp.push(game.get_player(1).unwrap().get_hp());
+        }
+
+        assert_eq!(hp, vec![ 17, 17, 14, 14, 14, 14 ]);
+        assert!(game.get_statuses().is_empty());
+        assert_eq!(game.get_log(), &[ "Bob takes 3 fire damage from being burning", "Bob takes 3 fire damage from being burning" ]);
+    }
+
+    #[test]
+    fn poison_stacks_and_ticks_at_the_end_of_turn() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        let poison = card("[0]: { apply poisoned 1 => target(1 in Player); }");
+        game.play_card(0, &poison, &mut TargetOpponent).unwrap();
+        game.play_card(0, &poison, &mut TargetOpponent).unwrap();
+        game.end_turn();
+        game.start_turn(&CardLibrary::default());
+
+        assert_eq!(game.get_status(StatusTarget::Player(1), StatusKind::Poisoned).map(|s| s.stacks), Some(2));
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20);
+        game.end_turn();
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 18);
+        assert!(game.get_statuses().is_empty());
+    }
+
+    #[test]
+    fn frozen_cards_and_players_cant_be_played() {
+        let library = stack_library();
+        let mut game = stack_game(&library);
+        let hand = game.get_player(0).unwrap().get_zones().get(Zone::Hand).to_vec();
+
+        game.apply_status(StatusKind::Frozen, StatusTarget::Instance(hand[0]), 1);
+        assert_eq!(game.get_legal_actions(&library), vec![ Action::PlayCard(hand[1]), Action::EndTurn, Action::Concede ]);
+        assert_eq!(game.cast(0, hand[0], &library, &mut TargetOpponent), Err(PlayCardError::Prevented(StatusKind::Frozen)));
+
+        game.apply_status(StatusKind::Frozen, StatusTarget::Player(0), 1);
+        assert_eq!(game.get_legal_actions(&library), vec![ Action::EndTurn, Action::Concede ]);
+        assert_eq!(game.cast(0, hand[1], &library, &mut TargetOpponent), Err(PlayCardError::Prevented(StatusKind::Frozen)));
+
+        // both wear off at the end of Alice's turn
+        game.end_turn();
+        assert!(game.get_statuses().is_empty());
+    }
+
+    #[test]
+    fn auras_stay_in_play_and_layer_their_modifiers() {
+        let sources = [
+            // written out of order: additions still come before multiplications
+            ("pyromancy.card", "@name \"Pyromancy\" #aura [0]: { modify fire damage * 2; modify fire damage + 2; modify cost - 1; }"),
+            ("scorch.card", "@name \"Scorch\" [2]: { 3 fire => target(1 in Player); 3 acid => target(1 in Player); }")
+        ];
+        let library = CardLibrary::from_sources(sources.map(|(path, script)| (PathBuf::from(path), String::from(script)))).unwrap();
+        let mut game = new_game(ManaPool::new(1, 0, 10));
+        let rules = DeckRules { min_size: 1, ..DeckRules::default() };
+        game.add_deck(0, &DeckList::parse("1 Pyromancy\n1 Scorch").unwrap().validate(&library, &rules).unwrap());
+        game.draw(0);
+        game.draw(0);
+        let pyromancy = in_hand(&game, &library, 0, "Pyromancy");
+        let scorch = in_hand(&game, &library, 0, "Scorch");
+        assert_eq!(game.get_legal_actions(&library), vec![ Action::PlayCard(pyromancy), Action::EndTurn, Action::Concede ]);
+
+        game.play_from_hand(0, pyromancy, &library, &mut TargetOpponent).unwrap();
+        game.end_turn();
+        game.end_turn();
+        game.play_from_hand(0, scorch, &library, &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(0).unwrap().get_zones().get(Zone::Play), &[ pyromancy ]);
+        assert_eq!(game.get_player(0).unwrap().get_mana().get_available(), 0);
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20 - (3 + 2) * 2 - 3);
+    }
+
+    fn trigger_library() -> CardLibrary {
+        let sources = [
+            ("echo.card", "@name \"Echo\" #aura [0]: { } when play { log \"echo\"; }"),
+            ("kindling.card", "@name \"Kindling\" #aura [0]: { } when turn start { 1 fire => target(1 in Player); }"),
+            ("bolt.card", "@name \"Bolt\" [1]: { log \"bolt\"; }"),
+            ("negate.card", "@name \"Negate\" #reaction [1]: { counter; }")
+        ];
+        CardLibrary::from_sources(sources.map(|(path, script)| (PathBuf::from(path), String::from(script)))).unwrap()
+    }
+
+    /// Alice has Echo and Kindling in play and holds a Bolt, Bob holds a Negate; it's Alice's turn
+    fn trigger_game(library: &CardLibrary) -> GameState {
+        let mut game = new_game(ManaPool::new(2, 0, 10));
+        let rules = DeckRules { min_size: 1, ..DeckRules::default() };
+        game.add_deck(0, &DeckList::parse("1 Echo\n1 Kindling\n1 Bolt").unwrap().validate(library, &rules).unwrap());
+        game.add_deck(1, &DeckList::parse("1 Negate").unwrap().validate(library, &rules).unwrap());
+        for player in [ 0, 0, 0, 1 ] {
+            game.draw(player);
+        }
+        for name in [ "Echo", "Kindling" ] {
+            game.play_from_hand(0, in_hand(&game, library, 0, name), library, &mut TargetOpponent).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn triggered_abilities_wait_on_the_stack() {
+        let library = trigger_library();
+        let mut game = trigger_game(&library);
+        let echo = game.get_player(0).unwrap().get_zones().get(Zone::Play)[0];
+        let bolt = in_hand(&game, &library, 0, "Bolt");
+        // Echo saw Kindling being played
+        assert_eq!(game.get_log(), &[ "echo" ]);
+
+        game.cast(0, bolt, &library, &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_stack(), &[ StackEntry { instance: bolt, player: 0, ability: None }, StackEntry { instance: echo, player: 0, ability: Some(0) } ]);
+        // everyone gets a say on the ability, starting with the active player
+        assert_eq!(game.get_priority_player(), 0);
+        assert_eq!(game.get_passes(), 0);
+        game.pass_priority();
+        game.pass_priority();
+        game.resolve_top(&library, &mut TargetOpponent);
+        game.pass_priority();
+        game.pass_priority();
+        game.resolve_top(&library, &mut TargetOpponent);
+
+        assert_eq!(game.get_log(), &[ "echo", "echo", "bolt" ]);
+        assert_eq!(game.get_player(0).unwrap().get_zones().get(Zone::Play).len(), 2);
+    }
+
+    #[test]
+    fn countered_abilities_leave_their_aura_in_play() {
+        let library = trigger_library();
+        let mut game = trigger_game(&library);
+        let echo = game.get_player(0).unwrap().get_zones().get(Zone::Play)[0];
+
+        game.cast(0, in_hand(&game, &library, 0, "Bolt"), &library, &mut TargetOpponent).unwrap();
+        game.pass_priority();
+        game.cast(1, in_hand(&game, &library, 1, "Negate"), &library, &mut TargetOpponent).unwrap();
+        // Negate set off Echo too: Bolt, Echo, Negate, Echo
+        assert_eq!(game.get_stack().len(), 4);
+        game.resolve_stack(&library, &mut TargetOpponent);
+
+        assert_eq!(game.get_log(), &[ "echo", "echo", "bolt" ]);
+        assert!(game.get_player(0).unwrap().get_zones().get(Zone::Play).contains(&echo));
+    }
+
+    #[test]
+    fn turn_start_abilities_trigger_on_their_owners_turn() {
+        let library = trigger_library();
+        let mut game = trigger_game(&library);
+
+        game.end_turn();
+        game.start_turn(&library);
+        assert!(game.get_stack().is_empty());
+        game.end_turn();
+        game.start_turn(&library);
+
+        let kindling = game.get_player(0).unwrap().get_zones().get(Zone::Play)[1];
+        assert_eq!(game.get_stack(), &[ StackEntry { instance: kindling, player: 0, ability: Some(0) } ]);
+        assert_eq!(game.get_legal_actions(&library), vec![ Action::Pass, Action::Concede ]);
+        game.resolve_stack(&library, &mut TargetOpponent);
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 19);
+    }
+
+    #[test]
+    fn modifiers_without_an_aura_last_until_the_end_of_turn() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.play_card(0, &card("[0]: { modify hp + 5 => target(1 in Player); modify damage = 1; log \"{target(1 in Player).hp}\"; 9 => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_log(), &[ "25" ]);
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 19);
+        game.end_turn();
+        assert!(game.get_modifiers().is_empty());
+        assert_eq!(game.get_modified(1, &ModifiedValue::Property("hp".into()), 19), 19);
+    }
+
+    #[test]
+    fn resistance_reduces_damage_taken() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.add_modifier(1, ModifiedValue::DamageTaken(Some(DamageType::new("ice"))), ModifierOperation::Divide(2), None);
+
+        game.play_card(0, &card("[0]: { 5 ice => target(1 in Player); 5 fire => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20 - 2 - 5);
+        assert_eq!(game.get_stats().get_damage().get(&DamageType::new("ice")), Some(&2));
+    }
+
+    #[test]
+    fn shield_prevents_only_the_next_fire_damage() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.play_card(0, &card("[0]: { 1 => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+        game.add_replacement(1, ReplacedEvent::Damage(Some(DamageType::new("fire"))), ReplacementOutcome::Prevent, Some(1), None);
+
+        game.play_card(0, &card("[0]: { 3 ice => target(1 in Player); 5 fire => target(1 in Player); 5 fire => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20 - 1 - 3 - 5);
+        assert!(game.get_replacements().is_empty());
+        assert_eq!(game.get_log(), &[ "5 fire damage to Bob is prevented" ]);
+    }
+
+    #[test]
+    fn discarded_cards_are_exiled_instead() {
+        let library = stack_library();
+        let mut game = stack_game(&library);
+        let bolt = in_hand(&game, &library, 0, "Bolt");
+        game.play_card(0, &card("[0]: { instead discard exile; }"), &mut TargetOpponent).unwrap();
+
+        game.play_from_hand(0, bolt, &library, &mut TargetOpponent).unwrap();
+
+        let zones = game.get_player(0).unwrap().get_zones();
+        assert_eq!(zones.get(Zone::Exile), &[ bolt ]);
+        assert!(zones.get(Zone::Discard).is_empty());
+        // without an aura or a number of uses, it's gone at the end of the turn
+        game.end_turn();
+        assert!(game.get_replacements().is_empty());
+    }
+
+    #[test]
+    fn each_replacement_applies_once_per_event() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        // Bob and Alice send damage to each other
+        game.add_replacement(1, ReplacedEvent::Damage(None), ReplacementOutcome::Redirect(0), None, None);
+        game.add_replacement(0, ReplacedEvent::Damage(None), ReplacementOutcome::Redirect(1), None, None);
+
+        game.play_card(0, &card("[0]: { 5 => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(0).unwrap().get_hp(), 20);
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 15);
+        assert_eq!(game.get_log(), &[ "5 none damage to Bob goes to Alice instead", "5 none damage to Alice goes to Bob instead" ]);
+    }
+
+    #[test]
+    fn oldest_replacement_goes_first() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.add_replacement(1, ReplacedEvent::Damage(None), ReplacementOutcome::Redirect(0), Some(1), None);
+        game.add_replacement(1, ReplacedEvent::Damage(None), ReplacementOutcome::Prevent, Some(1), None);
+
+        game.play_card(0, &card("[0]: { 5 => target(1 in Player); 5 => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        // the first hit goes to Alice before the shield gets a say, so the shield stops the second
+        assert_eq!(game.get_player(0).unwrap().get_hp(), 15);
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20);
+    }
+
+    fn elemental_game() -> GameState {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        let table = InteractionTable::parse("fire frozen: multiply 2, remove frozen
+ice wet: apply frozen 2").unwrap();
+        game.set_interactions(Arc::new(table));
+        game
+    }
+
+    #[test]
+    fn fire_melts_frozen_targets_for_double_damage() {
+        let mut game = elemental_game();
+        game.apply_status(StatusKind::Frozen, StatusTarget::Player(1), 2);
+
+        game.play_card(0, &card("[0]: { 3 fire => target(1 in Player); 3 fire => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        // only the first hit meets the ice
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20 - 6 - 3);
+        assert_eq!(game.get_status(StatusTarget::Player(1), StatusKind::Frozen), None);
+        assert_eq!(game.get_log(), &[ "fire damage meets frozen on Bob (multiply 2, remove frozen): 3 becomes 6" ]);
+    }
+
+    #[test_case("fire burning: remove frozen\nfire frozen: multiply 2" ; "By another status")]
+    #[test_case("fire frozen: remove frozen\nfire frozen: multiply 2" ; "By the same status")]
+    fn removed_statuses_stop_interacting(table: &str) {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.set_interactions(Arc::new(InteractionTable::parse(table).unwrap()));
+        game.apply_status(StatusKind::Burning, StatusTarget::Player(1), 2);
+        game.apply_status(StatusKind::Frozen, StatusTarget::Player(1), 2);
+
+        game.play_card(0, &card("[0]: { 3 fire => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 17);
+        assert_eq!(game.get_status(StatusTarget::Player(1), StatusKind::Frozen), None);
+        assert_eq!(game.get_log().len(), 1);
+    }
+
+    #[test]
+    fn statuses_removed_by_a_tick_stop_ticking() {
+        let mut game = elemental_game();
+        game.fix_dice(|_| 3);
+        game.apply_status(StatusKind::Burning, StatusTarget::Player(1), 2);
+        game.apply_status(StatusKind::Frozen, StatusTarget::Player(1), 2);
+
+        game.end_turn();
+        game.start_turn(&CardLibrary::default());
+
+        // the burn melts the ice, which takes it out of the statuses still to tick
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20 - 6);
+        assert_eq!(game.get_status(StatusTarget::Player(1), StatusKind::Frozen), None);
+        assert!(game.get_status(StatusTarget::Player(1), StatusKind::Burning).is_some());
+    }
+
+    #[test]
+    fn ice_freezes_wet_targets() {
+        let mut game = elemental_game();
+        game.apply_status(StatusKind::Wet, StatusTarget::Player(1), 1);
+
+        game.play_card(0, &card("[0]: { 2 ice => target(1 in Player); 2 fire => target(0 in Player); }"), &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 18);
+        assert_eq!(game.get_status(StatusTarget::Player(1), StatusKind::Frozen).map(|status| status.turns), Some(2));
+        assert!(game.get_status(StatusTarget::Player(1), StatusKind::Wet).is_some());
+        // interactions only look at the statuses of the player taking the damage
+        assert_eq!(game.get_log().len(), 1);
+    }
+
+    #[test]
+    fn no_interactions_without_a_table() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.apply_status(StatusKind::Frozen, StatusTarget::Player(1), 2);
+
+        game.play_card(0, &card("[0]: { 3 fire => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 17);
+        assert!(game.get_status(StatusTarget::Player(1), StatusKind::Frozen).is_some());
+    }
+
+    #[test]
+    fn damage_types_have_default_resistance_and_vulnerability() {
+        let mut game = new_game(ManaPool::new(5, 0, 10));
+        game.set_damage_types(Arc::new(DamageTypeRegistry::parse("psychic \"Psychic\" resistant\nacid \"Acid\" vulnerable").unwrap()));
+        game.add_modifier(1, ModifiedValue::DamageTaken(Some(DamageType::new("acid"))), ModifierOperation::Add(1), None);
+
+        game.play_card(0, &card("[0]: { 5 psychic => target(1 in Player); 3 acid => target(1 in Player); 3 fire => target(1 in Player); }"), &mut TargetOpponent).unwrap();
+
+        // the default comes before the player's own modifiers
+        assert_eq!(game.get_player(1).unwrap().get_hp(), 20 - 2 - 7 - 3);
+        assert_eq!(game.get_stats().get_damage().get(&DamageType::new("acid")), Some(&7));
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::game_zones::types::{DamageType, ModifierOperation, StatusKind};
+
+/// One thing that happens when damage of a type meets a status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractionEffect {
+    /// Change the amount of damage
+    Amount(ModifierOperation),
+    /// Take a status off the target
+    Remove(StatusKind),
+    /// Put a status on the target for a number of turns
+    Apply(StatusKind, u16)
+}
+
+impl Display for InteractionEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InteractionEffect::Amount(ModifierOperation::Set(amount)) => write!(f, "set {}", amount),
+            InteractionEffect::Amount(ModifierOperation::Add(amount)) => write!(f, "add {}", amount),
+            InteractionEffect::Amount(ModifierOperation::Multiply(amount)) => write!(f, "multiply {}", amount),
+            InteractionEffect::Amount(ModifierOperation::Divide(amount)) => write!(f, "divide {}", amount),
+            InteractionEffect::Remove(status) => write!(f, "remove {}", status),
+            InteractionEffect::Apply(status, turns) => write!(f, "apply {} {}", status, turns)
+        }
+    }
+}
+
+/// What happens when damage of a type hits a target with a status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interaction {
+    pub damage_type: DamageType,
+    pub status: StatusKind,
+    /// Applied in order
+    pub effects: Vec<InteractionEffect>
+}
+
+/// Same form as a line of the table
+impl Display for Interaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let effects: Vec<String> = self.effects.iter().map(InteractionEffect::to_string).collect();
+        write!(f, "{} {}: {}", self.damage_type, self.status, effects.join(", "))
+    }
+}
+
+#[derive(Debug)]
+pub enum InteractionsErrorKind {
+    Io(std::io::Error),
+    /// Line isn't `<damage type> <status>: <effect>, ...`
+    InvalidLine,
+    /// Not a lowercase name like `fire`
+    InvalidDamageType(String),
+    UnknownStatus(String),
+    /// Effect isn't `set|add|multiply|divide <n>`, `remove <status>` or `apply <status> <turns>`
+    InvalidEffect(String)
+}
+
+#[derive(Debug)]
+pub struct InteractionsError {
+    /// 1-based line of the file (0 when it's about the file as a whole)
+    pub line: usize,
+    pub kind: InteractionsErrorKind
+}
+
+impl Display for InteractionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            InteractionsErrorKind::Io(err) => write!(f, "{}", err),
+            InteractionsErrorKind::InvalidLine => write!(f, "expected '<damage type> <status>: <effect>, ...'"),
+            InteractionsErrorKind::InvalidDamageType(name) => write!(f, "invalid damage type '{}'", name),
+            InteractionsErrorKind::UnknownStatus(name) => write!(f, "unknown status '{}'", name),
+            InteractionsErrorKind::InvalidEffect(effect) => write!(f, "invalid effect '{}'", effect)
+        }
+    }
+}
+
+/// How damage types interact with statuses, e.g. `fire frozen: multiply 2, remove frozen`.
+/// The default table has no interactions at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InteractionTable {
+    interactions: Vec<Interaction>
+}
+
+impl InteractionTable {
+    pub fn new(interactions: Vec<Interaction>) -> Self {
+        InteractionTable { interactions }
+    }
+
+    /// One `<damage type> <status>: <effect>, ...` per line, with `//` comments
+    pub fn parse(source: &str) -> Result<Self, InteractionsError> {
+        let mut interactions = vec![ ];
+        for (index, content) in source.lines().enumerate() {
+            let line = index + 1;
+            let content = content.split("//").next().unwrap_or_default().trim();
+            if content.is_empty() {
+                continue;
+            }
+            let error = |kind| InteractionsError { line, kind };
+
+            let (key, effects) = content.split_once(':').ok_or(error(InteractionsErrorKind::InvalidLine))?;
+            let key: Vec<&str> = key.split_whitespace().collect();
+            let (damage_type, status) = match *key {
+                [ damage_type, status ] => (
+                    DamageType::try_from(damage_type).map_err(|_| error(InteractionsErrorKind::InvalidDamageType(damage_type.to_string())))?,
+                    StatusKind::try_from(status).map_err(|_| error(InteractionsErrorKind::UnknownStatus(status.to_string())))?
+                ),
+                _ => return Err(error(InteractionsErrorKind::InvalidLine))
+            };
+            let effects = effects.split(',')
+                .map(|effect| parse_effect(effect.trim()).ok_or_else(|| error(InteractionsErrorKind::InvalidEffect(effect.trim().to_string()))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            interactions.push(Interaction { damage_type, status, effects });
+        }
+        Ok(InteractionTable { interactions })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, InteractionsError> {
+        let source = std::fs::read_to_string(path).map_err(|err| InteractionsError { line: 0, kind: InteractionsErrorKind::Io(err) })?;
+        InteractionTable::parse(&source)
+    }
+
+    pub fn get_interactions(&self) -> &[Interaction] {
+        &self.interactions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interactions.is_empty()
+    }
+
+    /// Every interaction between the damage type and the status, in the order they were written
+    pub fn find<'t>(&'t self, damage_type: &'t DamageType, status: StatusKind) -> impl Iterator<Item=&'t Interaction> {
+        self.interactions.iter().filter(move |interaction| interaction.damage_type == *damage_type && interaction.status == status)
+    }
+}
+
+/// Same form `parse` reads
+impl Display for InteractionTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for interaction in &self.interactions {
+            writeln!(f, "{}", interaction)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_effect(effect: &str) -> Option<InteractionEffect> {
+    let parts: Vec<&str> = effect.split_whitespace().collect();
+    let effect = match *parts {
+        [ "set", amount ] => InteractionEffect::Amount(ModifierOperation::Set(amount.parse().ok()?)),
+        [ "add", amount ] => InteractionEffect::Amount(ModifierOperation::Add(amount.parse().ok()?)),
+        [ "multiply", amount ] => InteractionEffect::Amount(ModifierOperation::Multiply(amount.parse().ok()?)),
+        [ "divide", amount ] => InteractionEffect::Amount(ModifierOperation::Divide(amount.parse().ok()?)),
+        [ "remove", status ] => InteractionEffect::Remove(StatusKind::try_from(status).ok()?),
+        [ "apply", status, turns ] => InteractionEffect::Apply(StatusKind::try_from(status).ok()?, turns.parse().ok()?),
+        _ => return None
+    };
+    Some(effect)
+}
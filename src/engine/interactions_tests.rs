@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use crate::engine::interactions::{Interaction, InteractionEffect, InteractionTable, InteractionsErrorKind};
+    use crate::game_zones::types::{DamageType, ModifierOperation, StatusKind};
+    use test_case::test_case;
+
+    const TABLE: &str = "// fire melts ice\nfire frozen: multiply 2, remove frozen\n\nice wet: apply frozen 1 // soaked targets freeze\n";
+
+    #[test]
+    fn parse_table() {
+        let table = InteractionTable::parse(TABLE).unwrap();
+
+        assert_eq!(table.get_interactions(), &[
+            Interaction {
+                damage_type: DamageType::new("fire"),
+                status: StatusKind::Frozen,
+                effects: vec![ InteractionEffect::Amount(ModifierOperation::Multiply(2)), InteractionEffect::Remove(StatusKind::Frozen) ]
+            },
+            Interaction { damage_type: DamageType::new("ice"), status: StatusKind::Wet, effects: vec![ InteractionEffect::Apply(StatusKind::Frozen, 1) ] }
+        ]);
+    }
+
+    #[test]
+    fn find_matches_type_and_status() {
+        let table = InteractionTable::parse(TABLE).unwrap();
+
+        assert_eq!(table.find(&DamageType::new("fire"), StatusKind::Frozen).count(), 1);
+        assert_eq!(table.find(&DamageType::new("fire"), StatusKind::Wet).count(), 0);
+        assert_eq!(table.find(&DamageType::new("ice"), StatusKind::Frozen).count(), 0);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let table = InteractionTable::parse(TABLE).unwrap();
+
+        assert_eq!(InteractionTable::parse(&table.to_string()).unwrap(), table);
+        assert!(InteractionTable::parse("// nothing\n").unwrap().is_empty());
+    }
+
+    #[test_case("fire frozen multiply 2", 1; "missing colon")]
+    #[test_case("\nfire: add 1", 2; "missing status")]
+    #[test_case("Plasma frozen: add 1", 1; "invalid damage type")]
+    #[test_case("fire soggy: add 1", 1; "unknown status")]
+    #[test_case("fire frozen: explode", 1; "unknown effect")]
+    #[test_case("fire frozen: add many", 1; "invalid amount")]
+    #[test_case("fire frozen: apply wet", 1; "apply without turns")]
+    fn invalid_table(source: &str, line: usize) {
+        let error = InteractionTable::parse(source).unwrap_err();
+        assert_eq!(error.line, line);
+        assert!(!matches!(error.kind, InteractionsErrorKind::Io(_)));
+    }
+}
@@ -0,0 +1,73 @@
+/// Mana available to a player.
+/// Capacity grows by `growth` at the start of each of their turns (up to `maximum`) and the pool refills to capacity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManaPool {
+    available: u16,
+    capacity: u16,
+    growth: u16,
+    maximum: u16
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsufficientMana {
+    pub cost: u16,
+    pub available: u16
+}
+
+impl Default for ManaPool {
+    /// Start empty, grow by one each turn up to ten
+    fn default() -> Self {
+        ManaPool::new(0, 1, 10)
+    }
+}
+
+impl ManaPool {
+    pub fn new(starting_capacity: u16, growth: u16, maximum: u16) -> Self {
+        let capacity = starting_capacity.min(maximum);
+        ManaPool { available: capacity, capacity, growth, maximum }
+    }
+
+    /// Pool exactly as it was at some point (e.g. from a snapshot)
+    pub fn restore(available: u16, capacity: u16, growth: u16, maximum: u16) -> Self {
+        ManaPool { available, capacity, growth, maximum }
+    }
+
+    pub fn get_available(&self) -> u16 {
+        self.available
+    }
+
+    pub fn get_capacity(&self) -> u16 {
+        self.capacity
+    }
+
+    pub fn get_growth(&self) -> u16 {
+        self.growth
+    }
+
+    pub fn get_maximum(&self) -> u16 {
+        self.maximum
+    }
+
+    /// Start of turn: grow the capacity, then refill the pool
+    pub fn refresh(&mut self) {
+        self.capacity = self.capacity.saturating_add(self.growth).min(self.maximum);
+        self.available = self.capacity;
+    }
+
+    pub fn can_pay(&self, cost: u16) -> bool {
+        self.available >= cost
+    }
+
+    pub fn pay(&mut self, cost: u16) -> Result<(), InsufficientMana> {
+        if !self.can_pay(cost) {
+            return Err(InsufficientMana { cost, available: self.available });
+        }
+        self.available -= cost;
+        Ok(())
+    }
+
+    /// Temporary mana (not limited by capacity) until the next refresh
+    pub fn gain(&mut self, amount: u16) {
+        self.available = self.available.saturating_add(amount);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::game_zones::{card_instance::InstanceId, types::{ModifiedValue, ModifierOperation}};
+
+/// Cards with this tag stay in play after resolving, and so do the modifiers they add
+pub const AURA_TAG: &str = "aura";
+
+/// A continuous change to one of a player's values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Modifier {
+    /// Player whose value is changed
+    pub player: usize,
+    pub value: ModifiedValue,
+    pub operation: ModifierOperation,
+    /// Aura it lasts as long as; without one (or when the card doesn't stay in play), it lasts until the end of the turn
+    pub source: Option<InstanceId>,
+    /// Order modifiers were added in; within a layer, earlier ones apply first
+    pub timestamp: u32
+}
+
+/// Run a value through the modifiers layer by layer, in timestamp order within each layer
+pub fn apply_modifiers<'m>(base: i32, modifiers: impl Iterator<Item=&'m Modifier>) -> i32 {
+    let mut modifiers: Vec<&Modifier> = modifiers.collect();
+    modifiers.sort_by_key(|modifier| (modifier.operation.get_layer(), modifier.timestamp));
+    modifiers.iter().fold(base, |value, modifier| modifier.operation.apply(value))
+}
@@ -0,0 +1,75 @@
+use std::rc::Rc;
+
+use crate::game_zones::zone::Zones;
+
+use super::mana::ManaPool;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+    name: Rc<str>,
+    hp: i32,
+    mana: ManaPool,
+    /// Reduction to card costs, exposed to scripts as `$discount`
+    discount: u16,
+    zones: Zones,
+    conceded: bool
+}
+
+impl Player {
+    pub fn new(name: &str, hp: i32, mana: ManaPool) -> Self {
+        Player { name: Rc::from(name), hp, mana, discount: 0, zones: Zones::default(), conceded: false }
+    }
+
+    /// Player exactly as they were at some point (e.g. from a snapshot)
+    pub fn restore(name: &str, hp: i32, mana: ManaPool, discount: u16, zones: Zones, conceded: bool) -> Self {
+        Player { name: Rc::from(name), hp, mana, discount, zones, conceded }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_hp(&self) -> i32 {
+        self.hp
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.hp <= 0 || self.conceded
+    }
+
+    pub fn has_conceded(&self) -> bool {
+        self.conceded
+    }
+
+    pub fn concede(&mut self) {
+        self.conceded = true;
+    }
+
+    pub fn take_damage(&mut self, amount: i32) {
+        self.hp -= amount.max(0);
+    }
+
+    pub fn get_mana(&self) -> &ManaPool {
+        &self.mana
+    }
+
+    pub fn get_mana_mut(&mut self) -> &mut ManaPool {
+        &mut self.mana
+    }
+
+    pub fn get_discount(&self) -> u16 {
+        self.discount
+    }
+
+    pub fn set_discount(&mut self, discount: u16) {
+        self.discount = discount;
+    }
+
+    pub fn get_zones(&self) -> &Zones {
+        &self.zones
+    }
+
+    pub fn get_zones_mut(&mut self) -> &mut Zones {
+        &mut self.zones
+    }
+}
@@ -0,0 +1,47 @@
+use crate::game_zones::{card_instance::InstanceId, types::{DamageType, ReplacedEvent, ReplacementOutcome}, zone::Zone};
+
+/// "If the player would ..., ... instead"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplacementEffect {
+    /// Player whose damage or cards it watches
+    pub player: usize,
+    pub event: ReplacedEvent,
+    pub outcome: ReplacementOutcome,
+    /// Times it can still apply before it's used up; `None` when it only ends with its aura or the turn
+    pub uses: Option<u16>,
+    /// Aura it lasts as long as
+    pub source: Option<InstanceId>,
+    /// Order effects were added in; when several apply to one event, the oldest goes first
+    pub timestamp: u32
+}
+
+/// An event as it's being replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Damage { player: usize, amount: i32, damage_type: DamageType },
+    /// `owner` is the card's owner
+    Move { instance: InstanceId, owner: usize, to: Zone }
+}
+
+impl ReplacementEffect {
+    pub fn watches(&self, event: &Event) -> bool {
+        match (&self.event, event) {
+            (ReplacedEvent::Damage(filter), Event::Damage { player, damage_type, .. }) => {
+                *player == self.player && filter.as_ref().is_none_or(|filter| filter == damage_type)
+            },
+            (ReplacedEvent::Move(zone), Event::Move { owner, to, .. }) => *owner == self.player && zone == to,
+            _ => false
+        }
+    }
+
+    /// The event after this replacement, or `None` when it no longer happens
+    pub fn replace(&self, event: Event) -> Option<Event> {
+        match (self.outcome, event) {
+            (ReplacementOutcome::Prevent, Event::Damage { .. }) => None,
+            (ReplacementOutcome::Redirect(player), Event::Damage { amount, damage_type, .. }) => Some(Event::Damage { player, amount, damage_type }),
+            (ReplacementOutcome::MoveTo(to), Event::Move { instance, owner, .. }) => Some(Event::Move { instance, owner, to }),
+            // outcomes that don't fit the event are never created
+            (_, event) => Some(event)
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::game_zones::{card_instance::InstanceId, damage_types::DamageTypeRegistry};
+use crate::library::{card_library::CardLibrary, deck_list::{DeckError, DeckRules}};
+use crate::parsing::expressions::ExpressionResult;
+
+use super::controller::{settle_targets, Action, Controller};
+use super::duel::{Duel, DuelConfig, DuelOutcome, DuelSetup, PlayerSetup};
+use super::game_state::GameState;
+use super::interactions::InteractionTable;
+use super::mana::ManaPool;
+use super::snapshot::{escape, save_snapshot, unescape};
+
+/// Bumped whenever the format changes; older versions are still readable
+/// - 2: the interaction table
+/// - 3: damage types added to or changed from the built-in ones
+pub const REPLAY_VERSION: u32 = 3;
+
+const HEADER: &str = "mage_duel replay";
+
+/// What a controller answered when it was asked something
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    Action(Action),
+    Targets(Vec<ExpressionResult>)
+}
+
+/// One answer from one controller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub player: usize,
+    /// `fingerprint` of the state the question was asked in
+    pub state: u64,
+    pub choice: Choice
+}
+
+/// Everything needed to play a duel again exactly as it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionLog {
+    pub setup: DuelSetup,
+    pub decisions: Vec<Decision>,
+    pub outcome: DuelOutcome,
+    /// `fingerprint` of the state when the duel ended
+    pub final_state: u64
+}
+
+/// Cheap summary of the whole duel state: 64-bit FNV-1a hash of its snapshot
+pub fn fingerprint(state: &GameState) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in save_snapshot(state).bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Passes every question on to another controller and writes down its answers
+pub struct RecordingController<'a> {
+    inner: &'a mut dyn Controller,
+    player: usize,
+    decisions: Rc<RefCell<Vec<Decision>>>
+}
+
+impl<'a> RecordingController<'a> {
+    pub fn new(inner: &'a mut dyn Controller, player: usize, decisions: Rc<RefCell<Vec<Decision>>>) -> Self {
+        RecordingController { inner, player, decisions }
+    }
+}
+
+impl Controller for RecordingController<'_> {
+    fn choose_action(&mut self, state: &GameState, options: &[Action]) -> Action {
+        let action = self.inner.choose_action(state, options);
+        self.decisions.borrow_mut().push(Decision { player: self.player, state: fingerprint(state), choice: Choice::Action(action) });
+        action
+    }
+
+    fn choose_targets(&mut self, state: &GameState, candidates: &[ExpressionResult], count: u16, up_to: bool) -> Vec<ExpressionResult> {
+        // what gets written down is what the card ends up using, not whatever the controller asked for
+        let targets = settle_targets(self.inner.choose_targets(state, candidates, count, up_to), candidates, count, up_to);
+        self.decisions.borrow_mut().push(Decision { player: self.player, state: fingerprint(state), choice: Choice::Targets(targets.clone()) });
+        targets
+    }
+}
+
+/// Play a duel, writing down every decision made in it
+pub fn record_duel(library: &CardLibrary, setup: &DuelSetup, rules: &DeckRules, controllers: &mut [&mut dyn Controller]) -> Result<ActionLog, Vec<DeckError>> {
+    let mut duel = Duel::from_setup(library, setup, rules)?;
+    let decisions = Rc::new(RefCell::new(vec![ ]));
+
+    let mut recorders: Vec<RecordingController> = controllers.iter_mut()
+        .enumerate()
+        .map(|(player, controller)| RecordingController::new(&mut **controller, player, decisions.clone()))
+        .collect();
+    let mut recorders: Vec<&mut dyn Controller> = recorders.iter_mut().map(|r| r as &mut dyn Controller).collect();
+    let outcome = duel.run(&mut recorders);
+    drop(recorders);
+
+    let decisions = decisions.take();
+    Ok(ActionLog { setup: setup.clone(), decisions, outcome, final_state: fingerprint(duel.get_state()) })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DivergenceReason {
+    /// The recording was asked for more decisions than it has
+    OutOfDecisions,
+    /// The duel ended with decisions left over
+    UnusedDecisions(usize),
+    /// The next decision was made by the other player
+    WrongPlayer(usize),
+    /// The state differs from the one the decision was made in
+    StateMismatch,
+    /// An action was recorded where targets were asked for, or the other way round
+    WrongKind,
+    /// The recorded action isn't legal any more
+    IllegalAction(Action),
+    /// The duel ended differently
+    OutcomeMismatch(DuelOutcome),
+    /// The duel ended the same way, but in a different state
+    FinalStateMismatch
+}
+
+/// The first point where a replay stopped matching its recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index of the recorded decision (the number of decisions when it's about the end of the duel)
+    pub decision: usize,
+    pub turn: u16,
+    pub player: usize,
+    pub reason: DivergenceReason
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "decision {} (turn {}, player {}): ", self.decision, self.turn, self.player)?;
+        match &self.reason {
+            DivergenceReason::OutOfDecisions => write!(f, "no decisions left in the recording"),
+            DivergenceReason::UnusedDecisions(count) => write!(f, "duel ended with {} decisions left", count),
+            DivergenceReason::WrongPlayer(player) => write!(f, "recorded for player {}", player),
+            DivergenceReason::StateMismatch => write!(f, "state differs from the recording"),
+            DivergenceReason::WrongKind => write!(f, "recorded decision answers a different question"),
+            DivergenceReason::IllegalAction(action) => write!(f, "recorded action {:?} is not legal", action),
+            DivergenceReason::OutcomeMismatch(outcome) => write!(f, "duel ended with {:?}", outcome),
+            DivergenceReason::FinalStateMismatch => write!(f, "final state differs from the recording")
+        }
+    }
+}
+
+/// How a replay went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub outcome: DuelOutcome,
+    /// `None` when the replay matched the recording bit for bit
+    pub divergence: Option<Divergence>
+}
+
+/// Decisions shared by both sides of a replay
+struct ReplayCursor {
+    decisions: Vec<Decision>,
+    position: usize,
+    divergence: Option<Divergence>
+}
+
+impl ReplayCursor {
+    /// The next decision, if it was made by `player` in exactly this state
+    fn next(&mut self, state: &GameState, player: usize) -> Option<Choice> {
+        if self.divergence.is_some() {
+            return None;
+        }
+        let reason = match self.decisions.get(self.position) {
+            None => DivergenceReason::OutOfDecisions,
+            Some(decision) if decision.player != player => DivergenceReason::WrongPlayer(decision.player),
+            Some(decision) if decision.state != fingerprint(state) => DivergenceReason::StateMismatch,
+            Some(decision) => {
+                self.position += 1;
+                return Some(decision.choice.clone());
+            }
+        };
+        self.diverge(state, player, reason);
+        None
+    }
+
+    fn diverge(&mut self, state: &GameState, player: usize, reason: DivergenceReason) {
+        if self.divergence.is_none() {
+            self.divergence = Some(Divergence { decision: self.position, turn: state.get_turn(), player, reason });
+        }
+    }
+}
+
+/// Answers with the recorded decisions; once the duel diverges, it concedes so the replay ends quickly
+struct ReplayController {
+    player: usize,
+    cursor: Rc<RefCell<ReplayCursor>>
+}
+
+impl Controller for ReplayController {
+    fn choose_action(&mut self, state: &GameState, options: &[Action]) -> Action {
+        let mut cursor = self.cursor.borrow_mut();
+        match cursor.next(state, self.player) {
+            Some(Choice::Action(action)) if options.contains(&action) => return action,
+            Some(Choice::Action(action)) => cursor.diverge(state, self.player, DivergenceReason::IllegalAction(action)),
+            Some(Choice::Targets(_)) => cursor.diverge(state, self.player, DivergenceReason::WrongKind),
+            None => {}
+        }
+        Action::Concede
+    }
+
+    fn choose_targets(&mut self, state: &GameState, _candidates: &[ExpressionResult], _count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+        let mut cursor = self.cursor.borrow_mut();
+        match cursor.next(state, self.player) {
+            Some(Choice::Targets(targets)) => return targets,
+            Some(Choice::Action(_)) => cursor.diverge(state, self.player, DivergenceReason::WrongKind),
+            None => {}
+        }
+        vec![ ]
+    }
+}
+
+/// Play a recorded duel again and report the first point where it differs from the recording.
+/// Deck rules aren't checked again: the recording is what was actually played.
+pub fn replay(library: &CardLibrary, log: &ActionLog) -> Result<ReplayReport, Vec<DeckError>> {
+    let mut duel = Duel::from_setup(library, &log.setup, &DeckRules::unrestricted())?;
+    let cursor = Rc::new(RefCell::new(ReplayCursor { decisions: log.decisions.clone(), position: 0, divergence: None }));
+
+    let mut controllers: Vec<ReplayController> = (0 .. log.setup.players.len())
+        .map(|player| ReplayController { player, cursor: cursor.clone() })
+        .collect();
+    let mut controllers: Vec<&mut dyn Controller> = controllers.iter_mut().map(|c| c as &mut dyn Controller).collect();
+    let outcome = duel.run(&mut controllers);
+
+    let mut cursor = cursor.borrow_mut();
+    let state = duel.get_state();
+    let player = state.get_active_player();
+    if cursor.position < cursor.decisions.len() {
+        let left = cursor.decisions.len() - cursor.position;
+        cursor.diverge(state, player, DivergenceReason::UnusedDecisions(left));
+    }
+    if outcome != log.outcome {
+        cursor.diverge(state, player, DivergenceReason::OutcomeMismatch(outcome));
+    }
+    if fingerprint(state) != log.final_state {
+        cursor.diverge(state, player, DivergenceReason::FinalStateMismatch);
+    }
+
+    Ok(ReplayReport { outcome, divergence: cursor.divergence.take() })
+}
+
+#[derive(Debug)]
+pub enum ActionLogErrorKind {
+    Io(std::io::Error),
+    /// First line isn't `mage_duel replay <version>`
+    MissingHeader,
+    /// Written by a newer version than we can read
+    UnsupportedVersion(u32),
+    /// Line isn't a valid `<key> <values>` entry
+    InvalidLine,
+    /// `deck` line before any `player` line
+    NoPlayer,
+    /// No `outcome` line
+    MissingOutcome
+}
+
+#[derive(Debug)]
+pub struct ActionLogError {
+    /// 1-based line of the recording (0 when it's about the file as a whole)
+    pub line: usize,
+    pub kind: ActionLogErrorKind
+}
+
+impl Display for ActionLogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ActionLogErrorKind::Io(err) => write!(f, "{}", err),
+            ActionLogErrorKind::MissingHeader => write!(f, "expected '{} <version>'", HEADER),
+            ActionLogErrorKind::UnsupportedVersion(version) => write!(f, "version {} is newer than {}", version, REPLAY_VERSION),
+            ActionLogErrorKind::InvalidLine => write!(f, "invalid entry"),
+            ActionLogErrorKind::NoPlayer => write!(f, "deck before any player"),
+            ActionLogErrorKind::MissingOutcome => write!(f, "recording has no outcome")
+        }
+    }
+}
+
+/// Write a recording as line-based text, in the same spirit as snapshots
+pub fn save_action_log(log: &ActionLog) -> String {
+    let mut out = String::new();
+    // writing to a String can't fail
+    let _ = write_action_log(log, &mut out);
+    out
+}
+
+fn write_action_log(log: &ActionLog, out: &mut String) -> std::fmt::Result {
+    let config = &log.setup.config;
+    writeln!(out, "{} {}", HEADER, REPLAY_VERSION)?;
+    writeln!(out, "seed {}", log.setup.seed)?;
+    writeln!(out, "hp {}", config.starting_hp)?;
+    writeln!(out, "mana {} {} {} {}", config.mana.get_available(), config.mana.get_capacity(), config.mana.get_growth(), config.mana.get_maximum())?;
+    writeln!(out, "opening_hand {}", config.opening_hand)?;
+    writeln!(out, "max_turns {}", config.max_turns)?;
+    let built_in = DamageTypeRegistry::default();
+    for info in config.damage_types.get_types().iter().filter(|&info| built_in.get(&info.damage_type) != Some(info)) {
+        writeln!(out, "damage_type {}", info)?;
+    }
+    for interaction in config.interactions.get_interactions() {
+        writeln!(out, "interaction {}", interaction)?;
+    }
+
+    for player in &log.setup.players {
+        writeln!(out, "player {}", escape(&player.name))?;
+        writeln!(out, "deck {}", escape(&player.deck_list))?;
+    }
+
+    for decision in &log.decisions {
+        write!(out, "decision {} {:016x} ", decision.player, decision.state)?;
+        match &decision.choice {
+            Choice::Action(Action::PlayCard(instance)) => writeln!(out, "play {}", instance.0)?,
+            Choice::Action(Action::EndTurn) => writeln!(out, "end")?,
+            Choice::Action(Action::Pass) => writeln!(out, "pass")?,
+            Choice::Action(Action::Concede) => writeln!(out, "concede")?,
+            Choice::Targets(targets) => {
+                let targets: Vec<String> = targets.iter().filter_map(target_name).collect();
+                writeln!(out, "targets {}", targets.join(" "))?;
+            }
+        }
+    }
+
+    let winner = log.outcome.winner.map(|w| w.to_string()).unwrap_or(String::from("none"));
+    writeln!(out, "outcome {} {} {:016x}", winner, log.outcome.turns, log.final_state)
+}
+
+/// Read a recording written by `save_action_log`
+pub fn load_action_log(source: &str) -> Result<ActionLog, ActionLogError> {
+    let mut lines = source.lines().enumerate().map(|(index, line)| (index + 1, line));
+
+    let header = lines.next().and_then(|(_, line)| line.strip_prefix(HEADER)).map(str::trim);
+    let version = header.and_then(|v| v.parse::<u32>().ok())
+        .ok_or(ActionLogError { line: 1, kind: ActionLogErrorKind::MissingHeader })?;
+    if version > REPLAY_VERSION {
+        return Err(ActionLogError { line: 1, kind: ActionLogErrorKind::UnsupportedVersion(version) });
+    }
+
+    let mut setup = DuelSetup { players: vec![ ], seed: 0, config: DuelConfig::default() };
+    let mut decisions = vec![ ];
+    let mut result = None;
+    let mut interactions = vec![ ];
+    let mut damage_types = String::new();
+
+    for (line, content) in lines {
+        if content.trim().is_empty() {
+            continue;
+        }
+        let error = |kind| ActionLogError { line, kind };
+        let invalid = || error(ActionLogErrorKind::InvalidLine);
+        let (key, value) = content.split_once(' ').unwrap_or((content, ""));
+
+        match key {
+            "seed" => setup.seed = value.parse().map_err(|_| invalid())?,
+            "hp" => setup.config.starting_hp = value.parse().map_err(|_| invalid())?,
+            "mana" => {
+                let values = value.split_whitespace().map(str::parse::<u16>).collect::<Result<Vec<u16>, _>>().ok();
+                match values.as_deref() {
+                    Some(&[ available, capacity, growth, maximum ]) => setup.config.mana = ManaPool::restore(available, capacity, growth, maximum),
+                    _ => return Err(invalid())
+                }
+            },
+            "opening_hand" => setup.config.opening_hand = value.parse().map_err(|_| invalid())?,
+            "max_turns" => setup.config.max_turns = value.parse().map_err(|_| invalid())?,
+            "damage_type" => {
+                DamageTypeRegistry::parse(value).map_err(|_| invalid())?;
+                damage_types.push_str(value);
+                damage_types.push('\n');
+            },
+            "interaction" => {
+                let table = InteractionTable::parse(value).map_err(|_| invalid())?;
+                interactions.extend(table.get_interactions().iter().cloned());
+            },
+            "player" => setup.players.push(PlayerSetup { name: unescape(value), deck_list: String::new() }),
+            "deck" => setup.players.last_mut().ok_or(error(ActionLogErrorKind::NoPlayer))?.deck_list = unescape(value),
+            "decision" => decisions.push(parse_decision(value).ok_or_else(invalid)?),
+            "outcome" => result = Some(parse_outcome(value).ok_or_else(invalid)?),
+            _ => return Err(invalid())
+        }
+    }
+
+    let (outcome, final_state) = result.ok_or(ActionLogError { line: 0, kind: ActionLogErrorKind::MissingOutcome })?;
+    setup.config.interactions = Arc::new(InteractionTable::new(interactions));
+    // every line parsed on its own, so they parse together
+    setup.config.damage_types = Arc::new(DamageTypeRegistry::parse(&damage_types).unwrap_or_default());
+    Ok(ActionLog { setup, decisions, outcome, final_state })
+}
+
+pub fn save_action_log_to_file(log: &ActionLog, path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, save_action_log(log))
+}
+
+pub fn load_action_log_from_file(path: &Path) -> Result<ActionLog, ActionLogError> {
+    let source = std::fs::read_to_string(path).map_err(|err| ActionLogError { line: 0, kind: ActionLogErrorKind::Io(err) })?;
+    load_action_log(&source)
+}
+
+/// `<player> <state> play <id>|end|pass|concede|targets <target>*`
+fn parse_decision(value: &str) -> Option<Decision> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let (player, state, kind, rest) = match parts.as_slice() {
+        [ player, state, kind, rest @ .. ] => (player.parse().ok()?, u64::from_str_radix(state, 16).ok()?, *kind, rest),
+        _ => return None
+    };
+
+    let choice = match (kind, rest) {
+        ("play", [ id ]) => Choice::Action(Action::PlayCard(InstanceId(id.parse().ok()?))),
+        ("end", [ ]) => Choice::Action(Action::EndTurn),
+        ("pass", [ ]) => Choice::Action(Action::Pass),
+        ("concede", [ ]) => Choice::Action(Action::Concede),
+        ("targets", targets) => Choice::Targets(targets.iter().map(|t| parse_target(t)).collect::<Option<Vec<_>>>()?),
+        _ => return None
+    };
+    Some(Decision { player, state, choice })
+}
+
+/// `<winner>|none <turns> <final state>`
+fn parse_outcome(value: &str) -> Option<(DuelOutcome, u64)> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if let &[ winner, turns, state ] = parts.as_slice() {
+        let winner = if winner == "none" { None } else { Some(winner.parse().ok()?) };
+        return Some((DuelOutcome { winner, turns: turns.parse().ok()? }, u64::from_str_radix(state, 16).ok()?));
+    }
+    None
+}
+
+/// Players are the only thing cards can target for now, so they're the only candidates a recording can hold
+fn target_name(target: &ExpressionResult) -> Option<String> {
+    match target {
+        ExpressionResult::Player(index) => Some(format!("p{}", index)),
+        _ => None
+    }
+}
+
+fn parse_target(value: &str) -> Option<ExpressionResult> {
+    Some(ExpressionResult::Player(value.strip_prefix('p')?.parse().ok()?))
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::engine::controller::{Action, Controller, RandomController};
+    use crate::engine::game_state::GameState;
+    use crate::engine::duel::{DuelConfig, DuelSetup, PlayerSetup};
+    use crate::engine::interactions::InteractionTable;
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::engine::replay::{load_action_log, record_duel, replay, save_action_log, ActionLog, ActionLogErrorKind, DivergenceReason};
+    use crate::library::card_library::CardLibrary;
+    use crate::library::test_library::{self, rules};
+    use crate::parsing::expressions::ExpressionResult;
+    use test_case::test_case;
+
+    fn library(damage: &str) -> CardLibrary {
+        let firebolt = format!("@name \"Firebolt\" [1]: {{ $ = target(1 in Player); {} fire => $; log \"Firebolt hits {{$.name}}\"; }}", damage);
+        let spark = "@name \"Spark\" [0]: { 1 lightning => target(^1 in Player); }";
+        test_library::library(&[
+            ("firebolt.card", &firebolt),
+            ("spark.card", spark),
+            ("negate.card", "@name \"Negate\" #reaction [1]: { counter; }")
+        ])
+    }
+
+    fn setup(seed: u64) -> DuelSetup {
+        let deck_list = String::from("// burn\n5 Firebolt\n5 Spark\n3 Negate");
+        DuelSetup {
+            players: vec![
+                PlayerSetup { name: String::from("Alice\nthe Red"), deck_list: deck_list.clone() },
+                PlayerSetup { name: String::from("Bob"), deck_list }
+            ],
+            seed,
+            config: DuelConfig::default()
+        }
+    }
+
+    fn record(library: &CardLibrary, seed: u64) -> ActionLog {
+        let mut alice = RandomController::new(seed + 1);
+        let mut bob = RandomController::new(seed + 2);
+        let mut controllers: [ &mut dyn Controller; 2 ] = [ &mut alice, &mut bob ];
+        record_duel(library, &setup(seed), &rules(), &mut controllers).unwrap()
+    }
+
+    #[test_case(1)]
+    #[test_case(42)]
+    #[test_case(1234)]
+    fn replay_matches_recording(seed: u64) {
+        let library = library("1d6");
+        let log = record(&library, seed);
+        assert!(!log.decisions.is_empty());
+
+        let report = replay(&library, &log).unwrap();
+
+        assert_eq!(report.divergence, None);
+        assert_eq!(report.outcome, log.outcome);
+    }
+
+    #[test]
+    fn log_round_trip() {
+        let library = library("1d6");
+        let log = record(&library, 7);
+
+        let saved = save_action_log(&log);
+        let loaded = load_action_log(&saved).unwrap();
+
+        assert_eq!(loaded, log);
+        assert_eq!(replay(&library, &loaded).unwrap().divergence, None);
+    }
+
+    #[test]
+    fn interactions_are_recorded() {
+        let library = library("1d6");
+        let mut log = record(&library, 7);
+        log.setup.config.interactions = Arc::new(InteractionTable::parse("fire frozen: multiply 2, remove frozen\nice wet: apply frozen 1").unwrap());
+
+        let saved = save_action_log(&log);
+        assert!(saved.contains("\ninteraction fire frozen: multiply 2, remove frozen\n"));
+        assert_eq!(load_action_log(&saved).unwrap(), log);
+    }
+
+    #[test]
+    fn changed_damage_types_are_recorded() {
+        let library = library("1d6");
+        let mut log = record(&library, 7);
+        log.setup.config.damage_types = Arc::new(DamageTypeRegistry::parse("sonic \"Sonic\"\nfire \"Fire\" resistant").unwrap());
+
+        let saved = save_action_log(&log);
+        // only what differs from the built-in types
+        assert_eq!(saved.matches("\ndamage_type ").count(), 2);
+        assert_eq!(load_action_log(&saved).unwrap(), log);
+    }
+
+    /// Plays at random, but asks for targets that can't be picked
+    struct WildTargets(RandomController);
+
+    impl Controller for WildTargets {
+        fn choose_action(&mut self, state: &GameState, options: &[Action]) -> Action {
+            self.0.choose_action(state, options)
+        }
+
+        fn choose_targets(&mut self, _state: &GameState, _candidates: &[ExpressionResult], _count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+            vec![ ExpressionResult::Integer(3), ExpressionResult::Player(9) ]
+        }
+    }
+
+    #[test]
+    fn only_used_targets_are_recorded() {
+        let library = library("1d6");
+        let mut alice = WildTargets(RandomController::new(8));
+        let mut bob = WildTargets(RandomController::new(9));
+        let mut controllers: [ &mut dyn Controller; 2 ] = [ &mut alice, &mut bob ];
+        let log = record_duel(&library, &setup(7), &rules(), &mut controllers).unwrap();
+
+        let saved = save_action_log(&log);
+        assert!(saved.contains(" targets p0\n"));
+        assert!(!saved.contains("p9"));
+        let loaded = load_action_log(&saved).unwrap();
+        assert_eq!(loaded, log);
+        assert_eq!(replay(&library, &loaded).unwrap().divergence, None);
+    }
+
+    #[test]
+    fn changed_card_diverges() {
+        let log = record(&library("1d6"), 42);
+
+        let report = replay(&library("1d6 + 1"), &log).unwrap();
+        let divergence = report.divergence.unwrap();
+
+        assert_eq!(divergence.reason, DivergenceReason::StateMismatch);
+        assert!(divergence.decision > 0);
+    }
+
+    #[test]
+    fn missing_decisions_diverge() {
+        let library = library("1d6");
+        let mut log = record(&library, 42);
+        let cut = log.decisions.len() / 2;
+        log.decisions.truncate(cut);
+
+        let divergence = replay(&library, &log).unwrap().divergence.unwrap();
+
+        assert_eq!(divergence.decision, cut);
+        assert_eq!(divergence.reason, DivergenceReason::OutOfDecisions);
+    }
+
+    #[test]
+    fn unused_decisions_diverge() {
+        let library = library("1d6");
+        let mut log = record(&library, 42);
+        let last = log.decisions.last().unwrap().clone();
+        log.decisions.push(last);
+
+        let divergence = replay(&library, &log).unwrap().divergence.unwrap();
+
+        assert_eq!(divergence.reason, DivergenceReason::UnusedDecisions(1));
+    }
+
+    #[test_case("mage_duel snapshot 2\n", 1; "wrong header")]
+    #[test_case("mage_duel replay 1\nseed x\n", 2; "invalid seed")]
+    #[test_case("mage_duel replay 1\ndeck 5 Spark\n", 2; "deck without player")]
+    #[test_case("mage_duel replay 1\ndecision 0 00ff dance\n", 2; "unknown decision")]
+    #[test_case("mage_duel replay 1\nseed 4\n", 0; "no outcome")]
+    #[test_case("mage_duel replay 2\ninteraction fire: add 1\n", 2; "invalid interaction")]
+    #[test_case("mage_duel replay 3\ndamage_type sonic\n", 2; "invalid damage type")]
+    fn invalid_log(source: &str, line: usize) {
+        let error = load_action_log(source).unwrap_err();
+        assert_eq!(error.line, line);
+        assert!(!matches!(error.kind, ActionLogErrorKind::Io(_)));
+    }
+}
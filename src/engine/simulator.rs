@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::game_zones::{dice_roller::DiceRoller, types::DamageType};
+use crate::library::{card_library::{CardLibrary, Diagnostic, LibrarySources}, deck_list::{Deck, DeckError, DeckList, DeckRules}};
+
+use super::controller::Controller;
+use super::duel::{Duel, DuelConfig};
+
+/// Builds a fresh controller for one game from a seed, so every game (and every thread) gets its own
+pub type ControllerFactory<'f> = dyn for<'a> Fn(&'a CardLibrary, u64) -> Box<dyn Controller + 'a> + Sync + 'f;
+
+/// Closures don't infer the signature `ControllerFactory` needs on their own; passing them through here does
+pub fn factory<F>(factory: F) -> F where F: for<'a> Fn(&'a CardLibrary, u64) -> Box<dyn Controller + 'a> + Sync {
+    factory
+}
+
+/// A batch of headless duels between two decks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation {
+    /// Deck lists as written (see `DeckList`)
+    pub deck_lists: [String; 2],
+    pub games: u32,
+    /// Every game's dice and controllers are seeded from this, so a simulation can be repeated exactly
+    pub seed: u64,
+    pub config: DuelConfig,
+    /// Worker threads for `simulate_parallel`
+    pub threads: usize
+}
+
+/// One of the decks doesn't follow the rules
+#[derive(Debug)]
+pub struct InvalidDeck {
+    /// 0 for the first deck, 1 for the second
+    pub deck: usize,
+    pub errors: Vec<DeckError>
+}
+
+impl Display for InvalidDeck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "deck {}: {}", self.deck + 1, error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a parallel simulation couldn't start
+#[derive(Debug)]
+pub enum SimulationError {
+    /// Some of the card scripts don't load
+    Library(Vec<Diagnostic>),
+    InvalidDeck(InvalidDeck)
+}
+
+impl From<InvalidDeck> for SimulationError {
+    fn from(invalid: InvalidDeck) -> Self {
+        SimulationError::InvalidDeck(invalid)
+    }
+}
+
+impl Display for SimulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::Library(diagnostics) => diagnostics.iter().try_for_each(|diagnostic| writeln!(f, "{}", diagnostic)),
+            SimulationError::InvalidDeck(invalid) => write!(f, "{}", invalid)
+        }
+    }
+}
+
+/// Totals over every game of a simulation.
+/// Decks take turns going first, so results are always given per deck rather than per seat.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub games: u32,
+    pub wins: [u32; 2],
+    pub draws: u32,
+    pub total_turns: u64,
+    /// Damage dealt to players by either deck
+    pub damage: BTreeMap<DamageType, i64>,
+    /// Times each card was played, by name
+    pub plays: BTreeMap<String, u64>
+}
+
+impl SimulationReport {
+    /// Fraction of games the deck won, from 0 to 1
+    pub fn get_win_rate(&self, deck: usize) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.wins[deck] as f64 / self.games as f64
+    }
+
+    /// Average number of turns per game, counting each player's turn
+    pub fn get_average_length(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.total_turns as f64 / self.games as f64
+    }
+
+    /// Add the totals from another batch of games
+    pub fn merge(&mut self, other: SimulationReport) {
+        self.games += other.games;
+        self.wins[0] += other.wins[0];
+        self.wins[1] += other.wins[1];
+        self.draws += other.draws;
+        self.total_turns += other.total_turns;
+        for (damage_type, amount) in other.damage {
+            *self.damage.entry(damage_type).or_default() += amount;
+        }
+        for (card, count) in other.plays {
+            *self.plays.entry(card).or_default() += count;
+        }
+    }
+}
+
+impl Display for SimulationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "games: {} ({} drawn)", self.games, self.draws)?;
+        for deck in 0 .. 2 {
+            writeln!(f, "deck {} wins: {} ({:.1}%)", deck + 1, self.wins[deck], self.get_win_rate(deck) * 100.0)?;
+        }
+        writeln!(f, "average length: {:.1} turns", self.get_average_length())?;
+        writeln!(f, "damage:")?;
+        for (damage_type, amount) in &self.damage {
+            writeln!(f, "  {} {}", damage_type, amount)?;
+        }
+        writeln!(f, "plays:")?;
+        for (card, count) in &self.plays {
+            writeln!(f, "  {} {}", card, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run every game of the simulation on this thread
+pub fn simulate(library: &CardLibrary, simulation: &Simulation, rules: &DeckRules, controllers: [&ControllerFactory; 2]) -> Result<SimulationReport, InvalidDeck> {
+    let decks = validate_decks(library, simulation, rules)?;
+    Ok(run_games(library, &decks, simulation, controllers, 0 .. simulation.games))
+}
+
+/// Spread the games over `simulation.threads` threads.
+/// Card scripts can't be shared between threads, so each one builds its own (simplified) library from the sources.
+/// The report is the same as `simulate` would give, whatever the number of threads.
+pub fn simulate_parallel(sources: &LibrarySources, simulation: &Simulation, rules: &DeckRules, controllers: [&ControllerFactory; 2]) -> Result<SimulationReport, SimulationError> {
+    let build = || sources.build().map(CardLibrary::simplified);
+    let decks = validate_decks(&build().map_err(SimulationError::Library)?, simulation, rules)?;
+    let threads = simulation.threads.max(1) as u32;
+
+    let mut report = SimulationReport::default();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0 .. threads)
+            .map(|worker| {
+                let decks = &decks;
+                scope.spawn(move || {
+                    let games = (worker .. simulation.games).step_by(threads as usize);
+                    // parsing is deterministic, so the scripts build on every thread once they've built here
+                    let library = build().expect("Card scripts that built once build again.");
+                    run_games(&library, decks, simulation, controllers, games)
+                })
+            })
+            .collect();
+        for worker in workers {
+            report.merge(worker.join().expect("Simulation thread panicked."));
+        }
+    });
+    Ok(report)
+}
+
+fn validate_decks(library: &CardLibrary, simulation: &Simulation, rules: &DeckRules) -> Result<[Deck; 2], InvalidDeck> {
+    let validate = |deck: usize| {
+        DeckList::parse(&simulation.deck_lists[deck])
+            .and_then(|list| list.validate(library, rules))
+            .map_err(|errors| InvalidDeck { deck, errors })
+    };
+    Ok([ validate(0)?, validate(1)? ])
+}
+
+fn run_games(library: &CardLibrary, decks: &[Deck; 2], simulation: &Simulation, controllers: [&ControllerFactory; 2], games: impl Iterator<Item=u32>) -> SimulationReport {
+    let mut report = SimulationReport::default();
+    for game in games {
+        let seed = DiceRoller::new(simulation.seed.wrapping_add(game as u64)).next_u64();
+        // decks take turns going first
+        let seats = if game % 2 == 0 { [ 0, 1 ] } else { [ 1, 0 ] };
+
+        let players = seats.iter().map(|&deck| (format!("Deck {}", deck + 1), decks[deck].clone())).collect();
+        let mut duel = Duel::new(library, players, seed, simulation.config.clone());
+        let mut seated: Vec<Box<dyn Controller>> = seats.iter()
+            .map(|&deck| controllers[deck](library, seed.wrapping_add(deck as u64 + 1)))
+            .collect();
+        let mut seated: Vec<&mut dyn Controller> = seated.iter_mut().map(|c| c.as_mut() as &mut dyn Controller).collect();
+        let outcome = duel.run(&mut seated);
+
+        report.games += 1;
+        report.total_turns += outcome.turns as u64;
+        match outcome.winner {
+            Some(seat) => report.wins[seats[seat]] += 1,
+            None => report.draws += 1
+        }
+
+        let stats = duel.get_state().get_stats();
+        for (damage_type, &amount) in stats.get_damage() {
+            *report.damage.entry(damage_type.clone()).or_default() += amount;
+        }
+        for (&card, &count) in stats.get_plays() {
+            let name = library.get(card).map(|c| c.get_name().to_string()).unwrap_or(card.to_string());
+            *report.plays.entry(name).or_default() += count as u64;
+        }
+    }
+    report
+}
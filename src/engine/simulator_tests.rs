@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::engine::controller::{Controller, RandomController};
+    use crate::engine::duel::DuelConfig;
+    use crate::engine::simulator::{simulate, simulate_parallel, ControllerFactory, Simulation, SimulationError};
+    use crate::game_zones::types::DamageType;
+    use crate::library::card_library::CardLibrary;
+    use crate::library::test_library::{library, rules, sources};
+    use test_case::test_case;
+
+    const SCRIPTS: [ (&str, &str); 3 ] = [
+        ("firebolt.card", "@name \"Firebolt\" [1]: { 1d6 fire => target(1 in Player); }"),
+        ("spark.card", "@name \"Spark\" [0]: { 1 lightning => target(^1 in Player); }"),
+        ("fizzle.card", "@name \"Fizzle\" [0]: { log \"nothing happens\"; }")
+    ];
+
+    fn simulation(games: u32, threads: usize) -> Simulation {
+        Simulation {
+            deck_lists: [ String::from("5 Firebolt\n5 Spark"), String::from("10 Fizzle") ],
+            games,
+            seed: 42,
+            config: DuelConfig::default(),
+            threads
+        }
+    }
+
+    fn random(_library: &CardLibrary, seed: u64) -> Box<dyn Controller + '_> {
+        Box::new(RandomController::new(seed))
+    }
+
+    const RANDOM: [ &ControllerFactory; 2 ] = [ &random, &random ];
+
+    #[test]
+    fn totals_add_up() {
+        let report = simulate(&library(&SCRIPTS), &simulation(20, 1), &rules(), RANDOM).unwrap();
+
+        assert_eq!(report.games, 20);
+        assert_eq!(report.wins[0] + report.wins[1] + report.draws, 20);
+        assert!(report.get_average_length() > 0.0);
+        assert!(report.plays["Firebolt"] > 0);
+        assert!(report.plays["Fizzle"] > 0);
+        assert!(report.damage[&DamageType::new("fire")] > 0);
+        assert!(!report.damage.contains_key(&DamageType::new("ice")));
+    }
+
+    #[test]
+    fn same_seed_same_report() {
+        let first = simulate(&library(&SCRIPTS), &simulation(10, 1), &rules(), RANDOM).unwrap();
+        let second = simulate(&library(&SCRIPTS), &simulation(10, 1), &rules(), RANDOM).unwrap();
+        let mut reseeded = simulation(10, 1);
+        reseeded.seed += 1;
+
+        assert_eq!(first, second);
+        assert_ne!(simulate(&library(&SCRIPTS), &reseeded, &rules(), RANDOM).unwrap(), first);
+    }
+
+    #[test_case(1)]
+    #[test_case(3)]
+    #[test_case(8)]
+    fn threads_give_the_same_report(threads: usize) {
+        let single = simulate(&library(&SCRIPTS), &simulation(10, 1), &rules(), RANDOM).unwrap();
+        let parallel = simulate_parallel(&sources(&SCRIPTS), &simulation(10, threads), &rules(), RANDOM).unwrap();
+        assert_eq!(parallel, single);
+    }
+
+    #[test]
+    fn invalid_deck() {
+        let mut simulation = simulation(1, 1);
+        simulation.deck_lists[1] = String::from("3 Fireball");
+
+        let error = simulate(&library(&SCRIPTS), &simulation, &rules(), RANDOM).unwrap_err();
+
+        assert_eq!(error.deck, 1);
+        assert!(!error.errors.is_empty());
+    }
+
+    #[test]
+    fn parallel_reports_scripts_that_dont_load() {
+        let mut sources = sources(&SCRIPTS);
+        sources.scripts.push((PathBuf::from("broken.card"), String::from("[1]: { 1d6 fire => ; }")));
+
+        let error = simulate_parallel(&sources, &simulation(4, 2), &rules(), RANDOM).unwrap_err();
+
+        assert!(matches!(error, SimulationError::Library(diagnostics) if diagnostics.len() == 1));
+    }
+}
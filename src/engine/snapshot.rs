@@ -0,0 +1,480 @@
+use std::fmt::{Display, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::game_zones::{card_instance::{CardInstance, InstanceId}, dice_roller::DiceRoller, types::{DamageType, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind}, zone::{Zone, Zones}};
+use crate::library::card_library::CardId;
+
+use super::{game_state::{GameState, Phase, StackEntry}, mana::ManaPool, modifiers::Modifier, player::Player, replacements::ReplacementEffect, status::{StatusEffect, StatusTarget}};
+
+/// Bumped whenever the format changes; older versions are still readable
+/// - 2: players have a `conceded` flag
+/// - 3: the stack, priority and passes
+/// - 4: statuses
+/// - 5: modifiers and the play zone
+/// - 6: replacement effects and the exile zone
+/// - 7: triggered abilities waiting on the stack
+pub const SNAPSHOT_VERSION: u32 = 7;
+
+const HEADER: &str = "mage_duel snapshot";
+
+#[derive(Debug)]
+pub enum SnapshotErrorKind {
+    Io(std::io::Error),
+    /// First line isn't `mage_duel snapshot <version>`
+    MissingHeader,
+    /// Snapshot was written by a newer version than we can read
+    UnsupportedVersion(u32),
+    /// Line isn't a valid `<key> <values>` entry
+    InvalidLine,
+    /// Player field given before any `player` line
+    NoPlayer,
+    /// Instance IDs must count up from 0 with no gaps
+    InvalidInstance(u32),
+    /// Zone refers to an instance that doesn't exist
+    UnknownInstance(u32),
+    /// Refers to a player past the last `player` section
+    UnknownPlayer(usize)
+}
+
+#[derive(Debug)]
+pub struct SnapshotError {
+    /// 1-based line of the snapshot (0 when it's about the file as a whole)
+    pub line: usize,
+    pub kind: SnapshotErrorKind
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            SnapshotErrorKind::Io(err) => write!(f, "{}", err),
+            SnapshotErrorKind::MissingHeader => write!(f, "expected '{} <version>'", HEADER),
+            SnapshotErrorKind::UnsupportedVersion(version) => write!(f, "version {} is newer than {}", version, SNAPSHOT_VERSION),
+            SnapshotErrorKind::InvalidLine => write!(f, "invalid entry"),
+            SnapshotErrorKind::NoPlayer => write!(f, "player field before any player"),
+            SnapshotErrorKind::InvalidInstance(id) => write!(f, "instance {} is out of order", id),
+            SnapshotErrorKind::UnknownInstance(id) => write!(f, "instance {} does not exist", id),
+            SnapshotErrorKind::UnknownPlayer(index) => write!(f, "player {} does not exist", index)
+        }
+    }
+}
+
+/// Write the full duel state as a versioned, line-based text snapshot
+pub fn save_snapshot(state: &GameState) -> String {
+    let mut out = String::new();
+    // writing to a String can't fail
+    let _ = write_snapshot(state, &mut out);
+    out
+}
+
+fn write_snapshot(state: &GameState, out: &mut String) -> std::fmt::Result {
+    writeln!(out, "{} {}", HEADER, SNAPSHOT_VERSION)?;
+    writeln!(out, "turn {}", state.get_turn())?;
+    writeln!(out, "phase {}", phase_name(state.get_phase()))?;
+    writeln!(out, "active {}", state.get_active_player())?;
+    writeln!(out, "dice {} {}", state.get_dice_roller().get_seed(), state.get_dice_roller().get_state())?;
+
+    writeln!(out, "priority {}", state.get_priority_player())?;
+    writeln!(out, "passes {}", state.get_passes())?;
+
+    for instance in state.get_instances() {
+        writeln!(out, "instance {} {} {}", instance.id.0, instance.card, instance.owner)?;
+    }
+    for entry in state.get_stack() {
+        match entry.ability {
+            Some(ability) => writeln!(out, "stack {} {} {}", entry.instance.0, entry.player, ability)?,
+            None => writeln!(out, "stack {} {}", entry.instance.0, entry.player)?
+        }
+    }
+    for status in state.get_statuses() {
+        let target = match status.target {
+            StatusTarget::Player(player) => format!("player {}", player),
+            StatusTarget::Instance(instance) => format!("instance {}", instance.0)
+        };
+        writeln!(out, "status {} {} {} {}", status.kind, target, status.turns, status.stacks)?;
+    }
+    for modifier in state.get_modifiers() {
+        let source = modifier.source.map_or(String::from("-"), |instance| instance.0.to_string());
+        let (operation, amount) = match modifier.operation {
+            ModifierOperation::Set(amount) => ("set", amount),
+            ModifierOperation::Add(amount) => ("add", amount),
+            ModifierOperation::Multiply(amount) => ("multiply", amount),
+            ModifierOperation::Divide(amount) => ("divide", amount)
+        };
+        writeln!(out, "modifier {} {} {} {} {} {}", modifier.player, modifier.timestamp, source, modified_value_name(&modifier.value), operation, amount)?;
+    }
+    for replacement in state.get_replacements() {
+        let source = replacement.source.map_or(String::from("-"), |instance| instance.0.to_string());
+        let uses = replacement.uses.map_or(String::from("-"), |uses| uses.to_string());
+        let event = match &replacement.event {
+            ReplacedEvent::Damage(damage_type) => format!("damage {}", damage_type.as_ref().map_or(String::from("any"), DamageType::to_string)),
+            ReplacedEvent::Move(zone) => format!("move {}", zone)
+        };
+        let outcome = match replacement.outcome {
+            ReplacementOutcome::Prevent => String::from("prevent"),
+            ReplacementOutcome::Redirect(player) => format!("redirect {}", player),
+            ReplacementOutcome::MoveTo(zone) => format!("move {}", zone)
+        };
+        writeln!(out, "replacement {} {} {} {} {} {}", replacement.player, replacement.timestamp, source, uses, event, outcome)?;
+    }
+
+    for player in state.get_players() {
+        let mana = player.get_mana();
+        writeln!(out, "player {}", escape(player.get_name()))?;
+        writeln!(out, "hp {}", player.get_hp())?;
+        writeln!(out, "mana {} {} {} {}", mana.get_available(), mana.get_capacity(), mana.get_growth(), mana.get_maximum())?;
+        writeln!(out, "discount {}", player.get_discount())?;
+        writeln!(out, "conceded {}", player.has_conceded() as u8)?;
+        for zone in Zone::ALL {
+            let ids: Vec<String> = player.get_zones().get(zone).iter().map(|id| id.0.to_string()).collect();
+            writeln!(out, "{} {}", zone, ids.join(" "))?;
+        }
+    }
+
+    for message in state.get_log() {
+        writeln!(out, "log {}", escape(message))?;
+    }
+    Ok(())
+}
+
+/// Read a snapshot written by `save_snapshot`
+pub fn load_snapshot(source: &str) -> Result<GameState, SnapshotError> {
+    let mut lines = source.lines().enumerate().map(|(index, line)| (index + 1, line));
+
+    let header = lines.next().and_then(|(_, line)| line.strip_prefix(HEADER)).map(str::trim);
+    let version = header.and_then(|v| v.parse::<u32>().ok())
+        .ok_or(SnapshotError { line: 1, kind: SnapshotErrorKind::MissingHeader })?;
+    if version > SNAPSHOT_VERSION {
+        return Err(SnapshotError { line: 1, kind: SnapshotErrorKind::UnsupportedVersion(version) });
+    }
+
+    let mut turn = 0;
+    let mut phase = Phase::Start;
+    let mut active_player = 0;
+    let mut dice_roller = DiceRoller::new(0);
+    let mut instances: Vec<CardInstance> = vec![ ];
+    let mut players: Vec<PlayerFields> = vec![ ];
+    let mut log = vec![ ];
+    let mut stack = vec![ ];
+    let mut statuses = vec![ ];
+    let mut modifiers = vec![ ];
+    let mut replacements = vec![ ];
+    // older snapshots were always taken with the active player to act
+    let mut priority = None;
+    let mut passes = 0;
+    // players come last, so whatever refers to one is only checked once they're all read
+    let mut player_references: Vec<(usize, usize)> = vec![ ];
+
+    for (line, content) in lines {
+        if content.trim().is_empty() {
+            continue;
+        }
+        let error = |kind| SnapshotError { line, kind };
+        let (key, value) = content.split_once(' ').unwrap_or((content, ""));
+
+        match key {
+            "turn" => turn = value.parse().map_err(|_| error(SnapshotErrorKind::InvalidLine))?,
+            "phase" => phase = parse_phase(value).ok_or(error(SnapshotErrorKind::InvalidLine))?,
+            "active" => {
+                active_player = value.parse().map_err(|_| error(SnapshotErrorKind::InvalidLine))?;
+                player_references.push((line, active_player));
+            },
+            "priority" => {
+                let player = value.parse().map_err(|_| error(SnapshotErrorKind::InvalidLine))?;
+                player_references.push((line, player));
+                priority = Some(player);
+            },
+            "passes" => passes = value.parse().map_err(|_| error(SnapshotErrorKind::InvalidLine))?,
+            "dice" => match parse_numbers(value).as_deref() {
+                Some(&[ seed, state ]) => dice_roller = DiceRoller::restore(seed, state),
+                _ => return Err(error(SnapshotErrorKind::InvalidLine))
+            },
+            "instance" => {
+                let instance = parse_instance(value).ok_or(error(SnapshotErrorKind::InvalidLine))?;
+                if instance.id.0 as usize != instances.len() {
+                    return Err(error(SnapshotErrorKind::InvalidInstance(instance.id.0)));
+                }
+                player_references.push((line, instance.owner));
+                instances.push(instance);
+            },
+            "stack" => {
+                // `<instance> <player>` for a card, followed by the ability's index for a triggered ability
+                let (id, player, ability) = match parse_numbers::<usize>(value).as_deref() {
+                    Some(&[ id, player ]) => (id, player, None),
+                    Some(&[ id, player, ability ]) => (id, player, Some(ability)),
+                    _ => return Err(error(SnapshotErrorKind::InvalidLine))
+                };
+                let id = u32::try_from(id).map_err(|_| error(SnapshotErrorKind::InvalidLine))?;
+                // instances are always written before the stack
+                if id as usize >= instances.len() {
+                    return Err(error(SnapshotErrorKind::UnknownInstance(id)));
+                }
+                player_references.push((line, player));
+                stack.push(StackEntry { instance: InstanceId(id), player, ability });
+            },
+            "status" => {
+                let status = parse_status(value).ok_or(error(SnapshotErrorKind::InvalidLine))?;
+                match status.target {
+                    StatusTarget::Player(player) => player_references.push((line, player)),
+                    // instances are always written before statuses
+                    StatusTarget::Instance(instance) if instance.0 as usize >= instances.len() => {
+                        return Err(error(SnapshotErrorKind::UnknownInstance(instance.0)));
+                    },
+                    StatusTarget::Instance(_) => ()
+                }
+                statuses.push(status);
+            },
+            "modifier" => {
+                let modifier = parse_modifier(value).ok_or(error(SnapshotErrorKind::InvalidLine))?;
+                // instances are always written before modifiers
+                if let Some(instance) = modifier.source {
+                    if instance.0 as usize >= instances.len() {
+                        return Err(error(SnapshotErrorKind::UnknownInstance(instance.0)));
+                    }
+                }
+                player_references.push((line, modifier.player));
+                modifiers.push(modifier);
+            },
+            "replacement" => {
+                let replacement = parse_replacement(value).ok_or(error(SnapshotErrorKind::InvalidLine))?;
+                // instances are always written before replacement effects
+                if let Some(instance) = replacement.source {
+                    if instance.0 as usize >= instances.len() {
+                        return Err(error(SnapshotErrorKind::UnknownInstance(instance.0)));
+                    }
+                }
+                player_references.push((line, replacement.player));
+                if let ReplacementOutcome::Redirect(player) = replacement.outcome {
+                    player_references.push((line, player));
+                }
+                replacements.push(replacement);
+            },
+            "player" => players.push(PlayerFields { name: unescape(value), ..PlayerFields::default() }),
+            "log" => log.push(unescape(value)),
+            _ => {
+                let player = players.last_mut().ok_or(error(SnapshotErrorKind::NoPlayer))?;
+                if key == "hp" {
+                    // the only signed field: players can be knocked below zero
+                    player.hp = value.parse().map_err(|_| error(SnapshotErrorKind::InvalidLine))?;
+                    continue;
+                }
+                match key {
+                    "mana" => match parse_numbers(value).as_deref() {
+                        Some(&[ available, capacity, growth, maximum ]) => player.mana = ManaPool::restore(available, capacity, growth, maximum),
+                        _ => return Err(error(SnapshotErrorKind::InvalidLine))
+                    },
+                    "discount" => player.discount = value.parse().map_err(|_| error(SnapshotErrorKind::InvalidLine))?,
+                    "conceded" => player.conceded = value.parse::<u8>().map_err(|_| error(SnapshotErrorKind::InvalidLine))? != 0,
+                    zone => {
+                        let zone = Zone::try_from(zone).map_err(|_| error(SnapshotErrorKind::InvalidLine))?;
+                        let ids: Vec<u32> = parse_numbers(value).ok_or(error(SnapshotErrorKind::InvalidLine))?;
+                        for id in ids {
+                            // instances are always written before the players whose zones hold them
+                            if id as usize >= instances.len() {
+                                return Err(error(SnapshotErrorKind::UnknownInstance(id)));
+                            }
+                            player.zones.get_mut(zone).push(InstanceId(id));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(&(line, player)) = player_references.iter().find(|&&(_, player)| player >= players.len()) {
+        return Err(SnapshotError { line, kind: SnapshotErrorKind::UnknownPlayer(player) });
+    }
+
+    let players = players.into_iter()
+        .map(|p| Player::restore(&p.name, p.hp, p.mana, p.discount, p.zones, p.conceded))
+        .collect();
+    let mut state = GameState::restore(players, instances, active_player, turn, phase, dice_roller, log);
+    state.restore_stack(stack, priority.unwrap_or(active_player), passes);
+    state.restore_statuses(statuses);
+    state.restore_modifiers(modifiers);
+    state.restore_replacements(replacements);
+    Ok(state)
+}
+
+pub fn save_snapshot_to_file(state: &GameState, path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, save_snapshot(state))
+}
+
+pub fn load_snapshot_from_file(path: &Path) -> Result<GameState, SnapshotError> {
+    let source = std::fs::read_to_string(path).map_err(|err| SnapshotError { line: 0, kind: SnapshotErrorKind::Io(err) })?;
+    load_snapshot(&source)
+}
+
+/// Player as it's being read, before everything is known
+#[derive(Default)]
+struct PlayerFields {
+    name: String,
+    hp: i32,
+    mana: ManaPool,
+    discount: u16,
+    zones: Zones,
+    conceded: bool
+}
+
+/// Whitespace-separated numbers, all of which have to fit in `T`
+fn parse_numbers<T: FromStr>(value: &str) -> Option<Vec<T>> {
+    value.split_whitespace().map(|number| number.parse().ok()).collect()
+}
+
+/// `<id> <card id> <owner>`
+fn parse_instance(value: &str) -> Option<CardInstance> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if let &[ id, card, owner ] = parts.as_slice() {
+        return Some(CardInstance {
+            id: InstanceId(id.parse().ok()?),
+            card: CardId::try_from(card).ok()?,
+            owner: owner.parse().ok()?
+        });
+    }
+    None
+}
+
+/// `<kind> player <index>|instance <id> <turns> <stacks>`; a status always has at least one of each
+fn parse_status(value: &str) -> Option<StatusEffect> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if let &[ kind, target_kind, target, turns, stacks ] = parts.as_slice() {
+        let target = match target_kind {
+            "player" => StatusTarget::Player(target.parse().ok()?),
+            "instance" => StatusTarget::Instance(InstanceId(target.parse().ok()?)),
+            _ => return None
+        };
+        return Some(StatusEffect {
+            kind: StatusKind::try_from(kind).ok()?,
+            target,
+            turns: turns.parse().ok().filter(|&turns| turns > 0)?,
+            stacks: stacks.parse().ok().filter(|&stacks| stacks > 0)?
+        });
+    }
+    None
+}
+
+/// `<player> <timestamp> <source id|-> <value> <operation> <amount>`
+fn parse_modifier(value: &str) -> Option<Modifier> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if let &[ player, timestamp, source, ref modified_value @ .., operation, amount ] = parts.as_slice() {
+        let amount = amount.parse().ok()?;
+        return Some(Modifier {
+            player: player.parse().ok()?,
+            value: parse_modified_value(modified_value)?,
+            operation: match operation {
+                "set" => ModifierOperation::Set(amount),
+                "add" => ModifierOperation::Add(amount),
+                "multiply" => ModifierOperation::Multiply(amount),
+                "divide" => ModifierOperation::Divide(amount),
+                _ => return None
+            },
+            source: match source {
+                "-" => None,
+                id => Some(InstanceId(id.parse().ok()?))
+            },
+            timestamp: timestamp.parse().ok()?
+        });
+    }
+    None
+}
+
+/// `<player> <timestamp> <source id|-> <uses|-> <damage <type|any>|move <zone>> <prevent|redirect <player>|move <zone>>`
+fn parse_replacement(value: &str) -> Option<ReplacementEffect> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if let &[ player, timestamp, source, uses, event_kind, event, ref outcome @ .. ] = parts.as_slice() {
+        let event = match event_kind {
+            "damage" => ReplacedEvent::Damage(parse_damage_type_filter(event)?),
+            "move" => ReplacedEvent::Move(Zone::try_from(event).ok()?),
+            _ => return None
+        };
+        let outcome = match *outcome {
+            [ "prevent" ] => ReplacementOutcome::Prevent,
+            [ "redirect", player ] => ReplacementOutcome::Redirect(player.parse().ok()?),
+            [ "move", zone ] => ReplacementOutcome::MoveTo(Zone::try_from(zone).ok()?),
+            _ => return None
+        };
+        if !event.allows(outcome) {
+            return None;
+        }
+        return Some(ReplacementEffect {
+            player: player.parse().ok()?,
+            event,
+            outcome,
+            uses: match uses {
+                "-" => None,
+                uses => Some(uses.parse().ok()?)
+            },
+            source: match source {
+                "-" => None,
+                id => Some(InstanceId(id.parse().ok()?))
+            },
+            timestamp: timestamp.parse().ok()?
+        });
+    }
+    None
+}
+
+/// A damage type, or `any`
+fn parse_damage_type_filter(name: &str) -> Option<Option<DamageType>> {
+    match name {
+        "any" => Some(None),
+        name => DamageType::try_from(name).ok().map(Some)
+    }
+}
+
+/// `damage <type|any>`, `taken <type|any>`, `cost` or `property <name>`
+fn modified_value_name(value: &ModifiedValue) -> String {
+    let damage_type = |damage_type: &Option<DamageType>| damage_type.as_ref().map_or(String::from("any"), DamageType::to_string);
+    match value {
+        ModifiedValue::DamageDealt(damage_type_filter) => format!("damage {}", damage_type(damage_type_filter)),
+        ModifiedValue::DamageTaken(damage_type_filter) => format!("taken {}", damage_type(damage_type_filter)),
+        ModifiedValue::Cost => String::from("cost"),
+        ModifiedValue::Property(property) => format!("property {}", property)
+    }
+}
+
+fn parse_modified_value(parts: &[&str]) -> Option<ModifiedValue> {
+    match *parts {
+        [ "damage", name ] => Some(ModifiedValue::DamageDealt(parse_damage_type_filter(name)?)),
+        [ "taken", name ] => Some(ModifiedValue::DamageTaken(parse_damage_type_filter(name)?)),
+        [ "cost" ] => Some(ModifiedValue::Cost),
+        [ "property", property ] => Some(ModifiedValue::Property(Rc::from(property))),
+        _ => None
+    }
+}
+
+fn phase_name(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Start => "start",
+        Phase::Main => "main",
+        Phase::End => "end"
+    }
+}
+
+fn parse_phase(value: &str) -> Option<Phase> {
+    [ Phase::Start, Phase::Main, Phase::End ].into_iter().find(|&phase| phase_name(phase) == value)
+}
+
+/// Keep text on one line: backslashes and newlines are escaped
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+pub(crate) fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\')
+        }
+    }
+    result
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod tests {
+    use crate::engine::controller::{Action, Controller};
+    use crate::engine::game_state::{GameState, Phase};
+    use crate::engine::mana::ManaPool;
+    use crate::engine::player::Player;
+    use crate::engine::snapshot::{load_snapshot, save_snapshot, SnapshotErrorKind};
+    use crate::engine::status::StatusTarget;
+    use crate::game_zones::card_instance::InstanceId;
+    use crate::game_zones::dice_roller::DiceRoller;
+    use crate::game_zones::types::{DamageType, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+    use crate::game_zones::zone::Zone;
+    use crate::library::card_library::CardLibrary;
+    use crate::library::deck_list::DeckList;
+    use crate::library::test_library::{library, rules};
+    use crate::parsing::expressions::ExpressionResult;
+    use test_case::test_case;
+
+    struct TargetOpponent;
+
+    impl Controller for TargetOpponent {
+        fn choose_action(&mut self, _state: &GameState, _options: &[Action]) -> Action {
+            Action::EndTurn
+        }
+
+        fn choose_targets(&mut self, _state: &GameState, _candidates: &[ExpressionResult], count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+            vec![ ExpressionResult::Player(1); count as usize ]
+        }
+    }
+
+    const SCRIPTS: [ (&str, &str); 2 ] = [
+        ("firebolt.card", "@name \"Firebolt\" [1]: { $ = target(1 in Player); 1d10 fire => $; log \"Firebolt hits {$.name}\\nfor real\"; }"),
+        ("echo.card", "@name \"Echo\" #aura [0]: { } when play { 1d4 ice => target(1 in Player); }")
+    ];
+
+    /// A few turns into a duel, with cards in every zone
+    fn game_in_progress(library: &CardLibrary) -> GameState {
+        let deck = DeckList::parse("5 Firebolt").unwrap().validate(library, &rules()).unwrap();
+        let players = vec![ Player::new("Alice \\ the Red", 20, ManaPool::new(4, 1, 10)), Player::new("Bob", 20, ManaPool::new(4, 1, 10)) ];
+        let mut game = GameState::new(players, DiceRoller::new(42));
+        game.add_deck(0, &deck);
+        game.add_deck(1, &deck);
+
+        game.start_turn(library);
+        game.draw(0);
+        let instance = game.get_player(0).unwrap().get_zones().get(Zone::Hand)[0];
+        game.play_from_hand(0, instance, library, &mut TargetOpponent).unwrap();
+        game.apply_status(StatusKind::Poisoned, StatusTarget::Player(1), 2);
+        game.apply_status(StatusKind::Poisoned, StatusTarget::Player(1), 1);
+        game.apply_status(StatusKind::Frozen, StatusTarget::Instance(InstanceId(3)), 1);
+        game.add_modifier(0, ModifiedValue::DamageDealt(Some(DamageType::new("fire"))), ModifierOperation::Add(2), Some(InstanceId(4)));
+        game.add_modifier(1, ModifiedValue::DamageTaken(None), ModifierOperation::Divide(2), None);
+        game.add_modifier(1, ModifiedValue::Property("hp".into()), ModifierOperation::Set(-1), None);
+        game.add_replacement(1, ReplacedEvent::Damage(Some(DamageType::new("ice"))), ReplacementOutcome::Redirect(0), Some(2), None);
+        game.add_replacement(0, ReplacedEvent::Move(Zone::Discard), ReplacementOutcome::MoveTo(Zone::Exile), None, Some(InstanceId(4)));
+        game
+    }
+
+    #[test]
+    fn round_trip() {
+        let library = library(&SCRIPTS);
+        let game = game_in_progress(&library);
+
+        let saved = save_snapshot(&game);
+        let loaded = load_snapshot(&saved).unwrap();
+
+        assert_eq!(save_snapshot(&loaded), saved);
+        assert_eq!(loaded.get_players(), game.get_players());
+        assert_eq!(loaded.get_instances(), game.get_instances());
+        assert_eq!(loaded.get_log(), game.get_log());
+        assert_eq!(loaded.get_statuses(), game.get_statuses());
+        assert_eq!(loaded.get_modifiers(), game.get_modifiers());
+        assert_eq!(loaded.get_replacements(), game.get_replacements());
+        assert_eq!(loaded.get_phase(), Phase::Main);
+    }
+
+    #[test]
+    fn loaded_game_continues_identically() {
+        let library = library(&SCRIPTS);
+        let mut game = game_in_progress(&library);
+        let mut loaded = load_snapshot(&save_snapshot(&game)).unwrap();
+
+        for state in [ &mut game, &mut loaded ] {
+            state.draw(0);
+            let instance = state.get_player(0).unwrap().get_zones().get(Zone::Hand)[0];
+            state.play_from_hand(0, instance, &library, &mut TargetOpponent).unwrap();
+        }
+
+        assert_eq!(save_snapshot(&loaded), save_snapshot(&game));
+    }
+
+    #[test]
+    fn pending_abilities_are_kept() {
+        let library = library(&SCRIPTS);
+        let deck = DeckList::parse("1 Echo\n1 Firebolt").unwrap().validate(&library, &rules()).unwrap();
+        let players = vec![ Player::new("Alice", 20, ManaPool::new(4, 1, 10)), Player::new("Bob", 20, ManaPool::new(4, 1, 10)) ];
+        let mut game = GameState::new(players, DiceRoller::new(42));
+        game.add_deck(0, &deck);
+        game.draw(0);
+        game.draw(0);
+        let hand = game.get_player(0).unwrap().get_zones().get(Zone::Hand).to_vec();
+        let echo_id = library.get_by_name("Echo").unwrap().get_id();
+        let (echo, firebolt) = if game.get_instance(hand[0]).unwrap().card == echo_id { (hand[0], hand[1]) } else { (hand[1], hand[0]) };
+        game.play_from_hand(0, echo, &library, &mut TargetOpponent).unwrap();
+        game.cast(0, firebolt, &library, &mut TargetOpponent).unwrap();
+        assert_eq!(game.get_stack().len(), 2);
+
+        let saved = save_snapshot(&game);
+        assert!(saved.contains(&format!("\nstack {} 0 0\n", echo.0)));
+        let mut loaded = load_snapshot(&saved).unwrap();
+        assert_eq!(loaded.get_stack(), game.get_stack());
+
+        for state in [ &mut game, &mut loaded ] {
+            state.resolve_stack(&library, &mut TargetOpponent);
+        }
+        assert_eq!(save_snapshot(&loaded), save_snapshot(&game));
+    }
+
+    #[test]
+    fn load_handwritten_state() {
+        let source = "\
+mage_duel snapshot 1
+turn 6
+phase main
+active 1
+dice 1 99
+instance 0 0123456789abcdef 1
+player Alice
+hp -3
+mana 0 6 1 10
+discount 0
+deck
+hand
+discard
+player Bob
+hp 12
+mana 4 6 1 10
+discount 2
+deck
+hand 0
+discard
+";
+        let game = load_snapshot(source).unwrap();
+
+        assert_eq!(game.get_turn(), 6);
+        assert_eq!(game.get_active_player(), 1);
+        assert!(game.get_player(0).unwrap().is_defeated());
+        assert_eq!(game.get_player(1).unwrap().get_discount(), 2);
+        assert_eq!(game.get_player(1).unwrap().get_zones().get(Zone::Hand), &[ InstanceId(0) ]);
+        assert_eq!(game.get_dice_roller(), &DiceRoller::restore(1, 99));
+    }
+
+    #[test_case("turn 1", 1 ; "Missing header")]
+    #[test_case("mage_duel snapshot 99", 1 ; "Newer version")]
+    #[test_case("mage_duel snapshot 1\nturn many", 2 ; "Invalid number")]
+    #[test_case("mage_duel snapshot 1\nhp 20", 2 ; "Field before player")]
+    #[test_case("mage_duel snapshot 1\ninstance 1 0123456789abcdef 0", 2 ; "Instance out of order")]
+    #[test_case("mage_duel snapshot 1\nplayer Alice\nhand 3", 3 ; "Unknown instance")]
+    #[test_case("mage_duel snapshot 1\nplayer Alice\nmana 1 2", 3 ; "Too few values")]
+    #[test_case("mage_duel snapshot 1\nplayer Alice\nmana 65536 6 1 10", 3 ; "Mana out of range")]
+    #[test_case("mage_duel snapshot 1\nplayer Alice\ndiscount 65536", 3 ; "Discount out of range")]
+    #[test_case("mage_duel snapshot 1\nplayer Alice\nhand 4294967296", 3 ; "Instance ID out of range")]
+    #[test_case("mage_duel snapshot 3\nstack 4294967296 0", 2 ; "Stack entry out of range")]
+    #[test_case("mage_duel snapshot 7\ninstance 0 0123456789abcdef 0\nstack 0 0 1 2", 3 ; "Stack entry with too many values")]
+    #[test_case("mage_duel snapshot 4\nstatus frozen player 0 0 1", 2 ; "Status with no turns left")]
+    #[test_case("mage_duel snapshot 4\nstatus poisoned player 0 1 0", 2 ; "Status with no stacks")]
+    #[test_case("mage_duel snapshot 4\nstatus soggy player 0 1 1", 2 ; "Unknown status")]
+    #[test_case("mage_duel snapshot 4\nstatus frozen instance 7 1 1", 2 ; "Status on unknown instance")]
+    #[test_case("mage_duel snapshot 5\nmodifier 0 0 - damage Wet add 1", 2 ; "Invalid damage type")]
+    #[test_case("mage_duel snapshot 5\nmodifier 0 0 - cost halve 1", 2 ; "Unknown modifier operation")]
+    #[test_case("mage_duel snapshot 6\nreplacement 0 0 - 1 move discard prevent", 2 ; "Prevented zone move")]
+    #[test_case("mage_duel snapshot 6\nreplacement 0 0 - 1 damage any", 2 ; "Replacement without outcome")]
+    #[test_case("mage_duel snapshot 1\nactive 2\nplayer Alice\nplayer Bob", 2 ; "Unknown active player")]
+    #[test_case("mage_duel snapshot 3\npriority 5\nplayer Alice", 2 ; "Unknown player with priority")]
+    #[test_case("mage_duel snapshot 1\ninstance 0 0123456789abcdef 1\nplayer Alice", 2 ; "Instance of unknown owner")]
+    #[test_case("mage_duel snapshot 3\ninstance 0 0123456789abcdef 0\nstack 0 1\nplayer Alice", 3 ; "Stack entry of unknown player")]
+    #[test_case("mage_duel snapshot 4\nstatus frozen player 1 1 1\nplayer Alice", 2 ; "Status on unknown player")]
+    #[test_case("mage_duel snapshot 5\nmodifier 3 0 - cost add 1\nplayer Alice", 2 ; "Modifier of unknown player")]
+    #[test_case("mage_duel snapshot 6\nreplacement 1 0 - - damage any prevent\nplayer Alice", 2 ; "Replacement of unknown player")]
+    #[test_case("mage_duel snapshot 6\nreplacement 0 0 - - damage any redirect 1\nplayer Alice", 2 ; "Redirect to unknown player")]
+    fn invalid_snapshot(source: &str, expected_line: usize) {
+        let error = load_snapshot(source).err().unwrap();
+
+        assert_eq!(error.line, expected_line);
+        assert!(!matches!(error.kind, SnapshotErrorKind::Io(_)));
+    }
+}
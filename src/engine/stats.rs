@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+use crate::game_zones::types::DamageType;
+use crate::library::card_library::CardId;
+
+/// Running totals about a duel, for balance work. Not part of the game itself, so snapshots don't keep them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DuelStats {
+    damage: HashMap<DamageType, i64>,
+    plays: HashMap<CardId, u32>
+}
+
+impl DuelStats {
+    pub fn record_damage(&mut self, damage_type: DamageType, amount: i32) {
+        *self.damage.entry(damage_type).or_default() += amount as i64;
+    }
+
+    pub fn record_play(&mut self, card: CardId) {
+        *self.plays.entry(card).or_default() += 1;
+    }
+
+    /// Damage dealt to players, by type
+    pub fn get_damage(&self) -> &HashMap<DamageType, i64> {
+        &self.damage
+    }
+
+    /// How many times each card was played from hand
+    pub fn get_plays(&self) -> &HashMap<CardId, u32> {
+        &self.plays
+    }
+}
@@ -0,0 +1,84 @@
+use crate::game_zones::{card_instance::InstanceId, types::{DamageType, Dice, StatusKind}};
+
+use super::game_state::Phase;
+
+/// What applying a status does when its target already has it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stacking {
+    /// Keep a single stack and the longer of the two durations
+    Refresh,
+    /// Add a stack and keep the longer of the two durations
+    Intensify
+}
+
+/// Damage a status deals whenever it ticks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickDamage {
+    /// Ticks as the affected player's turn enters this phase
+    pub phase: Phase,
+    /// Rolled once per stack; without dice, every stack deals 1
+    pub dice: Option<Dice>,
+    pub damage_type: DamageType
+}
+
+/// How a kind of status behaves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusRules {
+    pub stacking: Stacking,
+    pub tick: Option<TickDamage>,
+    /// An affected player can't play cards, and an affected card can't be played
+    pub prevents_plays: bool
+}
+
+impl StatusRules {
+    pub fn of(kind: StatusKind) -> Self {
+        match kind {
+            StatusKind::Burning => StatusRules {
+                stacking: Stacking::Refresh,
+                tick: Some(TickDamage { phase: Phase::Start, dice: Some(Dice::new(4)), damage_type: DamageType::new("fire") }),
+                prevents_plays: false
+            },
+            StatusKind::Frozen => StatusRules { stacking: Stacking::Refresh, tick: None, prevents_plays: true },
+            StatusKind::Poisoned => StatusRules {
+                stacking: Stacking::Intensify,
+                tick: Some(TickDamage { phase: Phase::End, dice: None, damage_type: DamageType::new("necrotic") }),
+                prevents_plays: false
+            },
+            // does nothing on its own, but changes how damage lands (see `InteractionTable`)
+            StatusKind::Wet => StatusRules { stacking: Stacking::Refresh, tick: None, prevents_plays: false }
+        }
+    }
+}
+
+/// Whatever a status is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusTarget {
+    /// Index of a player in the duel
+    Player(usize),
+    /// A card instance; it follows its owner's turns, and any damage it takes goes to its owner
+    Instance(InstanceId)
+}
+
+/// A status on one target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub target: StatusTarget,
+    /// Ends of the affected player's turn left before it wears off
+    pub turns: u16,
+    pub stacks: u16
+}
+
+impl StatusEffect {
+    pub fn get_rules(&self) -> StatusRules {
+        StatusRules::of(self.kind)
+    }
+
+    /// Apply the same kind of status again, following its stacking rule
+    pub fn reapply(&mut self, turns: u16) {
+        if self.get_rules().stacking == Stacking::Intensify {
+            self.stacks = self.stacks.saturating_add(1);
+        }
+        self.turns = self.turns.max(turns);
+    }
+}
@@ -1 +1,6 @@
-pub mod types;
\ No newline at end of file
+pub mod types;
+pub mod damage_types;
+pub mod dice_roller;
+pub mod zone;
+pub mod card_instance;
+mod damage_types_tests;
\ No newline at end of file
@@ -0,0 +1,14 @@
+use crate::library::card_library::CardId;
+
+/// Identifies one physical copy of a card within a duel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InstanceId(pub u32);
+
+/// One copy of a library card, owned by a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardInstance {
+    pub id: InstanceId,
+    pub card: CardId,
+    /// Index of the owning player
+    pub owner: usize
+}
@@ -0,0 +1,190 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use super::types::{DamageType, ModifierOperation};
+
+/// How players take a type of damage unless something says otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Susceptibility {
+    Normal,
+    /// Takes half, rounded down
+    Resistant,
+    /// Takes double
+    Vulnerable
+}
+
+impl Susceptibility {
+    /// What it does to the damage, if anything
+    pub fn get_operation(self) -> Option<ModifierOperation> {
+        match self {
+            Susceptibility::Normal => None,
+            Susceptibility::Resistant => Some(ModifierOperation::Divide(2)),
+            Susceptibility::Vulnerable => Some(ModifierOperation::Multiply(2))
+        }
+    }
+}
+
+impl Display for Susceptibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Susceptibility::Normal => "normal",
+            Susceptibility::Resistant => "resistant",
+            Susceptibility::Vulnerable => "vulnerable"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug)]
+pub struct SusceptibilityParseError;
+
+impl TryFrom<&str> for Susceptibility {
+    type Error = SusceptibilityParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        [ Susceptibility::Normal, Susceptibility::Resistant, Susceptibility::Vulnerable ].into_iter()
+            .find(|susceptibility| susceptibility.to_string() == value)
+            .ok_or(SusceptibilityParseError)
+    }
+}
+
+/// One damage type the registry knows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DamageTypeInfo {
+    pub damage_type: DamageType,
+    /// Name to show players, e.g. `Fire`
+    pub display_name: String,
+    pub susceptibility: Susceptibility
+}
+
+/// Same form as a line of the registry file
+impl Display for DamageTypeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} \"{}\"", self.damage_type, self.display_name)?;
+        if self.susceptibility != Susceptibility::Normal {
+            write!(f, " {}", self.susceptibility)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum DamageTypesErrorKind {
+    Io(std::io::Error),
+    /// Line isn't `<name> "<display name>" [normal|resistant|vulnerable]`
+    InvalidLine,
+    /// Not a lowercase name like `fire` or `cold_iron`
+    InvalidName(String),
+    UnknownSusceptibility(String)
+}
+
+#[derive(Debug)]
+pub struct DamageTypesError {
+    /// 1-based line of the file (0 when it's about the file as a whole)
+    pub line: usize,
+    pub kind: DamageTypesErrorKind
+}
+
+impl Display for DamageTypesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            DamageTypesErrorKind::Io(err) => write!(f, "{}", err),
+            DamageTypesErrorKind::InvalidLine => write!(f, "expected '<name> \"<display name>\" [normal|resistant|vulnerable]'"),
+            DamageTypesErrorKind::InvalidName(name) => write!(f, "invalid damage type name '{}'", name),
+            DamageTypesErrorKind::UnknownSusceptibility(name) => write!(f, "unknown susceptibility '{}'", name)
+        }
+    }
+}
+
+/// Every damage type scripts can use, and how players take each one by default.
+/// The default registry has the built-in types (`none` included), all taken normally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DamageTypeRegistry {
+    types: Vec<DamageTypeInfo>
+}
+
+impl Default for DamageTypeRegistry {
+    fn default() -> Self {
+        let types = DamageType::BUILT_IN.iter()
+            .map(|&name| {
+                let mut display_name = name.to_string();
+                display_name[.. 1].make_ascii_uppercase();
+                DamageTypeInfo { damage_type: DamageType::new(name), display_name, susceptibility: Susceptibility::Normal }
+            })
+            .collect();
+        DamageTypeRegistry { types }
+    }
+}
+
+impl DamageTypeRegistry {
+    /// The built-in types, plus or redefined by one `<name> "<display name>" [normal|resistant|vulnerable]` per line,
+    /// with `//` comments
+    pub fn parse(source: &str) -> Result<Self, DamageTypesError> {
+        let mut registry = DamageTypeRegistry::default();
+        for (index, content) in source.lines().enumerate() {
+            let line = index + 1;
+            let content = content.split("//").next().unwrap_or_default().trim();
+            if content.is_empty() {
+                continue;
+            }
+            let error = |kind| DamageTypesError { line, kind };
+
+            let (name, rest) = content.split_once('"').ok_or(error(DamageTypesErrorKind::InvalidLine))?;
+            let (display_name, susceptibility) = rest.split_once('"').ok_or(error(DamageTypesErrorKind::InvalidLine))?;
+            let name = name.trim();
+            let damage_type = DamageType::try_from(name).map_err(|_| error(DamageTypesErrorKind::InvalidName(name.to_string())))?;
+            if display_name.trim().is_empty() {
+                return Err(error(DamageTypesErrorKind::InvalidLine));
+            }
+            let susceptibility = match susceptibility.trim() {
+                "" => Susceptibility::Normal,
+                name => Susceptibility::try_from(name).map_err(|_| error(DamageTypesErrorKind::UnknownSusceptibility(name.to_string())))?
+            };
+
+            registry.register(DamageTypeInfo { damage_type, display_name: display_name.trim().to_string(), susceptibility });
+        }
+        Ok(registry)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, DamageTypesError> {
+        let source = std::fs::read_to_string(path).map_err(|err| DamageTypesError { line: 0, kind: DamageTypesErrorKind::Io(err) })?;
+        DamageTypeRegistry::parse(&source)
+    }
+
+    /// Add a type, or replace the one with the same name
+    pub fn register(&mut self, info: DamageTypeInfo) {
+        match self.types.iter_mut().find(|existing| existing.damage_type == info.damage_type) {
+            Some(existing) => *existing = info,
+            None => self.types.push(info)
+        }
+    }
+
+    pub fn get_types(&self) -> &[DamageTypeInfo] {
+        &self.types
+    }
+
+    pub fn get(&self, damage_type: &DamageType) -> Option<&DamageTypeInfo> {
+        self.types.iter().find(|info| info.damage_type == *damage_type)
+    }
+
+    /// The registered type with this name
+    pub fn find(&self, name: &str) -> Option<&DamageType> {
+        self.types.iter().map(|info| &info.damage_type).find(|damage_type| damage_type.get_name() == name)
+    }
+
+    /// How players take the type by default; unregistered types are taken normally
+    pub fn get_susceptibility(&self, damage_type: &DamageType) -> Susceptibility {
+        self.get(damage_type).map_or(Susceptibility::Normal, |info| info.susceptibility)
+    }
+}
+
+/// Same form `parse` reads
+impl Display for DamageTypeRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for info in &self.types {
+            writeln!(f, "{}", info)?;
+        }
+        Ok(())
+    }
+}
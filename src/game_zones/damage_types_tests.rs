@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use crate::game_zones::damage_types::{DamageTypeInfo, DamageTypeRegistry, DamageTypesErrorKind, Susceptibility};
+    use crate::game_zones::types::{DamageType, ModifierOperation};
+    use test_case::test_case;
+
+    #[test]
+    fn built_in_types() {
+        let registry = DamageTypeRegistry::default();
+
+        assert_eq!(registry.get_types().len(), DamageType::BUILT_IN.len());
+        assert_eq!(registry.get(&DamageType::new("fire")).unwrap().display_name, "Fire");
+        assert_eq!(registry.find("none"), Some(&DamageType::none()));
+        assert_eq!(registry.find("sonic"), None);
+        assert!(registry.get_types().iter().all(|info| info.susceptibility == Susceptibility::Normal));
+    }
+
+    #[test]
+    fn add_and_redefine_types() {
+        let registry = DamageTypeRegistry::parse("// new\nsonic \"Sonic Boom\" vulnerable\n\npsychic \"Mind\" resistant // tough players\n").unwrap();
+
+        assert_eq!(registry.get_types().len(), DamageType::BUILT_IN.len() + 1);
+        assert_eq!(registry.get(&DamageType::new("sonic")), Some(&DamageTypeInfo {
+            damage_type: DamageType::new("sonic"),
+            display_name: String::from("Sonic Boom"),
+            susceptibility: Susceptibility::Vulnerable
+        }));
+        assert_eq!(registry.get(&DamageType::new("psychic")).unwrap().display_name, "Mind");
+        assert_eq!(registry.get_susceptibility(&DamageType::new("psychic")), Susceptibility::Resistant);
+        assert_eq!(registry.get_susceptibility(&DamageType::new("fire")), Susceptibility::Normal);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let registry = DamageTypeRegistry::parse("sonic \"Sonic\" vulnerable\nfire \"Flame\" normal").unwrap();
+
+        assert_eq!(DamageTypeRegistry::parse(&registry.to_string()).unwrap(), registry);
+    }
+
+    #[test_case(Susceptibility::Normal, None)]
+    #[test_case(Susceptibility::Resistant, Some(ModifierOperation::Divide(2)))]
+    #[test_case(Susceptibility::Vulnerable, Some(ModifierOperation::Multiply(2)))]
+    fn susceptibility_operation(susceptibility: Susceptibility, operation: Option<ModifierOperation>) {
+        assert_eq!(susceptibility.get_operation(), operation);
+    }
+
+    #[test_case("sonic", 1; "missing display name")]
+    #[test_case("sonic \"Sonic", 1; "unclosed display name")]
+    #[test_case("\nsonic \"\"", 2; "empty display name")]
+    #[test_case("Sonic \"Sonic\"", 1; "uppercase name")]
+    #[test_case("sonic boom \"Sonic\"", 1; "name with a space")]
+    #[test_case("sonic \"Sonic\" immune", 1; "unknown susceptibility")]
+    fn invalid_registry(source: &str, line: usize) {
+        let error = DamageTypeRegistry::parse(source).unwrap_err();
+        assert_eq!(error.line, line);
+        assert!(!matches!(error.kind, DamageTypesErrorKind::Io(_)));
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::types::Dice;
+
+/// Seedable source of randomness for every dice roll in a duel.
+/// Uses splitmix64, which is small, fast and fully reproducible from its state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceRoller {
+    seed: u64,
+    state: u64
+}
+
+impl DiceRoller {
+    pub fn new(seed: u64) -> Self {
+        DiceRoller { seed, state: seed }
+    }
+
+    /// Pick up exactly where another roller left off
+    pub fn restore(seed: u64, state: u64) -> Self {
+        DiceRoller { seed, state }
+    }
+
+    /// Seed from the system clock when the caller doesn't care about reproducing the rolls
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        DiceRoller::new(nanos)
+    }
+
+    /// The seed this roller was created with
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Current position in the sequence; together with the seed, this reproduces every future roll
+    pub fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound` (bound must be non-zero)
+    pub fn next_below(&mut self, bound: u64) -> u64 {
+        // rejection sampling keeps the distribution uniform
+        let zone = u64::MAX - (u64::MAX % bound);
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Roll a single die, returning a value from 1 to the number of sides
+    pub fn roll(&mut self, dice: Dice) -> u16 {
+        if dice.get_sides() == 0 {
+            return 0;
+        }
+        self.next_below(dice.get_sides() as u64) as u16 + 1
+    }
+}
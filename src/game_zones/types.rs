@@ -1,19 +1,201 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum DamageType {
-    None,
-    Fire,
-    Lightning,
-    Force,
-    Divine,
-    Necrotic,
-    Acid,
-    Ice,
-    Psychic
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::{dice_roller::DiceRoller, zone::Zone};
+
+/// Kind of damage, by the name scripts use for it (e.g. `fire`).
+/// Which kinds exist is up to the `DamageTypeRegistry`; `none` is untyped damage.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DamageType(Arc<str>);
+
+impl DamageType {
+    /// Types every registry starts with
+    pub const BUILT_IN: [&'static str; 9] = [ "none", "fire", "lightning", "force", "divine", "necrotic", "acid", "ice", "psychic" ];
+
+    /// A type whose name is known to be valid, like the built-in ones; anything else goes through `try_from`
+    pub fn new(name: &str) -> Self {
+        debug_assert!(DamageType::try_from(name).is_ok(), "invalid damage type name '{}'", name);
+        DamageType(Arc::from(name))
+    }
+
+    /// Untyped damage
+    pub fn none() -> Self {
+        DamageType::new("none")
+    }
+
+    pub fn is_none(&self) -> bool {
+        &*self.0 == "none"
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for DamageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 #[derive(Debug)]
 pub struct DamageTypeParseError;
 
+/// Any name that could be a damage type: a lowercase letter followed by lowercase letters, digits or underscores.
+/// Whether the type exists is up to the registry.
+impl TryFrom<&str> for DamageType {
+    type Error = DamageTypeParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut chars = value.chars();
+        let valid = chars.next().is_some_and(|first| first.is_ascii_lowercase())
+            && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid {
+            return Err(DamageTypeParseError);
+        }
+        Ok(DamageType(Arc::from(value)))
+    }
+}
+
+/// Lingering condition on a player or card instance (what it does is up to the engine)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StatusKind {
+    Burning,
+    Frozen,
+    Poisoned,
+    Wet
+}
+
+impl StatusKind {
+    pub const ALL: [StatusKind; 4] = [ StatusKind::Burning, StatusKind::Frozen, StatusKind::Poisoned, StatusKind::Wet ];
+}
+
+impl std::fmt::Display for StatusKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            StatusKind::Burning => "burning",
+            StatusKind::Frozen => "frozen",
+            StatusKind::Poisoned => "poisoned",
+            StatusKind::Wet => "wet"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug)]
+pub struct StatusKindParseError;
+
+impl TryFrom<&str> for StatusKind {
+    type Error = StatusKindParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        StatusKind::ALL.into_iter().find(|kind| kind.to_string() == value).ok_or(StatusKindParseError)
+    }
+}
+
+/// A number the engine works out that continuous modifiers can change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModifiedValue {
+    /// Damage dealt by the player's cards, of one type or (`None`) any type
+    DamageDealt(Option<DamageType>),
+    /// Damage the player takes, of one type or (`None`) any type
+    DamageTaken(Option<DamageType>),
+    /// What the player's cards cost
+    Cost,
+    /// An integer property of the player as scripts read it (e.g. `hp`)
+    Property(Rc<str>)
+}
+
+impl ModifiedValue {
+    /// Whether a modifier of this value changes `value`; a modifier of any damage type covers every type
+    pub fn covers(&self, value: &ModifiedValue) -> bool {
+        match (self, value) {
+            (ModifiedValue::DamageDealt(None), ModifiedValue::DamageDealt(_)) => true,
+            (ModifiedValue::DamageTaken(None), ModifiedValue::DamageTaken(_)) => true,
+            _ => self == value
+        }
+    }
+}
+
+/// What a modifier does to the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModifierOperation {
+    Set(i32),
+    Add(i32),
+    Multiply(i32),
+    /// Rounds toward zero; dividing by zero gives zero
+    Divide(i32)
+}
+
+impl ModifierOperation {
+    /// Modifiers apply layer by layer, lowest first: setting, then adding, then multiplying and dividing
+    pub fn get_layer(self) -> u8 {
+        match self {
+            ModifierOperation::Set(_) => 0,
+            ModifierOperation::Add(_) => 1,
+            ModifierOperation::Multiply(_) | ModifierOperation::Divide(_) => 2
+        }
+    }
+
+    pub fn apply(self, value: i32) -> i32 {
+        match self {
+            ModifierOperation::Set(amount) => amount,
+            ModifierOperation::Add(amount) => value.saturating_add(amount),
+            ModifierOperation::Multiply(amount) => value.saturating_mul(amount),
+            ModifierOperation::Divide(0) => 0,
+            ModifierOperation::Divide(amount) => value / amount
+        }
+    }
+}
+
+/// Something about to happen that a replacement effect can change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplacedEvent {
+    /// The player taking damage of one type or (`None`) any type
+    Damage(Option<DamageType>),
+    /// One of the player's cards moving to the zone
+    Move(Zone)
+}
+
+/// What happens instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacementOutcome {
+    /// The damage isn't dealt
+    Prevent,
+    /// The damage goes to this player
+    Redirect(usize),
+    /// The card goes to this zone
+    MoveTo(Zone)
+}
+
+impl ReplacedEvent {
+    /// Whether the outcome makes sense for this kind of event
+    pub fn allows(&self, outcome: ReplacementOutcome) -> bool {
+        matches!(
+            (self, outcome),
+            (ReplacedEvent::Damage(_), ReplacementOutcome::Prevent | ReplacementOutcome::Redirect(_)) | (ReplacedEvent::Move(_), ReplacementOutcome::MoveTo(_))
+        )
+    }
+}
+
+/// Something that happens in a duel that sets off an aura's `when` abilities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerEvent {
+    /// `when play`: anyone plays a card
+    Play,
+    /// `when turn start`: the aura's owner starts their turn
+    TurnStart
+}
+
+impl std::fmt::Display for TriggerEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerEvent::Play => write!(f, "play"),
+            TriggerEvent::TurnStart => write!(f, "turn start")
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Dice {
     sides: u8
@@ -34,18 +216,25 @@ impl From<std::num::ParseIntError> for ParseDiceError {
 }
 
 impl Dice {
+    pub fn new(sides: u8) -> Self {
+        Dice { sides }
+    }
+
     pub fn get_sides(self) -> u8 {
         self.sides
     }
-    // TODO: roll()
+
+    pub fn roll(self, roller: &mut DiceRoller) -> u16 {
+        roller.roll(self)
+    }
 }
 
 impl TryFrom<&str> for Dice {
     type Error = ParseDiceError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value.starts_with('d') {
-            let sides = value[1..].parse::<u8>()?;
+        if let Some(sides) = value.strip_prefix('d') {
+            let sides = sides.parse::<u8>()?;
             return Ok(Dice{ sides });
         }
         Err(Self::Error::InvalidFormat)
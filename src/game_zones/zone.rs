@@ -0,0 +1,95 @@
+use super::card_instance::InstanceId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zone {
+    Deck,
+    Hand,
+    Discard,
+    /// Auras that stay around after resolving
+    Play,
+    /// Out of the duel for good
+    Exile
+}
+
+impl Zone {
+    pub const ALL: [Zone; 5] = [ Zone::Deck, Zone::Hand, Zone::Discard, Zone::Play, Zone::Exile ];
+}
+
+impl std::fmt::Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Zone::Deck => "deck",
+            Zone::Hand => "hand",
+            Zone::Discard => "discard",
+            Zone::Play => "play",
+            Zone::Exile => "exile"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug)]
+pub struct ZoneParseError;
+
+impl TryFrom<&str> for Zone {
+    type Error = ZoneParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Zone::ALL.into_iter().find(|zone| zone.to_string() == value).ok_or(ZoneParseError)
+    }
+}
+
+/// Where a player's cards are. The top of the deck is the end of its list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Zones {
+    deck: Vec<InstanceId>,
+    hand: Vec<InstanceId>,
+    discard: Vec<InstanceId>,
+    play: Vec<InstanceId>,
+    exile: Vec<InstanceId>
+}
+
+impl Zones {
+    pub fn get(&self, zone: Zone) -> &[InstanceId] {
+        match zone {
+            Zone::Deck => &self.deck,
+            Zone::Hand => &self.hand,
+            Zone::Discard => &self.discard,
+            Zone::Play => &self.play,
+            Zone::Exile => &self.exile
+        }
+    }
+
+    pub fn get_mut(&mut self, zone: Zone) -> &mut Vec<InstanceId> {
+        match zone {
+            Zone::Deck => &mut self.deck,
+            Zone::Hand => &mut self.hand,
+            Zone::Discard => &mut self.discard,
+            Zone::Play => &mut self.play,
+            Zone::Exile => &mut self.exile
+        }
+    }
+
+    /// Zone that currently holds the instance
+    pub fn find(&self, instance: InstanceId) -> Option<Zone> {
+        Zone::ALL.into_iter().find(|&zone| self.get(zone).contains(&instance))
+    }
+
+    /// Move an instance from wherever it is to the top of another zone
+    pub fn move_to(&mut self, instance: InstanceId, to: Zone) -> bool {
+        let from = match self.find(instance) {
+            Some(from) => from,
+            None => return false
+        };
+        self.get_mut(from).retain(|&i| i != instance);
+        self.get_mut(to).push(instance);
+        true
+    }
+
+    /// Take the top card of the deck and put it in hand
+    pub fn draw(&mut self) -> Option<InstanceId> {
+        let instance = self.deck.pop()?;
+        self.hand.push(instance);
+        Some(instance)
+    }
+}
@@ -0,0 +1,10 @@
+// explicit returns are the house style
+#![allow(clippy::needless_return)]
+
+pub mod parsing;
+pub mod game_zones;
+pub mod engine;
+pub mod library;
+pub mod ai;
+pub mod lsp;
+pub mod cli;
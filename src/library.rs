@@ -0,0 +1,10 @@
+pub mod card_library;
+pub mod catalog;
+pub mod deck_list;
+pub mod lints;
+mod card_library_tests;
+mod catalog_tests;
+mod deck_list_tests;
+mod lints_tests;
+#[cfg(test)]
+pub(crate) mod test_library;
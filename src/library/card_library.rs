@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::parsing::{card::Card, cst::parse_cst, parser::ParseError, tokenizer::{Span, TokenizerError}};
+
+/// Card scripts are the files in a library directory with this extension
+pub const CARD_FILE_EXTENSION: &str = "card";
+
+/// Identifies a card across runs: derived from the script's path relative to the library root,
+/// so adding or removing other cards never changes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CardId(u64);
+
+impl CardId {
+    /// 64-bit FNV-1a hash of the relative path (with `/` separators on every platform)
+    pub fn from_relative_path(path: &str) -> Self {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in path.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        CardId(hash)
+    }
+
+    pub fn get_value(self) -> u64 {
+        self.0
+    }
+}
+
+impl Display for CardId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl TryFrom<&str> for CardId {
+    type Error = std::num::ParseIntError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(CardId(u64::from_str_radix(value, 16)?))
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Tokenizer(TokenizerError),
+    Parse(ParseError),
+    /// Another card already has this name
+    DuplicateName(Rc<str>),
+    /// Two paths hashed to the same card ID
+    DuplicateId(CardId),
+    /// Links to directories aren't followed, since they could lead back up the tree
+    SymlinkedDirectory
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl From<TokenizerError> for LoadError {
+    fn from(err: TokenizerError) -> Self {
+        LoadError::Tokenizer(err)
+    }
+}
+
+impl From<ParseError> for LoadError {
+    fn from(err: ParseError) -> Self {
+        LoadError::Parse(err)
+    }
+}
+
+/// Something wrong with one file in the library
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub error: LoadError,
+    /// Where in the script it went wrong, for tokenizer and parse errors
+    pub span: Option<Span>
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.error {
+            LoadError::Io(err) => write!(f, "{}: {}", self.path.display(), err),
+            LoadError::Tokenizer(err) => write!(f, "{}: tokenizer error: {}", self.path.display(), err),
+            LoadError::Parse(err) => write!(f, "{}: parse error: {}", self.path.display(), err),
+            LoadError::DuplicateName(name) => write!(f, "{}: another card is already named '{}'", self.path.display(), name),
+            LoadError::DuplicateId(id) => write!(f, "{}: card ID {} is already taken", self.path.display(), id),
+            LoadError::SymlinkedDirectory => write!(f, "{}: symlinked directories aren't followed", self.path.display())
+        }
+    }
+}
+
+/// A successfully loaded card
+pub struct LibraryCard {
+    id: CardId,
+    name: Rc<str>,
+    path: PathBuf,
+    card: Card
+}
+
+impl LibraryCard {
+    pub fn get_id(&self) -> CardId {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get_card(&self) -> &Card {
+        &self.card
+    }
+}
+
+/// Every card a host knows about, loaded from a directory of scripts
+#[derive(Default)]
+pub struct CardLibrary {
+    cards: Vec<LibraryCard>,
+    by_id: HashMap<CardId, usize>,
+    by_name: HashMap<Rc<str>, usize>
+}
+
+impl CardLibrary {
+    /// Recursively load every card script under `root`, with the built-in damage types.
+    /// Every file is checked, and all problems are reported together.
+    pub fn load(root: &Path) -> Result<CardLibrary, Vec<Diagnostic>> {
+        CardLibrary::load_with(root, &DamageTypeRegistry::default())
+    }
+
+    /// Like `load`, but scripts can use any of the registry's damage types
+    pub fn load_with(root: &Path, damage_types: &DamageTypeRegistry) -> Result<CardLibrary, Vec<Diagnostic>> {
+        match CardLibrary::load_partial(root, damage_types) {
+            (library, diagnostics) if diagnostics.is_empty() => Ok(library),
+            (_, diagnostics) => Err(diagnostics)
+        }
+    }
+
+    /// Load every card script under `root` that loads fine, along with what's wrong with the rest
+    pub fn load_partial(root: &Path, damage_types: &DamageTypeRegistry) -> (CardLibrary, Vec<Diagnostic>) {
+        let (sources, mut diagnostics) = read_sources(root);
+        let (library, mut more) = CardLibrary::from_sources_partial(sources, damage_types);
+        diagnostics.append(&mut more);
+        (library, diagnostics)
+    }
+
+    /// Build a library from `(relative path, script)` pairs, with the built-in damage types
+    pub fn from_sources(sources: impl IntoIterator<Item=(PathBuf, String)>) -> Result<CardLibrary, Vec<Diagnostic>> {
+        CardLibrary::from_sources_with(sources, &DamageTypeRegistry::default())
+    }
+
+    /// Like `from_sources`, but scripts can use any of the registry's damage types
+    pub fn from_sources_with(sources: impl IntoIterator<Item=(PathBuf, String)>, damage_types: &DamageTypeRegistry) -> Result<CardLibrary, Vec<Diagnostic>> {
+        match CardLibrary::from_sources_partial(sources, damage_types) {
+            (library, diagnostics) if diagnostics.is_empty() => Ok(library),
+            (_, diagnostics) => Err(diagnostics)
+        }
+    }
+
+    /// Like `from_sources_with`, but keeps the cards that load fine
+    pub fn from_sources_partial(sources: impl IntoIterator<Item=(PathBuf, String)>, damage_types: &DamageTypeRegistry) -> (CardLibrary, Vec<Diagnostic>) {
+        let mut library = CardLibrary::default();
+        let mut diagnostics = vec![ ];
+
+        for (path, source) in sources {
+            if let Err((error, span)) = library.add(&path, &source, damage_types) {
+                diagnostics.push(Diagnostic { path, error, span });
+            }
+        }
+
+        (library, diagnostics)
+    }
+
+    /// The same library with every card simplified (see `Card::simplified`), for running duels
+    pub fn simplified(self) -> CardLibrary {
+        let cards = self.cards.into_iter()
+            .map(|card| LibraryCard { card: card.card.simplified(), ..card })
+            .collect();
+        CardLibrary { cards, ..self }
+    }
+
+    fn add(&mut self, path: &Path, source: &str, damage_types: &DamageTypeRegistry) -> Result<CardId, (LoadError, Option<Span>)> {
+        let cst = parse_cst(source, damage_types).map_err(|(err, span)| (err.into(), Some(span)))?;
+        let card = cst.to_card().map_err(|(err, index)| (err.into(), cst.tokens().get(index).map(|token| token.span)))?;
+
+        let relative: Vec<String> = path.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+        let id = CardId::from_relative_path(&relative.join("/"));
+        // unnamed cards go by their file name
+        let name: Rc<str> = match card.get_name() {
+            Some(name) => Rc::from(name),
+            None => Rc::from(path.file_stem().unwrap_or_default().to_string_lossy().as_ref())
+        };
+
+        if self.by_id.contains_key(&id) {
+            return Err((LoadError::DuplicateId(id), None));
+        }
+        if self.by_name.contains_key(&name) {
+            return Err((LoadError::DuplicateName(name), None));
+        }
+
+        self.by_id.insert(id, self.cards.len());
+        self.by_name.insert(name.clone(), self.cards.len());
+        self.cards.push(LibraryCard { id, name, path: path.to_path_buf(), card });
+        Ok(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&LibraryCard> {
+        self.cards.iter()
+    }
+
+    pub fn get(&self, id: CardId) -> Option<&LibraryCard> {
+        self.by_id.get(&id).map(|&index| &self.cards[index])
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&LibraryCard> {
+        self.by_name.get(name).map(|&index| &self.cards[index])
+    }
+
+    /// Every card with `#tag` in its header
+    pub fn get_by_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item=&'a LibraryCard> {
+        self.cards.iter().filter(move |c| c.card.has_tag(tag))
+    }
+}
+
+/// Card scripts as read from a library directory, before any of them is parsed.
+/// Cards can't be shared between threads, but this can: each thread builds its own library from it.
+#[derive(Debug, Clone, Default)]
+pub struct LibrarySources {
+    /// `(relative path, script)` pairs, as `CardLibrary::from_sources` takes them
+    pub scripts: Vec<(PathBuf, String)>,
+    pub damage_types: DamageTypeRegistry
+}
+
+impl LibrarySources {
+    /// Read every card script under `root`, reporting every file that can't be read
+    pub fn read(root: &Path, damage_types: &DamageTypeRegistry) -> Result<LibrarySources, Vec<Diagnostic>> {
+        match read_sources(root) {
+            (scripts, diagnostics) if diagnostics.is_empty() => Ok(LibrarySources { scripts, damage_types: damage_types.clone() }),
+            (_, diagnostics) => Err(diagnostics)
+        }
+    }
+
+    /// Parse the scripts into a library, without touching the file system
+    pub fn build(&self) -> Result<CardLibrary, Vec<Diagnostic>> {
+        CardLibrary::from_sources_with(self.scripts.iter().cloned(), &self.damage_types)
+    }
+}
+
+/// Every card script under `root` with its path relative to `root`, along with what couldn't be read
+fn read_sources(root: &Path) -> (Vec<(PathBuf, String)>, Vec<Diagnostic>) {
+    let mut diagnostics = vec![ ];
+    let mut paths = vec![ ];
+    collect_card_paths(root, &mut paths, &mut diagnostics);
+    // sorted so that diagnostics (and which duplicate "wins") don't depend on the file system
+    paths.sort();
+
+    let mut sources = vec![ ];
+    for path in paths {
+        match std::fs::read_to_string(&path) {
+            Ok(source) => {
+                let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+                sources.push((relative, source));
+            },
+            Err(err) => diagnostics.push(Diagnostic { path, error: err.into(), span: None })
+        }
+    }
+    (sources, diagnostics)
+}
+
+/// Every card script under `dir`, recursively.
+/// An unreadable directory is reported, but doesn't stop the rest of the tree from being searched.
+/// Links to scripts are read like any other script; links to directories are reported and skipped.
+pub fn collect_card_paths(dir: &Path, paths: &mut Vec<PathBuf>, diagnostics: &mut Vec<Diagnostic>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            diagnostics.push(Diagnostic { path: dir.to_path_buf(), error: err.into(), span: None });
+            return;
+        }
+    };
+
+    for entry in entries {
+        // the entry's own type: unlike `Path::is_dir`, it doesn't follow links
+        let (path, file_type) = match entry.and_then(|entry| Ok((entry.path(), entry.file_type()?))) {
+            Ok(entry) => entry,
+            Err(err) => {
+                diagnostics.push(Diagnostic { path: dir.to_path_buf(), error: err.into(), span: None });
+                continue;
+            }
+        };
+        if file_type.is_dir() {
+            collect_card_paths(&path, paths, diagnostics);
+        } else if file_type.is_symlink() && path.is_dir() {
+            diagnostics.push(Diagnostic { path, error: LoadError::SymlinkedDirectory, span: None });
+        } else if path.extension().is_some_and(|ext| ext == CARD_FILE_EXTENSION) {
+            paths.push(path);
+        }
+    }
+}
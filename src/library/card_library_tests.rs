@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::library::card_library::{CardId, CardLibrary, LoadError};
+
+    fn source(path: &str, script: &str) -> (PathBuf, String) {
+        (PathBuf::from(path), String::from(script))
+    }
+
+    #[test]
+    fn load_directory_recursively() {
+        let root = std::env::temp_dir().join(format!("mage_duel_library_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("spells/fire")).unwrap();
+        std::fs::write(root.join("spells/fire/firebolt.card"), "#attack [1]: { 1d10 fire => target(1 in Player); }").unwrap();
+        std::fs::write(root.join("shield.card"), "#defense [2]: { }").unwrap();
+        std::fs::write(root.join("notes.txt"), "not a card").unwrap();
+
+        let library = CardLibrary::load(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let library = library.unwrap();
+        assert_eq!(library.len(), 2);
+        let firebolt = library.get_by_name("firebolt").unwrap();
+        assert_eq!(firebolt.get_id(), CardId::from_relative_path("spells/fire/firebolt.card"));
+        assert_eq!(library.get(firebolt.get_id()).unwrap().get_name(), "firebolt");
+        assert!(library.get_by_name("notes").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_are_reported_not_followed() {
+        let root = std::env::temp_dir().join(format!("mage_duel_symlinks_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("spells")).unwrap();
+        std::fs::write(root.join("spells/firebolt.card"), "[1]: { }").unwrap();
+        // a loop back up the tree, and a link to a single script
+        std::os::unix::fs::symlink(&root, root.join("spells/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("spells/firebolt.card"), root.join("bolt.card")).unwrap();
+
+        let (library, diagnostics) = CardLibrary::load_partial(&root, &DamageTypeRegistry::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(library.len(), 2);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(diagnostics[0].error, LoadError::SymlinkedDirectory));
+        assert!(diagnostics[0].path.ends_with("spells/loop"));
+    }
+
+    #[test]
+    fn ids_are_stable() {
+        let first = CardLibrary::from_sources([ source("a.card", "[1]: { }") ]).unwrap();
+        let second = CardLibrary::from_sources([ source("b.card", "[1]: { }"), source("a.card", "[1]: { }") ]).unwrap();
+
+        let id = first.get_by_name("a").unwrap().get_id();
+        assert_eq!(second.get_by_name("a").unwrap().get_id(), id);
+        assert_eq!(CardId::try_from(id.to_string().as_str()).unwrap(), id);
+    }
+
+    #[test]
+    fn named_cards_go_by_their_name() {
+        let library = CardLibrary::from_sources([ source("spells/fb.card", "@name \"Firebolt\" [1]: { }") ]).unwrap();
+
+        assert!(library.get_by_name("Firebolt").is_some());
+        assert!(library.get_by_name("fb").is_none());
+    }
+
+    #[test]
+    fn lookup_by_tag() {
+        let library = CardLibrary::from_sources([
+            source("firebolt.card", "#attack #fire [1]: { }"),
+            source("frostbolt.card", "#attack #ice [1]: { }"),
+            source("ward.card", "#defense [1]: { }"),
+        ]).unwrap();
+
+        let mut attacks: Vec<&str> = library.get_by_tag("attack").map(|c| c.get_name()).collect();
+        attacks.sort();
+        assert_eq!(attacks, vec![ "firebolt", "frostbolt" ]);
+        assert_eq!(library.get_by_tag("fire").count(), 1);
+    }
+
+    #[test]
+    fn report_every_broken_file() {
+        let result = CardLibrary::from_sources([
+            source("good.card", "[1]: { }"),
+            source("bad_token.card", "[1]: { ? }"),
+            source("bad_type.card", "[true]: { }"),
+            source("other/good.card", "[2]: { }"),
+        ]);
+
+        let diagnostics = result.err().unwrap();
+        assert_eq!(diagnostics.len(), 3);
+        assert!(matches!(diagnostics[0].error, LoadError::Tokenizer(_)));
+        assert!(matches!(diagnostics[1].error, LoadError::Parse(_)));
+        assert!(matches!(diagnostics[2].error, LoadError::DuplicateName(_)));
+        assert_eq!(diagnostics[2].path, PathBuf::from("other/good.card"));
+    }
+
+    #[test]
+    fn scripts_use_registered_damage_types() {
+        let sonic = source("shriek.card", "[1]: { 3 sonic => target(1 in Player); }");
+        let damage_types = DamageTypeRegistry::parse("sonic \"Sonic\" vulnerable").unwrap();
+
+        assert!(CardLibrary::from_sources_with([ sonic.clone() ], &damage_types).is_ok());
+        let diagnostics = CardLibrary::from_sources([ sonic ]).err().unwrap();
+        assert!(matches!(diagnostics[0].error, LoadError::Parse(_)));
+    }
+
+    #[test]
+    fn diagnostics_say_where() {
+        let diagnostics = CardLibrary::from_sources([ source("bad.card", "[1]: {\n  2 fire => ; }") ]).err().unwrap();
+        let span = diagnostics[0].span.unwrap();
+        assert_eq!((span.start, span.end), (19, 20));
+        assert_eq!(diagnostics[0].to_string(), "bad.card: parse error: unexpected ';'");
+    }
+
+    #[test]
+    fn partial_library_keeps_good_cards() {
+        let sources = [ source("good.card", "[1]: { }"), source("bad.card", "[1]: { ? }") ];
+        let (library, diagnostics) = CardLibrary::from_sources_partial(sources, &DamageTypeRegistry::default());
+
+        assert_eq!(library.len(), 1);
+        assert!(library.get_by_name("good").is_some());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].span.is_some());
+    }
+
+    #[test]
+    fn simplified_library_keeps_its_cards() {
+        let library = CardLibrary::from_sources([ source("zap.card", "#attack [2 - 1]: { if false { log \"never\"; } }") ]).unwrap().simplified();
+
+        let zap = library.get_by_name("zap").unwrap();
+        assert_eq!(library.get(zap.get_id()).unwrap().get_name(), "zap");
+        assert_eq!(library.get_by_tag("attack").count(), 1);
+        assert!(zap.get_card().get_ast().as_literal(zap.get_card().get_cost()).is_some());
+        assert!(zap.get_card().get_body().is_empty());
+    }
+}
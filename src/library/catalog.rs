@@ -0,0 +1,61 @@
+use crate::parsing::card::Card;
+use crate::parsing::expressions::ExpressionResult;
+use crate::parsing::optimizer::constant_value;
+
+use super::card_library::{CardLibrary, LibraryCard};
+
+/// Markdown listing every card in the library by name: what's printed on it, then the designer
+/// notes from its `///` comments and those of its funcs, for reviewing a set as a whole
+pub fn catalog(library: &CardLibrary) -> String {
+    let mut cards: Vec<&LibraryCard> = library.iter().collect();
+    cards.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+    let mut output = String::from("# Card catalogue\n");
+    for card in cards {
+        output.push('\n');
+        output.push_str(&entry(card.get_name(), card.get_card()));
+    }
+    output
+}
+
+/// One card's section of the catalogue
+fn entry(name: &str, card: &Card) -> String {
+    let metadata = card.get_metadata();
+    let cost = match constant_value(card.get_ast(), card.get_cost()) {
+        Some(ExpressionResult::Integer(cost)) => format!("cost {}", cost),
+        _ => String::from("cost varies")
+    };
+    let mut details = vec![ metadata.rarity.to_string(), cost ];
+    details.extend(metadata.set_code.as_ref().map(|set_code| format!("set {}", set_code)));
+    if !card.get_tags().is_empty() {
+        details.push(card.get_tags().iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" "));
+    }
+
+    let mut paragraphs = vec![ format!("## {}", name), details.join(" · ") ];
+    paragraphs.extend(metadata.text.as_ref().map(|text| text.to_string()));
+    paragraphs.extend(metadata.flavor.as_ref().map(|flavor| format!("*{}*", flavor)));
+    if let Some(doc) = &metadata.doc {
+        paragraphs.push(String::from("Notes:"));
+        paragraphs.push(doc.lines().map(|line| format!("> {}", line).trim_end().to_string()).collect::<Vec<_>>().join("\n"));
+    }
+
+    let ast = card.get_ast();
+    let funcs: Vec<String> = ast.func_ids()
+        .map(|func| {
+            let func = &ast[func];
+            let item = format!("- `{}`: {}", func.get_signature(), ast.get_type(func.body));
+            let Some(doc) = &func.doc else {
+                return item;
+            };
+            // the rest of the notes line up under the first line
+            let lines: Vec<String> = doc.lines().map(|line| format!("  {}", line).trim_end().to_string()).collect();
+            format!("{}. {}", item, lines.join("\n").trim_start())
+        })
+        .collect();
+    if !funcs.is_empty() {
+        paragraphs.push(String::from("Funcs:"));
+        paragraphs.push(funcs.join("\n"));
+    }
+
+    paragraphs.join("\n\n") + "\n"
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use crate::library::catalog::catalog;
+    use crate::library::test_library::library;
+
+    const SCRIPTS: [ (&str, &str); 2 ] = [
+        ("zap.card", "\
+/// Half of the roll, rounded up.
+///
+/// Shared with Inferno.
+func half($amount: integer) = ($amount + 1) / 2;
+func bolt() = half(2d6);
+/// Cheap on purpose.
+///
+/// Playtest at 2?
+@name \"Zap\" @rarity uncommon @set \"BASE\" @text \"Deal half of 2d6 fire damage.\" @flavor \"Bzzt.\"
+#attack #fire [1]: { bolt() fire => target(1 in Player); }"),
+        ("ward.card", "[3 - $discount]: { log \"warded\"; }")
+    ];
+
+    #[test]
+    fn cards_by_name_with_their_notes() {
+        assert_eq!(catalog(&library(&SCRIPTS)), "\
+# Card catalogue
+
+## Zap
+
+uncommon · cost 1 · set BASE · #attack #fire
+
+Deal half of 2d6 fire damage.
+
+*Bzzt.*
+
+Notes:
+
+> Cheap on purpose.
+>
+> Playtest at 2?
+
+Funcs:
+
+- `half($amount: integer)`: integer. Half of the roll, rounded up.
+
+  Shared with Inferno.
+- `bolt()`: integer
+
+## ward
+
+common · cost varies
+");
+    }
+}
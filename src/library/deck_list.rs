@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::parsing::card::Rarity;
+
+use super::card_library::{CardId, CardLibrary};
+
+/// One line of a deck list: `3 Firebolt` (the card can be a name or a card ID)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeckEntry {
+    /// 1-based line in the deck file
+    pub line: usize,
+    pub count: u16,
+    pub card: Rc<str>
+}
+
+/// Plain-text deck list. Blank lines and `//` comments are ignored; every other line is `<count> <card>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeckList {
+    entries: Vec<DeckEntry>
+}
+
+/// Deck list whose cards were all found in a library and which follows the rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    cards: Vec<(CardId, u16)>
+}
+
+impl Deck {
+    /// Each card in the deck with its number of copies
+    pub fn get_cards(&self) -> &[(CardId, u16)] {
+        &self.cards
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.iter().map(|&(_, count)| count as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every copy of every card, in deck list order
+    pub fn expand(&self) -> Vec<CardId> {
+        self.cards.iter().flat_map(|&(id, count)| std::iter::repeat_n(id, count as usize)).collect()
+    }
+}
+
+/// What makes a deck legal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeckRules {
+    pub min_size: usize,
+    pub max_size: usize,
+    /// Copies of any one card
+    pub max_copies: u16,
+    /// Tighter copy limits for some rarities (e.g. one of each legendary)
+    pub rarity_limits: HashMap<Rarity, u16>,
+    /// Names of cards that can't be played at all
+    pub banned: Vec<Rc<str>>
+}
+
+impl DeckRules {
+    /// Anything goes, as long as every card exists
+    pub fn unrestricted() -> Self {
+        DeckRules { min_size: 0, max_size: usize::MAX, max_copies: u16::MAX, rarity_limits: HashMap::new(), banned: vec![ ] }
+    }
+}
+
+#[derive(Debug)]
+pub enum DeckRulesErrorKind {
+    Io(std::io::Error),
+    /// Line isn't `<rule> <value>`, or the value doesn't fit the rule
+    InvalidLine,
+    UnknownRule(String)
+}
+
+#[derive(Debug)]
+pub struct DeckRulesError {
+    /// 1-based line of the file (0 when it's about the file as a whole)
+    pub line: usize,
+    pub kind: DeckRulesErrorKind
+}
+
+impl Display for DeckRulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            DeckRulesErrorKind::Io(err) => write!(f, "{}", err),
+            DeckRulesErrorKind::InvalidLine => write!(f, "expected '<rule> <value>'"),
+            DeckRulesErrorKind::UnknownRule(name) => write!(f, "unknown rule '{}'", name)
+        }
+    }
+}
+
+impl DeckRules {
+    /// One rule per line, with `//` comments; rules not given keep their default:
+    /// `min_size 30`, `max_size 30`, `max_copies 3`, `rarity legendary 1` (copies of each card of that rarity)
+    /// and `banned <card>` (once per card)
+    pub fn parse(source: &str) -> Result<Self, DeckRulesError> {
+        let mut rules = DeckRules::default();
+        for (index, content) in source.lines().enumerate() {
+            let line = index + 1;
+            let content = content.split("//").next().unwrap_or_default().trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            let invalid = || DeckRulesError { line, kind: DeckRulesErrorKind::InvalidLine };
+            let (name, value) = content.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let value = value.trim();
+            match name {
+                "min_size" => rules.min_size = value.parse().map_err(|_| invalid())?,
+                "max_size" => rules.max_size = value.parse().map_err(|_| invalid())?,
+                "max_copies" => rules.max_copies = value.parse().map_err(|_| invalid())?,
+                "rarity" => {
+                    let (rarity, limit) = value.split_once(char::is_whitespace).ok_or_else(invalid)?;
+                    let rarity = Rarity::try_from(rarity).map_err(|_| invalid())?;
+                    rules.rarity_limits.insert(rarity, limit.trim().parse().map_err(|_| invalid())?);
+                },
+                // card names can have spaces
+                "banned" => rules.banned.push(Rc::from(value)),
+                _ => return Err(DeckRulesError { line, kind: DeckRulesErrorKind::UnknownRule(name.to_string()) })
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, DeckRulesError> {
+        let source = std::fs::read_to_string(path).map_err(|err| DeckRulesError { line: 0, kind: DeckRulesErrorKind::Io(err) })?;
+        DeckRules::parse(&source)
+    }
+}
+
+impl Default for DeckRules {
+    fn default() -> Self {
+        DeckRules {
+            min_size: 30,
+            max_size: 30,
+            max_copies: 3,
+            rarity_limits: HashMap::from([ (Rarity::Legendary, 1) ]),
+            banned: vec![ ]
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeckErrorKind {
+    /// Line isn't `<count> <card>`
+    InvalidLine,
+    /// No card with this name or ID in the library
+    UnknownCard(Rc<str>),
+    /// Same card listed on an earlier line
+    DuplicateEntry { card: Rc<str>, first_line: usize },
+    Banned(Rc<str>),
+    TooManyCopies { card: Rc<str>, count: u16, limit: u16 },
+    TooFewCards { count: usize, min: usize },
+    TooManyCards { count: usize, max: usize }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeckError {
+    /// Line in the deck file, if the problem is with a specific entry
+    pub line: Option<usize>,
+    pub kind: DeckErrorKind
+}
+
+impl Display for DeckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        match &self.kind {
+            DeckErrorKind::InvalidLine => write!(f, "expected '<count> <card>'"),
+            DeckErrorKind::UnknownCard(card) => write!(f, "no card named '{}'", card),
+            DeckErrorKind::DuplicateEntry { card, first_line } => write!(f, "'{}' is already listed on line {}", card, first_line),
+            DeckErrorKind::Banned(card) => write!(f, "'{}' is banned", card),
+            DeckErrorKind::TooManyCopies { card, count, limit } => write!(f, "{} copies of '{}', but at most {} are allowed", count, card, limit),
+            DeckErrorKind::TooFewCards { count, min } => write!(f, "deck has {} cards, but needs at least {}", count, min),
+            DeckErrorKind::TooManyCards { count, max } => write!(f, "deck has {} cards, but can have at most {}", count, max)
+        }
+    }
+}
+
+impl DeckList {
+    /// Parse a deck file, reporting every malformed line
+    pub fn parse(source: &str) -> Result<DeckList, Vec<DeckError>> {
+        let mut entries = vec![ ];
+        let mut errors = vec![ ];
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let content = line.split("//").next().unwrap_or_default().trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            let entry = content.split_once(char::is_whitespace)
+                .and_then(|(count, card)| Some((count.parse::<u16>().ok()?, card.trim())));
+            match entry {
+                Some((count, card)) if count > 0 => entries.push(DeckEntry { line: line_number, count, card: Rc::from(card) }),
+                _ => errors.push(DeckError { line: Some(line_number), kind: DeckErrorKind::InvalidLine })
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(DeckList { entries });
+        }
+        Err(errors)
+    }
+
+    pub fn get_entries(&self) -> &[DeckEntry] {
+        &self.entries
+    }
+
+    /// Resolve every card against the library and check the deck against the rules.
+    /// All problems are reported, not just the first.
+    pub fn validate(&self, library: &CardLibrary, rules: &DeckRules) -> Result<Deck, Vec<DeckError>> {
+        let mut cards: Vec<(CardId, u16)> = vec![ ];
+        let mut first_lines: HashMap<CardId, usize> = HashMap::new();
+        let mut errors = vec![ ];
+
+        for entry in &self.entries {
+            let error = |kind| DeckError { line: Some(entry.line), kind };
+
+            // names win over IDs, so a card can't be shadowed by something that looks like an ID
+            let library_card = library.get_by_name(&entry.card)
+                .or_else(|| CardId::try_from(entry.card.as_ref()).ok().and_then(|id| library.get(id)));
+            let library_card = match library_card {
+                Some(library_card) => library_card,
+                None => {
+                    errors.push(error(DeckErrorKind::UnknownCard(entry.card.clone())));
+                    continue;
+                }
+            };
+            let name: Rc<str> = Rc::from(library_card.get_name());
+
+            if let Some(&first_line) = first_lines.get(&library_card.get_id()) {
+                errors.push(error(DeckErrorKind::DuplicateEntry { card: name, first_line }));
+                continue;
+            }
+            first_lines.insert(library_card.get_id(), entry.line);
+
+            if rules.banned.contains(&name) {
+                errors.push(error(DeckErrorKind::Banned(name.clone())));
+            }
+
+            let rarity = library_card.get_card().get_metadata().rarity;
+            let limit = rules.rarity_limits.get(&rarity).map_or(rules.max_copies, |&l| l.min(rules.max_copies));
+            if entry.count > limit {
+                errors.push(error(DeckErrorKind::TooManyCopies { card: name, count: entry.count, limit }));
+            }
+
+            cards.push((library_card.get_id(), entry.count));
+        }
+
+        let deck = Deck { cards };
+        let count = deck.len();
+        if count < rules.min_size {
+            errors.push(DeckError { line: None, kind: DeckErrorKind::TooFewCards { count, min: rules.min_size } });
+        }
+        if count > rules.max_size {
+            errors.push(DeckError { line: None, kind: DeckErrorKind::TooManyCards { count, max: rules.max_size } });
+        }
+
+        if errors.is_empty() {
+            return Ok(deck);
+        }
+        Err(errors)
+    }
+}
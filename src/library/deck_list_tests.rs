@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::library::card_library::CardLibrary;
+    use crate::library::deck_list::{DeckError, DeckErrorKind, DeckList, DeckRules};
+    use crate::parsing::card::Rarity;
+    use test_case::test_case;
+
+    fn library() -> CardLibrary {
+        let sources = [
+            ("firebolt.card", "@name \"Firebolt\" #attack [1]: { }"),
+            ("frost_nova.card", "@name \"Frost Nova\" @rarity rare #attack [3]: { }"),
+            ("archmage.card", "@name \"The Archmage\" @rarity legendary [8]: { }"),
+            ("wish.card", "@name \"Wish\" [0]: { }"),
+        ];
+        CardLibrary::from_sources(sources.map(|(path, script)| (PathBuf::from(path), String::from(script)))).unwrap()
+    }
+
+    fn rules(min_size: usize, max_size: usize) -> DeckRules {
+        DeckRules { min_size, max_size, banned: vec![ "Wish".into() ], ..DeckRules::default() }
+    }
+
+    #[test]
+    fn parse_deck_list() {
+        let source = "\
+            // burn
+            3 Firebolt
+
+            2 Frost Nova // names can have spaces
+            ";
+        let deck_list = DeckList::parse(source).unwrap();
+
+        let entries = deck_list.get_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].line, entries[0].count, entries[0].card.as_ref()), (2, 3, "Firebolt"));
+        assert_eq!((entries[1].line, entries[1].count, entries[1].card.as_ref()), (4, 2, "Frost Nova"));
+    }
+
+    #[test_case("Firebolt" ; "Missing count")]
+    #[test_case("0 Firebolt" ; "Zero copies")]
+    #[test_case("3" ; "Missing card")]
+    #[test_case("-1 Firebolt" ; "Negative count")]
+    fn parse_invalid_line(line: &str) {
+        let source = format!("3 Firebolt\n{}", line);
+        let errors = DeckList::parse(&source).unwrap_err();
+
+        assert_eq!(errors, vec![ DeckError { line: Some(2), kind: DeckErrorKind::InvalidLine } ]);
+    }
+
+    #[test]
+    fn validate_legal_deck() {
+        let library = library();
+        let archmage_id = library.get_by_name("The Archmage").unwrap().get_id();
+        let source = format!("3 Firebolt\n2 Frost Nova\n1 {}", archmage_id);
+
+        let deck = DeckList::parse(&source).unwrap().validate(&library, &rules(6, 6)).unwrap();
+
+        assert_eq!(deck.len(), 6);
+        assert_eq!(deck.expand().iter().filter(|&&id| id == archmage_id).count(), 1);
+    }
+
+    #[test]
+    fn validate_reports_every_problem_with_its_line() {
+        let source = "\
+            4 Firebolt
+            1 Fireball
+            2 The Archmage
+            1 Wish
+            1 Firebolt";
+        let errors = DeckList::parse(source).unwrap().validate(&library(), &rules(10, 20)).unwrap_err();
+
+        let lines: Vec<Option<usize>> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![ Some(1), Some(2), Some(3), Some(4), Some(5), None ]);
+        assert_eq!(errors[0].kind, DeckErrorKind::TooManyCopies { card: "Firebolt".into(), count: 4, limit: 3 });
+        assert_eq!(errors[1].kind, DeckErrorKind::UnknownCard("Fireball".into()));
+        assert_eq!(errors[2].kind, DeckErrorKind::TooManyCopies { card: "The Archmage".into(), count: 2, limit: 1 });
+        assert_eq!(errors[3].kind, DeckErrorKind::Banned("Wish".into()));
+        assert_eq!(errors[4].kind, DeckErrorKind::DuplicateEntry { card: "Firebolt".into(), first_line: 1 });
+        assert_eq!(errors[5].kind, DeckErrorKind::TooFewCards { count: 7, min: 10 });
+        assert_eq!(errors[0].to_string(), "line 1: 4 copies of 'Firebolt', but at most 3 are allowed");
+    }
+
+    #[test]
+    fn validate_max_size() {
+        let errors = DeckList::parse("3 Firebolt").unwrap().validate(&library(), &rules(1, 2)).unwrap_err();
+
+        assert_eq!(errors, vec![ DeckError { line: None, kind: DeckErrorKind::TooManyCards { count: 3, max: 2 } } ]);
+    }
+
+    #[test]
+    fn parse_deck_rules() {
+        let rules = DeckRules::parse("// casual\nmin_size 20\nmax_size 40\n\nrarity rare 2 // fewer rares\nbanned Frost Nova\nbanned Wish\n").unwrap();
+
+        assert_eq!((rules.min_size, rules.max_size, rules.max_copies), (20, 40, 3));
+        assert_eq!(rules.rarity_limits.get(&Rarity::Rare), Some(&2));
+        assert_eq!(rules.rarity_limits.get(&Rarity::Legendary), Some(&1));
+        assert_eq!(rules.banned, vec![ "Frost Nova".into(), "Wish".into() ]);
+    }
+
+    #[test_case("min_size" ; "Missing value")]
+    #[test_case("max_copies lots" ; "Invalid number")]
+    #[test_case("rarity mythic 1" ; "Unknown rarity")]
+    #[test_case("rarity rare" ; "Missing limit")]
+    #[test_case("deck_size 30" ; "Unknown rule")]
+    fn invalid_deck_rules(line: &str) {
+        let error = DeckRules::parse(&format!("min_size 1\n{}", line)).unwrap_err();
+
+        assert_eq!(error.line, 2);
+    }
+}
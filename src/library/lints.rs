@@ -0,0 +1,286 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use crate::engine::modifiers::AURA_TAG;
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::parsing::card::Card;
+use crate::parsing::ast::{Ast, ExpressionId, StatementId};
+use crate::parsing::expressions::{BinaryOperator, Expression, ExpressionResult};
+use crate::parsing::optimizer::constant_value;
+use crate::parsing::statements::Statement;
+use crate::parsing::tokenizer::tokenize_with_comments;
+
+/// How bad a problem with a card is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The card can't be loaded
+    Error,
+    /// A lint: the card loads, but something about it looks wrong
+    Warning
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning")
+        }
+    }
+}
+
+/// Something about a card that's allowed, but probably a mistake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// Short kebab-case name of the rule, e.g. `empty-body`
+    pub rule: &'static str,
+    pub message: String
+}
+
+/// Every lint rule, with what it looks for
+pub const RULES: [(&str, &str); 10] = [
+    ("unnamed-card", "card has no @name, so it goes by its file name"),
+    ("empty-body", "card does nothing when played"),
+    ("inert-ability", "card isn't an aura, so it's never in play for its 'when' abilities to trigger"),
+    ("unused-variable", "variable is assigned but never read"),
+    ("unused-assignment", "value is assigned, then assigned again (or never read) before it's read"),
+    ("constant-condition", "condition comes out the same every time"),
+    ("unreachable-code", "statements in a branch that's never taken"),
+    ("needless-unique", "'+!' where there are no duplicates to drop"),
+    ("untyped-damage", "damage without a type, which nothing resists or is weak to"),
+    ("invalid-pragma", "'// lint:' comment that can't be understood")
+];
+
+/// What to do when a rule finds something
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    /// Report it as an error
+    Deny
+}
+
+#[derive(Debug)]
+pub struct LevelParseError;
+
+impl TryFrom<&str> for Level {
+    type Error = LevelParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "allow" => Ok(Level::Allow),
+            "warn" => Ok(Level::Warn),
+            "deny" => Ok(Level::Deny),
+            _ => Err(LevelParseError)
+        }
+    }
+}
+
+/// The level of each rule; `Warn` unless it's been set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintLevels {
+    levels: Vec<(&'static str, Level)>
+}
+
+impl LintLevels {
+    pub fn get(&self, rule: &str) -> Level {
+        self.levels.iter().rev().find(|(name, _)| *name == rule).map_or(Level::Warn, |&(_, level)| level)
+    }
+
+    pub fn set(&mut self, rule: &str, level: Level) -> Result<(), String> {
+        let (rule, _) = RULES.iter().find(|(name, _)| *name == rule).ok_or(format!("unknown lint rule '{}'", rule))?;
+        self.levels.push((rule, level));
+        Ok(())
+    }
+
+    /// How a lint is reported, or None when its rule is allowed
+    pub fn get_severity(&self, rule: &str) -> Option<Severity> {
+        match self.get(rule) {
+            Level::Allow => None,
+            Level::Warn => Some(Severity::Warning),
+            Level::Deny => Some(Severity::Error)
+        }
+    }
+
+    /// Levels for one script: these, then its `// lint: allow(rule, ...) deny(...)` comments in order.
+    /// Comments that can't be understood come back as `invalid-pragma` lints.
+    pub fn for_script(&self, source: &str, damage_types: &DamageTypeRegistry) -> (LintLevels, Vec<Lint>) {
+        let mut levels = self.clone();
+        let mut lints = vec![ ];
+        // a script that can't be tokenized has bigger problems than its pragmas
+        let comments = tokenize_with_comments(source, damage_types).unwrap_or_default();
+        for (_, span) in comments {
+            let pragma = source[span.start .. span.end].strip_prefix("//").map(str::trim_start).and_then(|text| text.strip_prefix("lint:"));
+            if let Some(Err(message)) = pragma.map(|pragma| levels.apply_pragma(pragma)) {
+                lints.push(Lint { rule: "invalid-pragma", message });
+            }
+        }
+        (levels, lints)
+    }
+
+    /// `allow(unused-variable, constant-condition) deny(untyped-damage)`
+    fn apply_pragma(&mut self, pragma: &str) -> Result<(), String> {
+        let mut rest = pragma.trim();
+        if rest.is_empty() {
+            return Err(String::from("'// lint:' needs a level, e.g. 'allow(unused-variable)'"));
+        }
+        while !rest.is_empty() {
+            let (level, after) = rest.split_once('(').ok_or(format!("expected '(' after '{}'", rest))?;
+            let level = Level::try_from(level.trim()).map_err(|_| format!("unknown lint level '{}'; expected allow, warn or deny", level.trim()))?;
+            let (rules, after) = after.split_once(')').ok_or(String::from("missing ')'"))?;
+            for rule in rules.split(',').map(str::trim).filter(|rule| !rule.is_empty()) {
+                self.set(rule, level)?;
+            }
+            rest = after.trim_start();
+        }
+        Ok(())
+    }
+}
+
+/// Every lint for the card, whatever the levels
+pub fn lint_card(card: &Card) -> Vec<Lint> {
+    let mut lints = vec![ ];
+    let mut report = |rule: &'static str| {
+        let message = RULES.iter().find(|(name, _)| *name == rule).map(|(_, message)| message.to_string()).unwrap_or_default();
+        lints.push(Lint { rule, message });
+    };
+
+    if card.get_name().is_none() {
+        report("unnamed-card");
+    }
+    if card.get_body().is_empty() && card.get_abilities().is_empty() {
+        report("empty-body");
+    }
+    if !card.get_abilities().is_empty() && !card.has_tag(AURA_TAG) {
+        report("inert-ability");
+    }
+
+    // the body and each ability are scopes of their own; the cost is read along with the body
+    let ast = card.get_ast();
+    lint_scope(ast, &[ card.get_cost() ], card.get_body(), &mut lints);
+    for ability in card.get_abilities() {
+        lint_scope(ast, &[ ], &ability.body, &mut lints);
+    }
+    lints
+}
+
+fn lint_scope(ast: &Ast, also_read: &[ExpressionId], body: &[StatementId], lints: &mut Vec<Lint>) {
+    let mut reads = HashSet::new();
+    for expression in also_read.iter().copied().chain(all_statements(ast, body).flat_map(|statement| ast.get_expressions(statement))) {
+        collect_reads(ast, expression, &mut reads);
+    }
+
+    let mut assigned = vec![ ];
+    for statement in all_statements(ast, body) {
+        if let Statement::Assignment { name, .. } = &ast[statement] {
+            // `$_name` is for a value that's deliberately left unread
+            if !reads.contains(name.as_ref()) && !name.starts_with("$_") && !assigned.contains(&name) {
+                lints.push(Lint { rule: "unused-variable", message: format!("'{}' is assigned but never read", name) });
+            }
+            assigned.push(name);
+        }
+    }
+
+    let mut dead = vec![ ];
+    find_dead_assignments(ast, body, &mut HashSet::new(), &reads, &mut dead);
+    lints.extend(dead.into_iter().rev());
+
+    lint_block(ast, body, lints);
+}
+
+/// Lints with the levels applied: allowed ones are left out, the rest have the severity they're reported at
+pub fn apply_levels(lints: Vec<Lint>, levels: &LintLevels) -> Vec<(Lint, Severity)> {
+    lints.into_iter()
+        .filter_map(|lint| levels.get_severity(lint.rule).map(|severity| (lint, severity)))
+        .collect()
+}
+
+/// Every statement in the body, those inside `if`s included, in order
+fn all_statements<'a>(ast: &'a Ast, body: &'a [StatementId]) -> impl Iterator<Item=StatementId> + 'a {
+    body.iter().flat_map(move |&statement| {
+        let inner: Box<dyn Iterator<Item=StatementId>> = match &ast[statement] {
+            Statement::If { then_body, else_body, .. } => Box::new(all_statements(ast, then_body).chain(all_statements(ast, else_body))),
+            _ => Box::new(std::iter::empty())
+        };
+        std::iter::once(statement).chain(inner)
+    })
+}
+
+fn collect_reads<'a>(ast: &'a Ast, expression: ExpressionId, reads: &mut HashSet<&'a str>) {
+    if let Expression::Variable(name) = &ast[expression] {
+        reads.insert(name);
+    }
+    for operand in ast.get_operands(expression) {
+        collect_reads(ast, operand, reads);
+    }
+}
+
+/// Walk the statements last to first, keeping track of the variables that are read before they're next
+/// assigned (`live`): assigning to a variable that isn't live is a value that's never read.
+/// Variables that are never read at all are left to `unused-variable`.
+fn find_dead_assignments<'a>(ast: &'a Ast, body: &[StatementId], live: &mut HashSet<&'a str>, reads: &HashSet<&str>, lints: &mut Vec<Lint>) {
+    for &statement in body.iter().rev() {
+        match &ast[statement] {
+            Statement::Assignment { name, .. } => {
+                let read_later = live.remove(name.as_ref());
+                if !read_later && reads.contains(name.as_ref()) {
+                    lints.push(Lint { rule: "unused-assignment", message: format!("value assigned to '{}' is never read", name) });
+                }
+            },
+            Statement::If { then_body, else_body, .. } => {
+                let mut else_live = live.clone();
+                find_dead_assignments(ast, else_body, &mut else_live, reads, lints);
+                find_dead_assignments(ast, then_body, live, reads, lints);
+                live.extend(else_live);
+            },
+            _ => { }
+        }
+        for expression in ast.get_expressions(statement) {
+            collect_reads(ast, expression, live);
+        }
+    }
+}
+
+fn lint_block(ast: &Ast, body: &[StatementId], lints: &mut Vec<Lint>) {
+    for &statement in body {
+        for expression in ast.get_expressions(statement) {
+            lint_expression(ast, expression, lints);
+        }
+
+        match &ast[statement] {
+            Statement::Damage { damage_type, .. } if damage_type.is_none() => {
+                lints.push(Lint { rule: "untyped-damage", message: String::from("damage has no type, so nothing resists it or is weak to it") });
+            },
+            Statement::If { condition, then_body, else_body } => {
+                if let Some(ExpressionResult::Boolean(value)) = constant_value(ast, *condition) {
+                    lints.push(Lint { rule: "constant-condition", message: format!("condition is always {}", value) });
+                    let (never_taken, branch) = if value { (else_body, "else") } else { (then_body, "if") };
+                    if !never_taken.is_empty() {
+                        lints.push(Lint { rule: "unreachable-code", message: format!("the {} branch never runs", branch) });
+                    }
+                }
+                lint_block(ast, then_body, lints);
+                lint_block(ast, else_body, lints);
+            },
+            _ => { }
+        }
+    }
+}
+
+fn lint_expression(ast: &Ast, expression: ExpressionId, lints: &mut Vec<Lint>) {
+    if let Expression::Binary(BinaryOperator::ConcatenateUnique, left, right) = ast[expression] {
+        if let (Some(ExpressionResult::List(left)), Some(right)) = (constant_value(ast, left), constant_value(ast, right)) {
+            let right = match right {
+                ExpressionResult::List(items) => items.to_vec(),
+                item => vec![ item ]
+            };
+            let items: Vec<&ExpressionResult> = left.iter().chain(right.iter()).collect();
+            let unique = items.iter().enumerate().all(|(index, item)| !items[.. index].contains(item));
+            if unique {
+                lints.push(Lint { rule: "needless-unique", message: String::from("there are no duplicates for '+!' to drop, so '+' does the same") });
+            }
+        }
+    }
+    for operand in ast.get_operands(expression) {
+        lint_expression(ast, operand, lints);
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::library::lints::{apply_levels, lint_card, Level, LintLevels, Severity};
+    use crate::parsing::parser::parse_card;
+    use crate::parsing::tokenizer::tokenize;
+    use test_case::test_case;
+
+    fn rules_found(script: &str) -> Vec<&'static str> {
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+        lint_card(&card).iter().map(|lint| lint.rule).collect()
+    }
+
+    #[test_case("@name \"Zap\" [1]: { 1 fire => target(1 in Player); }", &[ ] ; "Clean")]
+    #[test_case("[1]: { 1 fire => target(1 in Player); }", &[ "unnamed-card" ] ; "Unnamed")]
+    #[test_case("@name \"Zap\" [1]: { }", &[ "empty-body" ] ; "Empty body")]
+    #[test_case("[1]: { }", &[ "unnamed-card", "empty-body" ] ; "Both")]
+    #[test_case("@name \"Zap\" [1]: { $x = 2; $_y = 3; }", &[ "unused-variable" ] ; "Unused variable")]
+    #[test_case("@name \"Zap\" [1]: { $x = 2; $x = 3; log \"{$x}\"; }", &[ "unused-assignment" ] ; "Overwritten before read")]
+    #[test_case("@name \"Zap\" [1]: { $x = 2; log \"{$x}\"; $x = 3; }", &[ "unused-assignment" ] ; "Never read after")]
+    #[test_case("@name \"Zap\" [1]: { $x = 2; if $turn > 1 { $x = 3; } log \"{$x}\"; }", &[ ] ; "Read after either branch")]
+    #[test_case("@name \"Zap\" [1]: { $x = 2; if $turn > 1 { $x = 3; } else { $x = 4; } log \"{$x}\"; }", &[ "unused-assignment" ] ; "Overwritten in both branches")]
+    #[test_case("@name \"Zap\" [1]: { if 2 * 3 > 5 { log \"a\"; } }", &[ "constant-condition" ] ; "Constant condition")]
+    #[test_case("@name \"Zap\" [1]: { if false & $turn > 1 { log \"a\"; } else { log \"b\"; } }", &[ "constant-condition", "unreachable-code" ] ; "Never taken")]
+    #[test_case("@name \"Zap\" [1]: { if true { log \"a\"; } else { log \"b\"; } }", &[ "constant-condition", "unreachable-code" ] ; "Else never taken")]
+    #[test_case("@name \"Zap\" [1]: { if 1d6 > 3 { log \"a\"; } }", &[ ] ; "Dice are not constant")]
+    #[test_case("@name \"Zap\" [1]: { log \"{[fire] +! [ice]}\"; }", &[ "needless-unique" ] ; "Unique lists")]
+    #[test_case("@name \"Zap\" [1]: { log \"{[fire] +! fire}\"; }", &[ ] ; "Duplicates to drop")]
+    #[test_case("@name \"Zap\" [1]: { 2 => target(1 in Player); }", &[ "untyped-damage" ] ; "Damage of no type")]
+    #[test_case("@name \"Echo\" #aura [1]: { } when play { log \"echo\"; }", &[ ] ; "Aura with only an ability")]
+    #[test_case("@name \"Echo\" [1]: { log \"a\"; } when play { log \"echo\"; }", &[ "inert-ability" ] ; "Ability on a spell")]
+    #[test_case("@name \"Echo\" #aura [1]: { $x = 1; log \"{$x}\"; } when play { $x = 2; if true { } }", &[ "unused-variable", "constant-condition" ] ; "Abilities are linted on their own")]
+    fn lint_rules(script: &str, rules: &[&str]) {
+        assert_eq!(rules_found(script), rules);
+    }
+
+    #[test]
+    fn lints_name_what_they_found() {
+        let card = parse_card(tokenize("@name \"Zap\" [1]: { $hits = 2; if ~true { log \"a\"; } }").unwrap().into_iter()).unwrap();
+        let messages: Vec<String> = lint_card(&card).into_iter().map(|lint| lint.message).collect();
+        assert_eq!(messages, vec![ "'$hits' is assigned but never read", "condition is always false", "the if branch never runs" ]);
+    }
+
+    #[test]
+    fn levels() {
+        let mut levels = LintLevels::default();
+        assert_eq!(levels.get("empty-body"), Level::Warn);
+        levels.set("empty-body", Level::Deny).unwrap();
+        levels.set("unnamed-card", Level::Allow).unwrap();
+        assert!(levels.set("no-such-rule", Level::Allow).is_err());
+
+        let card = parse_card(tokenize("[1]: { }").unwrap().into_iter()).unwrap();
+        let reported: Vec<(&str, Severity)> = apply_levels(lint_card(&card), &levels).into_iter().map(|(lint, severity)| (lint.rule, severity)).collect();
+        assert_eq!(reported, vec![ ("empty-body", Severity::Error) ]);
+    }
+
+    #[test]
+    fn pragmas() {
+        let script = "// lint: allow(unused-variable, empty-body) deny(untyped-damage)\n[1]: { }\n// lint:warn(empty-body)\n\"// lint: allow(unnamed-card)\"";
+        let (levels, invalid) = LintLevels::default().for_script(script, &DamageTypeRegistry::default());
+
+        assert!(invalid.is_empty());
+        assert_eq!(levels.get("unused-variable"), Level::Allow);
+        assert_eq!(levels.get("untyped-damage"), Level::Deny);
+        // later pragmas win, and strings aren't comments
+        assert_eq!(levels.get("empty-body"), Level::Warn);
+        assert_eq!(levels.get("unnamed-card"), Level::Warn);
+    }
+
+    #[test_case("// lint: allow(no-such-rule)", "unknown lint rule 'no-such-rule'" ; "Unknown rule")]
+    #[test_case("// lint: forbid(empty-body)", "unknown lint level 'forbid'; expected allow, warn or deny" ; "Unknown level")]
+    #[test_case("// lint: allow(empty-body", "missing ')'" ; "Unclosed")]
+    #[test_case("// lint:", "'// lint:' needs a level, e.g. 'allow(unused-variable)'" ; "Empty")]
+    fn invalid_pragmas(script: &str, message: &str) {
+        let (_, invalid) = LintLevels::default().for_script(script, &DamageTypeRegistry::default());
+        assert_eq!(invalid.len(), 1);
+        assert_eq!((invalid[0].rule, invalid[0].message.as_str()), ("invalid-pragma", message));
+    }
+}
@@ -0,0 +1,22 @@
+//! Libraries and deck rules for tests: each test keeps its own card scripts and builds the rest from here
+
+use std::path::PathBuf;
+
+use super::card_library::{CardLibrary, LibrarySources};
+use super::deck_list::DeckRules;
+
+/// `(path, script)` pairs as card sources, with the built-in damage types
+pub fn sources(scripts: &[ (&str, &str) ]) -> LibrarySources {
+    let scripts = scripts.iter().map(|&(path, script)| (PathBuf::from(path), String::from(script))).collect();
+    LibrarySources { scripts, ..LibrarySources::default() }
+}
+
+/// Library of `(path, script)` pairs that must all load
+pub fn library(scripts: &[ (&str, &str) ]) -> CardLibrary {
+    sources(scripts).build().unwrap()
+}
+
+/// Loose enough for any small test deck: 1 to 30 cards, with as many copies as it likes
+pub fn rules() -> DeckRules {
+    DeckRules { min_size: 1, max_size: 30, max_copies: 30, ..DeckRules::default() }
+}
@@ -0,0 +1,6 @@
+pub mod json;
+pub mod analysis;
+pub mod server;
+mod json_tests;
+mod analysis_tests;
+mod server_tests;
\ No newline at end of file
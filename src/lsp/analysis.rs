@@ -0,0 +1,310 @@
+use std::ops::Range;
+
+use crate::game_zones::damage_types::{DamageTypeRegistry, Susceptibility};
+use crate::library::lints::{apply_levels, lint_card, LintLevels, Severity};
+use crate::parsing::expressions::ExpressionType;
+use crate::parsing::parser::{analyze_card, Annotations};
+use crate::parsing::symbol_table::BUILT_IN_SYMBOLS;
+use crate::parsing::tokenizer::{tokenize_spanned, Span};
+use crate::parsing::tokens::{Token, Tokens, SYMBOLS};
+
+/// Something wrong with an open script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentDiagnostic {
+    pub span: Span,
+    pub severity: Severity,
+    /// `tokenizer`, `parse` or the lint rule
+    pub code: &'static str,
+    pub message: String
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    DamageType,
+    Variable,
+    Func
+}
+
+/// Kinds of semantic token, in the order of the legend the server advertises
+pub const SEMANTIC_TOKEN_TYPES: [&str; 10] = [ "keyword", "operator", "variable", "number", "string", "enumMember", "type", "property", "comment", "function" ];
+
+/// Everything the server knows about one open script, worked out again on every change
+pub struct Document {
+    text: String,
+    tokens: Vec<(Tokens, Span)>,
+    annotations: Annotations,
+    diagnostics: Vec<DocumentDiagnostic>,
+    damage_types: DamageTypeRegistry,
+    /// Markdown for the card's name and `///` notes, if it has any
+    notes: Option<String>,
+    /// Markdown for each func's signature, type and notes, in the order they're defined
+    func_notes: Vec<String>
+}
+
+impl Document {
+    pub fn new(text: &str, damage_types: &DamageTypeRegistry) -> Self {
+        let mut document = Document {
+            text: text.to_string(),
+            tokens: vec![ ],
+            annotations: Annotations::default(),
+            diagnostics: vec![ ],
+            damage_types: damage_types.clone(),
+            notes: None,
+            func_notes: vec![ ]
+        };
+
+        match tokenize_spanned(text, damage_types) {
+            Ok(tokens) => document.tokens = tokens,
+            Err((err, span)) => {
+                document.diagnostics.push(DocumentDiagnostic { span, severity: Severity::Error, code: "tokenizer", message: err.to_string() });
+                return document;
+            }
+        }
+
+        let (result, annotations) = analyze_card(document.tokens.iter().map(|(token, _)| token.clone()));
+        document.annotations = annotations;
+        match result {
+            Ok(card) => {
+                document.notes = card.get_doc().map(|doc| match card.get_name() {
+                    Some(name) => format!("**{}**\n\n{}", name, doc),
+                    None => doc.to_string()
+                });
+                let ast = card.get_ast();
+                document.func_notes = ast.func_ids()
+                    .map(|func| {
+                        let func = &ast[func];
+                        let signature = format!("`{}`: {}", func.get_signature(), ast.get_type(func.body));
+                        match &func.doc {
+                            Some(doc) => format!("{}\n\n{}", signature, doc),
+                            None => signature
+                        }
+                    })
+                    .collect();
+                // lints are about the whole card, so they go on its first token after the funcs
+                let start = document.annotations.funcs.last().map(|func| func.end).unwrap_or_default();
+                let span = document.tokens.get(start).map(|&(_, span)| span).unwrap_or_default();
+                let (levels, mut lints) = LintLevels::default().for_script(text, damage_types);
+                lints.extend(lint_card(&card));
+                for (lint, severity) in apply_levels(lints, &levels) {
+                    document.diagnostics.push(DocumentDiagnostic { span, severity, code: lint.rule, message: lint.message });
+                }
+            },
+            Err((err, index)) => {
+                let span = document.tokens.get(index).map(|&(_, span)| span).unwrap_or_default();
+                document.diagnostics.push(DocumentDiagnostic { span, severity: Severity::Error, code: "parse", message: err.to_string() });
+            }
+        }
+        document
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_diagnostics(&self) -> &[DocumentDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Markdown describing what's at `offset`: the type of the expression, the odds of a dice roll,
+    /// what a damage type does, a func's signature and notes, the card's notes (over its doc comments and metadata)
+    pub fn hover(&self, offset: usize) -> Option<(Span, String)> {
+        let index = self.token_at(offset)?;
+        let (token, span) = &self.tokens[index];
+
+        if self.is_in_header(index) {
+            return self.notes.as_ref().map(|notes| (*span, notes.clone()));
+        }
+
+        if let Some(notes) = self.func_named(index).and_then(|func| self.func_notes.get(func)) {
+            return Some((*span, notes.clone()));
+        }
+
+        if let Tokens::DamageType(damage_type) = token {
+            let info = self.damage_types.get(&damage_type.clone().get_value())?;
+            let taken = match info.susceptibility {
+                Susceptibility::Normal => String::new(),
+                susceptibility => format!(", players are {} to it", susceptibility)
+            };
+            return Some((*span, format!("{} damage{}", info.display_name, taken)));
+        }
+
+        let (range, expression_type) = self.expression_at(index)?;
+        let start = self.tokens[range.start].1.start;
+        let end = self.tokens[range.end - 1].1.end;
+        let mut contents = format!("`{}`: {}", &self.text[start .. end], expression_type);
+        if let Some((count, sides)) = self.dice_roll_at(index) {
+            let (count, sides) = (count as f64, sides as f64);
+            contents.push_str(&format!("\n\n{}d{}: {} to {}, mean {:.1}", count, sides, count, count * sides, count * (sides + 1.0) / 2.0));
+        }
+        Some((Span { start, end }, contents))
+    }
+
+    /// Where the func or variable at `offset` was defined or declared
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        let index = self.token_at(offset)?;
+        if let Some(func) = self.func_named(index) {
+            return Some(self.tokens[self.annotations.funcs[func].start + 1].1);
+        }
+        let Tokens::Identifier(name) = &self.tokens[index].0 else {
+            return None;
+        };
+        // a variable is only in scope after it's declared, and any declaration between that one and
+        // here would be in a block that has ended (or it would shadow nothing), so the nearest one is it.
+        // A func's parameters are only in scope in its body, which sees nothing else.
+        self.annotations.declarations.iter()
+            .filter(|&&(declared, _)| declared <= index && self.is_identifier(declared, name.as_str()))
+            .filter(|&&(declared, _)| self.func_around(declared) == self.func_around(index))
+            .max_by_key(|&&(declared, _)| declared)
+            .map(|&(declared, _)| self.tokens[declared].1)
+    }
+
+    /// Keywords, damage types, and the funcs and variables declared before `offset`
+    pub fn completions(&self, offset: usize) -> Vec<(String, CompletionKind)> {
+        let mut completions: Vec<(String, CompletionKind)> = SYMBOLS.iter()
+            .filter(|symbol| symbol.chars().all(char::is_alphabetic))
+            .map(|&symbol| (symbol.to_string(), CompletionKind::Keyword))
+            .collect();
+        completions.extend(self.damage_types.get_types().iter()
+            .filter(|info| !info.damage_type.is_none())
+            .map(|info| (info.damage_type.to_string(), CompletionKind::DamageType)));
+
+        // a func can only be called once its definition is over
+        for func in &self.annotations.funcs {
+            if let Tokens::Identifier(name) = &self.tokens[func.start + 1].0 {
+                if self.tokens[func.end - 1].1.end <= offset {
+                    completions.push((name.as_str().to_string(), CompletionKind::Func));
+                }
+            }
+        }
+
+        // inside a func, only its parameters are in scope; outside, none of them are
+        let func = self.annotations.funcs.iter().find(|func| self.tokens[func.start].1.start < offset && offset <= self.tokens[func.end - 1].1.end);
+        let mut variables: Vec<String> = BUILT_IN_SYMBOLS.iter().map(|(name, _)| name.to_string()).collect();
+        for &(declared, _) in &self.annotations.declarations {
+            let (token, span) = &self.tokens[declared];
+            if span.end > offset || self.func_around(declared) != func {
+                continue;
+            }
+            if let Tokens::Identifier(name) = token {
+                if !variables.iter().any(|variable| variable == name.as_str()) {
+                    variables.push(name.as_str().to_string());
+                }
+            }
+        }
+        completions.extend(variables.into_iter().map(|name| (name, CompletionKind::Variable)));
+        completions
+    }
+
+    /// Every token that has a kind, as (span, index into `SEMANTIC_TOKEN_TYPES`)
+    pub fn semantic_tokens(&self) -> Vec<(Span, usize)> {
+        let kind = |name: &str| SEMANTIC_TOKEN_TYPES.iter().position(|&kind| kind == name).unwrap_or_default();
+        let mut semantic_tokens = vec![ ];
+        for (index, (token, span)) in self.tokens.iter().enumerate() {
+            let token_type = match token {
+                Tokens::Symbol(symbol) if symbol.as_str().chars().all(char::is_alphabetic) => kind("keyword"),
+                Tokens::Symbol(_) => kind("operator"),
+                Tokens::Identifier(name) if name.as_str().starts_with('$') => kind("variable"),
+                Tokens::Identifier(_) if index > 0 && matches!(&self.tokens[index - 1].0, Tokens::Symbol(s) if matches!(s.as_str(), "@" | ".")) => kind("property"),
+                Tokens::Identifier(_) if self.func_named(index).is_some() => kind("function"),
+                Tokens::Identifier(_) => kind("type"),
+                Tokens::Numeric(_) | Tokens::Dice(_) => kind("number"),
+                Tokens::Boolean(_) => kind("keyword"),
+                Tokens::DamageType(_) => kind("enumMember"),
+                Tokens::String(_) => kind("string"),
+                Tokens::DocComment(_) => kind("comment"),
+                Tokens::Comment | Tokens::EOF => continue
+            };
+            semantic_tokens.push((*span, token_type));
+        }
+        semantic_tokens
+    }
+
+    /// Index of the token under (or just before) `offset`
+    fn token_at(&self, offset: usize) -> Option<usize> {
+        let is_real = |token: &Tokens| !matches!(token, Tokens::EOF);
+        self.tokens.iter().position(|(token, span)| is_real(token) && span.start <= offset && offset < span.end)
+            .or_else(|| self.tokens.iter().position(|(token, span)| is_real(token) && span.end == offset))
+    }
+
+    /// The smallest expression that includes the token
+    fn expression_at(&self, index: usize) -> Option<(Range<usize>, &ExpressionType)> {
+        self.annotations.expressions.iter()
+            .filter(|(range, _)| range.contains(&index))
+            .min_by_key(|(range, _)| range.len())
+            .map(|(range, expression_type)| (range.clone(), expression_type))
+    }
+
+    /// `(count, sides)` when the token is part of a roll like `2d6` (or a lone `d6`)
+    fn dice_roll_at(&self, index: usize) -> Option<(u16, u8)> {
+        let dice_index = match &self.tokens[index].0 {
+            Tokens::Dice(_) => index,
+            Tokens::Numeric(_) => index + 1,
+            _ => return None
+        };
+        let (Tokens::Dice(dice), dice_span) = self.tokens.get(dice_index)? else {
+            return None;
+        };
+        let sides = dice.clone().get_value().get_sides();
+        match dice_index.checked_sub(1).map(|count_index| &self.tokens[count_index]) {
+            // `2d6` is a count right up against the dice
+            Some((Tokens::Numeric(count), count_span)) if count_span.end == dice_span.start => Some((count.clone().get_value(), sides)),
+            _ if dice_index == index => Some((1, sides)),
+            _ => None
+        }
+    }
+
+    /// After the funcs and before the tags and cost: the doc comments and metadata
+    fn is_in_header(&self, index: usize) -> bool {
+        let start = self.annotations.funcs.last().map(|func| func.end).unwrap_or_default();
+        index >= start && self.tokens[start .. index + 1].iter()
+            .all(|(token, _)| matches!(token, Tokens::DocComment(_) | Tokens::Identifier(_) | Tokens::String(_)) || matches!(token, Tokens::Symbol(s) if s.as_str() == "@"))
+    }
+
+    /// Which func (in the order they're defined) the token names, where it's defined or where it's called
+    fn func_named(&self, index: usize) -> Option<usize> {
+        let Tokens::Identifier(name) = &self.tokens[index].0 else {
+            return None;
+        };
+        if !matches!(self.tokens.get(index + 1), Some((Tokens::Symbol(s), _)) if s.as_str() == "(") {
+            return None;
+        }
+        self.annotations.funcs.iter().position(|func| self.is_identifier(func.start + 1, name.as_str()))
+    }
+
+    /// The definition of the func the token is part of, if it's in one
+    fn func_around(&self, index: usize) -> Option<&Range<usize>> {
+        self.annotations.funcs.iter().find(|func| func.contains(&index))
+    }
+
+    fn is_identifier(&self, index: usize, name: &str) -> bool {
+        matches!(&self.tokens[index].0, Tokens::Identifier(identifier) if identifier.as_str() == name)
+    }
+}
+
+/// Zero-based line and UTF-16 column of a byte offset, the way the protocol counts them
+pub fn to_position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[.. offset.min(text.len())];
+    let line = before.matches('\n').count();
+    let column = before.rsplit('\n').next().unwrap_or_default().encode_utf16().count();
+    (line, column)
+}
+
+/// Byte offset of a zero-based line and UTF-16 column, clamped to the text
+pub fn to_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start = match line {
+        0 => 0,
+        _ => match text.match_indices('\n').nth(line - 1) {
+            Some((newline, _)) => newline + 1,
+            None => return text.len()
+        }
+    };
+    let mut units = 0;
+    for (offset, c) in text[line_start ..].char_indices() {
+        if units >= column || c == '\n' {
+            return line_start + offset;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::library::lints::Severity;
+    use crate::lsp::analysis::{to_offset, to_position, CompletionKind, Document, SEMANTIC_TOKEN_TYPES};
+    use test_case::test_case;
+
+    const SCRIPT: &str = "@name \"Zap\" [1]: {\n  $hit = 2d6 + 1;\n  if $hit > 8 { $hit = 1; }\n  $hit fire => target(1 in Player);\n}";
+
+    fn document(script: &str) -> Document {
+        Document::new(script, &DamageTypeRegistry::default())
+    }
+
+    /// Offset of the nth match of `needle`
+    fn find(needle: &str, nth: usize) -> usize {
+        SCRIPT.match_indices(needle).nth(nth).unwrap().0
+    }
+
+    #[test]
+    fn clean_script_has_no_diagnostics() {
+        assert!(document(SCRIPT).get_diagnostics().is_empty());
+    }
+
+    #[test_case("[1]: {\n  2 fire => ; }", Severity::Error, "parse", (19, 20) ; "Parse error")]
+    #[test_case("[1]: { log \"oops; }", Severity::Error, "tokenizer", (11, 19) ; "Tokenizer error")]
+    #[test_case("@name \"Zap\" [1]: { }", Severity::Warning, "empty-body", (0, 1) ; "Lint")]
+    fn diagnostics(script: &str, severity: Severity, code: &str, span: (usize, usize)) {
+        let document = document(script);
+        let diagnostic = &document.get_diagnostics()[0];
+        assert_eq!((diagnostic.severity, diagnostic.code), (severity, code));
+        assert_eq!((diagnostic.span.start, diagnostic.span.end), span);
+    }
+
+    #[test]
+    fn hover_types() {
+        let document = document(SCRIPT);
+
+        let (_, contents) = document.hover(find("$hit", 1) + 1).unwrap();
+        assert_eq!(contents, "`$hit`: integer");
+        let (span, contents) = document.hover(find(">", 0)).unwrap();
+        assert_eq!(&SCRIPT[span.start .. span.end], "$hit > 8");
+        assert_eq!(contents, "`$hit > 8`: boolean");
+        assert!(document.hover(find("if", 0)).is_none());
+    }
+
+    #[test]
+    fn hover_dice_statistics() {
+        let document = document(SCRIPT);
+        let (_, contents) = document.hover(find("d6", 0)).unwrap();
+        assert_eq!(contents, "`2d6`: integer\n\n2d6: 2 to 12, mean 7.0");
+    }
+
+    #[test]
+    fn hover_card_notes() {
+        let script = "/// Deals 2 to 12.\n@name \"Zap\" [1]: { 2d6 fire => target(1 in Player); }";
+        let document = document(script);
+
+        let (span, contents) = document.hover(script.find("Zap").unwrap()).unwrap();
+        assert_eq!(&script[span.start .. span.end], "\"Zap\"");
+        assert_eq!(contents, "**Zap**\n\nDeals 2 to 12.");
+        assert!(document.hover(2).is_some());
+        assert!(Document::new(SCRIPT, &DamageTypeRegistry::default()).hover(find("Zap", 0)).is_none());
+    }
+
+    #[test]
+    fn hover_func_notes() {
+        let script = "/// Rounds up.\nfunc half($x: integer) = ($x + 1) / 2;\n/// Zaps.\n@name \"Zap\" [half(3)]: { }";
+        let document = document(script);
+
+        for nth in 0 .. 2 {
+            let (span, contents) = document.hover(script.match_indices("half").nth(nth).unwrap().0).unwrap();
+            assert_eq!(&script[span.start .. span.end], "half");
+            assert_eq!(contents, "`half($x: integer)`: integer\n\nRounds up.");
+        }
+        assert_eq!(document.hover(script.find("Zap").unwrap()).unwrap().1, "**Zap**\n\nZaps.");
+    }
+
+    #[test]
+    fn hover_damage_type() {
+        let damage_types = DamageTypeRegistry::parse("fire \"Fire\" resistant").unwrap();
+        let document = Document::new(SCRIPT, &damage_types);
+        let (_, contents) = document.hover(find("fire", 0)).unwrap();
+        assert_eq!(contents, "Fire damage, players are resistant to it");
+    }
+
+    #[test]
+    fn definition_of_variable() {
+        let document = document(SCRIPT);
+        let declaration = find("$hit", 0);
+
+        for nth in 1 .. 4 {
+            let span = document.definition(find("$hit", nth)).unwrap();
+            assert_eq!((span.start, span.end), (declaration, declaration + 4));
+        }
+        assert!(document.definition(find("Player", 0)).is_none());
+    }
+
+    const FUNCS: &str = "func half($hit: integer) = $hit / 2;\nfunc bolt($hit: integer) = half($hit) + 1;\n@name \"Zap\" [half(2)]: {\n  $hit = bolt(2d6);\n  $hit fire => target(1 in Player);\n}";
+
+    #[test]
+    fn definition_of_func() {
+        let document = document(FUNCS);
+        let at = |needle: &str, nth: usize| FUNCS.match_indices(needle).nth(nth).unwrap().0;
+        let definition = |offset: usize| document.definition(offset).map(|span| span.start);
+
+        assert_eq!(definition(at("half", 1)), Some(at("half", 0)));
+        assert_eq!(definition(at("half", 2)), Some(at("half", 0)));
+        assert_eq!(definition(at("bolt", 1)), Some(at("bolt", 0)));
+        assert_eq!(definition(at("bolt", 0)), Some(at("bolt", 0)));
+        // each func's parameter is its own, and the card's variable is neither of them
+        assert_eq!(definition(at("$hit", 1)), Some(at("$hit", 0)));
+        assert_eq!(definition(at("$hit", 3)), Some(at("$hit", 2)));
+        assert_eq!(definition(at("$hit", 5)), Some(at("$hit", 4)));
+        assert!(document.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn func_completions() {
+        let document = document(FUNCS);
+        let labels = |offset: usize| document.completions(offset);
+
+        let in_bolt = labels(FUNCS.find("half($hit)").unwrap());
+        assert!(in_bolt.contains(&(String::from("half"), CompletionKind::Func)));
+        assert!(!in_bolt.iter().any(|(label, _)| label == "bolt"));
+        assert_eq!(in_bolt.iter().filter(|(label, _)| label == "$hit").count(), 1);
+
+        let in_card = labels(FUNCS.find("[half").unwrap());
+        assert!(in_card.contains(&(String::from("bolt"), CompletionKind::Func)));
+        assert!(!in_card.iter().any(|(label, _)| label == "$hit"));
+    }
+
+    #[test]
+    fn completions() {
+        let document = document(SCRIPT);
+        let before = document.completions(0);
+        let after = document.completions(SCRIPT.len());
+
+        assert!(before.contains(&(String::from("target"), CompletionKind::Keyword)));
+        assert!(before.contains(&(String::from("fire"), CompletionKind::DamageType)));
+        assert!(!before.iter().any(|(label, _)| label == "none" || label == "{"));
+        assert!(before.contains(&(String::from("$turn"), CompletionKind::Variable)));
+        assert!(!before.iter().any(|(label, _)| label == "$hit"));
+        assert_eq!(after.iter().filter(|(label, _)| label == "$hit").count(), 1);
+    }
+
+    #[test]
+    fn semantic_tokens() {
+        let document = document("@name \"Zap\" [1]: { 2 fire => target(1 in Player); }");
+        let kinds: Vec<&str> = document.semantic_tokens().into_iter().map(|(_, kind)| SEMANTIC_TOKEN_TYPES[kind]).collect();
+        assert_eq!(&kinds[.. 7], &[ "operator", "property", "string", "operator", "number", "operator", "operator" ]);
+        assert_eq!(&kinds[8 ..], &[ "number", "enumMember", "operator", "keyword", "operator", "number", "keyword", "type", "operator", "operator", "operator" ]);
+    }
+
+    #[test]
+    fn func_semantic_tokens() {
+        let script = "func one() = 1; [one()]: { }";
+        let document = document(script);
+        let functions: Vec<usize> = document.semantic_tokens().into_iter()
+            .filter(|&(_, kind)| SEMANTIC_TOKEN_TYPES[kind] == "function")
+            .map(|(span, _)| span.start)
+            .collect();
+        assert_eq!(functions, vec![ 5, 17 ]);
+    }
+
+    #[test_case("ab\ncd", 4, (1, 1) ; "Second line")]
+    #[test_case("é🎲x", 6, (0, 3) ; "Wide characters")]
+    #[test_case("ab", 9, (0, 2) ; "Past the end")]
+    fn positions(text: &str, offset: usize, position: (usize, usize)) {
+        assert_eq!(to_position(text, offset), position);
+        assert_eq!(to_offset(text, position.0, position.1), offset.min(text.len()));
+    }
+}
@@ -0,0 +1,337 @@
+use std::fmt::Display;
+
+/// Just enough JSON for the language server protocol (and `check --json`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keys in the order they were written
+    Object(Vec<(String, Json)>)
+}
+
+impl Json {
+    pub fn object<'a>(members: impl IntoIterator<Item=(&'a str, Json)>) -> Json {
+        Json::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    /// Member of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None
+        }
+    }
+
+    /// Member of a member of ... an object, e.g. `["params", "textDocument", "uri"]`
+    pub fn get_path(&self, keys: &[&str]) -> Option<&Json> {
+        keys.iter().try_fold(self, |json, key| json.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    /// Numbers that are whole and not negative
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Json::Number(value) if value >= 0.0 && value.fract() == 0.0 => Some(value as usize),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Json, JsonError> {
+        let mut parser = JsonParser { text, offset: 0 };
+        let value = parser.parse_value()?;
+        parser.skip_white_space();
+        if parser.offset < text.len() {
+            return Err(parser.error(JsonErrorKind::TrailingCharacters));
+        }
+        Ok(value)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(items: Vec<Json>) -> Self {
+        Json::Array(items)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+/// Compact, with no white space between tokens
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            // whole numbers print without the `.0`
+            Json::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => write!(f, "{}", *value as i64),
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(value) => write_string(f, value),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            },
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, value: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?
+        }
+    }
+    write!(f, "\"")
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    UnexpectedEnd,
+    UnexpectedCharacter(char),
+    InvalidNumber,
+    InvalidEscape,
+    /// Something after the value
+    TrailingCharacters
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct JsonError {
+    /// Byte offset into the text
+    pub offset: usize,
+    pub kind: JsonErrorKind
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "offset {}: ", self.offset)?;
+        match &self.kind {
+            JsonErrorKind::UnexpectedEnd => write!(f, "unexpected end of JSON"),
+            JsonErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected '{}'", c),
+            JsonErrorKind::InvalidNumber => write!(f, "invalid number"),
+            JsonErrorKind::InvalidEscape => write!(f, "invalid escape"),
+            JsonErrorKind::TrailingCharacters => write!(f, "more after the value")
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    offset: usize
+}
+
+impl JsonParser<'_> {
+    fn error(&self, kind: JsonErrorKind) -> JsonError {
+        JsonError { offset: self.offset, kind }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset ..].chars().next()
+    }
+
+    fn next(&mut self) -> Result<char, JsonError> {
+        let c = self.peek().ok_or(self.error(JsonErrorKind::UnexpectedEnd))?;
+        self.offset += c.len_utf8();
+        Ok(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.skip_white_space();
+        match self.next()? {
+            c if c == expected => Ok(()),
+            c => Err(JsonError { offset: self.offset - c.len_utf8(), kind: JsonErrorKind::UnexpectedCharacter(c) })
+        }
+    }
+
+    fn skip_white_space(&mut self) {
+        while self.peek().is_some_and(|c| matches!(c, ' ' | '\t' | '\n' | '\r')) {
+            self.offset += 1;
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, JsonError> {
+        self.skip_white_space();
+        match self.peek().ok_or(self.error(JsonErrorKind::UnexpectedEnd))? {
+            '{' => self.parse_object(),
+            '[' => self.parse_array(),
+            '"' => Ok(Json::String(self.parse_string()?)),
+            't' => self.parse_word("true", Json::Bool(true)),
+            'f' => self.parse_word("false", Json::Bool(false)),
+            'n' => self.parse_word("null", Json::Null),
+            c if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            c => Err(self.error(JsonErrorKind::UnexpectedCharacter(c)))
+        }
+    }
+
+    fn parse_word(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<Json, JsonError> {
+        let start = self.offset;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+            self.offset += 1;
+        }
+        self.text[start .. self.offset].parse()
+            .map(Json::Number)
+            .map_err(|_| JsonError { offset: start, kind: JsonErrorKind::InvalidNumber })
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Ok(value),
+                '\\' => {
+                    let unescaped = match self.next()? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.parse_unicode_escape()?,
+                        _ => return Err(self.error(JsonErrorKind::InvalidEscape))
+                    };
+                    value.push(unescaped);
+                },
+                c => value.push(c)
+            }
+        }
+    }
+
+    /// After `\u`: four hex digits, or a surrogate pair of them
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let first = self.parse_hex()?;
+        if !(0xd800 .. 0xdc00).contains(&first) {
+            return char::from_u32(first).ok_or(self.error(JsonErrorKind::InvalidEscape));
+        }
+        if self.next()? != '\\' || self.next()? != 'u' {
+            return Err(self.error(JsonErrorKind::InvalidEscape));
+        }
+        let second = self.parse_hex()?;
+        if !(0xdc00 .. 0xe000).contains(&second) {
+            return Err(self.error(JsonErrorKind::InvalidEscape));
+        }
+        char::from_u32(0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)).ok_or(self.error(JsonErrorKind::InvalidEscape))
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, JsonError> {
+        let digits = self.text.get(self.offset .. self.offset + 4).ok_or(self.error(JsonErrorKind::InvalidEscape))?;
+        let value = u32::from_str_radix(digits, 16).map_err(|_| self.error(JsonErrorKind::InvalidEscape))?;
+        self.offset += 4;
+        Ok(value)
+    }
+
+    fn parse_array(&mut self) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        let mut items = vec![ ];
+        self.skip_white_space();
+        if self.peek() == Some(']') {
+            self.offset += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_white_space();
+            match self.next()? {
+                ',' => continue,
+                ']' => return Ok(Json::Array(items)),
+                c => return Err(JsonError { offset: self.offset - c.len_utf8(), kind: JsonErrorKind::UnexpectedCharacter(c) })
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        let mut members = vec![ ];
+        self.skip_white_space();
+        if self.peek() == Some('}') {
+            self.offset += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_white_space();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            members.push((key, self.parse_value()?));
+            self.skip_white_space();
+            match self.next()? {
+                ',' => continue,
+                '}' => return Ok(Json::Object(members)),
+                c => return Err(JsonError { offset: self.offset - c.len_utf8(), kind: JsonErrorKind::UnexpectedCharacter(c) })
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use crate::lsp::json::{Json, JsonError, JsonErrorKind};
+    use test_case::test_case;
+
+    #[test]
+    fn parse_message() {
+        let json = Json::parse(r#" {"id": 3, "params": {"position": {"line": 0, "character": 12}}, "list": [true, null, -1.5e2], "text": "a\"b\n\u00e9"} "#).unwrap();
+
+        assert_eq!(json.get("id").and_then(Json::as_usize), Some(3));
+        assert_eq!(json.get_path(&[ "params", "position", "character" ]).and_then(Json::as_usize), Some(12));
+        assert_eq!(json.get("list").and_then(Json::as_array), Some(&[ Json::Bool(true), Json::Null, Json::Number(-150.0) ][..]));
+        assert_eq!(json.get("text").and_then(Json::as_str), Some("a\"b\né"));
+        assert!(json.get("missing").is_none());
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        assert_eq!(Json::parse(r#""\ud83c\udfb2""#).unwrap(), Json::from("🎲"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = Json::object([
+            ("name", Json::from("tab\there \"quoted\"")),
+            ("count", Json::from(2)),
+            ("half", Json::Number(0.5)),
+            ("items", Json::from(vec![ Json::Null, Json::from(false) ])),
+            ("nothing", Json::from(None::<usize>))
+        ]);
+
+        let text = json.to_string();
+        assert_eq!(text, r#"{"name":"tab\there \"quoted\"","count":2,"half":0.5,"items":[null,false],"nothing":null}"#);
+        assert_eq!(Json::parse(&text).unwrap(), json);
+    }
+
+    #[test_case("", 0, JsonErrorKind::UnexpectedEnd ; "Empty")]
+    #[test_case("{\"a\" 1}", 5, JsonErrorKind::UnexpectedCharacter('1') ; "Missing colon")]
+    #[test_case("[1, 2", 5, JsonErrorKind::UnexpectedEnd ; "Unclosed array")]
+    #[test_case("\"\\x\"", 3, JsonErrorKind::InvalidEscape ; "Bad escape")]
+    #[test_case("1-2", 0, JsonErrorKind::InvalidNumber ; "Bad number")]
+    #[test_case("true false", 5, JsonErrorKind::TrailingCharacters ; "Trailing")]
+    fn invalid(text: &str, offset: usize, kind: JsonErrorKind) {
+        assert_eq!(Json::parse(text), Err(JsonError { offset, kind }));
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+use crate::library::lints::Severity;
+use crate::parsing::tokenizer::Span;
+
+use super::analysis::{to_offset, to_position, CompletionKind, Document, SEMANTIC_TOKEN_TYPES};
+use super::json::Json;
+
+/// JSON-RPC error codes the server answers with
+const PARSE_ERROR: i32 = -32700;
+const INVALID_PARAMS: i32 = -32602;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// Language server for card scripts: keeps the open scripts, and answers the editor's requests about them.
+/// Scripts are always sent whole (full text sync).
+pub struct Server {
+    documents: HashMap<String, Document>,
+    damage_types: DamageTypeRegistry,
+    shut_down: bool,
+    exited: bool
+}
+
+impl Server {
+    pub fn new(damage_types: DamageTypeRegistry) -> Self {
+        Server { documents: HashMap::new(), damage_types, shut_down: false, exited: false }
+    }
+
+    /// The editor sent `exit`
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// `shutdown` came before `exit`, as it should
+    pub fn was_shut_down(&self) -> bool {
+        self.shut_down
+    }
+
+    /// Handle one message, returning the messages to send back (a response to a request,
+    /// diagnostics when a script changes)
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or_default();
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let Some(id) = message.get("id").cloned() else {
+            return self.handle_notification(method, params);
+        };
+
+        let result = match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Json::Null)
+            },
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/semanticTokens/full" => self.semantic_tokens(params),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method '{}'", method)))
+        };
+        vec![ response(id, result) ]
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let Some(uri) = params.get_path(&[ "textDocument", "uri" ]).and_then(Json::as_str) else {
+            if method == "exit" {
+                self.exited = true;
+            }
+            return vec![ ];
+        };
+        let text = match method {
+            "textDocument/didOpen" => params.get_path(&[ "textDocument", "text" ]).and_then(Json::as_str),
+            // full sync, so the last change is the whole script
+            "textDocument/didChange" => params.get("contentChanges").and_then(Json::as_array)
+                .and_then(|changes| changes.last())
+                .and_then(|change| change.get("text"))
+                .and_then(Json::as_str),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![ publish_diagnostics(uri, vec![ ]) ];
+            },
+            _ => None
+        };
+        let Some(text) = text else {
+            return vec![ ];
+        };
+
+        let document = Document::new(text, &self.damage_types);
+        let diagnostics = document.get_diagnostics().iter()
+            .map(|diagnostic| Json::object([
+                ("range", range(document.get_text(), diagnostic.span)),
+                ("severity", Json::from(match diagnostic.severity { Severity::Error => 1, Severity::Warning => 2 })),
+                ("code", Json::from(diagnostic.code)),
+                ("source", Json::from("mage_duel")),
+                ("message", Json::from(diagnostic.message.as_str()))
+            ]))
+            .collect();
+        self.documents.insert(uri.to_string(), document);
+        vec![ publish_diagnostics(uri, diagnostics) ]
+    }
+
+    /// The document and byte offset a request is about
+    fn locate<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a Document, usize), (i32, String)> {
+        let uri = params.get_path(&[ "textDocument", "uri" ]).and_then(Json::as_str)
+            .ok_or((INVALID_PARAMS, String::from("missing textDocument.uri")))?;
+        let document = self.documents.get(uri).ok_or((INVALID_PARAMS, format!("'{}' isn't open", uri)))?;
+        let line = params.get_path(&[ "position", "line" ]).and_then(Json::as_usize);
+        let character = params.get_path(&[ "position", "character" ]).and_then(Json::as_usize);
+        let offset = match (line, character) {
+            (Some(line), Some(character)) => to_offset(document.get_text(), line, character),
+            _ => 0
+        };
+        Ok((uri, document, offset))
+    }
+
+    fn hover(&self, params: &Json) -> Result<Json, (i32, String)> {
+        let (_, document, offset) = self.locate(params)?;
+        Ok(document.hover(offset).map_or(Json::Null, |(span, contents)| Json::object([
+            ("contents", Json::object([ ("kind", Json::from("markdown")), ("value", Json::from(contents)) ])),
+            ("range", range(document.get_text(), span))
+        ])))
+    }
+
+    fn definition(&self, params: &Json) -> Result<Json, (i32, String)> {
+        let (uri, document, offset) = self.locate(params)?;
+        Ok(document.definition(offset).map_or(Json::Null, |span| Json::object([
+            ("uri", Json::from(uri)),
+            ("range", range(document.get_text(), span))
+        ])))
+    }
+
+    fn completion(&self, params: &Json) -> Result<Json, (i32, String)> {
+        let (_, document, offset) = self.locate(params)?;
+        let items = document.completions(offset).into_iter()
+            .map(|(label, kind)| {
+                let kind = match kind {
+                    CompletionKind::Func => 3,
+                    CompletionKind::Variable => 6,
+                    CompletionKind::Keyword => 14,
+                    CompletionKind::DamageType => 20
+                };
+                Json::object([ ("label", Json::from(label)), ("kind", Json::from(kind as usize)) ])
+            })
+            .collect::<Vec<Json>>();
+        Ok(Json::from(items))
+    }
+
+    /// Encoded the protocol's way: five numbers per token, each position relative to the one before
+    fn semantic_tokens(&self, params: &Json) -> Result<Json, (i32, String)> {
+        let (_, document, _) = self.locate(params)?;
+        let text = document.get_text();
+        let mut data = vec![ ];
+        let (mut previous_line, mut previous_column) = (0, 0);
+        for (span, token_type) in document.semantic_tokens() {
+            let (line, column) = to_position(text, span.start);
+            // tokens can't run over lines, so a string that does is only marked up to the end of its first
+            let length = text[span.start .. span.end].split('\n').next().unwrap_or_default().encode_utf16().count();
+            let relative_column = if line == previous_line { column - previous_column } else { column };
+            data.extend([ line - previous_line, relative_column, length, token_type, 0 ].map(Json::from));
+            (previous_line, previous_column) = (line, column);
+        }
+        Ok(Json::object([ ("data", Json::from(data)) ]))
+    }
+}
+
+fn capabilities() -> Json {
+    let token_types = SEMANTIC_TOKEN_TYPES.iter().map(|&name| Json::from(name)).collect::<Vec<Json>>();
+    Json::object([
+        ("capabilities", Json::object([
+            ("textDocumentSync", Json::from(1)),
+            ("hoverProvider", Json::from(true)),
+            ("definitionProvider", Json::from(true)),
+            ("completionProvider", Json::object([ ("triggerCharacters", Json::from(vec![ Json::from("$") ])) ])),
+            ("semanticTokensProvider", Json::object([
+                ("legend", Json::object([ ("tokenTypes", Json::from(token_types)), ("tokenModifiers", Json::from(vec![ ])) ])),
+                ("full", Json::from(true))
+            ]))
+        ])),
+        ("serverInfo", Json::object([ ("name", Json::from("mage_duel")) ]))
+    ])
+}
+
+fn response(id: Json, result: Result<Json, (i32, String)>) -> Json {
+    let outcome = match result {
+        Ok(result) => ("result", result),
+        Err((code, message)) => ("error", Json::object([ ("code", Json::Number(code as f64)), ("message", Json::from(message)) ]))
+    };
+    Json::object([ ("jsonrpc", Json::from("2.0")), ("id", id), outcome ])
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object([
+        ("jsonrpc", Json::from("2.0")),
+        ("method", Json::from("textDocument/publishDiagnostics")),
+        ("params", Json::object([ ("uri", Json::from(uri)), ("diagnostics", Json::from(diagnostics)) ]))
+    ])
+}
+
+fn range(text: &str, span: Span) -> Json {
+    let position = |offset| {
+        let (line, character) = to_position(text, offset);
+        Json::object([ ("line", Json::from(line)), ("character", Json::from(character)) ])
+    };
+    Json::object([ ("start", position(span.start)), ("end", position(span.end)) ])
+}
+
+/// Next message's content, or None at the end of the input. Messages are `Content-Length: N` and any
+/// other headers, a blank line, then N bytes of JSON.
+pub fn read_message(input: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let mut content = vec![ 0; length.unwrap_or_default() ];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content).map(Some).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> std::io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    output.flush()
+}
+
+/// Answer messages until the editor says `exit` or closes the input
+pub fn serve(server: &mut Server, input: &mut impl BufRead, output: &mut impl Write) -> std::io::Result<()> {
+    while let Some(content) = read_message(input)? {
+        let replies = match Json::parse(&content) {
+            Ok(message) => server.handle(&message),
+            Err(err) => vec![ response(Json::Null, Err((PARSE_ERROR, err.to_string()))) ]
+        };
+        for reply in &replies {
+            write_message(output, reply)?;
+        }
+        if server.has_exited() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::lsp::json::Json;
+    use crate::lsp::server::{read_message, serve, write_message, Server};
+
+    const URI: &str = "file:///cards/zap.card";
+
+    /// Play a recorded session (one JSON message per entry) through the server, returning what it sent back
+    fn replay(messages: &[ String ]) -> (Server, Vec<Json>) {
+        let mut input = vec![ ];
+        for message in messages {
+            write_message(&mut input, &Json::parse(message).unwrap()).unwrap();
+        }
+        let mut output = vec![ ];
+        let mut server = Server::new(DamageTypeRegistry::default());
+        serve(&mut server, &mut Cursor::new(input), &mut output).unwrap();
+
+        let mut output = Cursor::new(output);
+        let mut replies = vec![ ];
+        while let Some(content) = read_message(&mut output).unwrap() {
+            replies.push(Json::parse(&content).unwrap());
+        }
+        (server, replies)
+    }
+
+    fn request(id: usize, method: &str, params: &str) -> String {
+        format!(r#"{{"jsonrpc": "2.0", "id": {}, "method": "{}", "params": {}}}"#, id, method, params)
+    }
+
+    fn notification(method: &str, params: &str) -> String {
+        format!(r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}}}"#, method, params)
+    }
+
+    fn at(line: usize, character: usize) -> String {
+        format!(r#"{{"textDocument": {{"uri": "{}"}}, "position": {{"line": {}, "character": {}}}}}"#, URI, line, character)
+    }
+
+    fn open(text: &str) -> String {
+        notification("textDocument/didOpen", &format!(r#"{{"textDocument": {{"uri": "{}", "languageId": "card", "version": 1, "text": {}}}}}"#, URI, Json::from(text)))
+    }
+
+    fn result(reply: &Json) -> &Json {
+        reply.get("result").unwrap()
+    }
+
+    #[test]
+    fn recorded_session() {
+        let script = "@name \"Zap\" [1]: {\n  $hit = 2d6;\n  $hit fire => target(1 in Player);\n}";
+        let (server, replies) = replay(&[
+            request(1, "initialize", r#"{"processId": null, "rootUri": null, "capabilities": {}}"#),
+            notification("initialized", "{}"),
+            open(script),
+            request(2, "textDocument/hover", &at(1, 10)),
+            request(3, "textDocument/definition", &at(2, 3)),
+            request(4, "textDocument/completion", &at(2, 0)),
+            request(5, "textDocument/semanticTokens/full", &format!(r#"{{"textDocument": {{"uri": "{}"}}}}"#, URI)),
+            notification("textDocument/didChange", &format!(r#"{{"textDocument": {{"uri": "{}", "version": 2}}, "contentChanges": [{{"text": "[1]: {{ 2 fire => ; }}"}}]}}"#, URI)),
+            notification("textDocument/didClose", &format!(r#"{{"textDocument": {{"uri": "{}"}}}}"#, URI)),
+            request(6, "shutdown", "null"),
+            notification("exit", "null"),
+            request(7, "textDocument/hover", &at(0, 0))
+        ]);
+
+        assert!(server.was_shut_down());
+        // nothing is answered after `exit`
+        assert_eq!(replies.len(), 9);
+
+        assert_eq!(replies[0].get("id").and_then(Json::as_usize), Some(1));
+        assert_eq!(result(&replies[0]).get_path(&[ "capabilities", "textDocumentSync" ]).and_then(Json::as_usize), Some(1));
+
+        assert_eq!(replies[1].get("method").and_then(Json::as_str), Some("textDocument/publishDiagnostics"));
+        assert_eq!(replies[1].get_path(&[ "params", "diagnostics" ]).and_then(Json::as_array).map(<[Json]>::len), Some(0));
+
+        let hover = result(&replies[2]);
+        assert_eq!(hover.get_path(&[ "contents", "value" ]).and_then(Json::as_str), Some("`2d6`: integer\n\n2d6: 2 to 12, mean 7.0"));
+        assert_eq!(hover.get_path(&[ "range", "start", "character" ]).and_then(Json::as_usize), Some(9));
+
+        let definition = result(&replies[3]);
+        assert_eq!(definition.get("uri").and_then(Json::as_str), Some(URI));
+        assert_eq!(definition.get("range").unwrap().to_string(), r#"{"start":{"line":1,"character":2},"end":{"line":1,"character":6}}"#);
+
+        let completions = result(&replies[4]).as_array().unwrap();
+        assert!(completions.iter().any(|item| item.to_string() == r#"{"label":"$hit","kind":6}"#));
+        assert!(completions.iter().any(|item| item.to_string() == r#"{"label":"acid","kind":20}"#));
+
+        // `@` then `name`: both on line 0, the second one character along
+        let data = result(&replies[5]).get("data").and_then(Json::as_array).unwrap();
+        assert_eq!(Json::from(data[.. 10].to_vec()).to_string(), "[0,0,1,1,0,0,1,4,7,0]");
+
+        let diagnostics = replies[6].get_path(&[ "params", "diagnostics" ]).and_then(Json::as_array).unwrap();
+        assert_eq!(diagnostics[0].to_string(), r#"{"range":{"start":{"line":0,"character":17},"end":{"line":0,"character":18}},"severity":1,"code":"parse","source":"mage_duel","message":"unexpected ';'"}"#);
+
+        assert_eq!(replies[7].get_path(&[ "params", "diagnostics" ]).and_then(Json::as_array).map(<[Json]>::len), Some(0));
+        assert_eq!(replies[8].get("id").and_then(Json::as_usize), Some(6));
+        assert_eq!(result(&replies[8]), &Json::Null);
+    }
+
+    #[test]
+    fn errors() {
+        let (server, replies) = replay(&[
+            request(1, "textDocument/formatting", "{}"),
+            request(2, "textDocument/hover", &at(0, 0)),
+            open("[1]: { }"),
+            request(3, "textDocument/hover", &at(0, 0)),
+        ]);
+
+        assert!(!server.was_shut_down());
+        assert_eq!(replies[0].get_path(&[ "error", "code" ]), Some(&Json::Number(-32601.0)));
+        assert_eq!(replies[1].get_path(&[ "error", "code" ]), Some(&Json::Number(-32602.0)));
+        assert_eq!(result(&replies[3]), &Json::Null);
+    }
+
+    #[test]
+    fn invalid_json() {
+        let mut output = vec![ ];
+        serve(&mut Server::new(DamageTypeRegistry::default()), &mut Cursor::new("Content-Length: 5\r\n\r\n{oops"), &mut output).unwrap();
+        let reply = Json::parse(&read_message(&mut Cursor::new(output)).unwrap().unwrap()).unwrap();
+
+        assert_eq!(reply.get("id"), Some(&Json::Null));
+        assert_eq!(reply.get_path(&[ "error", "code" ]), Some(&Json::Number(-32700.0)));
+    }
+
+    #[test]
+    fn read_framed_messages() {
+        let mut input = Cursor::new("Content-Length: 2\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}Content-Length: 4\r\n\r\nnull");
+        assert_eq!(read_message(&mut input).unwrap().as_deref(), Some("{}"));
+        assert_eq!(read_message(&mut input).unwrap().as_deref(), Some("null"));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+}
@@ -1,6 +1,24 @@
-pub mod parsing;
-pub mod game_zones;
+use std::process::ExitCode;
 
-fn main() {
-    println!("Hello, world!");
+use mage_duel::cli;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        None | Some("repl") => cli::repl::run(args.get(1 ..).unwrap_or_default()),
+        Some("simulate") => cli::simulate::run(&args[1 ..]),
+        Some("check") => cli::check::run(&args[1 ..]),
+        Some("lsp") => cli::lsp::run(&args[1 ..]),
+        Some("fmt") => cli::fmt::run(&args[1 ..]),
+        Some("catalog") => cli::catalog::run(&args[1 ..]),
+        _ => Err(format!("usage:\n  {}", [ cli::repl::USAGE, cli::simulate::USAGE, cli::check::USAGE, cli::lsp::USAGE, cli::fmt::USAGE, cli::catalog::USAGE ].join("\n  ")))
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
 }
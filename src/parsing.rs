@@ -1,6 +1,17 @@
 pub mod tokens;
 pub mod tokenizer;
+pub mod ast;
 pub mod expressions;
+pub mod statements;
+pub mod card;
 pub mod parser;
+pub mod cst;
+pub mod formatter;
+pub mod optimizer;
 pub mod symbol_table;
-mod tokenizer_tests;
\ No newline at end of file
+pub mod script_context;
+mod tokenizer_tests;
+mod parser_tests;
+mod cst_tests;
+mod formatter_tests;
+mod optimizer_tests;
\ No newline at end of file
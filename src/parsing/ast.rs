@@ -0,0 +1,258 @@
+use std::fmt::Debug;
+use std::ops::Index;
+
+use super::expressions::{Expression, ExpressionResult, ExpressionType, Func, InterpolationPart};
+use super::statements::Statement;
+
+/// Index of an expression in its `Ast`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExpressionId(u32);
+
+/// Index of a statement in its `Ast`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatementId(u32);
+
+/// Index of a func in its `Ast`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FuncId(u32);
+
+/// Every expression, statement and func of a script, each stored once and referred to by id.
+/// Nodes only point at nodes added before them, and an expression's type is worked out when it's added.
+#[derive(Debug, Clone, Default)]
+pub struct Ast {
+    expressions: Vec<Expression>,
+    types: Vec<ExpressionType>,
+    statements: Vec<Statement>,
+    funcs: Vec<Func>
+}
+
+impl Ast {
+    /// Add an expression whose type has already been checked
+    pub fn push_expression(&mut self, expression: Expression, expression_type: ExpressionType) -> ExpressionId {
+        self.expressions.push(expression);
+        self.types.push(expression_type);
+        ExpressionId(self.expressions.len() as u32 - 1)
+    }
+
+    pub fn push_statement(&mut self, statement: Statement) -> StatementId {
+        self.statements.push(statement);
+        StatementId(self.statements.len() as u32 - 1)
+    }
+
+    pub fn push_func(&mut self, func: Func) -> FuncId {
+        self.funcs.push(func);
+        FuncId(self.funcs.len() as u32 - 1)
+    }
+
+    /// The func with this name, if one has been defined
+    pub fn find_func(&self, name: &str) -> Option<FuncId> {
+        self.funcs.iter().position(|func| func.name.as_ref() == name).map(|index| FuncId(index as u32))
+    }
+
+    /// Every func, in the order they were defined
+    pub fn func_ids(&self) -> impl Iterator<Item = FuncId> {
+        (0 .. self.funcs.len() as u32).map(FuncId)
+    }
+
+    /// Put a different expression of the same type in place of another, for passes that rewrite the tree
+    pub fn replace_expression(&mut self, id: ExpressionId, expression: Expression) {
+        self.expressions[id.0 as usize] = expression;
+    }
+
+    pub fn replace_statement(&mut self, id: StatementId, statement: Statement) {
+        self.statements[id.0 as usize] = statement;
+    }
+
+    /// Point a func at a rewritten body of the same type
+    pub fn replace_func_body(&mut self, id: FuncId, body: ExpressionId) {
+        self.funcs[id.0 as usize].body = body;
+    }
+
+    /// Move an expression out to rebuild it without copying, leaving an empty list in its place until it's replaced
+    pub fn take_expression(&mut self, id: ExpressionId) -> Expression {
+        std::mem::replace(&mut self.expressions[id.0 as usize], Expression::List(vec![ ]))
+    }
+
+    /// Move a statement out to rebuild it without copying, leaving `counter;` in its place until it's replaced
+    pub fn take_statement(&mut self, id: StatementId) -> Statement {
+        std::mem::replace(&mut self.statements[id.0 as usize], Statement::Counter)
+    }
+
+    pub fn get_type(&self, id: ExpressionId) -> &ExpressionType {
+        &self.types[id.0 as usize]
+    }
+
+    /// Only literals know their value without being evaluated
+    pub fn as_literal(&self, id: ExpressionId) -> Option<&ExpressionResult> {
+        match &self[id] {
+            Expression::Literal(value) => Some(value),
+            _ => None
+        }
+    }
+
+    /// Subexpressions, in the order they're evaluated
+    pub fn get_operands(&self, id: ExpressionId) -> impl Iterator<Item = ExpressionId> + '_ {
+        let (pair, items, parts): ([ Option<ExpressionId>; 2 ], &[ExpressionId], &[InterpolationPart]) = match &self[id] {
+            &Expression::Binary(_, left, right) => ([ Some(left), Some(right) ], &[ ], &[ ]),
+            &Expression::Unary(_, operand) | &Expression::Target { count: operand, .. } | &Expression::Property { owner: operand, .. } => {
+                ([ Some(operand), None ], &[ ], &[ ])
+            },
+            Expression::List(items) | Expression::Call { args: items, .. } => ([ None, None ], items, &[ ]),
+            Expression::Interpolation(parts) => ([ None, None ], &[ ], parts),
+            Expression::Literal(_) | Expression::Variable(_) | Expression::Roll { .. } => ([ None, None ], &[ ], &[ ])
+        };
+        pair.into_iter().flatten()
+            .chain(items.iter().copied())
+            .chain(parts.iter().filter_map(|part| match part {
+                &InterpolationPart::Expression(expression) => Some(expression),
+                InterpolationPart::Text(_) => None
+            }))
+    }
+
+    /// Expressions the statement evaluates itself (not those of the statements inside it), in order
+    pub fn get_expressions(&self, id: StatementId) -> impl Iterator<Item = ExpressionId> {
+        let expressions = match self[id] {
+            Statement::Assignment { value, .. } => [ Some(value), None ],
+            Statement::Damage { amount, target, .. } => [ Some(amount), Some(target) ],
+            Statement::Status { turns, target, .. } => [ Some(turns), Some(target) ],
+            Statement::Modifier { amount, target, .. } => [ Some(amount), target ],
+            Statement::Replacement { redirect, .. } => [ redirect, None ],
+            Statement::Log(message) => [ Some(message), None ],
+            Statement::If { condition, .. } => [ Some(condition), None ],
+            Statement::Counter => [ None, None ]
+        };
+        expressions.into_iter().flatten()
+    }
+
+    /// The expression with its subexpressions written out in full, for `{:?}`
+    pub fn expression_tree(&self, id: ExpressionId) -> ExpressionTree<'_> {
+        ExpressionTree { ast: self, id }
+    }
+
+    /// The statement with its expressions and inner statements written out in full, for `{:?}`
+    pub fn statement_tree(&self, id: StatementId) -> StatementTree<'_> {
+        StatementTree { ast: self, id }
+    }
+}
+
+impl Index<ExpressionId> for Ast {
+    type Output = Expression;
+
+    fn index(&self, id: ExpressionId) -> &Expression {
+        &self.expressions[id.0 as usize]
+    }
+}
+
+impl Index<StatementId> for Ast {
+    type Output = Statement;
+
+    fn index(&self, id: StatementId) -> &Statement {
+        &self.statements[id.0 as usize]
+    }
+}
+
+impl Index<FuncId> for Ast {
+    type Output = Func;
+
+    fn index(&self, id: FuncId) -> &Func {
+        &self.funcs[id.0 as usize]
+    }
+}
+
+pub struct ExpressionTree<'a> {
+    ast: &'a Ast,
+    id: ExpressionId
+}
+
+impl Debug for ExpressionTree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tree = |id| self.ast.expression_tree(id);
+        match &self.ast[self.id] {
+            Expression::Literal(value) => f.debug_tuple("Literal").field(value).finish(),
+            Expression::Variable(name) => f.debug_tuple("Variable").field(name).finish(),
+            Expression::Binary(operator, left, right) => f.debug_struct("Binary")
+                .field("operator", operator)
+                .field("left", &tree(*left))
+                .field("right", &tree(*right))
+                .finish(),
+            Expression::Unary(operator, operand) => f.debug_struct("Unary")
+                .field("operator", operator)
+                .field("operand", &tree(*operand))
+                .finish(),
+            Expression::Roll { count, dice } => f.debug_struct("Roll").field("count", count).field("dice", dice).finish(),
+            Expression::List(items) => f.debug_tuple("List").field(&items.iter().map(|&item| tree(item)).collect::<Vec<_>>()).finish(),
+            Expression::Target { count, up_to, single, target_type } => f.debug_struct("Target")
+                .field("count", &tree(*count))
+                .field("up_to", up_to)
+                .field("single", single)
+                .field("target_type", target_type)
+                .finish(),
+            Expression::Interpolation(parts) => {
+                let mut list = f.debug_list();
+                for part in parts {
+                    match part {
+                        InterpolationPart::Text(text) => list.entry(text),
+                        InterpolationPart::Expression(expression) => list.entry(&tree(*expression))
+                    };
+                }
+                list.finish()
+            },
+            Expression::Property { owner, property } => f.debug_struct("Property")
+                .field("owner", &tree(*owner))
+                .field("property", property)
+                .finish(),
+            Expression::Call { func, args } => f.debug_struct("Call")
+                .field("func", &self.ast[*func].name)
+                .field("args", &args.iter().map(|&arg| tree(arg)).collect::<Vec<_>>())
+                .finish()
+        }
+    }
+}
+
+pub struct StatementTree<'a> {
+    ast: &'a Ast,
+    id: StatementId
+}
+
+impl Debug for StatementTree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tree = |id| self.ast.expression_tree(id);
+        let block = |body: &[StatementId]| body.iter().map(|&statement| self.ast.statement_tree(statement)).collect::<Vec<_>>();
+        match &self.ast[self.id] {
+            Statement::Assignment { name, value, declares } => f.debug_struct("Assignment")
+                .field("name", name)
+                .field("value", &tree(*value))
+                .field("declares", declares)
+                .finish(),
+            Statement::Damage { amount, damage_type, target } => f.debug_struct("Damage")
+                .field("amount", &tree(*amount))
+                .field("damage_type", damage_type)
+                .field("target", &tree(*target))
+                .finish(),
+            Statement::Status { status, turns, target } => f.debug_struct("Status")
+                .field("status", status)
+                .field("turns", &tree(*turns))
+                .field("target", &tree(*target))
+                .finish(),
+            Statement::Modifier { value, operation, amount, target } => f.debug_struct("Modifier")
+                .field("value", value)
+                .field("operation", operation)
+                .field("amount", &tree(*amount))
+                .field("target", &target.map(tree))
+                .finish(),
+            Statement::Replacement { event, outcome, redirect, uses } => f.debug_struct("Replacement")
+                .field("event", event)
+                .field("outcome", outcome)
+                .field("redirect", &redirect.map(tree))
+                .field("uses", uses)
+                .finish(),
+            Statement::Log(message) => f.debug_tuple("Log").field(&tree(*message)).finish(),
+            Statement::Counter => write!(f, "Counter"),
+            Statement::If { condition, then_body, else_body } => f.debug_struct("If")
+                .field("condition", &tree(*condition))
+                .field("then_body", &block(then_body))
+                .field("else_body", &block(else_body))
+                .finish()
+        }
+    }
+}
\ No newline at end of file
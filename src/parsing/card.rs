@@ -0,0 +1,168 @@
+use std::rc::Rc;
+
+use crate::game_zones::types::TriggerEvent;
+
+use super::{ast::{Ast, ExpressionId, StatementId}, optimizer, script_context::ScriptContext, symbol_table::SymbolTable};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Legendary
+}
+
+#[derive(Debug)]
+pub struct RarityParseError;
+
+impl TryFrom<&str> for Rarity {
+    type Error = RarityParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "common" => Ok(Rarity::Common),
+            "uncommon" => Ok(Rarity::Uncommon),
+            "rare" => Ok(Rarity::Rare),
+            "legendary" => Ok(Rarity::Legendary),
+            _ => Err(RarityParseError)
+        }
+    }
+}
+
+impl std::fmt::Display for Rarity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rarity::Common => write!(f, "common"),
+            Rarity::Uncommon => write!(f, "uncommon"),
+            Rarity::Rare => write!(f, "rare"),
+            Rarity::Legendary => write!(f, "legendary")
+        }
+    }
+}
+
+/// Everything about a card that isn't rules: `@name "Firebolt"`, `@rarity rare`, etc.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CardMetadata {
+    /// Human-readable name
+    pub name: Option<Rc<str>>,
+    /// Rules text as printed on the card
+    pub text: Option<Rc<str>>,
+    pub rarity: Rarity,
+    /// Code of the set the card belongs to (e.g. "BASE")
+    pub set_code: Option<Rc<str>>,
+    pub flavor: Option<Rc<str>>,
+    /// Designer notes from the `///` comments before the card (after any funcs)
+    pub doc: Option<Rc<str>>
+}
+
+/// `when play { ... }` after a card's body: what the card does each time the event happens while it's in play
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggeredAbility {
+    pub event: TriggerEvent,
+    pub body: Vec<StatementId>
+}
+
+/// A parsed card script: `func... @metadata... #tag [cost]: { body } when event { ability }...`.
+/// The funcs, cost and bodies are nodes of the card's own `Ast`.
+#[derive(Clone)]
+pub struct Card {
+    metadata: CardMetadata,
+    tags: Vec<Rc<str>>,
+    ast: Ast,
+    cost: ExpressionId,
+    body: Vec<StatementId>,
+    abilities: Vec<TriggeredAbility>
+}
+
+impl Card {
+    pub fn new(metadata: CardMetadata, tags: Vec<Rc<str>>, ast: Ast, cost: ExpressionId, body: Vec<StatementId>, abilities: Vec<TriggeredAbility>) -> Self {
+        Card { metadata, tags, ast, cost, body, abilities }
+    }
+
+    pub fn get_metadata(&self) -> &CardMetadata {
+        &self.metadata
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.metadata.name.as_deref()
+    }
+
+    pub fn get_doc(&self) -> Option<&str> {
+        self.metadata.doc.as_deref()
+    }
+
+    pub fn get_tags(&self) -> &[Rc<str>] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.as_ref() == tag)
+    }
+
+    pub fn get_ast(&self) -> &Ast {
+        &self.ast
+    }
+
+    /// Cost expression; may depend on the game (e.g. `[3 - $discount]`)
+    pub fn get_cost(&self) -> ExpressionId {
+        self.cost
+    }
+
+    pub fn get_body(&self) -> &[StatementId] {
+        &self.body
+    }
+
+    /// Triggered abilities, in the order they were written
+    pub fn get_abilities(&self) -> &[TriggeredAbility] {
+        &self.abilities
+    }
+
+    pub fn evaluate_cost(&self, symbol_table: &SymbolTable, context: &mut dyn ScriptContext) -> i32 {
+        self.ast.evaluate_integer(self.cost, symbol_table, context)
+    }
+
+    /// Run the body in its own scope
+    pub fn execute(&self, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        self.ast.execute_block(&self.body, symbol_table, context);
+    }
+
+    /// Run one of the triggered abilities in its own scope; nothing happens when there's no such ability
+    pub fn execute_ability(&self, ability: usize, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        if let Some(ability) = self.abilities.get(ability) {
+            self.ast.execute_block(&ability.body, symbol_table, context);
+        }
+    }
+
+    /// The same card with its funcs, cost, body and abilities simplified, to play rather than to read
+    pub fn simplified(mut self) -> Card {
+        for func in self.ast.func_ids().collect::<Vec<_>>() {
+            let body = self.ast[func].body;
+            let body = optimizer::simplify(&mut self.ast, body);
+            self.ast.replace_func_body(func, body);
+        }
+        let cost = optimizer::simplify(&mut self.ast, self.cost);
+        let body = optimizer::simplify_block(&mut self.ast, self.body);
+        let abilities = std::mem::take(&mut self.abilities).into_iter()
+            .map(|ability| TriggeredAbility { event: ability.event, body: optimizer::simplify_block(&mut self.ast, ability.body) })
+            .collect();
+        Card { cost, body, abilities, ..self }
+    }
+}
+
+impl std::fmt::Debug for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Card")
+            .field("funcs", &self.ast.func_ids()
+                .map(|func| (&self.ast[func].name, &self.ast[func].params, self.ast.expression_tree(self.ast[func].body), &self.ast[func].doc))
+                .collect::<Vec<_>>())
+            .field("metadata", &self.metadata)
+            .field("tags", &self.tags)
+            .field("cost", &self.ast.expression_tree(self.cost))
+            .field("body", &self.body.iter().map(|&statement| self.ast.statement_tree(statement)).collect::<Vec<_>>())
+            .field("abilities", &self.abilities.iter()
+                .map(|ability| (ability.event, ability.body.iter().map(|&statement| self.ast.statement_tree(statement)).collect::<Vec<_>>()))
+                .collect::<Vec<_>>())
+            .finish()
+    }
+}
\ No newline at end of file
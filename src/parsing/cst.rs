@@ -0,0 +1,414 @@
+use std::fmt::Display;
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+
+use super::card::Card;
+use super::parser::{parse_card_located, ParseError};
+use super::tokenizer::{tokenize_with_comments, Span, TokenizerError};
+use super::tokens::{Token, Tokens};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and carriage returns
+    Whitespace,
+    /// A single `\n`
+    Newline,
+    /// `//` up to the end of its line (not including the newline), or a whole `/* */` block
+    Comment
+}
+
+/// Source text between tokens that means nothing to the parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String
+}
+
+/// A token along with its source text and the trivia around it.
+/// A token's trailing trivia runs up to (not including) the next newline; everything after that
+/// leads the next token. Whatever follows the last token leads `EOF`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstToken {
+    pub token: Tokens,
+    pub text: String,
+    pub span: Span,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>
+}
+
+impl CstToken {
+    /// Its own text, without the trivia
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(&self.token, Tokens::Symbol(s) if s.as_str() == symbol)
+    }
+}
+
+/// The token and its trivia, exactly as they were in the source
+impl Display for CstToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.leading {
+            write!(f, "{}", trivia.text)?;
+        }
+        write!(f, "{}", self.text)?;
+        for trivia in &self.trailing {
+            write!(f, "{}", trivia.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tokenize, keeping everything in between as trivia so the tokens can be written back out unchanged
+pub fn tokenize_lossless(script: &str, damage_types: &DamageTypeRegistry) -> Result<Vec<CstToken>, (TokenizerError, Span)> {
+    let mut tokens: Vec<CstToken> = vec![ ];
+    let mut pending = vec![ ];
+    let mut previous_end = 0;
+    for (token, span) in tokenize_with_comments(script, damage_types)? {
+        push_white_space(&script[previous_end .. span.start], &mut pending);
+        previous_end = span.end;
+        if let Tokens::Comment = token {
+            pending.push(Trivia { kind: TriviaKind::Comment, text: script[span.start .. span.end].to_string() });
+            continue;
+        }
+
+        // up to the first newline trails the previous token
+        let split = match tokens.last() {
+            Some(_) => pending.iter().position(|trivia| trivia.kind == TriviaKind::Newline).unwrap_or(pending.len()),
+            None => 0
+        };
+        let leading = pending.split_off(split);
+        if let Some(previous) = tokens.last_mut() {
+            previous.trailing = std::mem::take(&mut pending);
+        }
+        tokens.push(CstToken { token, text: script[span.start .. span.end].to_string(), span, leading, trailing: vec![ ] });
+    }
+    Ok(tokens)
+}
+
+fn push_white_space(gap: &str, trivia: &mut Vec<Trivia>) {
+    for (index, line) in gap.split('\n').enumerate() {
+        if index > 0 {
+            trivia.push(Trivia { kind: TriviaKind::Newline, text: String::from("\n") });
+        }
+        if !line.is_empty() {
+            trivia.push(Trivia { kind: TriviaKind::Whitespace, text: line.to_string() });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The whole script, ending with `EOF`
+    Card,
+    /// `func name(...) = ...;` before the card, with its doc comments
+    Func,
+    /// `///` comments before the card, or at the start of a `Func`
+    Doc,
+    /// `@key value`
+    Metadata,
+    /// `#tag`
+    Tag,
+    /// `[ ... ]` before the body
+    Cost,
+    /// `{ ... }`
+    Block,
+    /// `when event { ... }` after the body
+    Ability,
+    /// Everything up to and including its `;`
+    Statement,
+    /// `if condition { ... }`, with its `else` and what follows it
+    If,
+    /// `( ... )` or `[ ... ]` inside a statement
+    Group,
+    /// Tokens that don't fit anywhere, such as after the card's closing brace
+    Error
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CstElement {
+    Node(CstNode),
+    Token(CstToken)
+}
+
+/// Concrete syntax tree: every token of the script, trivia included, grouped by structure.
+/// Building one never fails, so even a script that doesn't parse can be written back out byte for byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstNode {
+    kind: NodeKind,
+    children: Vec<CstElement>
+}
+
+impl CstNode {
+    pub fn get_kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn get_children(&self) -> &[CstElement] {
+        &self.children
+    }
+
+    /// Child nodes of a kind, in order
+    pub fn get_nodes(&self, kind: NodeKind) -> Vec<&CstNode> {
+        self.children.iter()
+            .filter_map(|child| match child {
+                CstElement::Node(node) if node.kind == kind => Some(node),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Every token under the node, in source order
+    pub fn tokens(&self) -> Vec<&CstToken> {
+        let mut tokens = vec![ ];
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a CstToken>) {
+        for child in &self.children {
+            match child {
+                CstElement::Node(node) => node.collect_tokens(tokens),
+                CstElement::Token(token) => tokens.push(token)
+            }
+        }
+    }
+
+    /// Source the node covers, without the trivia before its first token and after its last
+    pub fn get_span(&self) -> Span {
+        let tokens = self.tokens();
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => Span { start: first.span.start, end: last.span.end },
+            _ => Span::default()
+        }
+    }
+
+    /// Parse the card the tree holds (the AST is made from the CST's tokens, trivia aside).
+    /// On failure, the index is of the token the parser stopped at, as in `tokens()`.
+    pub fn to_card(&self) -> Result<Card, (ParseError, usize)> {
+        parse_card_located(self.tokens().into_iter().map(|token| token.token.clone()))
+    }
+}
+
+/// The exact source text of the node, trivia included
+impl Display for CstNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for child in &self.children {
+            match child {
+                CstElement::Node(node) => write!(f, "{}", node)?,
+                CstElement::Token(token) => write!(f, "{}", token)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tokenize a script and build its concrete syntax tree (a `Card` node)
+pub fn parse_cst(script: &str, damage_types: &DamageTypeRegistry) -> Result<CstNode, (TokenizerError, Span)> {
+    let tokens = tokenize_lossless(script, damage_types)?;
+    Ok(build_cst(tokens))
+}
+
+/// Group tokens from `tokenize_lossless` into a `Card` node
+pub fn build_cst(tokens: Vec<CstToken>) -> CstNode {
+    let mut builder = Builder { tokens: tokens.into_iter().peekable() };
+    builder.card()
+}
+
+struct Builder {
+    tokens: std::iter::Peekable<std::vec::IntoIter<CstToken>>
+}
+
+impl Builder {
+    fn peek_is(&mut self, symbol: &str) -> bool {
+        self.tokens.peek().is_some_and(|token| token.is_symbol(symbol))
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.tokens.peek().is_none_or(|token| matches!(token.token, Tokens::EOF))
+    }
+
+    /// Take the next token into `children`, unless there's nothing left but `EOF`
+    fn take(&mut self, children: &mut Vec<CstElement>) -> bool {
+        if self.at_end() {
+            return false;
+        }
+        children.extend(self.tokens.next().map(CstElement::Token));
+        true
+    }
+
+    /// Whether the next token starts a part of the header, so can't be part of the metadata or tag before it
+    fn at_header_item(&mut self) -> bool {
+        ["@", "#", "[", "{"].iter().any(|&symbol| self.peek_is(symbol)) || self.at_end()
+    }
+
+    /// Whether the next token ends the expression it would otherwise be part of
+    fn at_expression_end(&mut self) -> bool {
+        ["{", "}", ";"].iter().any(|&symbol| self.peek_is(symbol)) || self.at_end()
+    }
+
+    fn card(&mut self) -> CstNode {
+        let mut children = vec![ ];
+        let mut doc = self.doc();
+        while self.peek_is("func") {
+            children.push(CstElement::Node(self.func(doc)));
+            doc = self.doc();
+        }
+        children.extend(doc.map(CstElement::Node));
+        while self.peek_is("@") || self.peek_is("#") {
+            let kind = if self.peek_is("@") { NodeKind::Metadata } else { NodeKind::Tag };
+            let mut item = vec![ ];
+            self.take(&mut item);
+            // `@key value` or `#tag`
+            for _ in 0 .. if kind == NodeKind::Metadata { 2 } else { 1 } {
+                if !self.at_header_item() {
+                    self.take(&mut item);
+                }
+            }
+            children.push(CstElement::Node(CstNode { kind, children: item }));
+        }
+        if self.peek_is("[") {
+            let mut cost = self.group();
+            cost.kind = NodeKind::Cost;
+            children.push(CstElement::Node(cost));
+        }
+        // `:`, and whatever is out of place before the body
+        while !self.at_end() && !self.peek_is("{") {
+            self.take(&mut children);
+        }
+        if self.peek_is("{") {
+            children.push(CstElement::Node(self.block()));
+        }
+        while self.peek_is("when") {
+            children.push(CstElement::Node(self.ability()));
+        }
+        let mut rest = vec![ ];
+        while self.take(&mut rest) { }
+        if !rest.is_empty() {
+            children.push(CstElement::Node(CstNode { kind: NodeKind::Error, children: rest }));
+        }
+        children.extend(self.tokens.next().map(CstElement::Token));
+        CstNode { kind: NodeKind::Card, children }
+    }
+
+    /// `///` comments, if there are any
+    fn doc(&mut self) -> Option<CstNode> {
+        let mut children = vec![ ];
+        while self.tokens.peek().is_some_and(|token| matches!(token.token, Tokens::DocComment(_))) {
+            self.take(&mut children);
+        }
+        if children.is_empty() {
+            return None;
+        }
+        Some(CstNode { kind: NodeKind::Doc, children })
+    }
+
+    /// The doc comments, then `func` up to and including its `;` (or up to wherever the header or body starts if there isn't one)
+    fn func(&mut self, doc: Option<CstNode>) -> CstNode {
+        let mut children: Vec<CstElement> = doc.into_iter().map(CstElement::Node).collect();
+        self.take(&mut children);
+        while !self.at_expression_end() && !self.peek_is("@") && !self.peek_is("#") {
+            if self.peek_is("(") || self.peek_is("[") {
+                children.push(CstElement::Node(self.group()));
+            } else {
+                self.take(&mut children);
+            }
+        }
+        if self.peek_is(";") {
+            self.take(&mut children);
+        }
+        CstNode { kind: NodeKind::Func, children }
+    }
+
+    /// `when`, the event, and its block (if there is one)
+    fn ability(&mut self) -> CstNode {
+        let mut children = vec![ ];
+        self.take(&mut children);
+        while !self.at_expression_end() {
+            self.take(&mut children);
+        }
+        if self.peek_is("{") {
+            children.push(CstElement::Node(self.block()));
+        }
+        CstNode { kind: NodeKind::Ability, children }
+    }
+
+    /// `{`, its statements, and `}` (if there is one)
+    fn block(&mut self) -> CstNode {
+        let mut children = vec![ ];
+        self.take(&mut children);
+        while !self.at_end() {
+            if self.peek_is("}") {
+                self.take(&mut children);
+                break;
+            }
+            children.push(CstElement::Node(self.statement()));
+        }
+        CstNode { kind: NodeKind::Block, children }
+    }
+
+    fn statement(&mut self) -> CstNode {
+        if self.peek_is("if") {
+            return self.if_statement();
+        }
+        let mut children = vec![ ];
+        while !self.at_end() && !self.peek_is("}") {
+            if self.peek_is("(") || self.peek_is("[") {
+                children.push(CstElement::Node(self.group()));
+            } else if self.peek_is("{") {
+                children.push(CstElement::Node(self.block()));
+            } else if self.peek_is(";") {
+                self.take(&mut children);
+                break;
+            } else {
+                self.take(&mut children);
+            }
+        }
+        CstNode { kind: NodeKind::Statement, children }
+    }
+
+    fn if_statement(&mut self) -> CstNode {
+        let mut children = vec![ ];
+        self.take(&mut children);
+        while !self.at_expression_end() {
+            if self.peek_is("(") || self.peek_is("[") {
+                children.push(CstElement::Node(self.group()));
+            } else {
+                self.take(&mut children);
+            }
+        }
+        if self.peek_is("{") {
+            children.push(CstElement::Node(self.block()));
+            if self.peek_is("else") {
+                self.take(&mut children);
+                if self.peek_is("if") {
+                    children.push(CstElement::Node(self.if_statement()));
+                } else if self.peek_is("{") {
+                    children.push(CstElement::Node(self.block()));
+                }
+            }
+        }
+        CstNode { kind: NodeKind::If, children }
+    }
+
+    /// `(` or `[`, up to the bracket that closes it (or where the statement ends, if none does)
+    fn group(&mut self) -> CstNode {
+        let close = if self.peek_is("(") { ")" } else { "]" };
+        let mut children = vec![ ];
+        self.take(&mut children);
+        while !self.at_expression_end() {
+            if self.peek_is(close) {
+                self.take(&mut children);
+                break;
+            }
+            if self.peek_is("(") || self.peek_is("[") {
+                children.push(CstElement::Node(self.group()));
+            } else {
+                self.take(&mut children);
+            }
+        }
+        CstNode { kind: NodeKind::Group, children }
+    }
+}
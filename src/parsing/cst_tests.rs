@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::parsing::cst::{parse_cst, tokenize_lossless, CstElement, NodeKind, TriviaKind};
+    use crate::parsing::parser::{parse_card, ParseError};
+    use crate::parsing::tokenizer::tokenize;
+    use test_case::test_case;
+
+    #[test_case("@name \"Zap\" [1]: { 1 fire => target(1 in Player); }" ; "one line")]
+    #[test_case("// header\n@name \"Zap\"   // the name\n\t#attack\r\n[ 1 ]:{\n\n  $x = 2d6 + 1; // roll\n  if $x > 8 { log \"big {$x}\"; } else if true { } else { }\n}\n\n// the end" ; "comments and white space")]
+    #[test_case("/// Notes\n/// more\n[1]: { /* a /* nested */\n block */ log \"hi\"; }" ; "doc and block comments")]
+    #[test_case("#aura [0]: { }\nwhen play { log \"again\"; } // echo\nwhen" ; "abilities")]
+    #[test_case("func f($x: integer) = [ $x ];\n\nfunc g( ) = f(1) // one\n[g()]: { }" ; "funcs")]
+    #[test_case("" ; "empty")]
+    #[test_case("   \n// nothing but trivia\n" ; "only trivia")]
+    #[test_case("[1]: { 2 fire => ; } } stray (" ; "does not parse")]
+    #[test_case("@name [1: { if { ( ] ; " ; "unbalanced")]
+    fn round_trip(source: &str) {
+        let cst = parse_cst(source, &DamageTypeRegistry::default()).unwrap();
+        assert_eq!(cst.to_string(), source);
+        assert_eq!(cst.get_kind(), NodeKind::Card);
+    }
+
+    #[test]
+    fn trivia_attaches_up_to_the_newline() {
+        let tokens = tokenize_lossless("// top\n$x = 1; // one\n  // two\n", &DamageTypeRegistry::default()).unwrap();
+
+        let dollar_x = &tokens[0];
+        assert_eq!(dollar_x.get_text(), "$x");
+        let kinds: Vec<TriviaKind> = dollar_x.leading.iter().map(|trivia| trivia.kind).collect();
+        assert_eq!(kinds, vec![ TriviaKind::Comment, TriviaKind::Newline ]);
+        assert_eq!(dollar_x.trailing[0].text, " ");
+
+        let semicolon = &tokens[3];
+        let trailing: Vec<&str> = semicolon.trailing.iter().map(|trivia| trivia.text.as_str()).collect();
+        assert_eq!(trailing, vec![ " ", "// one" ]);
+
+        let eof = tokens.last().unwrap();
+        let leading: Vec<&str> = eof.leading.iter().map(|trivia| trivia.text.as_str()).collect();
+        assert_eq!(leading, vec![ "\n", "  ", "// two", "\n" ]);
+        assert_eq!(eof.get_text(), "");
+    }
+
+    #[test]
+    fn tree_shape() {
+        let cst = parse_cst("@name \"Zap\" #attack [1]: { $x = (1 + 2); if $x > 2 { log \"hi\"; } else { } }", &DamageTypeRegistry::default()).unwrap();
+
+        assert_eq!(cst.get_nodes(NodeKind::Metadata).len(), 1);
+        assert_eq!(cst.get_nodes(NodeKind::Tag)[0].to_string(), "#attack ");
+        assert_eq!(cst.get_nodes(NodeKind::Cost)[0].to_string(), "[1]");
+
+        let block = cst.get_nodes(NodeKind::Block)[0];
+        let statements: Vec<NodeKind> = block.get_children().iter()
+            .filter_map(|child| match child {
+                CstElement::Node(node) => Some(node.get_kind()),
+                CstElement::Token(_) => None
+            })
+            .collect();
+        assert_eq!(statements, vec![ NodeKind::Statement, NodeKind::If ]);
+
+        let assignment = block.get_nodes(NodeKind::Statement)[0];
+        assert_eq!(assignment.get_nodes(NodeKind::Group)[0].to_string(), "(1 + 2)");
+        let span = assignment.get_span();
+        assert_eq!(&"@name \"Zap\" #attack [1]: { $x = (1 + 2); if"[span.start .. span.end], "$x = (1 + 2);");
+        assert_eq!(block.get_nodes(NodeKind::If)[0].get_nodes(NodeKind::Block).len(), 2);
+    }
+
+    #[test]
+    fn abilities_follow_the_body() {
+        let cst = parse_cst("#aura [1]: { } when turn start { log \"hi\"; } when play { }", &DamageTypeRegistry::default()).unwrap();
+
+        let abilities = cst.get_nodes(NodeKind::Ability);
+        assert_eq!(abilities.len(), 2);
+        assert_eq!(abilities[0].to_string(), "when turn start { log \"hi\"; } ");
+        assert_eq!(abilities[0].get_nodes(NodeKind::Block)[0].get_nodes(NodeKind::Statement).len(), 1);
+        assert!(cst.get_nodes(NodeKind::Error).is_empty());
+    }
+
+    #[test]
+    fn funcs_come_first() {
+        let cst = parse_cst("func half($x: integer) = ($x + 1) / 2; func one() = 1;\n@name \"Zap\" [half(2)]: { }", &DamageTypeRegistry::default()).unwrap();
+
+        let funcs = cst.get_nodes(NodeKind::Func);
+        assert_eq!(funcs.len(), 2);
+        assert_eq!(funcs[0].to_string(), "func half($x: integer) = ($x + 1) / 2; ");
+        assert_eq!(funcs[0].get_nodes(NodeKind::Group).len(), 2);
+        assert_eq!(cst.get_nodes(NodeKind::Metadata).len(), 1);
+        assert!(cst.to_card().is_ok());
+    }
+
+    #[test]
+    fn stray_tokens_are_kept() {
+        let cst = parse_cst("[1]: { } }", &DamageTypeRegistry::default()).unwrap();
+        assert_eq!(cst.get_nodes(NodeKind::Error)[0].to_string(), "}");
+    }
+
+    #[test]
+    fn ast_from_cst() {
+        let source = "@name \"Zap\" // comment\n[1]: {\n  $x = 2d6; // roll\n  $x fire => target(1 in Player);\n}\n";
+        let card = parse_cst(source, &DamageTypeRegistry::default()).unwrap().to_card().unwrap();
+        let expected = parse_card(tokenize(source).unwrap().into_iter()).unwrap();
+
+        assert_eq!(card.get_name(), expected.get_name());
+        assert_eq!(format!("{:?}", card), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn ast_errors_index_cst_tokens() {
+        let cst = parse_cst("[1]: {\n  // comment\n  2 fire => ; }", &DamageTypeRegistry::default()).unwrap();
+        let Err((ParseError::UnexpectedToken(_), index)) = cst.to_card() else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(cst.tokens()[index].get_text(), ";");
+    }
+
+    #[test]
+    fn doc_comments_are_tokens() {
+        let cst = parse_cst("/// Notes\n[1]: { /* block */ }", &DamageTypeRegistry::default()).unwrap();
+
+        assert_eq!(cst.get_nodes(NodeKind::Doc)[0].to_string(), "/// Notes");
+        let open = cst.get_nodes(NodeKind::Block)[0].tokens()[0];
+        let trailing: Vec<TriviaKind> = open.trailing.iter().map(|trivia| trivia.kind).collect();
+        assert_eq!(trailing, vec![ TriviaKind::Whitespace, TriviaKind::Comment, TriviaKind::Whitespace ]);
+        assert_eq!(cst.to_card().unwrap().get_doc(), Some("Notes"));
+    }
+
+    #[test]
+    fn doc_comments_go_with_their_func() {
+        let cst = parse_cst("/// Rounds up\nfunc half($x: integer) = $x / 2;\n/// Notes\n[1]: { }", &DamageTypeRegistry::default()).unwrap();
+
+        let func = cst.get_nodes(NodeKind::Func)[0];
+        assert_eq!(func.get_nodes(NodeKind::Doc)[0].to_string(), "/// Rounds up");
+        assert_eq!(cst.get_nodes(NodeKind::Doc)[0].to_string(), "\n/// Notes");
+        assert_eq!(cst.to_card().unwrap().get_doc(), Some("Notes"));
+    }
+}
@@ -0,0 +1,296 @@
+use std::fmt::Display;
+
+use crate::game_zones::damage_types::DamageTypeRegistry;
+
+use super::card::Card;
+use super::parser::{parse_card, ParseError};
+use super::tokenizer::{tokenize_with, tokenize_with_comments, Span, TokenizerError};
+use super::tokens::{Token, Tokens};
+
+/// Indentation for each level of braces
+pub const INDENT: &str = "    ";
+
+#[derive(Debug)]
+pub enum FormatError {
+    Tokenizer(TokenizerError),
+    /// Only scripts that parse get formatted
+    Parse(ParseError),
+    /// The formatted script would parse to something else; this is a bug in the formatter
+    ChangedMeaning
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::Tokenizer(err) => write!(f, "tokenizer error: {}", err),
+            FormatError::Parse(err) => write!(f, "parse error: {}", err),
+            FormatError::ChangedMeaning => write!(f, "formatting would change what the script does")
+        }
+    }
+}
+
+/// Reprint a card script in the canonical style, keeping its comments (`/* */` ones where they are on the line):
+/// - each `func` on its own line, with no space before the brackets of it or a call to it
+/// - each `@metadata` on its own line, then the tags, cost and opening brace on one line
+/// - one statement per line, indented by `INDENT` per level of braces (`{ }` when empty)
+/// - a space around binary operators and `=>`, after commas, and none inside brackets
+/// - at most one blank line in a row, and none at the start or end of a block
+///
+/// Formatting is idempotent, and the result always parses to the same card.
+pub fn format_script(source: &str, damage_types: &DamageTypeRegistry) -> Result<String, FormatError> {
+    let before = parse(source, damage_types)?;
+    let tokens = tokenize_with_comments(source, damage_types).map_err(|(err, _)| FormatError::Tokenizer(err))?;
+
+    let ast = before.get_ast();
+    let funcs = ast.func_ids().map(|func| ast[func].name.to_string()).collect();
+    let mut printer = Printer::new(source, funcs);
+    let mut index = 0;
+    while let Some((token, span)) = tokens.get(index) {
+        let next = tokens.get(index + 1).map(|(token, _)| token);
+        match token {
+            Tokens::EOF => break,
+            Tokens::Comment | Tokens::DocComment(_) => printer.comment(*span),
+            // an empty block goes on one line
+            Tokens::Symbol(symbol) if symbol.as_str() == "{" && is_symbol(next, "}") => {
+                let (_, close) = tokens[index + 1];
+                printer.token(token, *span);
+                printer.line.push_str(" }");
+                printer.previous = Some(tokens[index + 1].0.clone());
+                printer.previous_end = close.end;
+                printer.after_close(tokens.get(index + 2).map(|(token, _)| token));
+                index += 1;
+            },
+            _ => {
+                printer.token(token, *span);
+                match token {
+                    Tokens::Symbol(symbol) if symbol.as_str() == "{" => {
+                        printer.end_line();
+                        printer.indent += 1;
+                    },
+                    Tokens::Symbol(symbol) if symbol.as_str() == ";" => printer.end_line(),
+                    Tokens::Symbol(symbol) if symbol.as_str() == "}" => printer.after_close(next),
+                    _ => { }
+                }
+            }
+        }
+        index += 1;
+    }
+    printer.end_line();
+    let formatted = printer.output;
+
+    let after = parse(&formatted, damage_types)?;
+    if describe(&before) != describe(&after) {
+        return Err(FormatError::ChangedMeaning);
+    }
+    Ok(formatted)
+}
+
+fn parse(source: &str, damage_types: &DamageTypeRegistry) -> Result<Card, FormatError> {
+    let tokens = tokenize_with(source, damage_types).map_err(FormatError::Tokenizer)?;
+    parse_card(tokens.into_iter()).map_err(FormatError::Parse)
+}
+
+/// Everything the parser made of the card (two cards that describe the same are the same card)
+fn describe(card: &Card) -> String {
+    format!("{:?}", card)
+}
+
+fn is_symbol(token: Option<&Tokens>, symbol: &str) -> bool {
+    matches!(token, Some(Tokens::Symbol(s)) if s.as_str() == symbol)
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    /// Names of the script's funcs, which are called like `target(...)`
+    funcs: Vec<String>,
+    output: String,
+    /// The line being printed, without its indentation
+    line: String,
+    indent: usize,
+    /// Last token printed (comments aside)
+    previous: Option<Tokens>,
+    /// Whether that token was a unary operator
+    previous_unary: bool,
+    /// Where the last token or comment printed ends in the source
+    previous_end: usize,
+    /// Still before the cost
+    in_header: bool,
+    /// Between a `func` and its `;`, where brackets are part of the func rather than the cost
+    in_func: bool,
+    /// The last header line printed was metadata
+    after_metadata: bool
+}
+
+impl<'a> Printer<'a> {
+    fn new(source: &'a str, funcs: Vec<String>) -> Self {
+        Printer {
+            source,
+            funcs,
+            output: String::new(),
+            line: String::new(),
+            indent: 0,
+            previous: None,
+            previous_unary: false,
+            previous_end: 0,
+            in_header: true,
+            in_func: false,
+            after_metadata: false
+        }
+    }
+
+    fn token(&mut self, token: &Tokens, span: Span) {
+        let text = &self.source[span.start .. span.end];
+        let closes = is_symbol(Some(token), "}");
+        let header_line = self.in_header && match token {
+            Tokens::Symbol(symbol) if symbol.as_str() == "@" => true,
+            // tags and cost go on the line after the metadata
+            Tokens::Symbol(symbol) if matches!(symbol.as_str(), "#" | "[") => self.after_metadata,
+            _ => false
+        };
+        if header_line || closes {
+            self.end_line();
+        }
+        if header_line {
+            self.after_metadata = is_symbol(Some(token), "@");
+        }
+        if is_symbol(Some(token), "func") || is_symbol(Some(token), ";") {
+            self.in_func = is_symbol(Some(token), "func");
+        }
+        if self.in_header && !self.in_func && self.indent == 0 && is_symbol(Some(token), "[") {
+            self.in_header = false;
+        }
+        if closes {
+            self.indent = self.indent.saturating_sub(1);
+        }
+
+        if self.line.is_empty() {
+            self.keep_blank_line(span, token);
+            // a statement broken up by a comment carries on one level in
+            if self.is_continuing() && !header_line && !closes && !is_symbol(Some(token), "{") {
+                self.line.push_str(INDENT);
+            }
+        } else if !self.is_call(token) && needs_space(self.previous.as_ref(), self.previous_unary, token) {
+            self.line.push(' ');
+        }
+        self.line.push_str(text);
+
+        let is_operator = matches!(token, Tokens::Symbol(symbol) if matches!(symbol.as_str(), "-" | "~" | "^"));
+        self.previous_unary = is_operator && !self.previous.as_ref().is_some_and(ends_value);
+        self.previous = Some(token.clone());
+        self.previous_end = span.end;
+    }
+
+    /// The token is the `(` after the name of a func
+    fn is_call(&self, token: &Tokens) -> bool {
+        is_symbol(Some(token), "(") && matches!(&self.previous, Some(Tokens::Identifier(name)) if self.funcs.iter().any(|func| func == name.as_str()))
+    }
+
+    /// In the middle of a statement (or header line)
+    fn is_continuing(&self) -> bool {
+        self.previous.as_ref().is_some_and(|previous| !ends_statement(previous))
+    }
+
+    fn comment(&mut self, span: Span) {
+        let text = self.source[span.start .. span.end].trim_end();
+        let printed_anything = !self.line.is_empty() || !self.output.is_empty();
+        let trailing = printed_anything && !self.source[self.previous_end .. span.start].contains('\n');
+        if trailing && !self.line.is_empty() && text.starts_with("/*") {
+            // a block comment can sit in the middle of a line
+            self.line.push(' ');
+            self.line.push_str(text);
+            self.previous_end = span.end;
+            return;
+        }
+        if trailing && self.line.is_empty() {
+            // the line it trails (e.g. after a `;`) has already been printed
+            self.output.pop();
+            self.output.push(' ');
+            self.output.push_str(text);
+            self.output.push('\n');
+            self.previous_end = span.end;
+            return;
+        }
+        if trailing {
+            self.line.push(' ');
+            self.line.push_str(text);
+        } else {
+            self.end_line();
+            self.keep_blank_line(span, &Tokens::Comment);
+            if self.is_continuing() && self.indent > 0 {
+                self.line.push_str(INDENT);
+            }
+            self.line.push_str(text);
+        }
+        self.previous_end = span.end;
+        self.end_line();
+    }
+
+    /// After a `}`: `else` carries on the same line, anything else starts a new one
+    fn after_close(&mut self, next: Option<&Tokens>) {
+        if !is_symbol(next, "else") {
+            self.end_line();
+        }
+    }
+
+    /// One blank line where the source had at least one, except at the start or end of a block
+    fn keep_blank_line(&mut self, span: Span, token: &Tokens) {
+        let gap = &self.source[self.previous_end.min(span.start) .. span.start];
+        let opens_block = is_symbol(self.previous.as_ref(), "{");
+        if gap.matches('\n').count() >= 2 && !self.output.is_empty() && !opens_block && !is_symbol(Some(token), "}") && !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+        for _ in 0 .. self.indent {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(self.line.trim_end());
+        self.output.push('\n');
+        self.line.clear();
+    }
+}
+
+/// Tokens after which a new line is a new statement (or header line) rather than the same one carrying on
+fn ends_statement(token: &Tokens) -> bool {
+    match token {
+        Tokens::Symbol(symbol) => matches!(symbol.as_str(), ";" | "{" | "}"),
+        _ => false
+    }
+}
+
+/// Tokens that end a value, so an operator after one is binary
+fn ends_value(token: &Tokens) -> bool {
+    match token {
+        Tokens::Symbol(symbol) => matches!(symbol.as_str(), ")" | "]"),
+        Tokens::Comment | Tokens::EOF => false,
+        _ => true
+    }
+}
+
+fn needs_space(previous: Option<&Tokens>, previous_unary: bool, token: &Tokens) -> bool {
+    let Some(previous) = previous else {
+        return false;
+    };
+    let symbol = |token: &Tokens| match token {
+        Tokens::Symbol(symbol) => Some(symbol.as_str().to_string()),
+        _ => None
+    };
+    let (before, after) = (symbol(previous), symbol(token));
+
+    // `2d6` is a count right up against the dice
+    if matches!((previous, token), (Tokens::Numeric(_), Tokens::Dice(_))) {
+        return false;
+    }
+    if matches!(after.as_deref(), Some(")" | "]" | "," | ";" | "." | ":")) {
+        return false;
+    }
+    if previous_unary || matches!(before.as_deref(), Some("(" | "[" | "." | "@" | "#")) {
+        return false;
+    }
+    // `target(...)` reads like a call
+    !(after.as_deref() == Some("(") && before.as_deref() == Some("target"))
+}
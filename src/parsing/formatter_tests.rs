@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use crate::game_zones::damage_types::DamageTypeRegistry;
+    use crate::parsing::formatter::{format_script, FormatError};
+    use test_case::test_case;
+
+    fn format(source: &str) -> String {
+        format_script(source, &DamageTypeRegistry::default()).unwrap()
+    }
+
+    #[test]
+    fn canonical_style() {
+        let source = "@name \"Fireball\"   @rarity rare #attack #fire [ 3-$discount ]:{$x=2d6+1;if $x>8{$x fire=>target(1 in Player);}else{log \"fizzle\";}\n modify damage-1=>target(^2 in Player);}";
+        assert_eq!(format(source), "\
+@name \"Fireball\"
+@rarity rare
+#attack #fire [3 - $discount]: {
+    $x = 2d6 + 1;
+    if $x > 8 {
+        $x fire => target(1 in Player);
+    } else {
+        log \"fizzle\";
+    }
+    modify damage - 1 => target(^2 in Player);
+}
+");
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let source = "// a header comment\n@name \"Zap\" // the name\n[1]: {\n\n\n  // deal some damage\n  2 fire // hot\n  => target(1 in Player);\n\n  $x = -1; // negative\n}\n// trailing\n";
+        assert_eq!(format(source), "\
+// a header comment
+@name \"Zap\" // the name
+[1]: {
+    // deal some damage
+    2 fire // hot
+        => target(1 in Player);
+
+    $x = -1; // negative
+}
+// trailing
+");
+    }
+
+    #[test_case("[1]: { }" ; "Empty body")]
+    #[test_case("[1]:{if true{}else if false{ } }" ; "Empty blocks")]
+    #[test_case("[2*(1+1)]: { $l = [fire, ice] +! [fire]; $b = ~true == false & 3 >= -2 | $turn ~= 1; $n = -(target(1 in Player).hp - -1) / 2; }" ; "Operators")]
+    #[test_case("@text \"Deals \\{2\\} damage\"\n#a [1]: { log \"{$turn + 1} turns\\n\"; apply burning 2 => target(1 in Player); instead next 2 fire damage prevent; }" ; "Strings and statements")]
+    #[test_case("[1]: { modify hp = -2; modify damage taken * 2; counter; }" ; "Modifiers")]
+    #[test_case("[1]: // why\n// more\n{ 2 // two\n// dice\nfire => target(1 // one\n in Player); }" ; "Comments everywhere")]
+    #[test_case("func f($x: list of damage type) = $x + fire; [1]: { log \"{f([ice])}\"; }" ; "Funcs")]
+    #[test_case("/// Adds fire\nfunc f($x: list of damage type) = $x + fire;\n/// The card\n[1]: { log \"{f([ice])}\"; }" ; "Documented funcs")]
+    #[test_case("/// Notes\n/// more\n@name \"Zap\" [1]: { 2 /* two /* nested */ */ fire => target(1 in Player); /* after */ }" ; "Doc and block comments")]
+    fn idempotent(source: &str) {
+        let once = format(source);
+        assert_eq!(format(&once), once);
+    }
+
+    #[test]
+    fn keeps_doc_and_block_comments() {
+        let source = "///  Notes\n@name \"Zap\" [1]:{2 /* two */ fire=>target(1 in Player);\n/* own\n   line */\nlog \"hi\";}";
+        assert_eq!(format(source), "\
+///  Notes
+@name \"Zap\"
+[1]: {
+    2 /* two */ fire => target(1 in Player);
+    /* own
+   line */
+    log \"hi\";
+}
+");
+    }
+
+    #[test]
+    fn abilities_start_their_own_line() {
+        let source = "#aura [1]:{ } when play{log \"echo\";}when   turn start { }";
+        assert_eq!(format(source), "\
+#aura [1]: { }
+when play {
+    log \"echo\";
+}
+when turn start { }
+");
+    }
+
+    #[test]
+    fn funcs_go_before_the_card() {
+        let source = "func half ( $amount:integer , $up : boolean )=( $amount+1 )/2 ; func pair($x:integer)=[ $x , $x ] ;\n\n@name \"Zap\" [half ( 1,true )]:{apply burning (1) => target(1 in Player);}";
+        assert_eq!(format(source), "\
+func half($amount: integer, $up: boolean) = ($amount + 1) / 2;
+func pair($x: integer) = [$x, $x];
+
+@name \"Zap\"
+[half(1, true)]: {
+    apply burning (1) => target(1 in Player);
+}
+");
+    }
+
+    #[test]
+    fn empty_blocks_stay_on_one_line() {
+        assert_eq!(format("[1]:{if true{}else{ } }"), "[1]: {\n    if true { } else { }\n}\n");
+    }
+
+    #[test]
+    fn rejects_broken_scripts() {
+        assert!(matches!(format_script("[1]: { log \"oops; }", &DamageTypeRegistry::default()), Err(FormatError::Tokenizer(_))));
+        assert!(matches!(format_script("[1]: { 2 fire => ; }", &DamageTypeRegistry::default()), Err(FormatError::Parse(_))));
+    }
+}
@@ -0,0 +1,227 @@
+use crate::game_zones::types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+
+use super::ast::{Ast, ExpressionId, StatementId};
+use super::expressions::{BinaryOperator, Expression, ExpressionResult, ExpressionType, InterpolationPart, UnaryOperator};
+use super::script_context::ScriptContext;
+use super::statements::Statement;
+use super::symbol_table::SymbolTable;
+
+/// The value of an expression that comes out the same every time: literals, and operations on them.
+/// Anything that reads a variable, calls a func, rolls dice, picks targets or looks at the game is left alone,
+/// and so is integer arithmetic that would overflow.
+pub fn constant_value(ast: &Ast, id: ExpressionId) -> Option<ExpressionResult> {
+    match &ast[id] {
+        Expression::Literal(value) => return Some(value.clone()),
+        // `false & ...` and `true | ...` don't depend on what's on the right
+        &Expression::Binary(operator @ (BinaryOperator::And | BinaryOperator::Or), left, right) => {
+            return match constant_value(ast, left)? {
+                ExpressionResult::Boolean(left) if left == (operator == BinaryOperator::Or) => Some(ExpressionResult::Boolean(left)),
+                _ => constant_value(ast, right)
+            };
+        },
+        Expression::Variable(_) | Expression::Roll { .. } | Expression::Target { .. } | Expression::Property { .. } | Expression::Call { .. } => return None,
+        Expression::Binary(..) | Expression::Unary(..) | Expression::List(_) | Expression::Interpolation(_) => { }
+    }
+    let values = ast.get_operands(id).map(|operand| constant_value(ast, operand)).collect::<Option<Vec<ExpressionResult>>>()?;
+    let integer = ExpressionResult::Integer;
+    match (&ast[id], values.as_slice()) {
+        (Expression::Binary(BinaryOperator::Add, ..), &[ ExpressionResult::Integer(l), ExpressionResult::Integer(r) ]) => l.checked_add(r).map(integer),
+        (Expression::Binary(BinaryOperator::Subtract, ..), &[ ExpressionResult::Integer(l), ExpressionResult::Integer(r) ]) => l.checked_sub(r).map(integer),
+        (Expression::Binary(BinaryOperator::Multiply, ..), &[ ExpressionResult::Integer(l), ExpressionResult::Integer(r) ]) => l.checked_mul(r).map(integer),
+        (Expression::Unary(UnaryOperator::Negate, _), &[ ExpressionResult::Integer(right) ]) => right.checked_neg().map(integer),
+        _ => Some(ast.evaluate(id, &SymbolTable::new(), &mut ConstantContext))
+    }
+}
+
+/// Whether evaluating the expression can be skipped without anyone noticing: it rolls no dice and picks no targets,
+/// and neither do the funcs it calls
+pub fn is_pure(ast: &Ast, id: ExpressionId) -> bool {
+    let pure = match ast[id] {
+        Expression::Roll { .. } | Expression::Target { .. } => false,
+        Expression::Call { func, .. } => is_pure(ast, ast[func].body),
+        _ => true
+    };
+    pure && ast.get_operands(id).all(|operand| is_pure(ast, operand))
+}
+
+/// Turn an expression with a constant value into a literal; any other expression is left as it was
+pub fn fold(ast: &mut Ast, id: ExpressionId) {
+    if ast.as_literal(id).is_some() {
+        return;
+    }
+    match constant_value(ast, id) {
+        // an empty list doesn't know what it holds, so it keeps the type the parser worked out
+        Some(value) if value.get_type() == *ast.get_type(id) => ast.replace_expression(id, Expression::Literal(value)),
+        _ => { }
+    }
+}
+
+/// The same expression with constants folded and identities like `x + 0` removed, bottom up.
+/// Gives the id of whatever now stands for the expression: the same node, rewritten in place, or one of its operands.
+/// Operands that were simplified away stay in the `Ast`, where nothing refers to them.
+pub fn simplify(ast: &mut Ast, id: ExpressionId) -> ExpressionId {
+    // operands only point at nodes added before them, so none of them looks at this one while it's taken out
+    let simplified = match ast.take_expression(id) {
+        Expression::Binary(operator, left, right) => {
+            let (left, right) = (simplify(ast, left), simplify(ast, right));
+            if let Some(shortcut) = simplify_binary(ast, operator, left, right) {
+                return shortcut;
+            }
+            Expression::Binary(operator, left, right)
+        },
+        Expression::Unary(operator, operand) => Expression::Unary(operator, simplify(ast, operand)),
+        Expression::List(items) => Expression::List(items.into_iter().map(|item| simplify(ast, item)).collect()),
+        Expression::Target { count, up_to, single, target_type } => Expression::Target { count: simplify(ast, count), up_to, single, target_type },
+        Expression::Interpolation(parts) => Expression::Interpolation(simplify_interpolation(ast, parts)),
+        Expression::Property { owner, property } => Expression::Property { owner: simplify(ast, owner), property },
+        Expression::Call { func, args } => Expression::Call { func, args: args.into_iter().map(|arg| simplify(ast, arg)).collect() },
+        leaf @ (Expression::Literal(_) | Expression::Variable(_) | Expression::Roll { .. }) => {
+            ast.replace_expression(id, leaf);
+            return id;
+        }
+    };
+    ast.replace_expression(id, simplified);
+    fold(ast, id);
+    id
+}
+
+/// The operand a binary operation comes down to, if it's an identity or is bound to be zero
+fn simplify_binary(ast: &Ast, operator: BinaryOperator, left: ExpressionId, right: ExpressionId) -> Option<ExpressionId> {
+    let is_integer = |id: ExpressionId, value: i32| ast.as_literal(id) == Some(&ExpressionResult::Integer(value));
+    let is_boolean = |id: ExpressionId, value: bool| ast.as_literal(id) == Some(&ExpressionResult::Boolean(value));
+    match operator {
+        BinaryOperator::Add | BinaryOperator::Subtract if is_integer(right, 0) => Some(left),
+        BinaryOperator::Add if is_integer(left, 0) => Some(right),
+        BinaryOperator::Multiply | BinaryOperator::Divide if is_integer(right, 1) => Some(left),
+        BinaryOperator::Multiply if is_integer(left, 1) => Some(right),
+        // times zero, zero divided by anything and anything divided by zero all come out as zero,
+        // but the other side still has to be evaluated if it rolls dice or picks targets
+        BinaryOperator::Multiply | BinaryOperator::Divide if is_pure(ast, left) && is_pure(ast, right) => {
+            [ left, right ].into_iter().find(|&operand| is_integer(operand, 0))
+        },
+        BinaryOperator::And | BinaryOperator::Or => {
+            let is_and = operator == BinaryOperator::And;
+            // `true & x`, `x & true`, `false | x` and `x | false` are all just x
+            if is_boolean(left, is_and) {
+                return Some(right);
+            }
+            if is_boolean(right, is_and) {
+                return Some(left);
+            }
+            // `x & false` and `x | true` don't depend on x, but it's still evaluated first
+            if is_boolean(right, !is_and) && is_pure(ast, left) {
+                return Some(right);
+            }
+            None
+        },
+        _ => None
+    }
+}
+
+/// Constant parts become text, joined with the text around them
+fn simplify_interpolation(ast: &mut Ast, parts: Vec<InterpolationPart>) -> Vec<InterpolationPart> {
+    let mut simplified: Vec<InterpolationPart> = vec![ ];
+    for part in parts {
+        let part = match part {
+            InterpolationPart::Expression(expression) => {
+                let expression = simplify(ast, expression);
+                match ast.as_literal(expression) {
+                    // literals are never players, so they show up in text as they display
+                    Some(value) => InterpolationPart::Text(value.to_string().into()),
+                    None => InterpolationPart::Expression(expression)
+                }
+            },
+            text => text
+        };
+        match (simplified.last_mut(), part) {
+            (Some(InterpolationPart::Text(previous)), InterpolationPart::Text(text)) => *previous = [ previous.as_ref(), text.as_ref() ].concat().into(),
+            (_, part) => simplified.push(part)
+        }
+    }
+    simplified
+}
+
+/// The statements that do the same as this one with its expressions simplified.
+/// An `if` with a constant condition is replaced by the body that runs, unless that body declares variables.
+pub fn simplify_statement(ast: &mut Ast, id: StatementId) -> Vec<StatementId> {
+    let simplified = match ast.take_statement(id) {
+        Statement::Assignment { name, value, declares } => Statement::Assignment { name, value: simplify(ast, value), declares },
+        Statement::Damage { amount, damage_type, target } => Statement::Damage { amount: simplify(ast, amount), damage_type, target: simplify(ast, target) },
+        Statement::Status { status, turns, target } => Statement::Status { status, turns: simplify(ast, turns), target: simplify(ast, target) },
+        Statement::Modifier { value, operation, amount, target } => {
+            Statement::Modifier { value, operation, amount: simplify(ast, amount), target: target.map(|target| simplify(ast, target)) }
+        },
+        Statement::Replacement { event, outcome, redirect, uses } => {
+            Statement::Replacement { event, outcome, redirect: redirect.map(|target| simplify(ast, target)), uses }
+        },
+        Statement::Log(message) => Statement::Log(simplify(ast, message)),
+        Statement::Counter => Statement::Counter,
+        Statement::If { condition, then_body, else_body } => {
+            let condition = simplify(ast, condition);
+            let then_body = simplify_block(ast, then_body);
+            let else_body = simplify_block(ast, else_body);
+            let body = match ast.as_literal(condition) {
+                Some(ExpressionResult::Boolean(true)) => then_body,
+                Some(ExpressionResult::Boolean(false)) => else_body,
+                _ => {
+                    ast.replace_statement(id, Statement::If { condition, then_body, else_body });
+                    return vec![ id ];
+                }
+            };
+            // the body has its own scope, so it can only take the place of the if when it declares nothing
+            if !body.iter().any(|&statement| matches!(ast[statement], Statement::Assignment { .. })) {
+                return body;
+            }
+            ast.replace_expression(condition, Expression::Literal(ExpressionResult::Boolean(true)));
+            Statement::If { condition, then_body: body, else_body: vec![ ] }
+        }
+    };
+    ast.replace_statement(id, simplified);
+    vec![ id ]
+}
+
+/// Simplify every statement of a block (see `simplify_statement`)
+pub fn simplify_block(ast: &mut Ast, body: Vec<StatementId>) -> Vec<StatementId> {
+    body.into_iter().flat_map(|statement| simplify_statement(ast, statement)).collect()
+}
+
+/// Context for evaluating expressions made only of literals, which never need one
+pub struct ConstantContext;
+
+impl ScriptContext for ConstantContext {
+    fn roll(&mut self, _dice: Dice) -> u16 {
+        unreachable!("constant expressions don't roll dice")
+    }
+
+    fn choose_targets(&mut self, _target_type: &ExpressionType, _count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+        unreachable!("constant expressions don't choose targets")
+    }
+
+    fn deal_damage(&mut self, _amount: i32, _damage_type: DamageType, _target: &ExpressionResult) {
+        unreachable!("expressions don't deal damage")
+    }
+
+    fn apply_status(&mut self, _status: StatusKind, _turns: i32, _target: &ExpressionResult) {
+        unreachable!("expressions don't apply statuses")
+    }
+
+    fn add_modifier(&mut self, _value: ModifiedValue, _operation: ModifierOperation, _target: Option<&ExpressionResult>) {
+        unreachable!("expressions don't add modifiers")
+    }
+
+    fn add_replacement(&mut self, _event: ReplacedEvent, _outcome: ReplacementOutcome, _uses: Option<u16>) {
+        unreachable!("expressions don't add replacements")
+    }
+
+    fn get_property(&mut self, _target: &ExpressionResult, _property: &str) -> ExpressionResult {
+        unreachable!("constant expressions don't read properties")
+    }
+
+    fn log(&mut self, _message: &str) {
+        unreachable!("expressions don't log")
+    }
+
+    fn counter(&mut self) -> bool {
+        unreachable!("expressions don't counter cards")
+    }
+}
\ No newline at end of file
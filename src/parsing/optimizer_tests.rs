@@ -0,0 +1,199 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::repl::Distribution;
+    use crate::game_zones::dice_roller::DiceRoller;
+    use crate::game_zones::types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+    use crate::parsing::ast::{Ast, ExpressionId};
+    use crate::parsing::card::Card;
+    use crate::parsing::expressions::{Expression, ExpressionResult, ExpressionType};
+    use crate::parsing::optimizer::{simplify, ConstantContext};
+    use crate::parsing::parser::{parse_card, parse_expression};
+    use crate::parsing::script_context::ScriptContext;
+    use crate::parsing::symbol_table::SymbolTable;
+    use crate::parsing::tokenizer::tokenize;
+    use test_case::test_case;
+
+    fn symbol_table() -> SymbolTable {
+        let mut symbol_table = SymbolTable::with_built_ins();
+        symbol_table.assign("$turn", ExpressionResult::Integer(3)).unwrap();
+        symbol_table
+    }
+
+    fn parse(script: &str) -> (Ast, ExpressionId) {
+        let mut ast = Ast::default();
+        let expression = parse_expression(tokenize(script).unwrap().into_iter(), &symbol_table(), &mut ast).unwrap();
+        (ast, expression)
+    }
+
+    fn simplified(script: &str) -> (Ast, ExpressionId) {
+        let (mut ast, expression) = parse(script);
+        let expression = simplify(&mut ast, expression);
+        (ast, expression)
+    }
+
+    /// The operator of a binary expression, or the name of any other kind of expression
+    fn kind(ast: &Ast, expression: ExpressionId) -> String {
+        match &ast[expression] {
+            Expression::Binary(operator, ..) => format!("{:?}", operator),
+            other => format!("{:?}", other).split([ ' ', '(' ]).next().unwrap().to_string()
+        }
+    }
+
+    #[test_case("2 + 3", "5" ; "Sum")]
+    #[test_case("2 * 3 - 10 / (4 - 2)", "1" ; "Arithmetic")]
+    #[test_case("5 / 0", "0" ; "Division by zero")]
+    #[test_case("-(2 + 3)", "-5" ; "Negation")]
+    #[test_case("[fire] +! [fire]", "[fire]" ; "Unique list")]
+    #[test_case("[1, 1 + 1] - [2]", "[1]" ; "List except")]
+    #[test_case("1 < 2 & ~false", "true" ; "Logic")]
+    #[test_case("false & 1d6 > 3", "false" ; "Short circuit")]
+    #[test_case("$turn * 0", "0" ; "Times zero")]
+    #[test_case("\"a {1 + 1} b\"", "a 2 b" ; "Interpolation")]
+    fn folds_to_a_literal(script: &str, value: &str) {
+        let (ast, simplified) = simplified(script);
+        assert_eq!(ast.as_literal(simplified).map(ExpressionResult::to_string).as_deref(), Some(value));
+    }
+
+    #[test_case("1d6 + 0", "Roll" ; "Plus zero")]
+    #[test_case("0 + 1d6 * 1", "Roll" ; "Times one")]
+    #[test_case("$turn - (2 - 2)", "Variable" ; "Minus zero")]
+    #[test_case("true & $turn > 1", "Greater" ; "And true")]
+    #[test_case("$turn > 1 | false", "Greater" ; "Or false")]
+    #[test_case("1d6 * 0", "Multiply" ; "Dice are still rolled")]
+    #[test_case("1d6 > 3 & false", "And" ; "Dice are rolled before the constant")]
+    #[test_case("1d6 - 1d6", "Subtract" ; "Rolls are different")]
+    #[test_case("60000 * 60000", "Multiply" ; "Overflow")]
+    #[test_case("target(2 - 1 in Player)", "Target" ; "Target count")]
+    #[test_case("\"{1} and {$turn}\"", "Interpolation" ; "Partly constant text")]
+    fn simplifies_to(script: &str, kind: &str) {
+        let (ast, simplified) = simplified(script);
+        assert_eq!(self::kind(&ast, simplified), kind);
+    }
+
+    #[test]
+    fn constant_text_joins_the_text_around_it() {
+        let (ast, simplified) = simplified("\"{1 + 1} or {$turn}{2 > 1}!\"");
+        assert_eq!(ast.get_operands(simplified).count(), 1);
+        assert_eq!(ast.evaluate(simplified, &symbol_table(), &mut ConstantContext), ExpressionResult::Text("2 or 3true!".into()));
+    }
+
+    #[test_case("1d6 + 0 + 2 * 3" ; "Plus a constant")]
+    #[test_case("2d6 * (3 - 2) - (4 - 4)" ; "Identities")]
+    #[test_case("1d4 * (2 - 2) + 1d6 / 1" ; "Rolls times zero")]
+    #[test_case("1d8 / (1 - 1) + 1d4 - $turn * 0" ; "Division by zero")]
+    #[test_case("-(1d6 - 0) * 1 + $turn" ; "Negation")]
+    #[test_case("(1d6 + 2) * (1d4 - 1d4 + 0)" ; "Same dice, different rolls")]
+    fn same_distribution(script: &str) {
+        let distribution = |(ast, expression): (Ast, ExpressionId)| Distribution::of(&ast, expression, &symbol_table(), &mut ConstantContext, &mut DiceRoller::new(0));
+        let before = distribution(parse(script));
+        let after = distribution(simplified(script));
+
+        assert!(before.exact);
+        assert_eq!(before, after);
+    }
+
+    /// Rolls with a seeded roller, always targets the opponent, and writes down everything that happens
+    struct RecordingContext {
+        dice_roller: DiceRoller,
+        events: Vec<String>
+    }
+
+    impl ScriptContext for RecordingContext {
+        fn roll(&mut self, dice: Dice) -> u16 {
+            let roll = dice.roll(&mut self.dice_roller);
+            self.events.push(format!("rolled d{} = {}", dice.get_sides(), roll));
+            roll
+        }
+
+        fn choose_targets(&mut self, _target_type: &ExpressionType, count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+            vec![ ExpressionResult::Player(1); count as usize ]
+        }
+
+        fn deal_damage(&mut self, amount: i32, damage_type: DamageType, target: &ExpressionResult) {
+            self.events.push(format!("{} {} => {}", amount, damage_type, target));
+        }
+
+        fn apply_status(&mut self, status: StatusKind, turns: i32, target: &ExpressionResult) {
+            self.events.push(format!("{} {} => {}", status, turns, target));
+        }
+
+        fn add_modifier(&mut self, value: ModifiedValue, operation: ModifierOperation, target: Option<&ExpressionResult>) {
+            self.events.push(format!("{:?} {:?} => {:?}", value, operation, target));
+        }
+
+        fn add_replacement(&mut self, event: ReplacedEvent, outcome: ReplacementOutcome, uses: Option<u16>) {
+            self.events.push(format!("{:?} {:?} {:?}", event, outcome, uses));
+        }
+
+        fn get_property(&mut self, target: &ExpressionResult, property: &str) -> ExpressionResult {
+            match property {
+                "name" => ExpressionResult::Text(format!("{}", target).into()),
+                _ => ExpressionResult::Integer(20)
+            }
+        }
+
+        fn log(&mut self, message: &str) {
+            self.events.push(message.to_string());
+        }
+
+        fn counter(&mut self) -> bool {
+            false
+        }
+    }
+
+    const CARD: &str = "@name \"Zap\" [3 - 1]: {
+        $x = 1d6 * 1 + 0;
+        if 2 > 1 { $x = $x + 2 * 3; } else { log \"never\"; }
+        if false { $y = 1d20; log \"{$y}\"; }
+        if $x > 9 | false { log \"big\"; }
+        if ~(1 == 1) { } else { apply burning 1 + 1 => target(1 in Player); }
+        (1 + 1) * $x fire => target(3 - 2 in Player);
+        log \"{target(1 in Player).name} takes {$x * 2} ({1 + 2} {[fire] +! [fire]})\";
+    }";
+
+    fn run(card: &Card, seed: u64) -> Vec<String> {
+        let mut context = RecordingContext { dice_roller: DiceRoller::new(seed), events: vec![ ] };
+        card.execute(&mut SymbolTable::new(), &mut context);
+        context.events
+    }
+
+    #[test]
+    fn same_card_with_the_same_seed() {
+        let card = parse_card(tokenize(CARD).unwrap().into_iter()).unwrap();
+        let simplified = parse_card(tokenize(CARD).unwrap().into_iter()).unwrap().simplified();
+
+        assert_eq!(simplified.get_ast().as_literal(simplified.get_cost()), Some(&ExpressionResult::Integer(2)));
+        // `if false` is gone, and so is `if ~(1 == 1)` now that its else body stands on its own
+        assert_eq!(simplified.get_body().len(), card.get_body().len() - 1);
+        for seed in 0 .. 20 {
+            assert_eq!(run(&simplified, seed), run(&card, seed));
+        }
+    }
+
+    #[test_case("if true { log \"a\"; } else { $z = 2; }", &[ "Log" ] ; "Then")]
+    #[test_case("if 1 > 2 { log \"a\"; }", &[ ] ; "Nothing")]
+    #[test_case("if true { $y = 1; log \"{$y}\"; }", &[ "If" ] ; "Keeps its scope")]
+    fn constant_conditions(block: &str, kinds: &[&str]) {
+        let card = parse_card(tokenize(&format!("[1]: {{ {} }}", block)).unwrap().into_iter()).unwrap().simplified();
+        let found: Vec<String> = card.get_body().iter()
+            .map(|&statement| format!("{:?}", card.get_ast()[statement]).split([ ' ', '(' ]).next().unwrap().to_string())
+            .collect();
+        assert_eq!(found, kinds);
+    }
+
+    #[test]
+    fn funcs_and_calls() {
+        let script = "func roll($n: integer) = 1d6 * 1 + $n * (2 - 1); func twice($n: integer) = $n * 2; [twice(1) * 0 + roll(2 - 1) * 0]: { }";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap().simplified();
+        let ast = card.get_ast();
+
+        let roll = ast.find_func("roll").unwrap();
+        assert_eq!(kind(ast, ast[roll].body), "Add");
+        assert!(ast.get_operands(ast[roll].body).all(|operand| matches!(ast[operand], Expression::Roll { .. } | Expression::Variable(_))));
+        // `twice` can be left out, but the dice `roll` rolls can't
+        assert_eq!(kind(ast, card.get_cost()), "Multiply");
+        let call = ast.get_operands(card.get_cost()).next().unwrap();
+        assert_eq!(kind(ast, call), "Call");
+        assert_eq!(ast.get_operands(call).map(|arg| ast.as_literal(arg).cloned()).collect::<Vec<_>>(), vec![ Some(ExpressionResult::Integer(1)) ]);
+    }
+}
@@ -1,35 +1,851 @@
 use std::iter::Iterator;
+use std::ops::Range;
+use std::rc::Rc;
 
-use super::tokens::Tokens;
+use crate::game_zones::{types::{DamageType, ModifiedValue, ReplacedEvent, ReplacementOutcome, StatusKind, TriggerEvent}, zone::Zone};
 
-pub fn parse_expression(mut tokens: impl Iterator<Item=Tokens>) {
-    
+use super::ast::{Ast, ExpressionId, StatementId};
+use super::card::{Card, CardMetadata, Rarity, TriggeredAbility};
+use super::expressions::*;
+use super::statements::{ModifierKind, Statement};
+use super::symbol_table::SymbolTable;
+use super::tokens::{StringLiteralToken, StringPart, Token, Tokens};
+
+#[derive(Debug)]
+pub enum ParseError {
+    /// Token was not valid at this point in the script
+    UnexpectedToken(Tokens),
+    /// Script ended before the card or expression was complete
+    UnexpectedEndOfFile,
+    /// Variable was used before anything was assigned to it
+    UndeclaredIdentifier(Rc<str>),
+    /// `target(... in X)` where X is not something we can target
+    UnknownTargetType(Rc<str>),
+    /// Variable was assigned a value of a different type than before
+    AssignmentTypeMismatch(Rc<str>),
+    /// `@key` that isn't one of the metadata fields
+    UnknownMetadata(Rc<str>),
+    /// Same `@key` given twice
+    DuplicateMetadata(Rc<str>),
+    /// Cost between the brackets must be an integer
+    InvalidCost,
+    /// Condition of an if statement must be a boolean
+    InvalidCondition,
+    /// Left-hand side of `=>` must be an integer
+    InvalidDamageAmount,
+    /// `<amount> x =>` where x isn't one of the registered damage types
+    UnknownDamageType(Rc<str>),
+    /// Only text can be logged
+    InvalidLogMessage,
+    /// `apply x` where x isn't a status
+    UnknownStatus(Rc<str>),
+    /// Number of turns a status lasts must be an integer
+    InvalidDuration,
+    /// `modify x` where x isn't damage, cost or an integer property
+    UnknownModifiedValue(Rc<str>),
+    /// Amount a modifier changes a value by must be an integer
+    InvalidModifierAmount,
+    /// `instead x` where x isn't damage or a zone, or an outcome that doesn't fit the event (e.g. exiling damage)
+    InvalidReplacement(Rc<str>),
+    /// `.property` that the value doesn't have
+    UnknownProperty(Rc<str>),
+    /// `when x` where x isn't something that can set off an ability
+    UnknownTrigger(Rc<str>),
+    /// Parameter type that isn't one of the types values can have
+    UnknownType(Rc<str>),
+    /// Two funcs, or two parameters of a func, with the same name
+    DuplicateName(Rc<str>),
+    /// Func called with the wrong number of arguments, or one of the wrong type
+    InvalidArguments(Rc<str>),
+    InvalidExpression(ParseExpressionError)
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedToken(Tokens::DocComment(_)) => write!(f, "doc comments only go before the card or a func"),
+            ParseError::UnexpectedToken(token) => write!(f, "unexpected '{}'", token),
+            ParseError::UnexpectedEndOfFile => write!(f, "unexpected end of file"),
+            ParseError::UndeclaredIdentifier(name) => write!(f, "'{}' is used before anything is assigned to it", name),
+            ParseError::UnknownTargetType(name) => write!(f, "can't target '{}'", name),
+            ParseError::AssignmentTypeMismatch(name) => write!(f, "'{}' is assigned a value of a different type than before", name),
+            ParseError::UnknownMetadata(key) => write!(f, "unknown metadata '@{}'", key),
+            ParseError::DuplicateMetadata(key) => write!(f, "'@{}' is given twice", key),
+            ParseError::InvalidCost => write!(f, "cost must be an integer"),
+            ParseError::InvalidCondition => write!(f, "condition must be a boolean"),
+            ParseError::InvalidDamageAmount => write!(f, "damage amount must be an integer"),
+            ParseError::UnknownDamageType(name) => write!(f, "unknown damage type '{}'", name),
+            ParseError::InvalidLogMessage => write!(f, "only text can be logged"),
+            ParseError::UnknownStatus(name) => write!(f, "unknown status '{}'", name),
+            ParseError::InvalidDuration => write!(f, "duration must be an integer"),
+            ParseError::UnknownModifiedValue(name) => write!(f, "can't modify '{}'", name),
+            ParseError::InvalidModifierAmount => write!(f, "modifier amount must be an integer"),
+            ParseError::InvalidReplacement(name) => write!(f, "invalid replacement '{}'", name),
+            ParseError::UnknownProperty(name) => write!(f, "unknown property '.{}'", name),
+            ParseError::UnknownTrigger(name) => write!(f, "unknown trigger '{}'", name),
+            ParseError::UnknownType(name) => write!(f, "unknown type '{}'", name),
+            ParseError::DuplicateName(name) => write!(f, "'{}' is defined twice", name),
+            ParseError::InvalidArguments(name) => write!(f, "wrong arguments for '{}'", name),
+            ParseError::InvalidExpression(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl From<ParseExpressionError> for ParseError {
+    fn from(err: ParseExpressionError) -> Self {
+        ParseError::InvalidExpression(err)
+    }
+}
+
+/// What the parser worked out about a card's tokens, for editor tooling.
+/// Token ranges are indices into the tokens that were parsed, end exclusive.
+#[derive(Debug, Default)]
+pub struct Annotations {
+    /// Every expression and subexpression, with its type
+    pub expressions: Vec<(Range<usize>, ExpressionType)>,
+    /// Variables declared by assignment and func parameters: the name's token, and the variable's type
+    pub declarations: Vec<(usize, ExpressionType)>,
+    /// Func definitions, from `func` to `;` (the name is the token after `func`)
+    pub funcs: Vec<Range<usize>>
+}
+
+/// Tokens with as much lookahead as the grammar needs
+struct TokenStream {
+    tokens: Vec<Tokens>,
+    position: usize,
+    annotations: Annotations
+}
+
+impl TokenStream {
+    fn new(tokens: impl Iterator<Item=Tokens>) -> Self {
+        TokenStream { tokens: tokens.collect(), position: 0, annotations: Annotations::default() }
+    }
+
+    /// Note the type of the expression that started at token `start` and ended just before the current one
+    fn annotate(&mut self, start: usize, expression_type: &ExpressionType) {
+        self.annotations.expressions.push((start .. self.position, expression_type.clone()));
+    }
+
+    fn peek(&self) -> Option<&Tokens> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&Tokens> {
+        self.tokens.get(self.position + n).filter(|t| !matches!(t, Tokens::EOF))
+    }
+
+    fn next(&mut self) -> Option<Tokens> {
+        let token = self.peek().cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn next_is_symbol(&self, symbol: &str) -> bool {
+        is_symbol(self.peek(), symbol)
+    }
+
+    /// Consume the next token if it is one of the given symbols
+    fn next_if_symbol(&mut self, symbols: &[&str]) -> Option<Tokens> {
+        if symbols.iter().any(|s| self.next_is_symbol(s)) {
+            return self.next();
+        }
+        None
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ParseError> {
+        match self.next() {
+            Some(token) if is_symbol(Some(&token), symbol) => Ok(()),
+            Some(token) => Err(ParseError::UnexpectedToken(token)),
+            None => Err(ParseError::UnexpectedEndOfFile)
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek().is_none()
+    }
+}
+
+fn is_symbol(token: Option<&Tokens>, symbol: &str) -> bool {
+    matches!(token, Some(Tokens::Symbol(s)) if s.as_str() == symbol)
+}
+
+/// Parse a single card script: `func... @metadata... #tag [cost]: { statements } when event { statements }...`
+pub fn parse_card(tokens: impl Iterator<Item=Tokens>) -> Result<Card, ParseError> {
+    parse_card_located(tokens).map_err(|(err, _)| err)
+}
+
+/// Like `parse_card`, but on failure also gives the index of the token parsing stopped at
+pub fn parse_card_located(tokens: impl Iterator<Item=Tokens>) -> Result<Card, (ParseError, usize)> {
+    analyze_card(tokens).0
+}
+
+/// Like `parse_card_located`, along with what was worked out about the tokens (up to the error, if any)
+pub fn analyze_card(tokens: impl Iterator<Item=Tokens>) -> (Result<Card, (ParseError, usize)>, Annotations) {
+    let mut tokens = TokenStream::new(tokens);
+    let result = parse_card_stream(&mut tokens).map_err(|err| {
+        // running out of tokens stops at `EOF`; anything else at the last token read
+        let index = match err {
+            ParseError::UnexpectedEndOfFile => tokens.position,
+            _ => tokens.position.saturating_sub(1)
+        };
+        (err, index.min(tokens.tokens.len().saturating_sub(1)))
+    });
+    (result, tokens.annotations)
+}
+
+fn parse_card_stream(tokens: &mut TokenStream) -> Result<Card, ParseError> {
+    let mut symbol_table = SymbolTable::with_built_ins();
+    let mut ast = Ast::default();
+
+    let mut doc = parse_doc_comments(tokens);
+    while tokens.next_if_symbol(&[ "func" ]).is_some() {
+        parse_func(tokens, &mut ast, doc)?;
+        doc = parse_doc_comments(tokens);
+    }
+
+    let mut metadata = parse_metadata(tokens)?;
+    metadata.doc = doc;
+
+    let mut tags = vec![ ];
+    while tokens.next_if_symbol(&[ "#" ]).is_some() {
+        match tokens.next() {
+            Some(Tokens::Identifier(tag)) => tags.push(Rc::from(tag.as_str())),
+            // damage types make for perfectly good tags (e.g. #fire)
+            Some(Tokens::DamageType(tag)) => tags.push(Rc::from(tag.as_str())),
+            Some(token) => return Err(ParseError::UnexpectedToken(token)),
+            None => return Err(ParseError::UnexpectedEndOfFile)
+        }
+    }
+
+    tokens.expect_symbol("[")?;
+    let cost = parse_logical_expression(tokens, &symbol_table, &mut ast)?;
+    if *ast.get_type(cost) != ExpressionType::Integer {
+        return Err(ParseError::InvalidCost);
+    }
+    tokens.expect_symbol("]")?;
+    tokens.expect_symbol(":")?;
+
+    let body = parse_block(tokens, &mut symbol_table, &mut ast)?;
+
+    let mut abilities = vec![ ];
+    while tokens.next_if_symbol(&[ "when" ]).is_some() {
+        let event = parse_trigger_event(tokens)?;
+        let body = parse_block(tokens, &mut symbol_table, &mut ast)?;
+        abilities.push(TriggeredAbility { event, body });
+    }
+    if let Some(token) = tokens.next() {
+        return Err(ParseError::UnexpectedToken(token));
+    }
+
+    Ok(Card::new(metadata, tags, ast, cost, body, abilities))
+}
+
+/// `name($param: type, ...) = body;`, after `func` (and the doc comments before it)
+fn parse_func(tokens: &mut TokenStream, ast: &mut Ast, doc: Option<Rc<str>>) -> Result<(), ParseError> {
+    let start = tokens.position - 1;
+    let name: Rc<str> = match tokens.next() {
+        Some(Tokens::Identifier(name)) => Rc::from(name.as_str()),
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+    if ast.find_func(&name).is_some() {
+        return Err(ParseError::DuplicateName(name));
+    }
+
+    // the body doesn't see the card's variables, or even the built-in ones: only what it's given
+    let mut symbol_table = SymbolTable::new();
+    let mut params: Vec<(Rc<str>, ExpressionType)> = vec![ ];
+    tokens.expect_symbol("(")?;
+    while !tokens.next_is_symbol(")") {
+        if !params.is_empty() {
+            tokens.expect_symbol(",")?;
+        }
+        let param: Rc<str> = match tokens.next() {
+            Some(Tokens::Identifier(param)) => Rc::from(param.as_str()),
+            Some(token) => return Err(ParseError::UnexpectedToken(token)),
+            None => return Err(ParseError::UnexpectedEndOfFile)
+        };
+        if params.iter().any(|(other, _)| *other == param) {
+            return Err(ParseError::DuplicateName(param));
+        }
+        let param_index = tokens.position - 1;
+        tokens.expect_symbol(":")?;
+        let param_type = parse_type(tokens)?;
+        symbol_table.declare(&param, param_type.clone());
+        tokens.annotations.declarations.push((param_index, param_type.clone()));
+        params.push((param, param_type));
+    }
+    tokens.expect_symbol(")")?;
+    tokens.expect_symbol("=")?;
+    let body = parse_logical_expression(tokens, &symbol_table, ast)?;
+    tokens.expect_symbol(";")?;
+
+    tokens.annotations.funcs.push(start .. tokens.position);
+    ast.push_func(Func { name, params, body, doc });
+    Ok(())
+}
+
+/// A type the way it's written in messages: `integer`, `damage type`, `list of player`...
+fn parse_type(tokens: &mut TokenStream) -> Result<ExpressionType, ParseError> {
+    let name = match tokens.next() {
+        Some(Tokens::Identifier(name)) => name,
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+    // the second word of `damage type` and `list of`
+    let mut expect_word = |word: &str| match tokens.next() {
+        Some(Tokens::Identifier(next)) if next.as_str() == word => Ok(()),
+        Some(token) => Err(ParseError::UnexpectedToken(token)),
+        None => Err(ParseError::UnexpectedEndOfFile)
+    };
+    match name.as_str() {
+        "integer" => Ok(ExpressionType::Integer),
+        "boolean" => Ok(ExpressionType::Boolean),
+        "dice" => Ok(ExpressionType::Dice),
+        "player" => Ok(ExpressionType::Player),
+        "text" => Ok(ExpressionType::Text),
+        "damage" => expect_word("type").map(|_| ExpressionType::DamageType),
+        "list" => {
+            expect_word("of")?;
+            Ok(ExpressionType::List(Box::new(parse_type(tokens)?)))
+        },
+        other => Err(ParseError::UnknownType(Rc::from(other)))
+    }
+}
+
+/// `play` or `turn start`, after `when`
+fn parse_trigger_event(tokens: &mut TokenStream) -> Result<TriggerEvent, ParseError> {
+    let name = match tokens.next() {
+        Some(Tokens::Identifier(name)) => name,
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+    match name.as_str() {
+        "play" => Ok(TriggerEvent::Play),
+        "turn" => match tokens.next() {
+            Some(Tokens::Identifier(phase)) if phase.as_str() == "start" => Ok(TriggerEvent::TurnStart),
+            Some(token) => Err(ParseError::UnexpectedToken(token)),
+            None => Err(ParseError::UnexpectedEndOfFile)
+        },
+        other => Err(ParseError::UnknownTrigger(Rc::from(other)))
+    }
+}
+
+/// `///` lines before the card or a func, joined with newlines
+fn parse_doc_comments(tokens: &mut TokenStream) -> Option<Rc<str>> {
+    let mut lines = vec![ ];
+    while let Some(Tokens::DocComment(line)) = tokens.peek() {
+        lines.push(line.as_str().to_string());
+        tokens.next();
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    Some(Rc::from(lines.join("\n")))
+}
+
+/// `@name "Firebolt" @rarity rare @set "BASE" @text "..." @flavor "..."`, in any order
+fn parse_metadata(tokens: &mut TokenStream) -> Result<CardMetadata, ParseError> {
+    let mut metadata = CardMetadata::default();
+    let mut seen: Vec<Rc<str>> = vec![ ];
+
+    while tokens.next_if_symbol(&[ "@" ]).is_some() {
+        let key: Rc<str> = match tokens.next() {
+            Some(Tokens::Identifier(key)) => Rc::from(key.as_str()),
+            Some(token) => return Err(ParseError::UnexpectedToken(token)),
+            None => return Err(ParseError::UnexpectedEndOfFile)
+        };
+        if seen.contains(&key) {
+            return Err(ParseError::DuplicateMetadata(key));
+        }
+        seen.push(key.clone());
+
+        if key.as_ref() == "rarity" {
+            metadata.rarity = match tokens.next() {
+                Some(Tokens::Identifier(rarity)) => Rarity::try_from(rarity.as_str()).map_err(|_| ParseError::UnexpectedToken(Tokens::Identifier(rarity)))?,
+                Some(token) => return Err(ParseError::UnexpectedToken(token)),
+                None => return Err(ParseError::UnexpectedEndOfFile)
+            };
+            continue;
+        }
+
+        let field = match key.as_ref() {
+            "name" => &mut metadata.name,
+            "text" => &mut metadata.text,
+            "set" => &mut metadata.set_code,
+            "flavor" => &mut metadata.flavor,
+            _ => return Err(ParseError::UnknownMetadata(key))
+        };
+        *field = match tokens.next() {
+            Some(Tokens::String(value)) => match value.get_plain_text() {
+                Some(text) => Some(Rc::from(text)),
+                // metadata is fixed, so there's nothing to interpolate
+                None => return Err(ParseError::UnexpectedToken(Tokens::String(value)))
+            },
+            Some(token) => return Err(ParseError::UnexpectedToken(token)),
+            None => return Err(ParseError::UnexpectedEndOfFile)
+        };
+    }
+
+    Ok(metadata)
+}
+
+/// Parse a lone expression into `ast` (handy for tools that don't care about whole cards)
+pub fn parse_expression(tokens: impl Iterator<Item=Tokens>, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let mut tokens = TokenStream::new(tokens);
+    let expression = parse_logical_expression(&mut tokens, symbol_table, ast)?;
+    if let Some(token) = tokens.next() {
+        return Err(ParseError::UnexpectedToken(token));
+    }
+    Ok(expression)
+}
+
+/// Parse a lone statement into `ast`, declaring any new variables in the symbol table
+pub fn parse_statement(tokens: impl Iterator<Item=Tokens>, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    let mut tokens = TokenStream::new(tokens);
+    let statement = parse_next_statement(&mut tokens, symbol_table, ast)?;
+    if let Some(token) = tokens.next() {
+        return Err(ParseError::UnexpectedToken(token));
+    }
+    Ok(statement)
 }
 
-fn parse_logical_expression() {
+fn parse_block(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<Vec<StatementId>, ParseError> {
+    tokens.expect_symbol("{")?;
+    symbol_table.push_scope();
+
+    let mut statements = vec![ ];
+    while !tokens.next_is_symbol("}") {
+        if tokens.is_at_end() {
+            return Err(ParseError::UnexpectedEndOfFile);
+        }
+        statements.push(parse_next_statement(tokens, symbol_table, ast)?);
+    }
 
+    symbol_table.pop_scope();
+    tokens.expect_symbol("}")?;
+    Ok(statements)
 }
 
-fn parse_equality_expression() {
+fn parse_next_statement(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    if tokens.next_if_symbol(&[ "if" ]).is_some() {
+        return parse_if_statement(tokens, symbol_table, ast);
+    }
+    if tokens.next_if_symbol(&[ "log" ]).is_some() {
+        return parse_log_statement(tokens, symbol_table, ast);
+    }
+    if tokens.next_if_symbol(&[ "apply" ]).is_some() {
+        return parse_status_statement(tokens, symbol_table, ast);
+    }
+    if tokens.next_if_symbol(&[ "modify" ]).is_some() {
+        return parse_modifier_statement(tokens, symbol_table, ast);
+    }
+    if tokens.next_if_symbol(&[ "instead" ]).is_some() {
+        return parse_replacement_statement(tokens, symbol_table, ast);
+    }
+    if tokens.next_if_symbol(&[ "counter" ]).is_some() {
+        tokens.expect_symbol(";")?;
+        return Ok(ast.push_statement(Statement::Counter));
+    }
+    if let Some(Tokens::Identifier(name)) = tokens.peek() {
+        if is_symbol(tokens.peek_nth(1), "=") {
+            let name = name.clone();
+            tokens.next();
+            tokens.next();
+            return parse_assignment(name.as_str(), tokens, symbol_table, ast);
+        }
+    }
+    parse_damage_statement(tokens, symbol_table, ast)
+}
+
+fn parse_if_statement(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    let condition = parse_logical_expression(tokens, symbol_table, ast)?;
+    if *ast.get_type(condition) != ExpressionType::Boolean {
+        return Err(ParseError::InvalidCondition);
+    }
+    let then_body = parse_block(tokens, symbol_table, ast)?;
+
+    let mut else_body = vec![ ];
+    if tokens.next_if_symbol(&[ "else" ]).is_some() {
+        if tokens.next_if_symbol(&[ "if" ]).is_some() {
+            else_body.push(parse_if_statement(tokens, symbol_table, ast)?);
+        } else {
+            else_body = parse_block(tokens, symbol_table, ast)?;
+        }
+    }
 
+    Ok(ast.push_statement(Statement::If { condition, then_body, else_body }))
 }
 
-fn parse_comparison_expression() {
+fn parse_log_statement(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    let message = parse_logical_expression(tokens, symbol_table, ast)?;
+    if *ast.get_type(message) != ExpressionType::Text {
+        return Err(ParseError::InvalidLogMessage);
+    }
+    tokens.expect_symbol(";")?;
 
+    Ok(ast.push_statement(Statement::Log(message)))
 }
 
-fn parse_additive_expression() {
+fn parse_assignment(name: &str, tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    // just after `name =`
+    let name_index = tokens.position.saturating_sub(2);
+    let value = parse_logical_expression(tokens, symbol_table, ast)?;
+    tokens.expect_symbol(";")?;
 
+    let value_type = ast.get_type(value);
+    let declares = match symbol_table.get_type(name) {
+        Some(existing) if existing != value_type => return Err(ParseError::AssignmentTypeMismatch(Rc::from(name))),
+        Some(_) => false,
+        None => {
+            symbol_table.declare(name, value_type.clone());
+            tokens.annotations.declarations.push((name_index, value_type.clone()));
+            true
+        }
+    };
+
+    Ok(ast.push_statement(Statement::Assignment { name: Rc::from(name), value, declares }))
 }
 
-fn parse_factor_expression() {
+fn parse_damage_statement(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    let amount = parse_logical_expression(tokens, symbol_table, ast)?;
+    if *ast.get_type(amount) != ExpressionType::Integer {
+        return Err(ParseError::InvalidDamageAmount);
+    }
+
+    let mut damage_type = DamageType::none();
+    match tokens.peek() {
+        Some(Tokens::DamageType(damage_type_token)) => {
+            damage_type = damage_type_token.clone().get_value();
+            tokens.next();
+        },
+        // the tokenizer only knows the damage types in its registry
+        Some(Tokens::Identifier(name)) => return Err(ParseError::UnknownDamageType(Rc::from(name.as_str()))),
+        _ => { }
+    }
 
+    tokens.expect_symbol("=>")?;
+    let target = parse_target_of(tokens, symbol_table, ast)?;
+    tokens.expect_symbol(";")?;
+
+    Ok(ast.push_statement(Statement::Damage { amount, damage_type, target }))
 }
 
-fn parse_unary_expression() {
+/// `apply <status> <turns> => <target>;`
+fn parse_status_statement(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    let status = match tokens.next() {
+        Some(Tokens::Identifier(name)) => StatusKind::try_from(name.as_str()).map_err(|_| ParseError::UnknownStatus(Rc::from(name.as_str())))?,
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+
+    let turns = parse_logical_expression(tokens, symbol_table, ast)?;
+    if *ast.get_type(turns) != ExpressionType::Integer {
+        return Err(ParseError::InvalidDuration);
+    }
 
+    tokens.expect_symbol("=>")?;
+    let target = parse_target_of(tokens, symbol_table, ast)?;
+    tokens.expect_symbol(";")?;
+
+    Ok(ast.push_statement(Statement::Status { status, turns, target }))
 }
 
-fn parse_primary_expression() {
-    
-}
\ No newline at end of file
+/// `modify <value> <+ - * / => <amount> [=> <target>];`
+fn parse_modifier_statement(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    let value = parse_modified_value(tokens)?;
+
+    let operation = match tokens.next() {
+        Some(Tokens::Symbol(symbol)) if symbol.as_str() == "+" => ModifierKind::Add,
+        Some(Tokens::Symbol(symbol)) if symbol.as_str() == "-" => ModifierKind::Subtract,
+        Some(Tokens::Symbol(symbol)) if symbol.as_str() == "*" => ModifierKind::Multiply,
+        Some(Tokens::Symbol(symbol)) if symbol.as_str() == "/" => ModifierKind::Divide,
+        Some(Tokens::Symbol(symbol)) if symbol.as_str() == "=" => ModifierKind::Set,
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+
+    let amount = parse_logical_expression(tokens, symbol_table, ast)?;
+    if *ast.get_type(amount) != ExpressionType::Integer {
+        return Err(ParseError::InvalidModifierAmount);
+    }
+
+    let mut target = None;
+    if tokens.next_if_symbol(&[ "=>" ]).is_some() {
+        target = Some(parse_target_of(tokens, symbol_table, ast)?);
+    }
+    tokens.expect_symbol(";")?;
+
+    Ok(ast.push_statement(Statement::Modifier { value, operation, amount, target }))
+}
+
+/// `instead [next [<uses>]] <[type] damage|zone> <prevent|redirect <player>|zone>;`
+fn parse_replacement_statement(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<StatementId, ParseError> {
+    let mut uses = None;
+    if matches!(tokens.peek(), Some(Tokens::Identifier(next)) if next.as_str() == "next") {
+        tokens.next();
+        uses = Some(1);
+        if let Some(Tokens::Numeric(count)) = tokens.peek() {
+            uses = Some(count.clone().get_value().max(1));
+            tokens.next();
+        }
+    }
+
+    let mut damage_type = None;
+    if let Some(Tokens::DamageType(damage_type_token)) = tokens.peek() {
+        damage_type = Some(damage_type_token.clone().get_value());
+        tokens.next();
+    }
+    let event = match tokens.next() {
+        Some(Tokens::Identifier(name)) if name.as_str() == "damage" => ReplacedEvent::Damage(damage_type),
+        Some(Tokens::Identifier(name)) if damage_type.is_none() => {
+            ReplacedEvent::Move(Zone::try_from(name.as_str()).map_err(|_| ParseError::InvalidReplacement(Rc::from(name.as_str())))?)
+        },
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+
+    let outcome = match tokens.next() {
+        Some(Tokens::Identifier(name)) if name.as_str() == "redirect" && matches!(event, ReplacedEvent::Damage(_)) => {
+            let target = parse_logical_expression(tokens, symbol_table, ast)?;
+            if *ast.get_type(target) != ExpressionType::Player {
+                return Err(ParseExpressionError::OperandTypesNotSupported.into());
+            }
+            tokens.expect_symbol(";")?;
+            return Ok(ast.push_statement(Statement::Replacement { event, outcome: ReplacementOutcome::Prevent, redirect: Some(target), uses }));
+        },
+        Some(Tokens::Identifier(name)) => {
+            let outcome = match name.as_str() {
+                "prevent" => Some(ReplacementOutcome::Prevent),
+                zone => Zone::try_from(zone).ok().map(ReplacementOutcome::MoveTo)
+            };
+            match outcome {
+                Some(outcome) if event.allows(outcome) => outcome,
+                _ => return Err(ParseError::InvalidReplacement(Rc::from(name.as_str())))
+            }
+        },
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+    tokens.expect_symbol(";")?;
+
+    Ok(ast.push_statement(Statement::Replacement { event, outcome, redirect: None, uses }))
+}
+
+/// `[type] damage`, `[type] damage taken`, `cost` or an integer property like `hp`
+fn parse_modified_value(tokens: &mut TokenStream) -> Result<ModifiedValue, ParseError> {
+    let mut damage_type = None;
+    if let Some(Tokens::DamageType(damage_type_token)) = tokens.peek() {
+        damage_type = Some(damage_type_token.clone().get_value());
+        tokens.next();
+    }
+
+    let name = match tokens.next() {
+        Some(Tokens::Identifier(name)) => name,
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+    match name.as_str() {
+        "damage" => {
+            if let Some(Tokens::Identifier(taken)) = tokens.peek() {
+                if taken.as_str() == "taken" {
+                    tokens.next();
+                    return Ok(ModifiedValue::DamageTaken(damage_type));
+                }
+            }
+            Ok(ModifiedValue::DamageDealt(damage_type))
+        },
+        // only damage comes in types
+        _ if damage_type.is_some() => Err(ParseError::UnexpectedToken(Tokens::Identifier(name))),
+        "cost" => Ok(ModifiedValue::Cost),
+        property if get_property_type(&ExpressionType::Player, property) == Some(ExpressionType::Integer) => Ok(ModifiedValue::Property(Rc::from(property))),
+        _ => Err(ParseError::UnknownModifiedValue(Rc::from(name.as_str())))
+    }
+}
+
+/// Right-hand side of `=>`: a player or a list of them
+fn parse_target_of(tokens: &mut TokenStream, symbol_table: &mut SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let target = parse_logical_expression(tokens, symbol_table, ast)?;
+    match ast.get_type(target) {
+        ExpressionType::Player => { },
+        ExpressionType::List(item_type) if **item_type == ExpressionType::Player => { },
+        _ => return Err(ParseExpressionError::OperandTypesNotSupported.into())
+    }
+    Ok(target)
+}
+
+fn parse_logical_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let start = tokens.position;
+    let mut left = parse_equality_expression(tokens, symbol_table, ast)?;
+    while let Some(operator) = tokens.next_if_symbol(&[ "&", "|" ]) {
+        let right = parse_equality_expression(tokens, symbol_table, ast)?;
+        left = ast.add_binary(left, &operator, right)?;
+        tokens.annotate(start, ast.get_type(left));
+    }
+    Ok(left)
+}
+
+fn parse_equality_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let start = tokens.position;
+    let mut left = parse_comparison_expression(tokens, symbol_table, ast)?;
+    while let Some(operator) = tokens.next_if_symbol(&[ "==", "~=" ]) {
+        let right = parse_comparison_expression(tokens, symbol_table, ast)?;
+        left = ast.add_binary(left, &operator, right)?;
+        tokens.annotate(start, ast.get_type(left));
+    }
+    Ok(left)
+}
+
+fn parse_comparison_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let start = tokens.position;
+    let mut left = parse_additive_expression(tokens, symbol_table, ast)?;
+    while let Some(operator) = tokens.next_if_symbol(&[ "<", ">", "<=", ">=" ]) {
+        let right = parse_additive_expression(tokens, symbol_table, ast)?;
+        left = ast.add_binary(left, &operator, right)?;
+        tokens.annotate(start, ast.get_type(left));
+    }
+    Ok(left)
+}
+
+fn parse_additive_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let start = tokens.position;
+    let mut left = parse_factor_expression(tokens, symbol_table, ast)?;
+    while let Some(operator) = tokens.next_if_symbol(&[ "+", "+!", "-" ]) {
+        let right = parse_factor_expression(tokens, symbol_table, ast)?;
+        left = ast.add_binary(left, &operator, right)?;
+        tokens.annotate(start, ast.get_type(left));
+    }
+    Ok(left)
+}
+
+fn parse_factor_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let start = tokens.position;
+    let mut left = parse_unary_expression(tokens, symbol_table, ast)?;
+    while let Some(operator) = tokens.next_if_symbol(&[ "*", "/" ]) {
+        let right = parse_unary_expression(tokens, symbol_table, ast)?;
+        left = ast.add_binary(left, &operator, right)?;
+        tokens.annotate(start, ast.get_type(left));
+    }
+    Ok(left)
+}
+
+fn parse_unary_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let start = tokens.position;
+    if let Some(operator) = tokens.next_if_symbol(&[ "-", "~", "^" ]) {
+        let right = parse_unary_expression(tokens, symbol_table, ast)?;
+        let unary = ast.add_unary(&operator, right)?;
+        tokens.annotate(start, ast.get_type(unary));
+        return Ok(unary);
+    }
+    parse_property_expression(tokens, symbol_table, ast)
+}
+
+fn parse_property_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let start = tokens.position;
+    let mut owner = parse_primary_expression(tokens, symbol_table, ast)?;
+    tokens.annotate(start, ast.get_type(owner));
+    while tokens.next_if_symbol(&[ "." ]).is_some() {
+        let property = match tokens.next() {
+            Some(Tokens::Identifier(property)) => property,
+            Some(token) => return Err(ParseError::UnexpectedToken(token)),
+            None => return Err(ParseError::UnexpectedEndOfFile)
+        };
+        owner = ast.add_property(owner, property.as_str())
+            .map_err(|_| ParseError::UnknownProperty(Rc::from(property.as_str())))?;
+        tokens.annotate(start, ast.get_type(owner));
+    }
+    Ok(owner)
+}
+
+fn parse_primary_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let token = tokens.next().ok_or(ParseError::UnexpectedEndOfFile)?;
+    match token {
+        Tokens::Numeric(ref count) => {
+            if let Some(Tokens::Dice(dice)) = tokens.peek() {
+                let roll = ast.add_roll(count.clone().get_value(), dice.clone().get_value());
+                tokens.next();
+                return Ok(roll);
+            }
+            Ok(ast.add_literal(ExpressionResult::try_from(token)?))
+        },
+        Tokens::Boolean(_) | Tokens::DamageType(_) | Tokens::Dice(_) => Ok(ast.add_literal(ExpressionResult::try_from(token)?)),
+        Tokens::String(ref string_token) if string_token.get_plain_text().is_some() => Ok(ast.add_literal(ExpressionResult::try_from(token)?)),
+        Tokens::String(ref string_token) => parse_interpolated_string(string_token, symbol_table, ast),
+        Tokens::Identifier(ref name) if tokens.next_is_symbol("(") => parse_call(name.as_str(), tokens, symbol_table, ast),
+        Tokens::Identifier(ref name) => {
+            match symbol_table.get_type(name.as_str()) {
+                Some(symbol_type) => Ok(ast.add_variable(name.as_str(), symbol_type.clone())),
+                None => Err(ParseError::UndeclaredIdentifier(Rc::from(name.as_str())))
+            }
+        },
+        Tokens::Symbol(ref symbol) if symbol.as_str() == "(" => {
+            let inner = parse_logical_expression(tokens, symbol_table, ast)?;
+            tokens.expect_symbol(")")?;
+            Ok(inner)
+        },
+        Tokens::Symbol(ref symbol) if symbol.as_str() == "[" => parse_list_expression(tokens, symbol_table, ast),
+        Tokens::Symbol(ref symbol) if symbol.as_str() == "target" => parse_target_expression(tokens, symbol_table, ast),
+        _ => Err(ParseError::UnexpectedToken(token))
+    }
+}
+
+/// `name(argument, ...)`, calling a func defined before the card
+fn parse_call(name: &str, tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let func = ast.find_func(name).ok_or_else(|| ParseError::UndeclaredIdentifier(Rc::from(name)))?;
+    tokens.expect_symbol("(")?;
+    let mut args = vec![ ];
+    while !tokens.next_is_symbol(")") {
+        if !args.is_empty() {
+            tokens.expect_symbol(",")?;
+        }
+        args.push(parse_logical_expression(tokens, symbol_table, ast)?);
+    }
+    tokens.expect_symbol(")")?;
+    ast.add_call(func, args).map_err(|_| ParseError::InvalidArguments(Rc::from(name)))
+}
+
+fn parse_interpolated_string(string_token: &StringLiteralToken, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let mut parts = vec![ ];
+    for part in string_token.get_parts() {
+        match part {
+            StringPart::Text(text) => parts.push(InterpolationPart::Text(text.clone())),
+            StringPart::Interpolation(inner) => {
+                let mut inner_tokens = TokenStream::new(inner.iter().cloned());
+                let expression = parse_logical_expression(&mut inner_tokens, symbol_table, ast)?;
+                if let Some(token) = inner_tokens.next() {
+                    return Err(ParseError::UnexpectedToken(token));
+                }
+                parts.push(InterpolationPart::Expression(expression));
+            }
+        }
+    }
+    Ok(ast.add_interpolation(parts))
+}
+
+fn parse_list_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    let mut items = vec![ parse_logical_expression(tokens, symbol_table, ast)? ];
+    while tokens.next_if_symbol(&[ "," ]).is_some() {
+        items.push(parse_logical_expression(tokens, symbol_table, ast)?);
+    }
+    tokens.expect_symbol("]")?;
+    Ok(ast.add_list(items)?)
+}
+
+fn parse_target_expression(tokens: &mut TokenStream, symbol_table: &SymbolTable, ast: &mut Ast) -> Result<ExpressionId, ParseError> {
+    tokens.expect_symbol("(")?;
+    let up_to = tokens.next_if_symbol(&[ "^" ]).is_some();
+    let count = parse_additive_expression(tokens, symbol_table, ast)?;
+    tokens.expect_symbol("in")?;
+
+    let target_type = match tokens.next() {
+        Some(Tokens::Identifier(name)) if name.as_str() == "Player" => ExpressionType::Player,
+        Some(Tokens::Identifier(name)) => return Err(ParseError::UnknownTargetType(Rc::from(name.as_str()))),
+        Some(token) => return Err(ParseError::UnexpectedToken(token)),
+        None => return Err(ParseError::UnexpectedEndOfFile)
+    };
+    tokens.expect_symbol(")")?;
+
+    Ok(ast.add_target(count, up_to, target_type)?)
+}
@@ -0,0 +1,421 @@
+#[cfg(test)]
+mod tests {
+    use crate::game_zones::types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind, TriggerEvent};
+    use crate::game_zones::zone::Zone;
+    use crate::parsing::ast::Ast;
+    use crate::parsing::card::Rarity;
+    use crate::parsing::expressions::{ExpressionResult, ExpressionType};
+    use crate::parsing::parser::{parse_card, parse_card_located, parse_expression, ParseError};
+    use crate::parsing::script_context::ScriptContext;
+    use crate::parsing::statements::{ModifierKind, Statement};
+    use crate::parsing::symbol_table::SymbolTable;
+    use crate::parsing::tokenizer::tokenize;
+    use test_case::test_case;
+
+    /// Rolls the maximum every time and always targets player 1
+    #[derive(Default)]
+    struct TestContext {
+        damage_dealt: Vec<(i32, DamageType, ExpressionResult)>,
+        log: Vec<String>,
+        statuses: Vec<(StatusKind, i32, ExpressionResult)>,
+        modifiers: Vec<(ModifiedValue, ModifierOperation, Option<ExpressionResult>)>,
+        replacements: Vec<(ReplacedEvent, ReplacementOutcome, Option<u16>)>,
+        counters: u32
+    }
+
+    impl ScriptContext for TestContext {
+        fn roll(&mut self, dice: Dice) -> u16 {
+            dice.get_sides() as u16
+        }
+
+        fn choose_targets(&mut self, _target_type: &ExpressionType, count: u16, _up_to: bool) -> Vec<ExpressionResult> {
+            (0 .. count).map(|_| ExpressionResult::Player(1)).collect()
+        }
+
+        fn deal_damage(&mut self, amount: i32, damage_type: DamageType, target: &ExpressionResult) {
+            self.damage_dealt.push((amount, damage_type, target.clone()));
+        }
+
+        fn apply_status(&mut self, status: StatusKind, turns: i32, target: &ExpressionResult) {
+            self.statuses.push((status, turns, target.clone()));
+        }
+
+        fn add_modifier(&mut self, value: ModifiedValue, operation: ModifierOperation, target: Option<&ExpressionResult>) {
+            self.modifiers.push((value, operation, target.cloned()));
+        }
+
+        fn add_replacement(&mut self, event: ReplacedEvent, outcome: ReplacementOutcome, uses: Option<u16>) {
+            self.replacements.push((event, outcome, uses));
+        }
+
+        fn get_property(&mut self, target: &ExpressionResult, property: &str) -> ExpressionResult {
+            match (target, property) {
+                (ExpressionResult::Player(1), "name") => ExpressionResult::Text("Bob".into()),
+                _ => ExpressionResult::Integer(20)
+            }
+        }
+
+        fn log(&mut self, message: &str) {
+            self.log.push(message.to_string());
+        }
+
+        fn counter(&mut self) -> bool {
+            self.counters += 1;
+            true
+        }
+    }
+
+    fn evaluate(script: &str) -> ExpressionResult {
+        let symbol_table = SymbolTable::new();
+        let mut ast = Ast::default();
+        let expression = parse_expression(tokenize(script).unwrap().into_iter(), &symbol_table, &mut ast).unwrap();
+        ast.evaluate(expression, &symbol_table, &mut TestContext::default())
+    }
+
+    #[test_case("2 + 3", 5 ; "Addition")]
+    #[test_case("2 + 3 * 4", 14 ; "Factor binds tighter")]
+    #[test_case("(2 + 3) * 4", 20 ; "Parentheses")]
+    #[test_case("10 - 4 - 3", 3 ; "Subtraction is left associative")]
+    #[test_case("-2 + 5", 3 ; "Negation")]
+    #[test_case("7 / 2", 3 ; "Integer division")]
+    #[test_case("7 / 0", 0 ; "Division by zero")]
+    #[test_case("2d6 + 1", 13 ; "Dice roll")]
+    fn evaluate_integer(script: &str, expected: i32) {
+        assert_eq!(evaluate(script), ExpressionResult::Integer(expected));
+    }
+
+    #[test_case("$turn + $turn", i32::MAX ; "Sum")]
+    #[test_case("$turn * 2d6", i32::MAX ; "Product")]
+    #[test_case("$mana - 1", i32::MIN ; "Difference")]
+    #[test_case("-$mana", i32::MAX ; "Negation")]
+    #[test_case("$mana / -1", 0 ; "Quotient")]
+    fn arithmetic_saturates(script: &str, expected: i32) {
+        let mut symbol_table = SymbolTable::with_built_ins();
+        symbol_table.assign("$turn", ExpressionResult::Integer(i32::MAX)).unwrap();
+        symbol_table.assign("$mana", ExpressionResult::Integer(i32::MIN)).unwrap();
+        let mut ast = Ast::default();
+        let expression = parse_expression(tokenize(script).unwrap().into_iter(), &symbol_table, &mut ast).unwrap();
+        assert_eq!(ast.evaluate(expression, &symbol_table, &mut TestContext::default()), ExpressionResult::Integer(expected));
+    }
+
+    #[test_case("1 < 2", true ; "Less than")]
+    #[test_case("2 <= 1", false ; "Less than or equal")]
+    #[test_case("1 + 1 == 2", true ; "Equality")]
+    #[test_case("fire ~= ice", true ; "Not equal")]
+    #[test_case("true & ~false", true ; "And with not")]
+    #[test_case("false | 1 > 2", false ; "Or")]
+    fn evaluate_boolean(script: &str, expected: bool) {
+        assert_eq!(evaluate(script), ExpressionResult::Boolean(expected));
+    }
+
+    #[test]
+    fn evaluate_unique_concatenation() {
+        let expected: ExpressionResult = ExpressionResult::List([
+            ExpressionResult::DamageType(DamageType::new("fire")),
+            ExpressionResult::DamageType(DamageType::new("ice")),
+        ].into());
+        assert_eq!(evaluate("[fire, ice] +! [fire]"), expected);
+    }
+
+    #[test]
+    fn evaluate_except() {
+        let expected: ExpressionResult = ExpressionResult::List([ ExpressionResult::Integer(1) ].into());
+        assert_eq!(evaluate("[1, 2, 2] - 2"), expected);
+    }
+
+    #[test_case("\"a\" + \"b\"", "ab" ; "Text concatenation")]
+    #[test_case("\"{1 + 2}d{6} {fire}\"", "3d6 fire" ; "Interpolated values")]
+    #[test_case("\"{[1, 2]} {true}\"", "[1, 2] true" ; "Interpolated list")]
+    #[test_case("\"{target(1 in Player)} has {target(1 in Player).hp}\"", "Bob has 20" ; "Interpolated player")]
+    fn evaluate_text(script: &str, expected: &str) {
+        assert_eq!(evaluate(script), ExpressionResult::Text(expected.into()));
+    }
+
+    #[test_case("1 + true" ; "Integer plus boolean")]
+    #[test_case("\"a\" - \"b\"" ; "Text subtraction")]
+    #[test_case("1 +! 2" ; "Unique concatenation of integers")]
+    #[test_case("1 + [1]" ; "Integer plus list")]
+    #[test_case("~1" ; "Not an integer")]
+    #[test_case("[1, fire]" ; "Mixed list")]
+    fn invalid_expression_types(script: &str) {
+        let result = parse_expression(tokenize(script).unwrap().into_iter(), &SymbolTable::new(), &mut Ast::default());
+        assert!(matches!(result, Err(ParseError::InvalidExpression(_))));
+    }
+
+    #[test]
+    fn undeclared_identifier() {
+        let result = parse_expression(tokenize("$x + 1").unwrap().into_iter(), &SymbolTable::new(), &mut Ast::default());
+        assert!(matches!(result, Err(ParseError::UndeclaredIdentifier(_))));
+    }
+
+    #[test]
+    fn parse_and_run_card() {
+        let script = "\
+            #attack
+            [3 - $discount]: {
+                // firebolt
+                $ = target(1 in Player);
+                $bonus = 2;
+                if $turn > 1 {
+                    $bonus = 4;
+                }
+                1d4 + $bonus fire => $;
+            }
+            ";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+        assert!(card.has_tag("attack"));
+
+        let mut symbol_table = SymbolTable::with_built_ins();
+        symbol_table.assign("$turn", ExpressionResult::Integer(2)).unwrap();
+        symbol_table.assign("$discount", ExpressionResult::Integer(1)).unwrap();
+        let mut context = TestContext::default();
+
+        assert_eq!(card.evaluate_cost(&symbol_table, &mut context), 2);
+
+        card.execute(&mut symbol_table, &mut context);
+        assert_eq!(context.damage_dealt, vec![ (8, DamageType::new("fire"), ExpressionResult::Player(1)) ]);
+    }
+
+    #[test]
+    fn parse_metadata() {
+        let script = "\
+            @name \"Firebolt\"
+            @rarity rare
+            @set \"BASE\"
+            @text \"Deal 1d10 fire damage.\"
+            #attack [1]: { 1d10 fire => target(1 in Player); }
+            ";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+
+        let metadata = card.get_metadata();
+        assert_eq!(card.get_name(), Some("Firebolt"));
+        assert_eq!(metadata.rarity, Rarity::Rare);
+        assert_eq!(metadata.set_code.as_deref(), Some("BASE"));
+        assert_eq!(metadata.text.as_deref(), Some("Deal 1d10 fire damage."));
+        assert_eq!(metadata.flavor, None);
+    }
+
+    #[test]
+    fn log_interpolated_message() {
+        let script = "[1]: { $ = target(1 in Player); $dmg = 2d4; $dmg fire => $; log \"Fireball hits {$.name} for {$dmg}\"; }";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+
+        let mut context = TestContext::default();
+        card.execute(&mut SymbolTable::with_built_ins(), &mut context);
+
+        assert_eq!(context.log, vec![ String::from("Fireball hits Bob for 8") ]);
+    }
+
+    #[test]
+    fn counter_statement() {
+        let card = parse_card(tokenize("#reaction [1]: { counter; if false { counter; } }").unwrap().into_iter()).unwrap();
+
+        let mut context = TestContext::default();
+        card.execute(&mut SymbolTable::with_built_ins(), &mut context);
+
+        assert!(card.has_tag("reaction"));
+        assert_eq!(context.counters, 1);
+    }
+
+    #[test]
+    fn status_statement() {
+        let card = parse_card(tokenize("[1]: { apply burning 1 + 1 => target(1 in Player); apply poisoned 3 => [target(1 in Player)]; }").unwrap().into_iter()).unwrap();
+
+        let mut context = TestContext::default();
+        card.execute(&mut SymbolTable::with_built_ins(), &mut context);
+
+        assert_eq!(context.statuses, vec![
+            (StatusKind::Burning, 2, ExpressionResult::Player(1)),
+            (StatusKind::Poisoned, 3, ExpressionResult::Player(1))
+        ]);
+    }
+
+    #[test]
+    fn modifier_statement() {
+        let script = "#aura [1]: { modify fire damage + 2; modify ice damage taken / 2; modify damage * 1 + 1; modify cost - 1 => target(1 in Player); modify hp = 5; }";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+
+        let mut context = TestContext::default();
+        card.execute(&mut SymbolTable::with_built_ins(), &mut context);
+
+        assert_eq!(context.modifiers, vec![
+            (ModifiedValue::DamageDealt(Some(DamageType::new("fire"))), ModifierOperation::Add(2), None),
+            (ModifiedValue::DamageTaken(Some(DamageType::new("ice"))), ModifierOperation::Divide(2), None),
+            (ModifiedValue::DamageDealt(None), ModifierOperation::Multiply(2), None),
+            (ModifiedValue::Cost, ModifierOperation::Add(-1), Some(ExpressionResult::Player(1))),
+            (ModifiedValue::Property("hp".into()), ModifierOperation::Set(5), None)
+        ]);
+        let kinds: Vec<ModifierKind> = card.get_body().iter()
+            .filter_map(|&statement| match card.get_ast()[statement] {
+                Statement::Modifier { operation, .. } => Some(operation),
+                _ => None
+            })
+            .collect();
+        assert_eq!(kinds, vec![ ModifierKind::Add, ModifierKind::Divide, ModifierKind::Multiply, ModifierKind::Subtract, ModifierKind::Set ]);
+    }
+
+    #[test]
+    fn replacement_statement() {
+        let script = "[1]: { instead next fire damage prevent; instead discard exile; instead next 2 damage redirect target(1 in Player); }";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+
+        let mut context = TestContext::default();
+        card.execute(&mut SymbolTable::with_built_ins(), &mut context);
+
+        assert_eq!(context.replacements, vec![
+            (ReplacedEvent::Damage(Some(DamageType::new("fire"))), ReplacementOutcome::Prevent, Some(1)),
+            (ReplacedEvent::Move(Zone::Discard), ReplacementOutcome::MoveTo(Zone::Exile), None),
+            (ReplacedEvent::Damage(None), ReplacementOutcome::Redirect(1), Some(2))
+        ]);
+    }
+
+    #[test]
+    fn triggered_abilities() {
+        let script = "#aura [1]: { $x = 1; } when play { log \"echo\"; } when turn start { $x = true; log \"{$x}\"; }";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+
+        let events: Vec<TriggerEvent> = card.get_abilities().iter().map(|ability| ability.event).collect();
+        assert_eq!(events, vec![ TriggerEvent::Play, TriggerEvent::TurnStart ]);
+
+        let mut context = TestContext::default();
+        card.execute(&mut SymbolTable::with_built_ins(), &mut context);
+        assert!(context.log.is_empty());
+        for ability in [ 1, 0, 2 ] {
+            card.execute_ability(ability, &mut SymbolTable::with_built_ins(), &mut context);
+        }
+        assert_eq!(context.log, vec![ String::from("true"), String::from("echo") ]);
+    }
+
+    #[test]
+    fn funcs() {
+        let script = "\
+            func half($amount: integer) = ($amount + 1) / 2;
+            func hurts($amounts: list of integer, $type: damage type) = \"{$amounts} {$type}\";
+            func bolt() = half(1d6 + 5);
+            [half(3)]: {
+                $hit = bolt();
+                $hit fire => target(1 in Player);
+                log hurts([ $hit, half($hit) ], fire);
+            }
+            ";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+
+        let mut symbol_table = SymbolTable::with_built_ins();
+        let mut context = TestContext::default();
+        assert_eq!(card.evaluate_cost(&symbol_table, &mut context), 2);
+
+        card.execute(&mut symbol_table, &mut context);
+        assert_eq!(context.damage_dealt, vec![ (6, DamageType::new("fire"), ExpressionResult::Player(1)) ]);
+        assert_eq!(context.log, vec![ String::from("[6, 3] fire") ]);
+    }
+
+    #[test_case("func f($x: integer) = $x; [f(true)]: { }" ; "Argument of the wrong type")]
+    #[test_case("func f($x: integer) = $x; [f(1, 2)]: { }" ; "Too many arguments")]
+    #[test_case("func f($x: integer) = $x; [f()]: { }" ; "Too few arguments")]
+    fn invalid_arguments(script: &str) {
+        let result = parse_card(tokenize(script).unwrap().into_iter());
+        assert!(matches!(result, Err(ParseError::InvalidArguments(name)) if &*name == "f"));
+    }
+
+    #[test_case("func f() = 1; func f() = 2; [1]: { }" ; "Func defined twice")]
+    #[test_case("func f($x: integer, $x: integer) = 1; [1]: { }" ; "Parameter named twice")]
+    fn duplicate_name(script: &str) {
+        let result = parse_card(tokenize(script).unwrap().into_iter());
+        assert!(matches!(result, Err(ParseError::DuplicateName(_))));
+    }
+
+    #[test_case("@name \"A\" @name \"B\" [1]: { }" ; "Duplicate metadata")]
+    #[test_case("@cost \"1\" [1]: { }" ; "Unknown metadata")]
+    #[test_case("@rarity mythic [1]: { }" ; "Unknown rarity")]
+    #[test_case("@name Firebolt [1]: { }" ; "Unquoted name")]
+    #[test_case("[true]: { }" ; "Boolean cost")]
+    #[test_case("#attack { }" ; "Missing cost")]
+    #[test_case("[1]: { 1 fire => 2; }" ; "Damage to an integer")]
+    #[test_case("[1]: { $x = 1; $x = true; }" ; "Assignment changes type")]
+    #[test_case("[1]: { if 1 { } }" ; "Integer condition")]
+    #[test_case("[1]: { 1 => target(1 in Dragon); }" ; "Unknown target type")]
+    #[test_case("[1]: {" ; "Unclosed body")]
+    #[test_case("[1]: { log 1; }" ; "Log an integer")]
+    #[test_case("[1]: { log \"{$x}\"; }" ; "Interpolate undeclared variable")]
+    #[test_case("[1]: { log \"{target(1 in Player).armor}\"; }" ; "Unknown property")]
+    #[test_case("@name \"{1}\" [1]: { }" ; "Interpolated metadata")]
+    #[test_case("[1]: { counter }" ; "Counter without semicolon")]
+    #[test_case("[1]: { apply soggy 2 => target(1 in Player); }" ; "Unknown status")]
+    #[test_case("[1]: { apply frozen true => target(1 in Player); }" ; "Boolean duration")]
+    #[test_case("[1]: { apply frozen 2 => 1; }" ; "Status on an integer")]
+    #[test_case("[1]: { modify armor + 1; }" ; "Unknown modified value")]
+    #[test_case("[1]: { modify name = 1; }" ; "Modify text property")]
+    #[test_case("[1]: { modify fire cost - 1; }" ; "Typed cost")]
+    #[test_case("[1]: { modify cost + true; }" ; "Boolean modifier amount")]
+    #[test_case("[1]: { modify cost 1; }" ; "Missing modifier operation")]
+    #[test_case("[1]: { instead damage exile; }" ; "Exile damage")]
+    #[test_case("[1]: { instead discard prevent; }" ; "Prevent a zone move")]
+    #[test_case("[1]: { instead discard redirect target(1 in Player); }" ; "Redirect a zone move")]
+    #[test_case("[1]: { instead fire discard exile; }" ; "Typed zone move")]
+    #[test_case("[1]: { instead lunch prevent; }" ; "Unknown replaced event")]
+    #[test_case("[1]: { instead damage redirect 1; }" ; "Redirect to an integer")]
+    #[test_case("#aura [1]: { } when { }" ; "Ability without an event")]
+    #[test_case("#aura [1]: { } when turn end { }" ; "Unknown turn event")]
+    #[test_case("#aura [1]: { } when play" ; "Ability without a body")]
+    #[test_case("#aura [1]: { $x = 1; } when play { log \"{$x}\"; }" ; "Ability reads the body's variables")]
+    #[test_case("func f() = $turn; [1]: { }" ; "Func reads a built-in variable")]
+    #[test_case("func f() = f(); [1]: { }" ; "Func calls itself")]
+    #[test_case("[f()]: { } " ; "Undefined func")]
+    #[test_case("func f($x: number) = $x; [1]: { }" ; "Unknown parameter type")]
+    #[test_case("func f($x) = $x; [1]: { }" ; "Parameter without a type")]
+    #[test_case("func f() = 1 [1]: { }" ; "Func without semicolon")]
+    #[test_case("@name \"Zap\" func f() = 1; [1]: { }" ; "Func after the metadata")]
+    fn invalid_card(script: &str) {
+        assert!(parse_card(tokenize(script).unwrap().into_iter()).is_err());
+    }
+
+    #[test]
+    fn unknown_trigger() {
+        let result = parse_card(tokenize("#aura [1]: { } when lunch { }").unwrap().into_iter());
+        assert!(matches!(result, Err(ParseError::UnknownTrigger(name)) if &*name == "lunch"));
+    }
+
+    #[test]
+    fn unknown_damage_type() {
+        let result = parse_card(tokenize("[1]: { 2 sonic => target(1 in Player); }").unwrap().into_iter());
+        assert!(matches!(result, Err(ParseError::UnknownDamageType(name)) if &*name == "sonic"));
+    }
+
+    #[test_case("[1]: { 2 fire => ; }", 8 ; "Unexpected token")]
+    #[test_case("[true]: { }", 1 ; "Error after an expression")]
+    #[test_case("[1]: { 2 fire => target(1 in Player);", 15 ; "End of file")]
+    fn parse_error_location(script: &str, index: usize) {
+        let (_, located) = parse_card_located(tokenize(script).unwrap().into_iter()).err().unwrap();
+        assert_eq!(located, index);
+    }
+
+    #[test]
+    fn doc_comments_attach_to_the_card() {
+        let card = parse_card(tokenize("/// Burns everything.\n///\n/// Playtest at 4 mana?\n@name \"Inferno\" [5]: { }").unwrap().into_iter()).unwrap();
+        assert_eq!(card.get_doc(), Some("Burns everything.\n\nPlaytest at 4 mana?"));
+        assert_eq!(card.get_name(), Some("Inferno"));
+
+        let card = parse_card(tokenize("// not a doc\n/* nor this */ [1]: { }").unwrap().into_iter()).unwrap();
+        assert_eq!(card.get_doc(), None);
+    }
+
+    #[test]
+    fn doc_comments_attach_to_funcs() {
+        let script = "/// Rounds up.\nfunc half($x: integer) = ($x + 1) / 2;\nfunc one() = 1;\n/// The card.\n[half(one())]: { }";
+        let card = parse_card(tokenize(script).unwrap().into_iter()).unwrap();
+        let ast = card.get_ast();
+
+        let docs: Vec<Option<&str>> = ast.func_ids().map(|func| ast[func].doc.as_deref()).collect();
+        assert_eq!(docs, vec![ Some("Rounds up."), None ]);
+        assert_eq!(ast[ast.find_func("half").unwrap()].get_signature(), "half($x: integer)");
+        assert_eq!(card.get_doc(), Some("The card."));
+    }
+
+    #[test_case("[1]: { /// nothing to document\n log \"hi\"; }" ; "In the body")]
+    #[test_case("@name \"Zap\" /// too late\n [1]: { }" ; "After the metadata")]
+    #[test_case("func f() = 1 /// not here\n; [1]: { }" ; "Inside a func")]
+    fn misplaced_doc_comment(script: &str) {
+        let err = parse_card(tokenize(script).unwrap().into_iter()).err().unwrap();
+        assert_eq!(err.to_string(), "doc comments only go before the card or a func");
+    }
+}
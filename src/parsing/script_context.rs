@@ -0,0 +1,26 @@
+use crate::game_zones::types::{DamageType, Dice, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+
+use super::expressions::{ExpressionResult, ExpressionType};
+
+/// The outside world as seen by a running script.
+/// Expressions and statements only describe what should happen; the context decides how (and to whom).
+pub trait ScriptContext {
+    /// Roll a single die
+    fn roll(&mut self, dice: Dice) -> u16;
+    /// Ask whoever is playing the card to pick `count` targets of the given type (or up to `count`)
+    fn choose_targets(&mut self, target_type: &ExpressionType, count: u16, up_to: bool) -> Vec<ExpressionResult>;
+    /// Deal damage of a given type to a target
+    fn deal_damage(&mut self, amount: i32, damage_type: DamageType, target: &ExpressionResult);
+    /// Put a status on a target for a number of turns
+    fn apply_status(&mut self, status: StatusKind, turns: i32, target: &ExpressionResult);
+    /// Keep changing one of a player's values from now on; without a target, the value of whoever played the card
+    fn add_modifier(&mut self, value: ModifiedValue, operation: ModifierOperation, target: Option<&ExpressionResult>);
+    /// Change an event that would happen to whoever played the card; `uses` limits how many times
+    fn add_replacement(&mut self, event: ReplacedEvent, outcome: ReplacementOutcome, uses: Option<u16>);
+    /// Read a property (e.g. `hp`) of a target; the parser has already checked that it exists
+    fn get_property(&mut self, target: &ExpressionResult, property: &str) -> ExpressionResult;
+    /// Write a message to the duel's log
+    fn log(&mut self, message: &str);
+    /// Remove the topmost card or ability still waiting on the stack without resolving it; false when there is none
+    fn counter(&mut self) -> bool;
+}
@@ -0,0 +1,129 @@
+use std::rc::Rc;
+
+use crate::game_zones::types::{DamageType, ModifiedValue, ModifierOperation, ReplacedEvent, ReplacementOutcome, StatusKind};
+
+use super::{ast::{Ast, ExpressionId, StatementId}, expressions::ExpressionResult, script_context::ScriptContext, symbol_table::SymbolTable};
+
+/// A node of the statement tree; its expressions and inner statements are other nodes of the same `Ast`
+#[derive(Debug, Clone)]
+pub enum Statement {
+    /// `$x = expression;`. `declares` when it's the first assignment to this name in its scope,
+    /// so it has to be declared at runtime as well.
+    Assignment { name: Rc<str>, value: ExpressionId, declares: bool },
+    /// `1d4 + 4 fire => $;`
+    Damage { amount: ExpressionId, damage_type: DamageType, target: ExpressionId },
+    /// `apply burning 2 => $;`
+    Status { status: StatusKind, turns: ExpressionId, target: ExpressionId },
+    /// `modify fire damage + 2;` or `modify cost - 1 => target(1 in Player);`
+    Modifier { value: ModifiedValue, operation: ModifierKind, amount: ExpressionId, target: Option<ExpressionId> },
+    /// `instead next fire damage prevent;` or `instead discard exile;`.
+    /// `redirect` is the player to redirect damage to, in place of the outcome.
+    Replacement { event: ReplacedEvent, outcome: ReplacementOutcome, redirect: Option<ExpressionId>, uses: Option<u16> },
+    /// `log "Fireball hits {$.name}";`
+    Log(ExpressionId),
+    /// `counter;` removes the card or ability this one was played in response to
+    Counter,
+    /// `if condition { ... } else { ... }`
+    If { condition: ExpressionId, then_body: Vec<StatementId>, else_body: Vec<StatementId> }
+}
+
+/// The operator of a `modify` statement; its amount is only known once the statement runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModifierKind {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Set
+}
+
+impl ModifierKind {
+    pub fn with_amount(self, amount: i32) -> ModifierOperation {
+        match self {
+            ModifierKind::Add => ModifierOperation::Add(amount),
+            ModifierKind::Subtract => ModifierOperation::Add(amount.saturating_neg()),
+            ModifierKind::Multiply => ModifierOperation::Multiply(amount),
+            ModifierKind::Divide => ModifierOperation::Divide(amount),
+            ModifierKind::Set => ModifierOperation::Set(amount)
+        }
+    }
+}
+
+/// Running statements
+impl Ast {
+    pub fn execute(&self, id: StatementId, symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        match &self[id] {
+            Statement::Assignment { name, value, declares } => {
+                let result = self.evaluate(*value, symbol_table, context);
+                if *declares {
+                    symbol_table.declare(name, self.get_type(*value).clone());
+                }
+                symbol_table.assign(name, result)
+                    .expect("Assignment was type-checked when the script was parsed.");
+            },
+            Statement::Damage { amount, damage_type, target } => {
+                let amount = self.evaluate_integer(*amount, symbol_table, context);
+                let target = self.evaluate(*target, symbol_table, context);
+                for_each_target(target, |target| context.deal_damage(amount, damage_type.clone(), target));
+            },
+            Statement::Status { status, turns, target } => {
+                let turns = self.evaluate_integer(*turns, symbol_table, context);
+                let target = self.evaluate(*target, symbol_table, context);
+                for_each_target(target, |target| context.apply_status(*status, turns, target));
+            },
+            Statement::Modifier { value, operation, amount, target } => {
+                // the amount is fixed when the modifier is added, not every time it applies
+                let operation = operation.with_amount(self.evaluate_integer(*amount, symbol_table, context));
+                match target {
+                    Some(target) => {
+                        let target = self.evaluate(*target, symbol_table, context);
+                        for_each_target(target, |target| context.add_modifier(value.clone(), operation, Some(target)));
+                    },
+                    None => context.add_modifier(value.clone(), operation, None)
+                }
+            },
+            Statement::Replacement { event, outcome, redirect, uses } => {
+                let outcome = match redirect {
+                    Some(target) => match self.evaluate(*target, symbol_table, context) {
+                        ExpressionResult::Player(player) => ReplacementOutcome::Redirect(player),
+                        other => panic!("Damage can only be redirected to a player, not {:?}", other)
+                    },
+                    None => *outcome
+                };
+                context.add_replacement(event.clone(), outcome, *uses);
+            },
+            Statement::Log(message) => {
+                let message = self.evaluate(*message, symbol_table, context);
+                context.log(&message.to_string());
+            },
+            Statement::Counter => {
+                context.counter();
+            },
+            Statement::If { condition, then_body, else_body } => {
+                let body = if self.evaluate_boolean(*condition, symbol_table, context) {
+                    then_body
+                } else {
+                    else_body
+                };
+                self.execute_block(body, symbol_table, context);
+            }
+        }
+    }
+
+    /// Run statements in their own scope
+    pub fn execute_block(&self, body: &[StatementId], symbol_table: &mut SymbolTable, context: &mut dyn ScriptContext) {
+        symbol_table.push_scope();
+        for &statement in body {
+            self.execute(statement, symbol_table, context);
+        }
+        symbol_table.pop_scope();
+    }
+}
+
+/// A list of targets is each of them in turn
+fn for_each_target(target: ExpressionResult, mut apply: impl FnMut(&ExpressionResult)) {
+    match target {
+        ExpressionResult::List(targets) => targets.iter().for_each(apply),
+        target => apply(&target)
+    }
+}
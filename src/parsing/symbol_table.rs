@@ -1 +1,106 @@
-pub struct SymbolTable {}
\ No newline at end of file
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::expressions::{ExpressionResult, ExpressionType};
+
+/// A named value in scope: its type is known while parsing, its value only once the script runs
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    symbol_type: ExpressionType,
+    value: Option<ExpressionResult>
+}
+
+impl Symbol {
+    pub fn get_type(&self) -> &ExpressionType {
+        &self.symbol_type
+    }
+
+    pub fn get_value(&self) -> Option<&ExpressionResult> {
+        self.value.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolTableError {
+    /// Symbol was never declared
+    Undeclared(Rc<str>),
+    /// Symbol was declared, but the new value does not match its type
+    TypeMismatch(Rc<str>)
+}
+
+/// Stack of scopes mapping identifiers to symbols (innermost scope last)
+#[derive(Debug, Clone)]
+pub struct SymbolTable {
+    scopes: Vec<HashMap<Rc<str>, Symbol>>
+}
+
+/// Variables every card can read without declaring them
+pub const BUILT_IN_SYMBOLS: [(&str, ExpressionType); 3] = [
+    ("$turn", ExpressionType::Integer),
+    ("$mana", ExpressionType::Integer),
+    ("$discount", ExpressionType::Integer),
+];
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        SymbolTable::new()
+    }
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable { scopes: vec![ HashMap::new() ] }
+    }
+
+    /// New table with the built-in variables declared in the global scope
+    pub fn with_built_ins() -> Self {
+        let mut table = SymbolTable::new();
+        for (name, symbol_type) in BUILT_IN_SYMBOLS {
+            table.declare(name, symbol_type);
+        }
+        table
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        // never pop the global scope
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    /// Declare a symbol in the innermost scope, shadowing any outer symbol with the same name
+    pub fn declare(&mut self, name: &str, symbol_type: ExpressionType) {
+        let scope = self.scopes.last_mut().expect("Symbol table always has a global scope");
+        scope.insert(Rc::from(name), Symbol { symbol_type, value: None });
+    }
+
+    /// Assign a value to the nearest symbol with this name
+    pub fn assign(&mut self, name: &str, value: ExpressionResult) -> Result<(), SymbolTableError> {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(symbol) = scope.get_mut(name) {
+                if !value.is_of_type(&symbol.symbol_type) {
+                    return Err(SymbolTableError::TypeMismatch(Rc::from(name)));
+                }
+                symbol.value = Some(value);
+                return Ok(());
+            }
+        }
+        Err(SymbolTableError::Undeclared(Rc::from(name)))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<&ExpressionType> {
+        self.get(name).map(Symbol::get_type)
+    }
+
+    pub fn get_value(&self, name: &str) -> Option<&ExpressionResult> {
+        self.get(name).and_then(Symbol::get_value)
+    }
+}
@@ -1,6 +1,6 @@
 use core::str::Chars;
 use core::convert::From;
-use crate::{game_zones::types::{DamageTypeParseError, ParseDiceError}, parsing::tokens::*};
+use crate::{game_zones::{damage_types::DamageTypeRegistry, types::{DamageTypeParseError, ParseDiceError}}, parsing::tokens::*};
 
 #[derive(Debug)]
 pub enum TokenizerError {
@@ -8,9 +8,33 @@ pub enum TokenizerError {
     ParseIntError(std::num::ParseIntError),
     ParseDamageTypeError(DamageTypeParseError),
     ParseDiceError(ParseDiceError),
+    /// String literal is missing its closing quote
+    UnterminatedString,
+    /// `/*` without the `*/` that closes it
+    UnterminatedComment,
+    /// Backslash followed by something that can't be escaped
+    InvalidEscape(char),
+    /// `{ }` inside a string that is empty, unclosed or contains another string
+    InvalidInterpolation,
     InvalidSyntax
 }
 
+impl std::fmt::Display for TokenizerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenizerError::ParseBoolError(err) => write!(f, "invalid boolean: {}", err),
+            TokenizerError::ParseIntError(err) => write!(f, "invalid number: {}", err),
+            TokenizerError::ParseDamageTypeError(_) => write!(f, "invalid damage type"),
+            TokenizerError::ParseDiceError(_) => write!(f, "invalid dice"),
+            TokenizerError::UnterminatedString => write!(f, "string is missing its closing quote"),
+            TokenizerError::UnterminatedComment => write!(f, "comment is missing its closing '*/'"),
+            TokenizerError::InvalidEscape(c) => write!(f, "'\\{}' is not an escape", c),
+            TokenizerError::InvalidInterpolation => write!(f, "'{{ }}' in a string must hold an expression"),
+            TokenizerError::InvalidSyntax => write!(f, "unexpected character")
+        }
+    }
+}
+
 impl From<core::str::ParseBoolError> for TokenizerError {
     fn from(err: core::str::ParseBoolError) -> Self {
         TokenizerError::ParseBoolError(err)
@@ -35,10 +59,61 @@ impl From<ParseDiceError> for TokenizerError {
     }
 }
 
+/// Tokenize with the built-in damage types
 pub fn tokenize(script: &str) -> Result<Vec<Tokens>, TokenizerError> {
+    tokenize_with(script, &DamageTypeRegistry::default())
+}
+
+/// Tokenize, recognising the registry's damage types
+pub fn tokenize_with(script: &str, damage_types: &DamageTypeRegistry) -> Result<Vec<Tokens>, TokenizerError> {
+    tokenize_spanned(script, damage_types)
+        .map(|tokens| tokens.into_iter().map(|(token, _)| token).collect())
+        .map_err(|(err, _)| err)
+}
+
+/// Byte range of something in a script
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span {
+    /// 1-based line and column (in characters) where it starts
+    pub fn get_start(self, script: &str) -> (usize, usize) {
+        line_column(script, self.start)
+    }
+
+    /// 1-based line and column (in characters) just past its end
+    pub fn get_end(self, script: &str) -> (usize, usize) {
+        line_column(script, self.end)
+    }
+}
+
+fn line_column(script: &str, offset: usize) -> (usize, usize) {
+    let before = &script[.. offset.min(script.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    (line, column)
+}
+
+/// Like `tokenize_with`, but also says where each token is (`EOF` is an empty span at the end).
+/// On failure, the span covers the token that couldn't be read, as far as it got.
+pub fn tokenize_spanned(script: &str, damage_types: &DamageTypeRegistry) -> Result<Vec<(Tokens, Span)>, (TokenizerError, Span)> {
+    tokenize_spans(script, damage_types, false)
+}
+
+/// Like `tokenize_spanned`, but keeps each comment as a `Comment`: a `//` one spans up to the end of its line,
+/// a `/* */` one up to the `*/` that closes it
+pub fn tokenize_with_comments(script: &str, damage_types: &DamageTypeRegistry) -> Result<Vec<(Tokens, Span)>, (TokenizerError, Span)> {
+    tokenize_spans(script, damage_types, true)
+}
+
+fn tokenize_spans(script: &str, damage_types: &DamageTypeRegistry, keep_comments: bool) -> Result<Vec<(Tokens, Span)>, (TokenizerError, Span)> {
     let mut tokens = vec![ ];
     let mut chars = script.chars();
     let mut next: Box<Option<char>> = Box::new(None);
+    let offset = |chars: &Chars| script.len() - chars.as_str().len();
         
     loop {
         let first: char;
@@ -55,26 +130,31 @@ pub fn tokenize(script: &str) -> Result<Vec<Tokens>, TokenizerError> {
             break;
         }
 
-        let token = read_next_token(first, &mut chars, &mut next)?;
-        if let Tokens::Comment = token {
-            // comments won't get pushed: just read until newline or eof
-            read_until_newline_or_eof(&mut chars);
-        } else {
-            tokens.push(token);
+        // whether it came from the look-ahead or not, `first` has been read from `chars`
+        let start = offset(&chars) - first.len_utf8();
+        let token = read_next_token(first, &mut chars, &mut next, damage_types)
+            .map_err(|err| (err, Span { start, end: offset(&chars) }))?;
+        // comments are only kept when asked for
+        if keep_comments || !matches!(token, Tokens::Comment) {
+            let end = offset(&chars) - next.map_or(0, char::len_utf8);
+            tokens.push((token, Span { start, end }));
         }
     }
-    tokens.push(Tokens::EOF);
+    tokens.push((Tokens::EOF, Span { start: script.len(), end: script.len() }));
 
     Ok(tokens)
 }
 
-fn read_next_token(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Option<char>>) -> Result<Tokens, TokenizerError> {
+fn read_next_token(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Option<char>>, damage_types: &DamageTypeRegistry) -> Result<Tokens, TokenizerError> {
     if first.is_numeric() {
         let numeric_token = parse_numeric(first, chars, next_first)?;
         return Ok(numeric_token);
     } else if first.is_alphabetic() || first == '$' || first == '_' {
-        let token = parse_identifier_keyword_or_damage_type(first, chars, next_first)?;
+        let token = parse_identifier_keyword_or_damage_type(first, chars, next_first, damage_types)?;
         return Ok(token);
+    } else if first == '"' {
+        let string_token = parse_string(chars, damage_types)?;
+        return Ok(string_token);
     } else {
         let syntax_token = parse_syntax(first, chars, next_first)?;
         return Ok(syntax_token);
@@ -84,7 +164,7 @@ fn read_next_token(first: char, chars: &mut Chars, next_first: &mut impl AsMut<O
 fn parse_numeric(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Option<char>>) -> Result<Tokens, std::num::ParseIntError> {
     let mut char_vec = vec![ first ];
 
-    while let Some(next) = chars.next() {
+    for next in chars.by_ref() {
         if next.is_numeric() {
             char_vec.push(next);
         } else {
@@ -99,7 +179,7 @@ fn parse_numeric(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Opt
     Ok(Tokens::Numeric(int_token))
 }
 
-fn parse_identifier_keyword_or_damage_type(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Option<char>>) -> Result<Tokens, TokenizerError> {
+fn parse_identifier_keyword_or_damage_type(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Option<char>>, damage_types: &DamageTypeRegistry) -> Result<Tokens, TokenizerError> {
     let mut char_vec = vec![ first ];
 
     while let Some(next) = chars.next() {
@@ -123,14 +203,14 @@ fn parse_identifier_keyword_or_damage_type(first: char, chars: &mut Chars, next_
     else if let Ok(bool_token) = BoolToken::try_from(final_string.clone()) {
         return Ok(Tokens::Boolean(bool_token));
     }
-    else if let Ok(damage_type_token) = DamageTypeToken::try_from(final_string.clone()) {
+    else if let Ok(damage_type_token) = DamageTypeToken::new(&final_string, damage_types) {
         return Ok(Tokens::DamageType(damage_type_token));
     }
     Ok(Tokens::Identifier(StringToken::from(final_string)))
 }
 
 fn parse_dice_token(mut char_vec: Vec<char>, chars: &mut Chars, next_first: &mut impl AsMut<Option<char>>) -> Result<Tokens, TokenizerError> {
-    while let Some(next) = chars.next() {
+    for next in chars.by_ref() {
         if next.is_numeric() {
             char_vec.push(next);
         } else {
@@ -144,6 +224,77 @@ fn parse_dice_token(mut char_vec: Vec<char>, chars: &mut Chars, next_first: &mut
     Ok(Tokens::Dice(dice_token))
 }
 
+/// String literals support the escapes `\\ \" \n \t \{ \}` and `{expression}` interpolation
+fn parse_string(chars: &mut Chars, damage_types: &DamageTypeRegistry) -> Result<Tokens, TokenizerError> {
+    let mut raw = String::new();
+    let mut text = String::new();
+    let mut current = String::new();
+    let mut parts = vec![ ];
+
+    while let Some(next) = chars.next() {
+        match next {
+            '"' => {
+                if !current.is_empty() {
+                    parts.push(StringPart::Text(current.into()));
+                }
+                return Ok(Tokens::String(StringLiteralToken::new(&raw, &text, parts)));
+            },
+            '\\' => {
+                let escaped = chars.next().ok_or(TokenizerError::UnterminatedString)?;
+                let unescaped = match escaped {
+                    '\\' | '"' | '{' | '}' => escaped,
+                    'n' => '\n',
+                    't' => '\t',
+                    _ => return Err(TokenizerError::InvalidEscape(escaped))
+                };
+                raw.push(next);
+                raw.push(escaped);
+                text.push(unescaped);
+                current.push(unescaped);
+            },
+            '{' => {
+                let inner = read_interpolation(chars)?;
+                raw.push('{');
+                raw.push_str(&inner);
+                raw.push('}');
+                text.push('{');
+                text.push_str(&inner);
+                text.push('}');
+
+                let mut inner_tokens = tokenize_with(&inner, damage_types)?;
+                inner_tokens.pop(); // EOF
+                if inner_tokens.is_empty() {
+                    return Err(TokenizerError::InvalidInterpolation);
+                }
+                if !current.is_empty() {
+                    parts.push(StringPart::Text(std::mem::take(&mut current).into()));
+                }
+                parts.push(StringPart::Interpolation(inner_tokens.into()));
+            },
+            _ => {
+                raw.push(next);
+                text.push(next);
+                current.push(next);
+            }
+        }
+    }
+
+    Err(TokenizerError::UnterminatedString)
+}
+
+/// Source of an interpolated expression, up to (and consuming) the closing brace
+fn read_interpolation(chars: &mut Chars) -> Result<String, TokenizerError> {
+    let mut inner = String::new();
+    for next in chars.by_ref() {
+        match next {
+            '}' => return Ok(inner),
+            '"' => return Err(TokenizerError::InvalidInterpolation),
+            _ => inner.push(next)
+        }
+    }
+    Err(TokenizerError::UnterminatedString)
+}
+
 fn parse_syntax(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Option<char>>) -> Result<Tokens, TokenizerError> {
     if !SYMBOLS.contains(&first.to_string().as_str()) {
         return Err(TokenizerError::InvalidSyntax);
@@ -162,7 +313,9 @@ fn parse_syntax(first: char, chars: &mut Chars, next_first: &mut impl AsMut<Opti
     } else if first == '/' {
         if let Some(next) = chars.next() {
             if next == '/' {
-                return Ok(Tokens::Comment);
+                return Ok(parse_line_comment(chars));
+            } else if next == '*' {
+                return parse_block_comment(chars);
             } else {
                 *next_first.as_mut() = Some(next);
             }
@@ -220,14 +373,37 @@ fn consume_white_space(chars: &mut Chars) -> Option<char> {
     }
 }
 
-fn read_until_newline_or_eof(chars: &mut Chars) {
-    loop {
-        if let Some(next) = chars.next() {
-            if next == '\n' {
-                break;
-            }
-        } else {
-            break;
+/// After `//`: the rest of the line (but not the newline). `///` makes it a doc comment, `////` doesn't.
+fn parse_line_comment(chars: &mut Chars) -> Tokens {
+    let rest = chars.as_str();
+    let line = &rest[.. rest.find('\n').unwrap_or(rest.len())];
+    *chars = rest[line.len() ..].chars();
+
+    match line.strip_prefix('/') {
+        Some(doc) if !doc.starts_with('/') => {
+            let doc = doc.strip_prefix(' ').unwrap_or(doc).trim_end();
+            Tokens::DocComment(StringToken::from(doc))
+        },
+        _ => Tokens::Comment
+    }
+}
+
+/// After `/*`: up to the matching `*/`, as block comments nest
+fn parse_block_comment(chars: &mut Chars) -> Result<Tokens, TokenizerError> {
+    let mut depth = 1;
+    while depth > 0 {
+        match chars.next() {
+            Some('/') if chars.as_str().starts_with('*') => {
+                chars.next();
+                depth += 1;
+            },
+            Some('*') if chars.as_str().starts_with('/') => {
+                chars.next();
+                depth -= 1;
+            },
+            Some(_) => { },
+            None => return Err(TokenizerError::UnterminatedComment)
         }
     }
+    Ok(Tokens::Comment)
 }
\ No newline at end of file
@@ -1,10 +1,10 @@
 #[cfg(test)]
 mod tests {
+    use crate::game_zones::damage_types::DamageTypeRegistry;
     use crate::game_zones::types::DamageType;
-    use crate::parsing::tokenizer::tokenize;
-    use crate::parsing::tokens::{DamageTypeToken, DiceToken, IntToken, StringToken, Token, Tokens};
+    use crate::parsing::tokenizer::{tokenize, tokenize_spanned, tokenize_with, tokenize_with_comments, Span, TokenizerError};
+    use crate::parsing::tokens::{DamageTypeToken, DiceToken, IntToken, StringPart, StringToken, Token, Tokens};
     use test_case::test_case;
-    use std::mem::discriminant;
 
     #[test_case("" ; "Empty string")]
     #[test_case("        " ; "All whitespace")]
@@ -36,14 +36,14 @@ mod tests {
         assert!(matches!(vec[1], Tokens::EOF));
     }
 
-    #[test_case("fire", DamageType::Fire ; "Parsing fire")]
-    #[test_case("lightning", DamageType::Lightning ; "Parsing lightning")]
-    #[test_case("ice", DamageType::Ice ; "Parsing ice")]
-    #[test_case("psychic", DamageType::Psychic ; "Parsing psychic")]
-    #[test_case("acid", DamageType::Acid ; "Parsing acid")]
-    #[test_case("necrotic", DamageType::Necrotic ; "Parsing necrotic")]
-    #[test_case("divine", DamageType::Divine ; "Parsing divine")]
-    #[test_case("force", DamageType::Force ; "Parsing force")]
+    #[test_case("fire", DamageType::new("fire") ; "Parsing fire")]
+    #[test_case("lightning", DamageType::new("lightning") ; "Parsing lightning")]
+    #[test_case("ice", DamageType::new("ice") ; "Parsing ice")]
+    #[test_case("psychic", DamageType::new("psychic") ; "Parsing psychic")]
+    #[test_case("acid", DamageType::new("acid") ; "Parsing acid")]
+    #[test_case("necrotic", DamageType::new("necrotic") ; "Parsing necrotic")]
+    #[test_case("divine", DamageType::new("divine") ; "Parsing divine")]
+    #[test_case("force", DamageType::new("force") ; "Parsing force")]
     fn tokenize_damage_type(script: &str, expected_value: DamageType) {
         let result = tokenize(script);
 
@@ -53,13 +53,26 @@ mod tests {
         assert_eq!(vec.len(), 2);
 
         if let Tokens::DamageType(dmg_type_token) = &vec[0] {
-            assert_eq!(discriminant(&dmg_type_token.clone().get_value()), discriminant(&expected_value));
+            assert_eq!(dmg_type_token.clone().get_value(), expected_value);
         } else {
             panic!("Expected to parse a damage type token.");
         }
         assert!(matches!(vec[1], Tokens::EOF));
     }
 
+    #[test]
+    fn tokenize_registered_damage_type() {
+        let damage_types = DamageTypeRegistry::parse("sonic \"Sonic\"").unwrap();
+
+        let registered = tokenize_with("sonic", &damage_types).unwrap();
+        let built_in = tokenize("sonic").unwrap();
+
+        assert_eq!(registered[0], Tokens::DamageType(DamageTypeToken::new("sonic", &damage_types).unwrap()));
+        assert_eq!(built_in[0], Tokens::Identifier(StringToken::from("sonic")));
+        // untyped damage is written by leaving the type out
+        assert!(matches!(tokenize_with("none", &damage_types).unwrap()[0], Tokens::Identifier(_)));
+    }
+
     #[test_case("false", false ; "Parse false")]
     #[test_case("true", true ; "Parse true")]
     fn tokenize_boolean(script: &str, expected_value: bool) {
@@ -93,8 +106,8 @@ mod tests {
         let vec = result.unwrap();
 
         assert_eq!(vec.len(), expected_token_count);
-        for i in 0 .. (expected_token_count-1) {
-            assert!(matches!(vec[i], Tokens::Symbol(_)))
+        for token in vec.iter().take(expected_token_count - 1) {
+            assert!(matches!(token, Tokens::Symbol(_)))
         }
         assert!(matches!(vec[expected_token_count - 1], Tokens::EOF));
     }
@@ -124,6 +137,10 @@ mod tests {
 
     #[test_case("// this is some stuff 1+1" ; "Parse comment")]
     #[test_case("// this is some stuff 1+1 \n" ; "Parse comment newline")]
+    #[test_case("//// not a doc comment" ; "Parse four slashes")]
+    #[test_case("/* block */" ; "Parse block comment")]
+    #[test_case("/* outer /* inner */ still outer */" ; "Parse nested block comment")]
+    #[test_case("/* spans\n lines **/" ; "Parse multi-line block comment")]
     fn tokenize_comment(script: &str) {
         let result = tokenize(script);
 
@@ -133,6 +150,61 @@ mod tests {
         assert!(matches!(vec[0], Tokens::EOF));
     }
 
+    #[test_case("\"Firebolt\"", "Firebolt" ; "Parse string")]
+    #[test_case("\"\"", "" ; "Parse empty string")]
+    #[test_case("\"fire // not a comment\"", "fire // not a comment" ; "Parse string with keywords")]
+    #[test_case("\"say \\\"hi\\\"\\n\"", "say \"hi\"\n" ; "Parse escapes")]
+    #[test_case("\"\\{not interpolated\\}\"", "{not interpolated}" ; "Parse escaped braces")]
+    fn tokenize_string(script: &str, expected_value: &str) {
+        let result = tokenize(script);
+
+        assert!(result.is_ok());
+        let vec = result.unwrap();
+
+        assert_eq!(vec.len(), 2);
+        if let Tokens::String(string_token) = &vec[0] {
+            assert_eq!(string_token.get_plain_text(), Some(expected_value));
+        } else {
+            panic!("Expected to parse a string token.");
+        }
+        assert!(matches!(vec[1], Tokens::EOF));
+    }
+
+    #[test]
+    fn tokenize_interpolated_string() {
+        let result = tokenize("\"hits {$.name} for {dmg}\"");
+
+        assert!(result.is_ok());
+        let vec = result.unwrap();
+
+        if let Tokens::String(string_token) = &vec[0] {
+            assert_eq!(string_token.get_plain_text(), None);
+            let parts = string_token.get_parts();
+            assert_eq!(parts.len(), 4);
+            assert_eq!(parts[0], StringPart::Text("hits ".into()));
+            assert_eq!(parts[1], StringPart::Interpolation([
+                Tokens::Identifier(StringToken::from("$")),
+                Tokens::Symbol(StringToken::from(".")),
+                Tokens::Identifier(StringToken::from("name")),
+            ].into()));
+            assert_eq!(parts[2], StringPart::Text(" for ".into()));
+            assert_eq!(parts[3], StringPart::Interpolation([ Tokens::Identifier(StringToken::from("dmg")) ].into()));
+        } else {
+            panic!("Expected to parse a string token.");
+        }
+    }
+
+    #[test_case("\"Firebolt" ; "Unterminated string")]
+    #[test_case("\"Firebolt {$" ; "Unterminated interpolation")]
+    #[test_case("\"\\q\"" ; "Invalid escape")]
+    #[test_case("\"{}\"" ; "Empty interpolation")]
+    #[test_case("\"{\"a\"}\"" ; "Nested string")]
+    fn tokenize_invalid_string(script: &str) {
+        let result = tokenize(script);
+
+        assert!(matches!(result, Err(TokenizerError::UnterminatedString | TokenizerError::InvalidEscape(_) | TokenizerError::InvalidInterpolation)));
+    }
+
     #[test]
     fn tokenize_real_thing() {
         let script = "\
@@ -169,7 +241,7 @@ mod tests {
             Tokens::Dice(DiceToken::try_from("d4").unwrap()),
             Tokens::Symbol(StringToken::from("+")),
             Tokens::Numeric(IntToken::try_from("4").unwrap()),
-            Tokens::DamageType(DamageTypeToken::try_from("fire").unwrap()),
+            Tokens::DamageType(DamageTypeToken::new("fire", &DamageTypeRegistry::default()).unwrap()),
             Tokens::Symbol(StringToken::from("=>")),
             Tokens::Identifier(StringToken::from("$")),
             Tokens::Symbol(StringToken::from(";")),
@@ -183,4 +255,52 @@ mod tests {
             assert_eq!(vec[i], expected[i]);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tokenize_spans() {
+        let script = "[1]: { // cost\n  3 fire => \"hot\"; }";
+        let tokens = tokenize_spanned(script, &DamageTypeRegistry::default()).unwrap();
+        let written: Vec<&str> = tokens.iter().map(|(_, span)| &script[span.start .. span.end]).collect();
+
+        assert_eq!(written, vec![ "[", "1", "]", ":", "{", "3", "fire", "=>", "\"hot\"", ";", "}", "" ]);
+        assert_eq!(tokens[5].1.get_start(script), (2, 3));
+        assert_eq!(tokens[8].1.get_end(script), (2, 18));
+    }
+
+    #[test]
+    fn tokenize_error_span() {
+        let script = "[1]: {\n  log \"oops; }";
+        let (err, span) = tokenize_spanned(script, &DamageTypeRegistry::default()).err().unwrap();
+
+        assert!(matches!(err, TokenizerError::UnterminatedString));
+        assert_eq!(span, Span { start: 13, end: script.len() });
+        assert_eq!(span.get_start(script), (2, 7));
+    }
+
+    #[test_case("/// Deals fire damage", "Deals fire damage" ; "Doc comment")]
+    #[test_case("///no space  \n", "no space" ; "Doc comment without a space")]
+    #[test_case("///", "" ; "Empty doc comment")]
+    fn tokenize_doc_comment(script: &str, expected_text: &str) {
+        let vec = tokenize(script).unwrap();
+
+        assert_eq!(vec, vec![ Tokens::DocComment(StringToken::from(expected_text)), Tokens::EOF ]);
+    }
+
+    #[test]
+    fn block_comment_ends_where_it_closes() {
+        let script = "1 /* a /* b */ c */ + 2 // end\n";
+        let tokens = tokenize_with_comments(script, &DamageTypeRegistry::default()).unwrap();
+        let written: Vec<&str> = tokens.iter().map(|(_, span)| &script[span.start .. span.end]).collect();
+
+        assert_eq!(written, vec![ "1", "/* a /* b */ c */", "+", "2", "// end", "" ]);
+    }
+
+    #[test_case("/* never closed" ; "Unterminated")]
+    #[test_case("/* outer /* inner */" ; "Unterminated outer")]
+    fn tokenize_unterminated_comment(script: &str) {
+        let (err, span) = tokenize_spanned(script, &DamageTypeRegistry::default()).err().unwrap();
+
+        assert!(matches!(err, TokenizerError::UnterminatedComment));
+        assert_eq!(span, Span { start: 0, end: script.len() });
+    }
+}
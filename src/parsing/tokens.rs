@@ -1,9 +1,10 @@
 use std::rc::Rc;
 use core::convert::{From, TryFrom};
+use crate::game_zones::damage_types::DamageTypeRegistry;
 use crate::game_zones::types::{DamageType, DamageTypeParseError, Dice, ParseDiceError};
 
 /// Reserved symbols and keywords in our lanaguage
-pub const SYMBOLS: [&'static str; 32] = [
+pub const SYMBOLS: [&str; 37] = [
     "{",
     "}",
     "(",
@@ -36,6 +37,11 @@ pub const SYMBOLS: [&'static str; 32] = [
     "when",
     "func",
     "target",
+    "log",
+    "counter",
+    "apply",
+    "modify",
+    "instead",
 ];
 
 /// This is a token => a fundamental piece of the language, representing an atomic syntactic unit
@@ -175,38 +181,36 @@ impl Token<DamageType> for DamageTypeToken {
     }
 }
 
-impl TryFrom<&str> for DamageTypeToken {
-    type Error = DamageTypeParseError;
-
-    fn try_from(val: &str) -> Result<Self, DamageTypeParseError> {
-        match val {
-            "fire" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Fire }),
-            "lightning" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Lightning }),
-            "acid" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Acid }),
-            "necrotic" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Necrotic }),
-            "ice" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Ice }),
-            "psychic" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Psychic }),
-            "force" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Force }),
-            "divine" => Ok(DamageTypeToken{ string_value: Rc::from(val), damage_type: DamageType::Divine }),
+impl DamageTypeToken {
+    /// A word that names one of the registry's damage types (`none` is never written out)
+    pub fn new(val: &str, damage_types: &DamageTypeRegistry) -> Result<Self, DamageTypeParseError> {
+        match damage_types.find(val) {
+            Some(damage_type) if !damage_type.is_none() => Ok(DamageTypeToken { string_value: Rc::from(val), damage_type: damage_type.clone() }),
             _ => Err(DamageTypeParseError)
         }
     }
 }
 
-impl TryFrom<String> for DamageTypeToken {
-    type Error = DamageTypeParseError;
-
-    fn try_from(val: String) -> Result<Self, DamageTypeParseError> {
-        DamageTypeToken::try_from(val.as_str())
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DiceToken {
     string_value: Rc<str>,
     dice_value: Dice
 }
 
+impl Token<Dice> for DiceToken {
+    fn to_string(self) -> String {
+        self.string_value.to_string()
+    }
+
+    fn as_str(&self) -> &str {
+        &self.string_value
+    }
+
+    fn get_value(self) -> Dice {
+        self.dice_value
+    }
+}
+
 impl TryFrom<&str> for DiceToken {
     type Error = ParseDiceError;
 
@@ -227,7 +231,59 @@ impl TryFrom<String> for DiceToken {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Piece of a string literal: plain text, or the tokens of an `{expression}` to interpolate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringPart {
+    Text(Rc<str>),
+    Interpolation(Rc<[Tokens]>)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringLiteralToken {
+    /// Contents between the quotes, exactly as written
+    string_value: Rc<str>,
+    /// Contents with escapes resolved (interpolations are left as written)
+    text_value: Rc<str>,
+    parts: Rc<[StringPart]>
+}
+
+impl StringLiteralToken {
+    pub fn new(string_value: &str, text_value: &str, parts: Vec<StringPart>) -> Self {
+        StringLiteralToken {
+            string_value: Rc::from(string_value),
+            text_value: Rc::from(text_value),
+            parts: parts.into()
+        }
+    }
+
+    pub fn get_parts(&self) -> &[StringPart] {
+        &self.parts
+    }
+
+    /// The text of the literal, unless it interpolates anything
+    pub fn get_plain_text(&self) -> Option<&str> {
+        if self.parts.iter().any(|part| matches!(part, StringPart::Interpolation(_))) {
+            return None;
+        }
+        Some(&self.text_value)
+    }
+}
+
+impl Token<String> for StringLiteralToken {
+    fn to_string(self) -> String {
+        self.string_value.to_string()
+    }
+
+    fn as_str(&self) -> &str {
+        &self.string_value
+    }
+
+    fn get_value(self) -> String {
+        self.text_value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 /// The various token types
 pub enum Tokens {
     /// Token is a numerical value
@@ -242,8 +298,29 @@ pub enum Tokens {
     DamageType(DamageTypeToken),
     /// Token is a boolean literal
     Boolean(BoolToken),
-    // Double-slash token, which means we ignore everything until a newline
+    /// Token is a string literal (value excludes the quotes)
+    String(StringLiteralToken),
+    // Double-slash token, which means we ignore everything until a newline (or a `/* */` block)
     Comment,
+    /// `///` comment documenting the card that follows; the value is its text after the slashes
+    DocComment(StringToken),
     /// Indicates the end of file (not really associated with a real token value)
     EOF
+}
+
+/// The token as it would be written in a script
+impl std::fmt::Display for Tokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tokens::Numeric(token) => write!(f, "{}", token.as_str()),
+            Tokens::Identifier(token) | Tokens::Symbol(token) => write!(f, "{}", token.as_str()),
+            Tokens::Dice(token) => write!(f, "{}", token.as_str()),
+            Tokens::DamageType(token) => write!(f, "{}", token.as_str()),
+            Tokens::Boolean(token) => write!(f, "{}", token.as_str()),
+            Tokens::String(token) => write!(f, "\"{}\"", token.as_str()),
+            Tokens::Comment => write!(f, "//"),
+            Tokens::DocComment(_) => write!(f, "///"),
+            Tokens::EOF => write!(f, "end of file")
+        }
+    }
 }
\ No newline at end of file